@@ -1,4 +1,20 @@
 fn main() {
+    // Embed the git commit and build date so logs and bug reports identify the exact build
+    let commit = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=SIEGESAVER_GIT_COMMIT={}", commit);
+    println!(
+        "cargo:rustc-env=SIEGESAVER_BUILD_DATE={}",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+
     // Only compile resources on Windows
     #[cfg(target_os = "windows")]
     {
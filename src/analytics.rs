@@ -0,0 +1,281 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::dissect::{recording_player, summarize};
+use crate::manifest::ManifestEntry;
+use crate::stats::local_date;
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// How the matches on one map went
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MapRecord {
+    pub map: String,
+    pub won: usize,
+    pub lost: usize,
+    pub drawn: usize,
+}
+
+impl MapRecord {
+    pub fn played(&self) -> usize {
+        self.won + self.lost + self.drawn
+    }
+
+    // Share of the matches played there that were won, from 0 to 1
+    pub fn winrate(&self) -> f64 {
+        self.won as f64 / self.played().max(1) as f64
+    }
+}
+
+// Kills and deaths of the matches backed up on one day
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DayKd {
+    pub day: NaiveDate,
+    pub kills: u64,
+    pub deaths: u64,
+}
+
+impl DayKd {
+    // Kills per death, counting no deaths as one
+    pub fn kd(&self) -> f64 {
+        self.kills as f64 / self.deaths.max(1) as f64
+    }
+}
+
+// Totals of the Analytics tab, over every match r6-dissect has read
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LibraryAnalytics {
+    pub matches: usize,
+    pub won: usize,
+    pub lost: usize,
+    pub kills: u64,
+    pub deaths: u64,
+    // Most played first
+    pub maps: Vec<MapRecord>,
+    // Rounds played with each operator, most played first
+    pub operators: Vec<(String, usize)>,
+    // Oldest first, only days with matches
+    pub kd_per_day: Vec<DayKd>,
+}
+
+impl LibraryAnalytics {
+    pub fn winrate(&self) -> f64 {
+        self.won as f64 / (self.won + self.lost).max(1) as f64
+    }
+
+    pub fn kd(&self) -> f64 {
+        self.kills as f64 / self.deaths.max(1) as f64
+    }
+}
+
+// The map the match was played on, as r6-dissect names it, e.g. "CLUBHOUSE"
+fn map_name(stats: &Value) -> Option<&str> {
+    stats["rounds"]
+        .as_array()?
+        .iter()
+        .find_map(|round| round["map"]["name"].as_str())
+        .filter(|name| !name.is_empty())
+}
+
+// Adds up the matches of `entries`, by name. A match found under the same name in several
+// destinations counts once; the day it counts for is the one it was first backed up.
+pub fn library_analytics<'a>(
+    entries: impl IntoIterator<Item = (&'a str, &'a ManifestEntry)>,
+) -> LibraryAnalytics {
+    let mut matches: BTreeMap<&str, &ManifestEntry> = BTreeMap::new();
+    for (name, entry) in entries {
+        if entry.match_stats.is_some() {
+            matches.entry(name).or_insert(entry);
+        }
+    }
+
+    let mut analytics = LibraryAnalytics::default();
+    let mut maps: BTreeMap<&str, MapRecord> = BTreeMap::new();
+    let mut operators: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut days: BTreeMap<NaiveDate, (u64, u64)> = BTreeMap::new();
+    for entry in matches.into_values() {
+        let Some(stats) = &entry.match_stats else {
+            continue;
+        };
+        let Some(summary) = summarize(stats) else {
+            continue;
+        };
+        analytics.matches += 1;
+        analytics.kills += summary.kills;
+        analytics.deaths += summary.deaths;
+        let map = map_name(stats).unwrap_or("?");
+        let record = maps.entry(map).or_insert_with(|| MapRecord {
+            map: map.to_string(),
+            ..MapRecord::default()
+        });
+        match summary.won() {
+            Some(true) => {
+                analytics.won += 1;
+                record.won += 1;
+            }
+            Some(false) => {
+                analytics.lost += 1;
+                record.lost += 1;
+            }
+            None => record.drawn += 1,
+        }
+        for round in stats["rounds"].as_array().into_iter().flatten() {
+            if let Some(operator) = recording_player(round)
+                .and_then(|player| player["operator"]["name"].as_str())
+                .filter(|operator| !operator.is_empty())
+            {
+                *operators.entry(operator).or_default() += 1;
+            }
+        }
+        if let Some(day) = local_date(entry.first_backup as i64) {
+            let (kills, deaths) = days.entry(day).or_default();
+            *kills += summary.kills;
+            *deaths += summary.deaths;
+        }
+    }
+
+    analytics.maps = maps.into_values().collect();
+    // Stable, so maps played as often stay in alphabetical order
+    analytics
+        .maps
+        .sort_by_key(|record| std::cmp::Reverse(record.played()));
+    analytics.operators = operators
+        .into_iter()
+        .map(|(operator, rounds)| (operator.to_string(), rounds))
+        .collect();
+    analytics
+        .operators
+        .sort_by_key(|(_, rounds)| std::cmp::Reverse(*rounds));
+    analytics.kd_per_day = days
+        .into_iter()
+        .map(|(day, (kills, deaths))| DayKd { day, kills, deaths })
+        .collect();
+    analytics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use serde_json::json;
+
+    // A match on `map` where the recording player, on team 0, plays `operators` and wins the
+    // rounds marked true
+    fn played(map: &str, operators: &[&str], won: &[bool], kills: u64, deaths: u64) -> Value {
+        let rounds: Vec<Value> = operators
+            .iter()
+            .zip(won)
+            .map(|(operator, won)| {
+                json!({
+                    "map": { "name": map },
+                    "recordingProfileID": "b0b",
+                    "players": [
+                        { "profileID": "b0b", "username": "Bob", "teamIndex": 0,
+                          "operator": { "name": operator } },
+                    ],
+                    "teams": [{ "won": won }, { "won": !won }],
+                })
+            })
+            .collect();
+        json!({
+            "rounds": rounds,
+            "stats": [{ "username": "Bob", "kills": kills, "deaths": deaths }],
+        })
+    }
+
+    fn entry(day: u32, match_stats: Option<Value>) -> ManifestEntry {
+        let time = Local.with_ymd_and_hms(2025, 11, day, 20, 0, 0).unwrap();
+        ManifestEntry {
+            first_backup: time.timestamp() as u64,
+            match_stats,
+            ..ManifestEntry::default()
+        }
+    }
+
+    #[test]
+    fn test_library_analytics() {
+        let entries = [
+            (
+                "Match-A",
+                entry(
+                    22,
+                    Some(played(
+                        "BANK",
+                        &["Ash", "Ash", "Thermite"],
+                        &[true, true, false],
+                        6,
+                        1,
+                    )),
+                ),
+            ),
+            (
+                "Match-B",
+                entry(
+                    22,
+                    Some(played("BANK", &["Ash", "Sledge"], &[false, false], 1, 2)),
+                ),
+            ),
+            (
+                "Match-C",
+                entry(23, Some(played("CLUBHOUSE", &["Jäger"], &[true], 3, 0))),
+            ),
+            // Not read by r6-dissect, and another copy of a match already counted
+            ("Match-D", entry(23, None)),
+            (
+                "Match-A",
+                entry(23, Some(played("BANK", &["Ash"], &[true], 6, 1))),
+            ),
+        ];
+        let analytics = library_analytics(entries.iter().map(|(name, entry)| (*name, entry)));
+
+        assert_eq!(analytics.matches, 3);
+        assert_eq!((analytics.won, analytics.lost), (2, 1));
+        assert_eq!((analytics.kills, analytics.deaths), (10, 3));
+        assert_eq!(
+            analytics.maps,
+            [
+                MapRecord {
+                    map: "BANK".to_string(),
+                    won: 1,
+                    lost: 1,
+                    drawn: 0,
+                },
+                MapRecord {
+                    map: "CLUBHOUSE".to_string(),
+                    won: 1,
+                    lost: 0,
+                    drawn: 0,
+                },
+            ]
+        );
+        assert_eq!(analytics.maps[0].winrate(), 0.5);
+        assert_eq!(
+            analytics.operators,
+            [
+                ("Ash".to_string(), 3),
+                ("Jäger".to_string(), 1),
+                ("Sledge".to_string(), 1),
+                ("Thermite".to_string(), 1),
+            ]
+        );
+        let day = |day| NaiveDate::from_ymd_opt(2025, 11, day).unwrap();
+        assert_eq!(
+            analytics.kd_per_day,
+            [
+                DayKd {
+                    day: day(22),
+                    kills: 7,
+                    deaths: 3,
+                },
+                DayKd {
+                    day: day(23),
+                    kills: 3,
+                    deaths: 0,
+                },
+            ]
+        );
+        assert_eq!(analytics.kd_per_day[1].kd(), 3.0);
+
+        assert_eq!(library_analytics([]), LibraryAnalytics::default());
+    }
+}
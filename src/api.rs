@@ -0,0 +1,349 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::history::BackupHistory;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// Port the API listens on unless another one is set
+pub const DEFAULT_API_PORT: u16 = 8765;
+// Entries GET /history returns unless `?limit=` asks for another number
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+// Request bodies are not used; anything longer than this is not read
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+// What a request to the API asks the window to do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiCommand {
+    StartWatching,
+    StopWatching,
+}
+
+// The answer to GET /status, kept up to date by the window
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ApiStatus {
+    pub version: String,
+    pub watching: bool,
+    // Seconds until backups resume, while they are paused
+    pub paused_seconds: Option<u64>,
+    pub sources: Vec<String>,
+    pub destination: String,
+    // Matches waiting to be backed up or being copied
+    pub queued: usize,
+    // Since SiegeSaver started
+    pub files_backed_up: u64,
+    pub bytes_backed_up: u64,
+    pub errors: u64,
+    // Seconds since the Unix epoch
+    pub last_backup: Option<u64>,
+}
+
+// A small HTTP server on 127.0.0.1 with JSON endpoints for dashboards and scripts:
+// GET /status, GET /history?limit=N, POST /start and POST /stop. Requests are answered one
+// at a time on a background thread until the server is dropped.
+pub struct ApiServer {
+    port: u16,
+    status: Arc<Mutex<ApiStatus>>,
+    commands: Receiver<ApiCommand>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ApiServer {
+    // Listens on `port`, or any free port for 0. The history is read from `history_path` for
+    // every request, and `wake` is called after a command arrives, so the window handles it
+    // even while it is hidden.
+    pub fn spawn(
+        port: u16,
+        history_path: PathBuf,
+        wake: impl Fn() + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let port = listener.local_addr()?.port();
+        let status = Arc::new(Mutex::new(ApiStatus::default()));
+        let (tx, commands) = channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_status = Arc::clone(&status);
+        let thread_stopped = Arc::clone(&stopped);
+        let thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if handle_connection(stream, port, &thread_status, &history_path, &tx) {
+                    wake();
+                }
+            }
+        });
+        Ok(Self {
+            port,
+            status,
+            commands,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn update(&self, status: ApiStatus) {
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = status;
+    }
+
+    // Commands received since the last call, oldest first
+    pub fn take_commands(&self) -> Vec<ApiCommand> {
+        self.commands.try_iter().collect()
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wakes the listener thread, which is waiting for a connection, and waits for it to
+        // close the port, so a new server can listen on it right away
+        if TcpStream::connect((Ipv4Addr::LOCALHOST, self.port)).is_ok() {
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+// Whether the Host header `host` names the API itself. A web page whose domain was rebound
+// to 127.0.0.1 sends its own domain, while its requests carry no Origin.
+fn is_local_host(host: &str, port: u16) -> bool {
+    host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port)
+}
+
+// Answers one request to the API on `port`; returns true if it sent a command
+fn handle_connection(
+    mut stream: TcpStream,
+    port: u16,
+    status: &Mutex<ApiStatus>,
+    history_path: &Path,
+    commands: &Sender<ApiCommand>,
+) -> bool {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+    let Ok(reader) = stream.try_clone() else {
+        return false;
+    };
+    let mut reader = BufReader::new(reader);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return false;
+    }
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    let mut from_browser = false;
+    let mut local_host = false;
+    let mut body_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).unwrap_or(0) == 0 || header.trim().is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        from_browser |= name == "origin";
+        if name == "host" {
+            local_host = is_local_host(value.trim(), port);
+        }
+        if name == "content-length" {
+            body_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let _ = std::io::copy(
+        &mut reader.take(body_length.min(MAX_BODY_BYTES)),
+        &mut std::io::sink(),
+    );
+
+    let (code, body, command) = if from_browser || !local_host {
+        // Web pages can send requests to localhost too; only other programs may use the API
+        (
+            403,
+            json!({ "error": "Requests from web pages are not allowed" }),
+            None,
+        )
+    } else {
+        route(method, target, status, history_path)
+    };
+    let sent = command.is_some_and(|command| commands.send(command).is_ok());
+    let body = body.to_string();
+    let reason = match code {
+        200 => "OK",
+        202 => "Accepted",
+        403 => "Forbidden",
+        404 => "Not Found",
+        _ => "Method Not Allowed",
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        code,
+        reason,
+        body.len(),
+        body
+    );
+    sent
+}
+
+fn route(
+    method: &str,
+    target: &str,
+    status: &Mutex<ApiStatus>,
+    history_path: &Path,
+) -> (u16, Value, Option<ApiCommand>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let command = match path {
+        "/start" => Some(ApiCommand::StartWatching),
+        "/stop" => Some(ApiCommand::StopWatching),
+        _ => None,
+    };
+    match (method, path, command) {
+        ("GET", "/status", _) => {
+            let status = status.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            (200, json!(status), None)
+        }
+        ("GET", "/history", _) => {
+            let limit = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("limit="))
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(DEFAULT_HISTORY_LIMIT);
+            let history = BackupHistory::load(history_path);
+            let newest: Vec<_> = history.entries.iter().rev().take(limit).collect();
+            (200, json!(newest), None)
+        }
+        ("POST", _, Some(command)) => (
+            202,
+            json!({ "accepted": path.trim_matches('/') }),
+            Some(command),
+        ),
+        (_, "/status" | "/history", _) | (_, _, Some(_)) => {
+            (405, json!({ "error": "Method not allowed" }), None)
+        }
+        _ => (404, json!({ "error": "Not found" }), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{HistoryEntry, HISTORY_FILE_NAME};
+    use std::sync::atomic::AtomicUsize;
+
+    // Sends `text`, with the Host a local program sends unless it has one
+    fn request(port: u16, text: &str) -> (String, Value) {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let text = match text.split_once("\r\n") {
+            Some((line, rest)) if !text.contains("\r\nHost:") => {
+                format!("{}\r\nHost: 127.0.0.1:{}\r\n{}", line, port, rest)
+            }
+            _ => text.to_string(),
+        };
+        stream.write_all(text.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status_line = head.lines().next().unwrap().to_string();
+        (status_line, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_api_server() {
+        let folder = std::env::temp_dir().join("siegesaver_api_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        let history_path = folder.join(HISTORY_FILE_NAME);
+        let mut history = BackupHistory::default();
+        for name in ["Match-A", "Match-B", "Match-C"] {
+            history.record(HistoryEntry {
+                name: name.to_string(),
+                time: 0,
+                files: 2,
+                bytes: 1024,
+                duration_ms: 5,
+                error: None,
+            });
+        }
+        history.save(&history_path).unwrap();
+
+        let woken = Arc::new(AtomicUsize::new(0));
+        let wake_count = Arc::clone(&woken);
+        let server = ApiServer::spawn(0, history_path, move || {
+            wake_count.fetch_add(1, Ordering::Relaxed);
+        })
+        .unwrap();
+        server.update(ApiStatus {
+            watching: true,
+            destination: "/backup".to_string(),
+            queued: 2,
+            ..Default::default()
+        });
+        let port = server.port();
+
+        let (status_line, body) = request(port, "GET /status HTTP/1.1\r\n\r\n");
+        assert_eq!(status_line, "HTTP/1.1 200 OK");
+        assert_eq!(body["watching"], true);
+        assert_eq!(body["destination"], "/backup");
+        assert_eq!(body["queued"], 2);
+
+        let (_, body) = request(port, "GET /history?limit=2 HTTP/1.1\r\n\r\n");
+        let names: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Match-C", "Match-B"]);
+
+        let (status_line, _) = request(port, "POST /stop HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+        assert_eq!(status_line, "HTTP/1.1 202 Accepted");
+        let (status_line, _) = request(port, "GET /start HTTP/1.1\r\n\r\n");
+        assert_eq!(status_line, "HTTP/1.1 405 Method Not Allowed");
+        let (status_line, _) = request(
+            port,
+            "POST /start HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n",
+        );
+        assert_eq!(status_line, "HTTP/1.1 403 Forbidden");
+        // A web page on a domain rebound to 127.0.0.1 sends no Origin to its own domain
+        let (status_line, _) = request(
+            port,
+            &format!(
+                "GET /status HTTP/1.1\r\nHost: evil.example:{}\r\n\r\n",
+                port
+            ),
+        );
+        assert_eq!(status_line, "HTTP/1.1 403 Forbidden");
+        let (status_line, _) = request(port, "GET /status HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(status_line, "HTTP/1.1 403 Forbidden");
+        let (status_line, _) = request(
+            port,
+            &format!("GET /status HTTP/1.1\r\nHost: localhost:{}\r\n\r\n", port),
+        );
+        assert_eq!(status_line, "HTTP/1.1 200 OK");
+        let (status_line, _) = request(port, "GET /missing HTTP/1.1\r\n\r\n");
+        assert_eq!(status_line, "HTTP/1.1 404 Not Found");
+
+        assert_eq!(server.take_commands(), [ApiCommand::StopWatching]);
+        assert_eq!(woken.load(Ordering::Relaxed), 1);
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
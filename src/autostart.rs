@@ -0,0 +1,330 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const DESKTOP_FILE_NAME: &str = "siegesaver.desktop";
+pub const SYSTEMD_UNIT_NAME: &str = "siegesaver.service";
+pub const LAUNCH_AGENT_LABEL: &str = "com.datuub.siegesaver";
+
+// How SiegeSaver is started on login on Linux. macOS uses a LaunchAgent and Windows
+// auto_launch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutostartMode {
+    // An XDG autostart entry that opens the app with the desktop session
+    #[default]
+    Desktop,
+    // A systemd user unit running `--headless`, also without a desktop session
+    Systemd,
+}
+
+impl AutostartMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            AutostartMode::Desktop => "with the desktop",
+            AutostartMode::Systemd => "in the background (systemd)",
+        }
+    }
+}
+
+// Quotes an argument for an Exec line. Desktop entries and systemd escape `$` differently,
+// and both expand `%` specifiers.
+fn exec_arg(arg: &str, systemd: bool) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+=:,".contains(c));
+    let arg = arg.replace('%', "%%");
+    if plain {
+        return arg;
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '$' if systemd => quoted.push_str("$$"),
+            '"' | '\\' | '`' | '$' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn exec_line(args: &[&str], systemd: bool) -> String {
+    let line = args
+        .iter()
+        .map(|arg| exec_arg(arg, systemd))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if systemd {
+        line
+    } else {
+        // Desktop entry values are escaped once more as strings
+        line.replace('\\', "\\\\")
+    }
+}
+
+pub fn desktop_entry(exe: &Path, config_path: &Path) -> String {
+    let exec = exec_line(
+        &[
+            &exe.to_string_lossy(),
+            "--config",
+            &config_path.to_string_lossy(),
+        ],
+        false,
+    );
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=SiegeSaver\n\
+         Comment=Backs up Rainbow Six Siege replays\n\
+         Exec={}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        exec
+    )
+}
+
+pub fn systemd_unit(exe: &Path, config_path: &Path) -> String {
+    let exec = exec_line(
+        &[
+            &exe.to_string_lossy(),
+            "--headless",
+            "--config",
+            &config_path.to_string_lossy(),
+        ],
+        true,
+    );
+    format!(
+        "[Unit]\n\
+         Description=SiegeSaver replay backup\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=30\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// A LaunchAgent that opens the app when the user logs in
+pub fn launch_agent_plist(exe: &Path, config_path: &Path) -> String {
+    let arguments: String = [
+        exe.to_string_lossy(),
+        "--config".into(),
+        config_path.to_string_lossy(),
+    ]
+    .iter()
+    .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+    .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>ProcessType</key>
+    <string>Interactive</string>
+</dict>
+</plist>
+"#,
+        LAUNCH_AGENT_LABEL, arguments
+    )
+}
+
+pub fn launch_agent_path(home_dir: &Path) -> PathBuf {
+    home_dir
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", LAUNCH_AGENT_LABEL))
+}
+
+// Where the entries go under the XDG config directory, e.g. `~/.config`
+pub fn desktop_entry_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("autostart").join(DESKTOP_FILE_NAME)
+}
+
+pub fn systemd_unit_path(config_dir: &Path) -> PathBuf {
+    config_dir
+        .join("systemd")
+        .join("user")
+        .join(SYSTEMD_UNIT_NAME)
+}
+
+fn systemctl(args: &[&str]) -> Result<(), String> {
+    let output = std::process::Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Cannot run systemctl: {}", e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "systemctl --user {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
+}
+
+fn write_entry(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+fn remove_entry(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Error removing {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Removes both kinds of entries, and the one auto_launch may have created before
+fn disable_linux_autostart(config_dir: &Path) -> Result<(), String> {
+    if let Ok(auto_launch) = crate::config::get_auto_launch() {
+        let _ = auto_launch.disable();
+    }
+    remove_entry(&desktop_entry_path(config_dir))?;
+    let unit = systemd_unit_path(config_dir);
+    if unit.exists() {
+        systemctl(&["disable", SYSTEMD_UNIT_NAME])?;
+        remove_entry(&unit)?;
+        systemctl(&["daemon-reload"])?;
+    }
+    Ok(())
+}
+
+// The agent is only written, not loaded, so nothing starts before the next login. It is
+// removed the same way, as unloading it would quit the copy it started.
+fn set_launch_agent(enabled: bool, home_dir: &Path, config_path: &Path) -> Result<(), String> {
+    if let Ok(auto_launch) = crate::config::get_auto_launch() {
+        let _ = auto_launch.disable();
+    }
+    let path = launch_agent_path(home_dir);
+    if !enabled {
+        return remove_entry(&path);
+    }
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let config_path = std::path::absolute(config_path).map_err(|e| e.to_string())?;
+    write_entry(&path, &launch_agent_plist(&exe, &config_path))
+}
+
+// Installs the entry for `mode` in place of any other, so SiegeSaver never starts twice
+fn enable_linux_autostart(
+    mode: AutostartMode,
+    config_dir: &Path,
+    config_path: &Path,
+) -> Result<(), String> {
+    disable_linux_autostart(config_dir)?;
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let config_path = std::path::absolute(config_path).map_err(|e| e.to_string())?;
+    match mode {
+        AutostartMode::Desktop => write_entry(
+            &desktop_entry_path(config_dir),
+            &desktop_entry(&exe, &config_path),
+        ),
+        AutostartMode::Systemd => {
+            write_entry(
+                &systemd_unit_path(config_dir),
+                &systemd_unit(&exe, &config_path),
+            )?;
+            systemctl(&["daemon-reload"])?;
+            systemctl(&["enable", SYSTEMD_UNIT_NAME])
+        }
+    }
+}
+
+// Turns starting on login on or off. On Linux, `mode` picks how; on Linux and macOS the
+// config file is passed along so the started copy uses the same settings.
+pub fn set_autostart(enabled: bool, mode: AutostartMode, config_path: &Path) -> Result<(), String> {
+    if cfg!(target_os = "linux") {
+        let config_dir = dirs::config_dir().ok_or("No config directory found")?;
+        return if enabled {
+            enable_linux_autostart(mode, &config_dir, config_path)
+        } else {
+            disable_linux_autostart(&config_dir)
+        };
+    }
+    if cfg!(target_os = "macos") {
+        let home_dir = dirs::home_dir().ok_or("No home directory found")?;
+        return set_launch_agent(enabled, &home_dir, config_path);
+    }
+    let auto_launch = crate::config::get_auto_launch()?;
+    if enabled {
+        auto_launch.enable()
+    } else {
+        auto_launch.disable()
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_autostart_entries() {
+        let exe = Path::new("/opt/siegesaver/siegesaver");
+        let config_path = Path::new("/home/player/My Backups/100% config.json");
+
+        let entry = desktop_entry(exe, config_path);
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains(
+            "\nExec=/opt/siegesaver/siegesaver --config \"/home/player/My Backups/100%% config.json\"\n"
+        ));
+
+        let unit = systemd_unit(exe, config_path);
+        assert!(unit.contains(
+            "\nExecStart=/opt/siegesaver/siegesaver --headless --config \"/home/player/My Backups/100%% config.json\"\n"
+        ));
+        assert!(unit.contains("WantedBy=default.target"));
+
+        assert_eq!(exec_arg("$HOME\\x", true), "\"$$HOME\\\\x\"");
+        assert_eq!(exec_line(&["a\"b"], false), "\"a\\\\\"b\"");
+
+        let plist = launch_agent_plist(exe, Path::new("/Users/player/R&D/config.json"));
+        assert!(plist.contains("    <string>com.datuub.siegesaver</string>\n"));
+        assert!(plist.contains(
+            "        <string>/opt/siegesaver/siegesaver</string>\n        \
+             <string>--config</string>\n        \
+             <string>/Users/player/R&amp;D/config.json</string>\n    </array>\n"
+        ));
+        assert_eq!(
+            launch_agent_path(Path::new("/Users/player")),
+            Path::new("/Users/player/Library/LaunchAgents/com.datuub.siegesaver.plist")
+        );
+
+        let config_dir = Path::new("/home/player/.config");
+        assert_eq!(
+            desktop_entry_path(config_dir),
+            config_dir.join("autostart").join("siegesaver.desktop")
+        );
+        assert_eq!(
+            systemd_unit_path(config_dir),
+            config_dir.join("systemd/user/siegesaver.service")
+        );
+    }
+}
@@ -0,0 +1,5031 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::config::normalize_path_input;
+use crate::crypto::{EncryptionKey, ENCRYPTED_EXTENSION};
+use crate::dedupe::{blob_path, collect_garbage, DEDUPE_STORE_NAME};
+use crate::dissect::{dissect_match, has_replays};
+use crate::hook::run_hook;
+use crate::journal::{BackupJournal, PendingBackup};
+use crate::longpath::{extended_path, regular_path};
+use crate::manifest::{sha256_hex, Manifest, VerifyReport};
+use crate::mirror::{
+    empty_trash, is_game_cleanup, move_to_trash, DeletionQueue, PendingDeletion, TRASH_KEEP,
+    TRASH_KEEP_DAYS,
+};
+use crate::quota::select_evictions;
+use crate::remote::{upload_item, Remote, UploadQueue};
+use crate::retention::{prune_candidates, RetentionPolicy};
+use crate::snapshot::{latest_snapshot, start_snapshot};
+use crate::status::{format_bytes, EventDetail, StatusEvent};
+use crate::storage::{StorageBackend, StorageFormat};
+use crate::throttle::{Throttle, ThrottledFs};
+use crate::tray::CopyActivity;
+use crate::versions::{KeptVersions, VERSIONS_FOLDER_NAME};
+use crate::volume::{ensure_volume_id, find_volume, read_volume_id, VOLUME_ID_FILE_NAME};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+// Statistics shared between the GUI and the event handler thread
+#[derive(Default)]
+pub struct BackupStats {
+    pub last_backup: Option<SystemTime>,
+    pub files_backed_up: u64,
+    pub bytes_backed_up: u64,
+    pub errors: u64,
+}
+
+impl BackupStats {
+    pub fn record_backup(&mut self, totals: CopyTotals) {
+        self.last_backup = Some(SystemTime::now());
+        self.files_backed_up += totals.files;
+        self.bytes_backed_up += totals.bytes;
+    }
+}
+
+// Number of files and bytes written by a copy operation
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct CopyTotals {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+// Options that control how the event handler writes backups
+#[derive(Clone, Default)]
+pub struct BackupOptions {
+    // Flush every copied file to disk before reporting it as backed up
+    pub fsync_writes: bool,
+    // Encrypt every copied file and store it as `<name>.enc`
+    pub encryption: Option<Arc<EncryptionKey>>,
+    // Absolute paths of source subfolders whose contents are never backed up
+    pub excluded_dirs: Vec<PathBuf>,
+    // Top up matches already in the destination with later rounds instead of skipping them
+    // entirely, and only rebuild archives that are out of date. Files whose backup is
+    // unchanged are never copied again, in either mode.
+    pub incremental: bool,
+    // Which files are copied at all
+    pub file_filter: FileFilter,
+    // Whether match folders are copied as they are or packed into one archive each
+    pub storage: StorageFormat,
+    // Compression level of tar.zst archives
+    pub zstd_level: i32,
+    // Read every copy back and compare its SHA-256 with the source, copying again on mismatch
+    pub verify_copies: bool,
+    // How many files of a match folder are copied at the same time; 0 counts as 1
+    pub copy_threads: usize,
+    // Set to stop the backup between files or chunks of a large file
+    pub cancel: Option<Arc<AtomicBool>>,
+    // How unencrypted files are put into the destination
+    pub copy_mode: CopyMode,
+    // What happens to symbolic links found in the source
+    pub symlinks: SymlinkMode,
+    // What happens to files and archives that already have a backup in the destination
+    pub conflicts: ConflictPolicy,
+    // Dedupe store of the destination. Unencrypted files are kept there once by hash and
+    // hard-linked into the match folders; see `BackupEngine::with_dedupe`.
+    pub dedupe_store: Option<PathBuf>,
+    // Where earlier versions of files that `ConflictPolicy::Merge` replaces are kept, instead
+    // of being overwritten; see `BackupEngine::with_versions`
+    pub versions: Option<KeptVersions>,
+}
+
+// How a file gets into the destination. Linking falls back to a plain copy whenever it is not
+// possible, e.g. because the destination is on another volume.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CopyMode {
+    // Plain copies. On APFS, and often on Btrfs and XFS, the system clones these by itself.
+    #[default]
+    Copy,
+    // Copy-on-write clones that share their data with the source until either is changed
+    // (Btrfs and XFS on Linux, APFS on macOS, ReFS on Windows; NTFS cannot clone at all)
+    Reflink,
+    // Hard links: instant and free, but a replay changed in place changes its backup too
+    Hardlink,
+}
+
+impl CopyMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyMode::Copy => "Copy",
+            CopyMode::Reflink => "Clone (reflink)",
+            CopyMode::Hardlink => "Hard link",
+        }
+    }
+
+    // Whether this system can put files into the destination this way at all
+    pub fn is_supported(self) -> bool {
+        match self {
+            CopyMode::Reflink => cfg!(any(target_os = "linux", target_os = "macos", windows)),
+            CopyMode::Copy | CopyMode::Hardlink => true,
+        }
+    }
+}
+
+// How a recursive copy treats symbolic links. Following them blindly can copy things from
+// outside the replay folder, or loop forever on a link to a parent folder.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymlinkMode {
+    #[default]
+    Skip,
+    // Recreates the link in the destination, pointing to the same target
+    Copy,
+    // Copies what the link points to, except broken links and links back into a folder the
+    // copy is already in
+    Follow,
+}
+
+impl SymlinkMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            SymlinkMode::Skip => "Skip",
+            SymlinkMode::Copy => "Copy as links",
+            SymlinkMode::Follow => "Follow",
+        }
+    }
+}
+
+// What a backup does with a file or match archive that is already in the destination. It
+// applies the same way to single files, to the files of a match folder and to archives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    // Copies it again only if the source changed since, e.g. a match that got another round
+    #[default]
+    Merge,
+    // Never touches it; only files that are not in the destination yet are copied
+    Skip,
+    // Copies it again, even if it is unchanged
+    Overwrite,
+    // Like `Merge`, but renames the earlier backup with a number instead of replacing it,
+    // e.g. to `round-01 (1).rec`, so both are kept
+    Rename,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 4] = [
+        ConflictPolicy::Merge,
+        ConflictPolicy::Skip,
+        ConflictPolicy::Overwrite,
+        ConflictPolicy::Rename,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConflictPolicy::Merge => "Update if changed",
+            ConflictPolicy::Skip => "Keep the existing backup",
+            ConflictPolicy::Overwrite => "Always copy again",
+            ConflictPolicy::Rename => "Keep both, numbered",
+        }
+    }
+}
+
+impl BackupOptions {
+    pub fn storage_backend(&self) -> Box<dyn StorageBackend> {
+        self.storage.backend(self.zstd_level)
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.excluded_dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    // Fails once the backup was cancelled
+    pub fn check_cancelled(&self) -> std::io::Result<()> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            return Err(std::io::Error::other(Cancelled));
+        }
+        Ok(())
+    }
+
+    // Where a file copied to `destination` actually ends up on disk
+    pub fn stored_path(&self, destination: &Path) -> PathBuf {
+        if self.encryption.is_none() {
+            return destination.to_path_buf();
+        }
+        let mut encrypted_name = destination.as_os_str().to_owned();
+        encrypted_name.push(".");
+        encrypted_name.push(ENCRYPTED_EXTENSION);
+        PathBuf::from(encrypted_name)
+    }
+}
+
+// Glob patterns matched against file names (case-insensitively) that decide which files are
+// backed up. A file is copied when it matches any include pattern and no exclude pattern.
+#[derive(Clone, Debug)]
+pub struct FileFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl FileFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    glob::Pattern::new(pattern)
+                        .map_err(|e| format!("Invalid file pattern \"{}\": {}", pattern, e))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn allows(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        self.include
+            .iter()
+            .any(|pattern| pattern.matches_with(name, options))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.matches_with(name, options))
+    }
+}
+
+impl Default for FileFilter {
+    // Only replay files, which is all the game needs restored
+    fn default() -> Self {
+        Self::new(&default_include_patterns(), &default_exclude_patterns())
+            .expect("default pattern is valid")
+    }
+}
+
+pub fn default_include_patterns() -> Vec<String> {
+    vec!["*.rec".to_string()]
+}
+
+// Files the game writes while a round is still being recorded, so copying them would only
+// back up a partial file under a name that is about to go away
+pub fn default_exclude_patterns() -> Vec<String> {
+    vec!["*.tmp".to_string(), "*.partial".to_string()]
+}
+
+// Splits a comma-separated pattern list as typed in the UI, e.g. "*.rec, *.json"
+pub fn parse_patterns(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Where the archive of a match folder ends up, e.g. `<destination>/Match-001.zip`
+pub fn archive_path(
+    destination_folder: &Path,
+    folder_name: &std::ffi::OsStr,
+    extension: &str,
+) -> PathBuf {
+    let mut name = folder_name.to_owned();
+    name.push(".");
+    name.push(extension);
+    destination_folder.join(name)
+}
+
+// Names the destination subfolders of several source folders after the folders themselves.
+// Folders with the same name, e.g. `MatchReplay` of two accounts, are told apart by their
+// parent folder and, if that is not enough, a number.
+pub fn source_labels(sources: &[PathBuf]) -> Vec<String> {
+    let name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "source".to_string())
+    };
+    let names: Vec<String> = sources.iter().map(|source| name(source)).collect();
+    let mut labels: Vec<String> = Vec::with_capacity(sources.len());
+    for (source, base) in sources.iter().zip(&names) {
+        let mut label = base.clone();
+        if names.iter().filter(|other| *other == base).count() > 1 {
+            if let Some(parent) = source
+                .parent()
+                .filter(|parent| parent.file_name().is_some())
+            {
+                label = format!("{}_{}", name(parent), base);
+            }
+        }
+        let mut unique = label.clone();
+        let mut counter = 2;
+        while labels.contains(&unique) {
+            unique = format!("{}-{}", label, counter);
+            counter += 1;
+        }
+        labels.push(unique);
+    }
+    labels
+}
+
+// Where each source folder is backed up to. A single source keeps using the destination
+// folder itself; with several, each one gets its own subfolder named by `source_labels`.
+pub fn source_destinations(sources: &[PathBuf], destination_folder: &Path) -> Vec<PathBuf> {
+    if sources.len() < 2 {
+        return vec![destination_folder.to_path_buf(); sources.len()];
+    }
+    source_labels(sources)
+        .into_iter()
+        .map(|label| destination_folder.join(label))
+        .collect()
+}
+
+// Parses the exclude list (one source-relative folder per line) into clean relative paths
+pub fn parse_exclude_dirs(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| {
+            normalize_path_input(line)
+                .trim_start_matches(std::path::MAIN_SEPARATOR)
+                .to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// Metadata the copy engine needs about a path
+#[derive(Clone, Copy, Debug)]
+pub struct FileMetadata {
+    pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    // Size in bytes; 0 for folders
+    pub len: u64,
+    // The path is a symbolic link. The other fields describe what it points to, or the link
+    // itself if its target is missing.
+    pub is_symlink: bool,
+}
+
+// Filesystem operations used by the copy engine, so the backup logic can run against an
+// in-memory fake in tests instead of the real disk and the notify thread
+pub trait FileSystem: Send + Sync {
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>>;
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata>;
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()>;
+    fn copy(&self, from: &Path, to: &Path) -> std::io::Result<u64>;
+    // Like `copy`, calling `progress` with (bytes copied, total bytes) while copying large
+    // files. An error returned by `progress` stops the copy.
+    fn copy_with_progress(
+        &self,
+        from: &Path,
+        to: &Path,
+        progress: &dyn Fn(u64, u64) -> std::io::Result<()>,
+    ) -> std::io::Result<u64> {
+        let _ = progress;
+        self.copy(from, to)
+    }
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()>;
+    // Forces a written file (and on Unix its directory entry) to disk
+    fn sync(&self, path: &Path) -> std::io::Result<()>;
+    // Deletes a file, or a folder with everything in it
+    fn remove(&self, path: &Path) -> std::io::Result<()>;
+    // Fails while a file cannot be opened for reading, e.g. when the game holds it locked
+    fn open_for_read(&self, path: &Path) -> std::io::Result<()>;
+    // Moves a file, replacing the one at `to`
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()>;
+    // Moves a file or folder to the system's recycle bin
+    fn recycle(&self, path: &Path) -> std::io::Result<()> {
+        let _ = path;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    // Creates `to` as a hard link to `from`
+    fn hard_link(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        let _ = (from, to);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    // Creates `to` as a copy-on-write clone of `from`
+    fn reflink(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        let _ = (from, to);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    // The absolute path of `path` with every symbolic link resolved
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+    // Where the symbolic link `path` points to, as stored in the link
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let _ = path;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    // Creates `link` as a symbolic link to `target`, a folder if `is_dir`. Windows only lets
+    // administrators and developer mode create them.
+    fn create_symlink(&self, target: &Path, link: &Path, is_dir: bool) -> std::io::Result<()> {
+        let _ = (target, link, is_dir);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    // Gives `to` the modification and access times of `from`, and on Windows also its
+    // creation time and attributes (read-only, hidden, ...). Works on files and folders.
+    fn copy_times(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        let _ = (from, to);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    // Bytes that can still be written to the volume of the folder `path`
+    fn available_space(&self, path: &Path) -> std::io::Result<u64> {
+        let _ = path;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+// Makes the first `length` bytes of `destination`, a file at `path` already that long, share
+// the clusters of `source`. Both must be on the same ReFS volume.
+#[cfg(windows)]
+fn duplicate_extents(
+    source: &fs::File,
+    destination: &fs::File,
+    path: &Path,
+    length: u64,
+) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{GetDiskFreeSpaceW, GetVolumePathNameW};
+    use windows_sys::Win32::System::Ioctl::{
+        DUPLICATE_EXTENTS_DATA, FSCTL_DUPLICATE_EXTENTS_TO_FILE,
+    };
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+    // At most this much per request, which must stay below 4 GiB
+    const CHUNK_BYTES: u64 = 1 << 30;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut volume = [0u16; 1024];
+    let (mut sectors_per_cluster, mut bytes_per_sector, mut free, mut total) = (0, 0, 0, 0);
+    // SAFETY: the path is NUL-terminated, the buffer length is what is passed, and the other
+    // pointers point to locals
+    let found = unsafe {
+        GetVolumePathNameW(wide.as_ptr(), volume.as_mut_ptr(), volume.len() as u32) != 0
+            && GetDiskFreeSpaceW(
+                volume.as_ptr(),
+                &mut sectors_per_cluster,
+                &mut bytes_per_sector,
+                &mut free,
+                &mut total,
+            ) != 0
+    };
+    if !found {
+        return Err(std::io::Error::last_os_error());
+    }
+    // Every range starts at a cluster and covers whole clusters, past the end of the file too
+    let cluster = u64::from(sectors_per_cluster) * u64::from(bytes_per_sector);
+    let clustered = length.div_ceil(cluster.max(1)) * cluster.max(1);
+    let mut offset = 0;
+    while offset < clustered {
+        let count = (clustered - offset).min(CHUNK_BYTES);
+        let request = DUPLICATE_EXTENTS_DATA {
+            FileHandle: source.as_raw_handle(),
+            SourceFileOffset: offset as i64,
+            TargetFileOffset: offset as i64,
+            ByteCount: count as i64,
+        };
+        let mut returned = 0;
+        // SAFETY: `request` holds an open handle and outlives the call, and no output buffer
+        // or overlapped I/O is used
+        let cloned = unsafe {
+            DeviceIoControl(
+                destination.as_raw_handle(),
+                FSCTL_DUPLICATE_EXTENTS_TO_FILE,
+                &request as *const DUPLICATE_EXTENTS_DATA as *const std::ffi::c_void,
+                std::mem::size_of::<DUPLICATE_EXTENTS_DATA>() as u32,
+                std::ptr::null_mut(),
+                0,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if cloned == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        offset += count;
+    }
+    Ok(())
+}
+
+// Files at least this large are copied in chunks, reporting progress after each one
+pub const CHUNKED_COPY_THRESHOLD: u64 = 8 * 1024 * 1024;
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+// The real filesystem
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    // Every path goes through `extended_path`, so paths longer than MAX_PATH work on Windows
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        // Joined to `path` rather than the extended form, which stays an implementation detail
+        fs::read_dir(extended_path(path))?
+            .map(|entry| entry.map(|e| path.join(e.file_name())))
+            .collect()
+    }
+
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        let path = extended_path(path);
+        let link = fs::symlink_metadata(&path)?;
+        let is_symlink = link.file_type().is_symlink();
+        let metadata = match is_symlink {
+            true => fs::metadata(&path).unwrap_or(link),
+            false => link,
+        };
+        Ok(FileMetadata {
+            is_dir: metadata.is_dir(),
+            modified: metadata.modified().ok(),
+            len: if metadata.is_dir() { 0 } else { metadata.len() },
+            is_symlink,
+        })
+    }
+
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(extended_path(path))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> std::io::Result<u64> {
+        fs::copy(extended_path(from), extended_path(to))
+    }
+
+    fn copy_with_progress(
+        &self,
+        from: &Path,
+        to: &Path,
+        progress: &dyn Fn(u64, u64) -> std::io::Result<()>,
+    ) -> std::io::Result<u64> {
+        let (from, to) = (extended_path(from), extended_path(to));
+        let mut source = fs::File::open(&from)?;
+        let metadata = source.metadata()?;
+        let total = metadata.len();
+        if total < CHUNKED_COPY_THRESHOLD {
+            return fs::copy(&from, &to);
+        }
+        let mut destination = fs::File::create(&to)?;
+        let mut buffer = vec![0; COPY_CHUNK_SIZE];
+        let mut copied = 0;
+        loop {
+            let read = match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            destination.write_all(&buffer[..read])?;
+            copied += read as u64;
+            progress(copied, total.max(copied))?;
+        }
+        destination.set_permissions(metadata.permissions())?;
+        Ok(copied)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        fs::read(extended_path(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        fs::write(extended_path(path), contents)
+    }
+
+    fn sync(&self, path: &Path) -> std::io::Result<()> {
+        // Windows requires write access to flush a file
+        fs::OpenOptions::new()
+            .write(true)
+            .open(extended_path(path))?
+            .sync_all()?;
+
+        #[cfg(unix)]
+        if let Some(parent) = path.parent() {
+            fs::File::open(parent)?.sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> std::io::Result<()> {
+        let path = extended_path(path);
+        // Symlinks are removed themselves, never what they point to
+        if fs::symlink_metadata(&path)?.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        }
+    }
+
+    fn open_for_read(&self, path: &Path) -> std::io::Result<()> {
+        fs::File::open(extended_path(path)).map(|_| ())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        fs::rename(extended_path(from), extended_path(to))
+    }
+
+    // The Windows Recycle Bin, or the Trash on macOS and freedesktop.org desktops
+    fn recycle(&self, path: &Path) -> std::io::Result<()> {
+        trash::delete(path).map_err(std::io::Error::other)
+    }
+
+    fn hard_link(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        fs::hard_link(extended_path(from), extended_path(to))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn reflink(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let source = fs::File::open(from)?;
+        let destination = fs::File::create(to)?;
+        // SAFETY: both descriptors stay open for the duration of the call
+        let result =
+            unsafe { libc::ioctl(destination.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+        if result == -1 {
+            let error = std::io::Error::last_os_error();
+            drop(destination);
+            let _ = fs::remove_file(to);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn reflink(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let from = CString::new(from.as_os_str().as_bytes())?;
+        let to = CString::new(to.as_os_str().as_bytes())?;
+        // SAFETY: both are NUL-terminated paths that outlive the call
+        if unsafe { libc::clonefile(from.as_ptr(), to.as_ptr(), 0) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Block cloning, which ReFS and Dev Drives support; NTFS fails and the file is copied
+    #[cfg(windows)]
+    fn reflink(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        use std::os::windows::fs::MetadataExt;
+
+        const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x200;
+        let (from, to) = (extended_path(from), extended_path(to));
+        let source = fs::File::open(&from)?;
+        let metadata = source.metadata()?;
+        // Clones of sparse files must be sparse themselves, which replays never are
+        if metadata.file_attributes() & FILE_ATTRIBUTE_SPARSE_FILE != 0 {
+            return Err(std::io::ErrorKind::Unsupported.into());
+        }
+        let destination = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&to)?;
+        let result = destination
+            .set_len(metadata.len())
+            .and_then(|()| duplicate_extents(&source, &destination, &to, metadata.len()));
+        if result.is_err() {
+            drop(destination);
+            let _ = fs::remove_file(&to);
+        }
+        result
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        // Windows returns the extended form
+        fs::canonicalize(extended_path(path)).map(|path| regular_path(&path).into_owned())
+    }
+
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+        fs::read_link(extended_path(path))
+    }
+
+    // The target is stored as it is, so relative targets stay relative
+    fn create_symlink(&self, target: &Path, link: &Path, is_dir: bool) -> std::io::Result<()> {
+        let link = extended_path(link);
+        #[cfg(unix)]
+        {
+            let _ = is_dir;
+            std::os::unix::fs::symlink(target, link)
+        }
+
+        #[cfg(windows)]
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+    }
+
+    fn copy_times(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        let (from, to) = (extended_path(from), extended_path(to));
+        let metadata = fs::metadata(&from)?;
+        let times = fs::FileTimes::new()
+            .set_accessed(metadata.accessed()?)
+            .set_modified(metadata.modified()?);
+        let mut open = fs::OpenOptions::new();
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+            use std::os::windows::fs::{FileTimesExt, MetadataExt, OpenOptionsExt};
+            use windows_sys::Win32::Storage::FileSystem::{
+                SetFileAttributesW, FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_HIDDEN,
+                FILE_ATTRIBUTE_NOT_CONTENT_INDEXED, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM,
+                FILE_FLAG_BACKUP_SEMANTICS, FILE_WRITE_ATTRIBUTES,
+            };
+
+            let times = times.set_created(metadata.created()?);
+            // Changing times only needs this right, so read-only files and folders work too
+            open.access_mode(FILE_WRITE_ATTRIBUTES)
+                .custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+            open.open(&to)?.set_times(times)?;
+
+            // The attributes SetFileAttributesW can set; others such as compression are not
+            // attributes of the data and are left to the destination
+            let attributes = metadata.file_attributes()
+                & (FILE_ATTRIBUTE_ARCHIVE
+                    | FILE_ATTRIBUTE_HIDDEN
+                    | FILE_ATTRIBUTE_NOT_CONTENT_INDEXED
+                    | FILE_ATTRIBUTE_READONLY
+                    | FILE_ATTRIBUTE_SYSTEM);
+            let path: Vec<u16> = to.as_os_str().encode_wide().chain([0]).collect();
+            // SAFETY: `path` is a NUL-terminated wide string that outlives the call
+            if unsafe { SetFileAttributesW(path.as_ptr(), attributes) } == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        #[cfg(not(windows))]
+        {
+            // Setting times on a file descriptor only needs ownership, not write access
+            open.read(true).open(&to)?.set_times(times)
+        }
+    }
+
+    #[cfg(windows)]
+    fn available_space(&self, path: &Path) -> std::io::Result<u64> {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+        let path: Vec<u16> = extended_path(path)
+            .as_os_str()
+            .encode_wide()
+            .chain([0])
+            .collect();
+        let mut available = 0;
+        // SAFETY: `path` is a NUL-terminated wide string and the totals not asked for are null
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                path.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(available)
+    }
+
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_cast)]
+    fn available_space(&self, path: &Path) -> std::io::Result<u64> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: `path` is NUL-terminated and `stats` is only read after statvfs filled it
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // The field types differ between systems
+        Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+}
+
+// Outcome of backing up one path reported by the watcher
+pub struct PathBackup {
+    pub message: StatusEvent,
+    // None if the backup failed
+    pub totals: Option<CopyTotals>,
+    // Failed because another program, usually the game, held a file open
+    pub locked: bool,
+    // Stopped through `BackupOptions::cancel`; not an error and not retried
+    pub cancelled: bool,
+    // Not started because the destination is low on space; held until there is room
+    pub low_space: bool,
+}
+
+#[derive(Debug)]
+struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub fn is_cancelled_error(error: &std::io::Error) -> bool {
+    error.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+}
+
+// Whether an error means the file is in use and may well be readable a bit later
+pub fn is_locked_error(error: &std::io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    error.kind() == std::io::ErrorKind::PermissionDenied
+        || (cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33)))
+}
+
+impl PathBackup {
+    fn finished(
+        result: std::io::Result<CopyTotals>,
+        mut detail: EventDetail,
+        path: PathBuf,
+        success: impl FnOnce(EventDetail) -> StatusEvent,
+    ) -> Self {
+        match result {
+            Ok(totals) => {
+                detail.files = Some(totals.files);
+                detail.bytes = Some(totals.bytes);
+                Self {
+                    message: success(detail),
+                    totals: Some(totals),
+                    locked: false,
+                    cancelled: false,
+                    low_space: false,
+                }
+            }
+            Err(e) if is_cancelled_error(&e) => Self {
+                message: StatusEvent::Warning(format!("Cancelled backing up {}", path.display())),
+                totals: None,
+                locked: false,
+                cancelled: true,
+                low_space: false,
+            },
+            Err(e) => {
+                detail.error = Some(e.to_string());
+                Self {
+                    message: StatusEvent::CopyError {
+                        path,
+                        source: e.to_string(),
+                        detail: Some(detail),
+                    },
+                    totals: None,
+                    locked: is_locked_error(&e),
+                    cancelled: false,
+                    low_space: false,
+                }
+            }
+        }
+    }
+}
+
+// Backs up a single created or modified path. `.rec` files are copied to the same relative
+// location in the destination, folders are merged into the destination recursively.
+// Files whose backup has the same size and is at least as new are skipped. Returns None for
+// paths that are ignored or unchanged files, and in incremental mode for folders with nothing
+// to copy.
+pub fn backup_path(
+    fs: &dyn FileSystem,
+    path: &Path,
+    source_folder: &Path,
+    destination_folder: &Path,
+    options: &BackupOptions,
+) -> Option<PathBackup> {
+    backup_path_with_progress(
+        fs,
+        path,
+        source_folder,
+        destination_folder,
+        options,
+        &|_, _| {},
+        &|_, _, _| {},
+    )
+}
+
+// Like `backup_path`, reporting (files done, files total) while a match folder is archived
+// and (source file, bytes copied, total bytes) while a large file is copied
+pub fn backup_path_with_progress(
+    fs: &dyn FileSystem,
+    path: &Path,
+    source_folder: &Path,
+    destination_folder: &Path,
+    options: &BackupOptions,
+    progress: &dyn Fn(usize, usize),
+    file_progress: &(dyn Fn(&Path, u64, u64) + Sync),
+) -> Option<PathBackup> {
+    if options.is_excluded(path) {
+        return None;
+    }
+
+    let metadata = fs.metadata(path).ok()?;
+    // Recreated as a link rather than copied, also if it points to a folder
+    let link_copy = metadata.is_symlink && options.symlinks == SymlinkMode::Copy;
+    if metadata.is_symlink && !link_copy && !follows(fs, path, &metadata, options, &[]) {
+        return None;
+    }
+
+    if !metadata.is_dir && !options.file_filter.allows(path) {
+        return None;
+    }
+
+    // Any change inside a match folder rebuilds that match's archive
+    let storage = options.storage_backend();
+    if let Some(extension) = storage.archive_extension() {
+        let relative_path = path.strip_prefix(source_folder).ok()?;
+        let mut components = relative_path.components();
+        let first = components.next()?;
+        if metadata.is_dir || components.next().is_some() {
+            let match_folder = source_folder.join(first);
+            let folder_name = match_folder.file_name()?;
+            let archive = archive_path(destination_folder, folder_name, extension);
+            return archive_match(
+                fs,
+                &match_folder,
+                &archive,
+                storage.as_ref(),
+                options,
+                progress,
+            );
+        }
+    }
+
+    if !metadata.is_dir || link_copy {
+        // Calculate relative path from source to get the destination path
+        let relative_path = path.strip_prefix(source_folder).ok()?;
+        let dest_path = destination_folder.join(relative_path);
+
+        if !link_copy {
+            let changed = || !is_unchanged(fs, path, &dest_path, options);
+            match resolve_conflict(fs, &dest_path, options, changed) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    return Some(PathBackup {
+                        message: StatusEvent::CopyError {
+                            path: relative_path.to_path_buf(),
+                            source: format!("could not rename the earlier backup: {}", e),
+                            detail: None,
+                        },
+                        totals: None,
+                        locked: false,
+                        cancelled: false,
+                        low_space: false,
+                    });
+                }
+            }
+        }
+
+        // Ensure the parent directory exists
+        if let Some(parent) = dest_path.parent() {
+            if let Err(e) = fs.create_dir_all(parent) {
+                return Some(PathBackup {
+                    message: StatusEvent::CopyError {
+                        path: relative_path.to_path_buf(),
+                        source: format!("could not create parent directory: {}", e),
+                        detail: None,
+                    },
+                    totals: None,
+                    locked: false,
+                    cancelled: false,
+                    low_space: false,
+                });
+            }
+        }
+
+        // Copy the file (overwrite if it exists)
+        let started = Instant::now();
+        let result = if link_copy {
+            match copy_link(fs, path, &dest_path, metadata.is_dir) {
+                Ok(false) => return None,
+                copied => copied.map(|_| CopyTotals { files: 1, bytes: 0 }),
+            }
+        } else {
+            copy_file_with_progress(fs, path, &dest_path, options, &|copied, total| {
+                file_progress(path, copied, total)
+            })
+            .map(|bytes| CopyTotals { files: 1, bytes })
+        };
+        let detail = EventDetail {
+            source: path.to_path_buf(),
+            destination: dest_path,
+            files: None,
+            bytes: None,
+            duration: Some(started.elapsed()),
+            error: None,
+        };
+        Some(PathBackup::finished(
+            result,
+            detail,
+            relative_path.to_path_buf(),
+            |detail| StatusEvent::FileBackedUp {
+                path: relative_path.to_path_buf(),
+                detail,
+            },
+        ))
+    } else {
+        // Handle directory creation events (for initial folder backup)
+        let folder_name = path.file_name()?;
+        // Nested folders keep their place below the match folder
+        let dest_path = match path.strip_prefix(source_folder) {
+            Ok(relative_path) if !relative_path.as_os_str().is_empty() => {
+                destination_folder.join(relative_path)
+            }
+            _ => destination_folder.join(folder_name),
+        };
+
+        // Always merge folders - copy every new or changed file to destination
+        // This ensures new files are backed up even if the folder exists
+        let started = Instant::now();
+        let result = copy_directory_with_progress(fs, path, &dest_path, options, file_progress);
+        if options.incremental && matches!(result, Ok(totals) if totals.files == 0) {
+            return None;
+        }
+        let detail = EventDetail {
+            source: path.to_path_buf(),
+            destination: dest_path,
+            files: None,
+            bytes: None,
+            duration: Some(started.elapsed()),
+            error: None,
+        };
+        Some(PathBackup::finished(
+            result,
+            detail,
+            PathBuf::from(folder_name),
+            |detail| StatusEvent::FolderBackedUp {
+                name: folder_name.to_string_lossy().into_owned(),
+                detail,
+            },
+        ))
+    }
+}
+
+// Files of a match folder that pass the filter and exclusions, in a stable order
+// Archives only hold file contents, so links are followed or skipped but never kept as links.
+fn collect_files(
+    fs: &dyn FileSystem,
+    folder: &Path,
+    options: &BackupOptions,
+    files: &mut Vec<PathBuf>,
+    ancestors: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    let mut entries = fs.read_dir(folder)?;
+    entries.sort();
+    enter_folder(fs, folder, options, ancestors)?;
+    for path in entries {
+        if options.is_excluded(&path) {
+            continue;
+        }
+        let metadata = fs.metadata(&path)?;
+        if !follows(fs, &path, &metadata, options, ancestors) {
+            continue;
+        }
+        if metadata.is_dir {
+            collect_files(fs, &path, options, files, ancestors)?;
+        } else if options.file_filter.allows(&path) {
+            files.push(path);
+        }
+    }
+    leave_folder(options, ancestors);
+    Ok(())
+}
+
+// Keeps track of the folders a walk that follows links is in, to notice links leading back
+// into one of them
+fn enter_folder(
+    fs: &dyn FileSystem,
+    folder: &Path,
+    options: &BackupOptions,
+    ancestors: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    if options.symlinks == SymlinkMode::Follow {
+        ancestors.push(fs.canonicalize(folder)?);
+    }
+    Ok(())
+}
+
+fn leave_folder(options: &BackupOptions, ancestors: &mut Vec<PathBuf>) {
+    if options.symlinks == SymlinkMode::Follow {
+        ancestors.pop();
+    }
+}
+
+// Whether a walk over the source goes into `path`. Links are only followed in
+// `SymlinkMode::Follow`, and never when they are broken or lead back into one of the
+// `ancestors`, which would never end.
+fn follows(
+    fs: &dyn FileSystem,
+    path: &Path,
+    metadata: &FileMetadata,
+    options: &BackupOptions,
+    ancestors: &[PathBuf],
+) -> bool {
+    if !metadata.is_symlink {
+        return true;
+    }
+    if options.symlinks != SymlinkMode::Follow {
+        return false;
+    }
+    match fs.canonicalize(path) {
+        Ok(target) => !(metadata.is_dir && ancestors.contains(&target)),
+        Err(_) => false,
+    }
+}
+
+// Recreates the link `source` as `destination`, pointing to the same target. Relative
+// targets stay relative, so they resolve inside the destination. Returns false if the
+// destination already is the same link.
+fn copy_link(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    is_dir: bool,
+) -> std::io::Result<bool> {
+    let target = fs.read_link(source)?;
+    if fs
+        .read_link(destination)
+        .is_ok_and(|existing| existing == target)
+    {
+        return Ok(false);
+    }
+    let part = part_path(destination);
+    let _ = fs.remove(&part);
+    fs.create_symlink(&target, &part, is_dir)?;
+    if let Err(e) = fs.rename(&part, destination) {
+        let _ = fs.remove(&part);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+// Packs a whole match folder into `archive` using the configured storage backend
+fn archive_match(
+    fs: &dyn FileSystem,
+    folder: &Path,
+    archive: &Path,
+    storage: &dyn StorageBackend,
+    options: &BackupOptions,
+    progress: &dyn Fn(usize, usize),
+) -> Option<PathBackup> {
+    let folder_name = folder.file_name()?;
+    let mut files = Vec::new();
+    let started = Instant::now();
+    let result = collect_files(fs, folder, options, &mut files, &mut Vec::new()).and_then(|()| {
+        // Numbered copies are only kept of archives that are out of date
+        let compare = options.incremental || options.conflicts == ConflictPolicy::Rename;
+        let changed = || !(compare && is_archive_up_to_date(fs, &files, archive, options));
+        if !resolve_conflict(fs, archive, options, changed)? {
+            return Ok(None);
+        }
+        write_archive(fs, folder, &files, archive, storage, options, progress).map(Some)
+    });
+    let result = match result {
+        Ok(None) => return None,
+        Ok(Some(totals)) => Ok(totals),
+        Err(e) => Err(e),
+    };
+    let detail = EventDetail {
+        source: folder.to_path_buf(),
+        destination: options.stored_path(archive),
+        files: None,
+        bytes: None,
+        duration: Some(started.elapsed()),
+        error: None,
+    };
+    Some(PathBackup::finished(
+        result,
+        detail,
+        PathBuf::from(folder_name),
+        |detail| StatusEvent::FolderArchived {
+            name: archive
+                .file_name()
+                .unwrap_or(folder_name)
+                .to_string_lossy()
+                .into_owned(),
+            detail,
+        },
+    ))
+}
+
+// Whether the archive is at least as new as every file that would go into it
+fn is_archive_up_to_date(
+    fs: &dyn FileSystem,
+    files: &[PathBuf],
+    archive: &Path,
+    options: &BackupOptions,
+) -> bool {
+    files
+        .iter()
+        .all(|file| is_up_to_date(fs, file, archive, options))
+}
+
+fn write_archive(
+    fs: &dyn FileSystem,
+    folder: &Path,
+    files: &[PathBuf],
+    archive: &Path,
+    storage: &dyn StorageBackend,
+    options: &BackupOptions,
+    progress: &dyn Fn(usize, usize),
+) -> std::io::Result<CopyTotals> {
+    let mut builder = storage.new_archive()?;
+    let mut totals = CopyTotals::default();
+    for (index, file) in files.iter().enumerate() {
+        let relative_path = file.strip_prefix(folder).map_err(std::io::Error::other)?;
+        // Archive entry names always use forward slashes
+        let entry_name = relative_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        options.check_cancelled()?;
+        let data = fs.read(file)?;
+        // Kept in the archive, so unpacked replays sort by date like the originals
+        let modified = fs.metadata(file)?.modified.unwrap_or_else(SystemTime::now);
+        builder.add_file(&entry_name, &data, modified)?;
+        totals.files += 1;
+        totals.bytes += data.len() as u64;
+        progress(index + 1, files.len());
+    }
+    let bytes = builder.finish()?;
+
+    if let Some(parent) = archive.parent() {
+        fs.create_dir_all(parent)?;
+    }
+    write_file(fs, archive, &bytes, options)?;
+    Ok(totals)
+}
+
+// Splits the match folders and replay files directly inside the source folder into the
+// `limit` most recently modified ones and the number of older ones left out
+pub fn newest_source_items(
+    fs: &dyn FileSystem,
+    source_folder: &Path,
+    limit: Option<usize>,
+    options: &BackupOptions,
+) -> std::io::Result<(Vec<PathBuf>, usize)> {
+    let mut items = Vec::new();
+    for path in fs.read_dir(source_folder)? {
+        if options.is_excluded(&path) {
+            continue;
+        }
+        let metadata = fs.metadata(&path)?;
+        if metadata.is_dir || options.file_filter.allows(&path) {
+            items.push((metadata.modified, path));
+        }
+    }
+
+    // Newest first; entries without a modification time sort last
+    items.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    let limit = limit.unwrap_or(items.len());
+    let skipped = items.len().saturating_sub(limit);
+    items.truncate(limit);
+    Ok((items.into_iter().map(|(_, path)| path).collect(), skipped))
+}
+
+// Where the copy of a top-level source item goes in the destination
+fn stored_item(
+    fs: &dyn FileSystem,
+    path: &Path,
+    destination_folder: &Path,
+    options: &BackupOptions,
+) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    Some(match fs.metadata(path) {
+        Ok(metadata) if !metadata.is_dir => options.stored_path(&destination_folder.join(name)),
+        Ok(_) => match options.storage_backend().archive_extension() {
+            Some(extension) => {
+                options.stored_path(&archive_path(destination_folder, name, extension))
+            }
+            None => destination_folder.join(name),
+        },
+        Err(_) => destination_folder.join(name),
+    })
+}
+
+// Whether a top-level source item already has a copy in the destination
+pub fn is_already_backed_up(
+    fs: &dyn FileSystem,
+    path: &Path,
+    destination_folder: &Path,
+    options: &BackupOptions,
+) -> bool {
+    stored_item(fs, path, destination_folder, options)
+        .is_some_and(|target| fs.metadata(&target).is_ok())
+}
+
+// How often a path that is still being written is looked at again
+const STABLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// How many quiet periods a backup waits for a path to stop changing before it is retried
+// later instead
+pub const STABLE_WAIT_PERIODS: u32 = 5;
+
+// What the files under a path looked like at one moment, to tell whether they are still
+// being written
+#[derive(PartialEq, Eq)]
+struct WriteState {
+    bytes: u64,
+    newest: Option<SystemTime>,
+    readable: bool,
+}
+
+fn write_state(fs: &dyn FileSystem, path: &Path, options: &BackupOptions) -> Option<WriteState> {
+    let mut state = WriteState {
+        bytes: 0,
+        newest: None,
+        readable: true,
+    };
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        if options.is_excluded(&path) {
+            continue;
+        }
+        let metadata = fs.metadata(&path).ok()?;
+        if metadata.is_dir {
+            pending.extend(fs.read_dir(&path).ok()?);
+            continue;
+        }
+        if !options.file_filter.allows(&path) {
+            continue;
+        }
+        state.bytes += metadata.len;
+        state.newest = state.newest.max(metadata.modified);
+        state.readable &= fs.open_for_read(&path).is_ok();
+    }
+    Some(state)
+}
+
+// Whether the backup of a single file is at least as new as the source. Files without a
+// modification time are always treated as changed.
+pub fn is_up_to_date(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+) -> bool {
+    let Ok(source_metadata) = fs.metadata(source) else {
+        return false;
+    };
+    let Ok(backup_metadata) = fs.metadata(&options.stored_path(destination)) else {
+        return false;
+    };
+    match (source_metadata.modified, backup_metadata.modified) {
+        (Some(source_modified), Some(backup_modified)) => backup_modified >= source_modified,
+        _ => false,
+    }
+}
+
+// Whether the backup of a single file is up to date and, unless encrypted, the same size as
+// the source, so copying it again would write the same bytes
+pub fn is_unchanged(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+) -> bool {
+    if !is_up_to_date(fs, source, destination, options) {
+        return false;
+    }
+    // Encrypted copies carry a nonce and tag, so their size says nothing
+    if options.encryption.is_some() {
+        return true;
+    }
+    match (fs.metadata(source), fs.metadata(destination)) {
+        (Ok(source), Ok(backup)) => source.len == backup.len,
+        _ => false,
+    }
+}
+
+// `path` with ` (n)` added to its name before the extensions, e.g. `round-01 (2).rec`
+fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // A leading dot belongs to the name, not to an extension
+    let split = name
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| *c == '.')
+        .map_or(name.len(), |(index, _)| index);
+    let (stem, extensions) = name.split_at(split);
+    path.with_file_name(format!("{} ({}){}", stem, n, extensions))
+}
+
+// Whether a file or archive is written to `destination`, which may already hold a backup of
+// it, according to `options.conflicts`. `changed` tells whether that backup is out of date.
+// With `ConflictPolicy::Rename`, the earlier backup is moved aside to the first free numbered
+// name before it would be replaced.
+fn resolve_conflict(
+    fs: &dyn FileSystem,
+    destination: &Path,
+    options: &BackupOptions,
+    changed: impl FnOnce() -> bool,
+) -> std::io::Result<bool> {
+    let stored = options.stored_path(destination);
+    if fs.metadata(&stored).is_err() {
+        return Ok(true);
+    }
+    match options.conflicts {
+        ConflictPolicy::Skip => Ok(false),
+        ConflictPolicy::Overwrite => Ok(true),
+        ConflictPolicy::Merge => {
+            if !changed() {
+                return Ok(false);
+            }
+            if let Some(versions) = &options.versions {
+                versions.keep_version(fs, &stored, chrono::Local::now().naive_local())?;
+            }
+            Ok(true)
+        }
+        ConflictPolicy::Rename => {
+            if !changed() {
+                return Ok(false);
+            }
+            let mut n = 1;
+            let aside = loop {
+                let aside = options.stored_path(&numbered_path(destination, n));
+                if fs.metadata(&aside).is_err() {
+                    break aside;
+                }
+                n += 1;
+            };
+            fs.rename(&stored, &aside)?;
+            Ok(true)
+        }
+    }
+}
+
+// Backs up one source folder into one destination folder and keeps the shared statistics up
+// to date. The GUI drives it from the watcher thread; it has no dependency on egui.
+pub struct BackupEngine {
+    fs: Arc<dyn FileSystem + Send + Sync>,
+    source_folder: PathBuf,
+    // Changes when the destination's drive comes back under another path
+    destination: RwLock<PathBuf>,
+    // Set by `with_volume_tracking`
+    volume_id: Option<String>,
+    // The destination was not reachable the last time it was checked
+    offline: AtomicBool,
+    // Free space to leave on the destination's drive
+    min_free_space: Option<u64>,
+    // Free bytes the destination needs before held backups resume; 0 while none are held
+    space_needed: AtomicU64,
+    options: BackupOptions,
+    dedupe: bool,
+    // Set by `with_versions`: how many earlier versions of each changed file are kept
+    keep_versions: Option<usize>,
+    stats: Arc<Mutex<BackupStats>>,
+    retention: RetentionPolicy,
+    quota_bytes: Option<u64>,
+    quiet_period: Duration,
+    remotes: Vec<Remote>,
+    upload_queue: Option<Arc<UploadQueue>>,
+    journal: Option<Arc<BackupJournal>>,
+    post_backup_command: Option<String>,
+    // Set by `with_match_stats`: the r6-dissect program
+    dissect: Option<PathBuf>,
+    activity: Option<CopyActivity>,
+    // Set by `with_mirror_deletions`, with the grace period
+    deletions: Option<(Arc<DeletionQueue>, Duration)>,
+    // Set by `with_move_after_verify`
+    move_sources: bool,
+    // Set by `with_recycle_bin`
+    recycle_pruned: bool,
+    // Set by `with_snapshot`: the dated folder in the destination this run writes into
+    snapshot: Option<String>,
+    // Whether that folder has been started from the previous snapshot
+    snapshot_started: Mutex<bool>,
+}
+
+impl BackupEngine {
+    pub fn new(
+        source_folder: PathBuf,
+        destination_folder: PathBuf,
+        options: BackupOptions,
+    ) -> Self {
+        Self {
+            fs: Arc::new(RealFs),
+            source_folder,
+            destination: RwLock::new(destination_folder),
+            volume_id: None,
+            offline: AtomicBool::new(false),
+            min_free_space: None,
+            space_needed: AtomicU64::new(0),
+            options,
+            dedupe: false,
+            keep_versions: None,
+            recycle_pruned: false,
+            stats: Arc::new(Mutex::new(BackupStats::default())),
+            retention: RetentionPolicy::default(),
+            quota_bytes: None,
+            quiet_period: Duration::ZERO,
+            remotes: Vec::new(),
+            upload_queue: None,
+            journal: None,
+            post_backup_command: None,
+            dissect: None,
+            activity: None,
+            deletions: None,
+            move_sources: false,
+            snapshot: None,
+            snapshot_started: Mutex::new(false),
+        }
+    }
+
+    // Runs the engine against another filesystem, e.g. an in-memory fake in tests
+    pub fn with_fs(mut self, fs: Arc<dyn FileSystem + Send + Sync>) -> Self {
+        self.fs = fs;
+        self
+    }
+
+    // Records into statistics shared with someone else, e.g. the GUI
+    pub fn with_stats(mut self, stats: Arc<Mutex<BackupStats>>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    // Prunes old matches from the destination after every successful backup
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    // Holds backups that would leave less than `min_free_space` bytes free on the destination's
+    // drive, instead of failing halfway through. Backups that do not fit at all are always
+    // held.
+    pub fn with_min_free_space(mut self, min_free_space: Option<u64>) -> Self {
+        self.min_free_space = min_free_space;
+        self
+    }
+
+    // Keeps the destination below `quota_bytes` by deleting the least recently backed-up
+    // matches after every successful backup
+    pub fn with_quota(mut self, quota_bytes: Option<u64>) -> Self {
+        self.quota_bytes = quota_bytes;
+        self
+    }
+
+    // Only backs up paths that did not change for `quiet_period`, see `wait_until_stable`
+    pub fn with_quiet_period(mut self, quiet_period: Duration) -> Self {
+        self.quiet_period = quiet_period;
+        self
+    }
+
+    // Uploads every item after it was backed up to the destination
+    pub fn with_remotes(mut self, remotes: Vec<Remote>) -> Self {
+        self.remotes = remotes;
+        self
+    }
+
+    // Mirror mode: deletes the backup of a match deleted from the source once `grace` has
+    // passed, see `source_removed`. Deletions wait on `queue`, where they can be called off.
+    pub fn with_mirror_deletions(mut self, queue: Arc<DeletionQueue>, grace: Duration) -> Self {
+        self.deletions = Some((queue, grace));
+        self
+    }
+
+    // Deletes finished matches from the source once their backup is verified, to free space
+    // on the game's drive
+    pub fn with_move_after_verify(mut self, enabled: bool) -> Self {
+        self.move_sources = enabled;
+        self
+    }
+
+    // Backs up into the dated folder `name` inside the destination, which starts out with hard
+    // links to everything in the previous one, so each run keeps a point-in-time copy
+    pub fn with_snapshot(mut self, name: String) -> Self {
+        self.snapshot = Some(name);
+        self
+    }
+
+    // Remembers unfinished uploads so they can be retried, even after a restart
+    pub fn with_upload_queue(mut self, queue: Arc<UploadQueue>) -> Self {
+        self.upload_queue = Some(queue);
+        self
+    }
+
+    // Records every running backup so an interrupted one can be resumed after a restart
+    pub fn with_journal(mut self, journal: Arc<BackupJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    // Runs every filesystem operation through `throttle`, so backups stay below its limits
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.fs = Arc::new(ThrottledFs::new(Arc::clone(&self.fs), throttle));
+        self
+    }
+
+    // Stores identical files only once, in a store folder in the destination that the match
+    // folders hard-link to. Unreferenced files are deleted from the store after pruning.
+    pub fn with_dedupe(mut self, enabled: bool) -> Self {
+        self.dedupe = enabled;
+        self
+    }
+
+    // Moves pruned and evicted backups to the system's recycle bin instead of deleting them
+    // for good. Where that fails, e.g. on drives without one, they are deleted after all.
+    pub fn with_recycle_bin(mut self, enabled: bool) -> Self {
+        self.recycle_pruned = enabled;
+        self
+    }
+
+    // Moves the backup of a file that changed into a versions folder in the destination before
+    // replacing it, keeping the `keep` newest versions of each file. Only incremental updates
+    // (`ConflictPolicy::Merge`) replace files this way.
+    pub fn with_versions(mut self, keep: Option<usize>) -> Self {
+        self.keep_versions = keep.filter(|keep| *keep > 0);
+        self
+    }
+
+    // Recognizes the destination by an ID written into it, so backups resume when its drive is
+    // plugged back in under another drive letter or mount point, and an empty mount point is
+    // never mistaken for it. Without it, the destination counts as reachable while its folder
+    // exists.
+    pub fn with_volume_tracking(mut self) -> Self {
+        self.volume_id = ensure_volume_id(self.fs.as_ref(), &self.destination_root()).ok();
+        self
+    }
+
+    // Runs a shell command after every successful backup, with the SIEGESAVER_* variables
+    // describing it in its environment
+    pub fn with_post_backup_command(mut self, command: Option<String>) -> Self {
+        self.post_backup_command = command;
+        self
+    }
+
+    // Reads every backed-up match with the r6-dissect program at `program` and stores the
+    // rounds, kills and scoreboard it finds in the manifest entry of the match
+    pub fn with_match_stats(mut self, program: Option<PathBuf>) -> Self {
+        self.dissect = program;
+        self
+    }
+
+    // Counts every backup as running while it copies, e.g. for the tray icon
+    pub fn with_activity(mut self, activity: CopyActivity) -> Self {
+        self.activity = Some(activity);
+        self
+    }
+
+    pub fn source_folder(&self) -> &Path {
+        &self.source_folder
+    }
+
+    // The destination folder as configured, where its drive is now; see `check_destination`
+    pub fn destination_root(&self) -> PathBuf {
+        self.destination
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    // Where the backups are: the destination root, or with snapshots the one of this run once
+    // it started writing, and the latest one before
+    pub fn destination_folder(&self) -> PathBuf {
+        let root = self.destination_root();
+        let Some(name) = &self.snapshot else {
+            return root;
+        };
+        if *self
+            .snapshot_started
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+        {
+            return root.join(name);
+        }
+        latest_snapshot(self.fs.as_ref(), &root).unwrap_or_else(|| root.join(name))
+    }
+
+    // Where backups are written, starting the snapshot of this run if there is one. If that
+    // fails, e.g. while the drive is unplugged, it is tried again on the next write.
+    fn writable_destination(&self) -> PathBuf {
+        let root = self.destination_root();
+        let Some(name) = &self.snapshot else {
+            return root;
+        };
+        let mut started = self
+            .snapshot_started
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let root_exists = self
+            .fs
+            .metadata(&root)
+            .is_ok_and(|metadata| metadata.is_dir);
+        if !*started && root_exists {
+            match start_snapshot(self.fs.as_ref(), &root, name) {
+                Ok(_) => *started = true,
+                Err(e) => tracing::warn!("Error starting the snapshot {}: {}", name, e),
+            }
+        }
+        root.join(name)
+    }
+
+    // Whether backups can be written into the destination right now
+    pub fn destination_available(&self) -> bool {
+        let fs = self.fs.as_ref();
+        let destination = self.destination_root();
+        let Some(id) = &self.volume_id else {
+            return fs
+                .metadata(&destination)
+                .is_ok_and(|metadata| metadata.is_dir);
+        };
+        match read_volume_id(fs, &destination) {
+            Some(found) => found == *id,
+            // Deleted by hand; a folder with backups in it is still the destination
+            None if Manifest::exists(fs, &destination) => fs
+                .write(&destination.join(VOLUME_ID_FILE_NAME), id.as_bytes())
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    // Checks whether the destination is reachable, e.g. after a backup failed, and reports
+    // when it goes away and comes back. A destination whose drive came back under another
+    // path is followed there. Returns whether backups can run.
+    pub fn check_destination(&self, status_tx: &Sender<StatusEvent>) -> bool {
+        let available = self.destination_available() || self.follow_moved_destination(status_tx);
+        let was_offline = self.offline.swap(!available, Ordering::Relaxed);
+        let destination = self.destination_root();
+        match (was_offline, available) {
+            (false, false) => {
+                let _ = status_tx.send(StatusEvent::DestinationOffline { destination });
+            }
+            (true, true) => {
+                let _ = status_tx.send(StatusEvent::DestinationOnline { destination });
+            }
+            _ => {}
+        }
+        available
+    }
+
+    // Whether the destination was unreachable the last time it was checked
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    // Whether a backup is held because the destination's drive is too full
+    pub fn is_low_on_space(&self) -> bool {
+        self.space_needed.load(Ordering::Relaxed) > 0
+    }
+
+    // Checks whether the drive has enough room again for the backup that was held for lack of
+    // it, and reports when it has. Returns whether backups can run.
+    pub fn check_free_space(&self, status_tx: &Sender<StatusEvent>) -> bool {
+        let needed = self.space_needed.load(Ordering::Relaxed);
+        if needed == 0 {
+            return true;
+        }
+        let destination = self.destination_folder();
+        if self
+            .fs
+            .available_space(&destination)
+            .is_ok_and(|available| available < needed)
+        {
+            return false;
+        }
+        self.space_needed.store(0, Ordering::Relaxed);
+        let _ = status_tx.send(StatusEvent::Info(format!(
+            "{} has enough free space again, resuming backups",
+            destination.display()
+        )));
+        true
+    }
+
+    // Roughly how many bytes backing up `path` writes: the files that changed since their
+    // last backup, uncompressed
+    fn estimated_size(&self, path: &Path, destination_folder: &Path) -> std::io::Result<u64> {
+        let fs = self.fs.as_ref();
+        let mut files = Vec::new();
+        if fs.metadata(path)?.is_dir {
+            collect_files(fs, path, &self.options, &mut files, &mut Vec::new())?;
+        } else {
+            files.push(path.to_path_buf());
+        }
+        let mut size = 0;
+        for file in files {
+            let relative = file.strip_prefix(&self.source_folder).unwrap_or(&file);
+            if !is_unchanged(fs, &file, &destination_folder.join(relative), &self.options) {
+                size += fs.metadata(&file)?.len;
+            }
+        }
+        Ok(size)
+    }
+
+    // Holds the backup of `path` if it would leave the destination's drive with less free
+    // space than configured. Returns the warning to show.
+    fn missing_space(&self, path: &Path, destination_folder: &Path) -> Option<String> {
+        // Not every filesystem can tell; those are never held
+        let available = self.fs.available_space(destination_folder).ok()?;
+        let size = self.estimated_size(path, destination_folder).ok()?;
+        let needed = size.saturating_add(self.min_free_space.unwrap_or(0));
+        if available >= needed {
+            return None;
+        }
+        self.space_needed.store(needed, Ordering::Relaxed);
+        Some(format!(
+            "Only {} free in {}; holding the backup of {} ({}) until {} are free",
+            format_bytes(available),
+            destination_folder.display(),
+            path.strip_prefix(&self.source_folder)
+                .unwrap_or(path)
+                .display(),
+            format_bytes(size),
+            format_bytes(needed)
+        ))
+    }
+
+    fn follow_moved_destination(&self, status_tx: &Sender<StatusEvent>) -> bool {
+        let Some(id) = &self.volume_id else {
+            return false;
+        };
+        let from = self.destination_root();
+        let Some(to) = find_volume(self.fs.as_ref(), id, &from) else {
+            return false;
+        };
+        *self.destination.write().unwrap_or_else(|e| e.into_inner()) = to.clone();
+        let _ = status_tx.send(StatusEvent::DestinationMoved { from, to });
+        true
+    }
+
+    pub fn stats(&self) -> Arc<Mutex<BackupStats>> {
+        Arc::clone(&self.stats)
+    }
+
+    // Backs up a single created or modified path and records the outcome in the statistics.
+    // Archiving progress is reported on `status_tx`. Returns None for paths that are ignored
+    // or already up to date.
+    pub fn backup(&self, path: &Path, status_tx: &Sender<StatusEvent>) -> Option<PathBackup> {
+        self.backup_cancellable(path, status_tx, None)
+    }
+
+    // Like `backup`, stopping between files or chunks of a large file once `cancel` is set
+    pub fn backup_cancellable(
+        &self,
+        path: &Path,
+        status_tx: &Sender<StatusEvent>,
+        cancel: Option<&Arc<AtomicBool>>,
+    ) -> Option<PathBackup> {
+        let destination_folder = self.destination_root();
+        let _span = tracing::info_span!(
+            "backup",
+            path = %path.display(),
+            destination = %destination_folder.display()
+        )
+        .entered();
+        // Never fill an empty mount point in place of the unplugged drive
+        if self.volume_id.is_some() && !self.destination_available() {
+            tracing::debug!("Destination is not reachable");
+            return Some(PathBackup {
+                message: StatusEvent::CopyError {
+                    path: path
+                        .strip_prefix(&self.source_folder)
+                        .unwrap_or(path)
+                        .to_path_buf(),
+                    source: format!("{} is not reachable", destination_folder.display()),
+                    detail: None,
+                },
+                totals: None,
+                locked: false,
+                cancelled: false,
+                low_space: false,
+            });
+        }
+        let destination_folder = self.writable_destination();
+        if let Some(warning) = self.missing_space(path, &destination_folder) {
+            tracing::debug!("{}", warning);
+            return Some(PathBackup {
+                message: StatusEvent::Warning(warning),
+                totals: None,
+                locked: false,
+                cancelled: false,
+                low_space: true,
+            });
+        }
+        let mut options = Cow::Borrowed(&self.options);
+        if let Some(cancel) = cancel {
+            options.to_mut().cancel = Some(Arc::clone(cancel));
+        }
+        if self.dedupe {
+            options.to_mut().dedupe_store = Some(destination_folder.join(DEDUPE_STORE_NAME));
+        }
+        if let Some(keep) = self.keep_versions {
+            options.to_mut().versions = Some(KeptVersions {
+                folder: destination_folder.join(VERSIONS_FOLDER_NAME),
+                keep,
+            });
+        }
+        let _copying = self.activity.as_ref().map(CopyActivity::start);
+        let progress = |done: usize, total: usize| {
+            let name = path
+                .strip_prefix(&self.source_folder)
+                .ok()
+                .and_then(|relative| relative.components().next())
+                .map(|first| first.as_os_str().to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let _ = status_tx.send(StatusEvent::Progress { name, done, total });
+        };
+        let file_progress = |file: &Path, copied: u64, total: u64| {
+            let _ = status_tx.send(StatusEvent::CopyProgress {
+                path: file
+                    .strip_prefix(&self.source_folder)
+                    .unwrap_or(file)
+                    .to_path_buf(),
+                copied,
+                total,
+            });
+        };
+        // Recorded by the configured destination, which stays the same across snapshots
+        let job = PendingBackup {
+            path: path.to_path_buf(),
+            destination_folder: self.destination_root(),
+        };
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.add(&job) {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error updating the backup journal: {}",
+                    e
+                )));
+            }
+        }
+        let backup = backup_path_with_progress(
+            self.fs.as_ref(),
+            path,
+            &self.source_folder,
+            &destination_folder,
+            &options,
+            &progress,
+            &file_progress,
+        );
+        // Failed backups are retried by the watcher, so only a crash leaves the job behind
+        if let Some(journal) = &self.journal {
+            let _ = journal.remove(&job);
+        }
+        let Some(backup) = backup else {
+            tracing::debug!("Nothing to back up");
+            return None;
+        };
+        match &backup.totals {
+            Some(totals) => tracing::debug!(files = totals.files, bytes = totals.bytes, "Copied"),
+            None => tracing::debug!("Failed: {}", backup.message),
+        }
+        // Failed because the drive is gone; the watcher holds it until the drive is back
+        let held = backup.totals.is_none() && !self.destination_available();
+        if let Ok(mut stats) = self.stats.lock() {
+            match backup.totals {
+                Some(totals) => stats.record_backup(totals),
+                // Retried, stopped on purpose or held, so not an error
+                None if backup.locked || backup.cancelled || backup.low_space || held => {}
+                None => stats.errors += 1,
+            }
+        }
+        if backup.totals.is_some() {
+            if self.retention.is_enabled() {
+                self.prune(false, status_tx);
+            }
+            if let Err(e) = self.update_manifest(&backup, status_tx) {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error updating the backup manifest: {}",
+                    e
+                )));
+            }
+            self.record_match_stats(&backup, status_tx);
+            self.upload(&backup, status_tx);
+            self.run_post_backup_command(&backup, status_tx);
+            if self.move_sources {
+                self.move_verified_sources(status_tx);
+            }
+        }
+        Some(backup)
+    }
+
+    // Backs up again what the journal lists as started but never finished for this engine's
+    // source and destination, e.g. because the app crashed or the PC lost power mid-copy.
+    // Returns how many backups were resumed.
+    pub fn resume_interrupted(&self, status_tx: &Sender<StatusEvent>) -> usize {
+        let Some(journal) = &self.journal else {
+            return 0;
+        };
+        let mut resumed = 0;
+        for job in journal.pending() {
+            if job.destination_folder != self.destination_root()
+                || !job.path.starts_with(&self.source_folder)
+            {
+                continue;
+            }
+            match self.backup(&job.path, status_tx) {
+                Some(backup) => {
+                    resumed += 1;
+                    let _ = status_tx.send(backup.message);
+                }
+                // Gone from the source meanwhile
+                None => {
+                    let _ = journal.remove(&job);
+                }
+            }
+        }
+        if resumed > 0 {
+            let _ = status_tx.send(StatusEvent::Info(format!(
+                "Resumed {} interrupted backup{}",
+                resumed,
+                if resumed == 1 { "" } else { "s" }
+            )));
+        }
+        resumed
+    }
+
+    // Waits until the size and modification time of the files under `path` did not change
+    // for the quiet period and each of them can be opened, so a replay the game is still
+    // writing is not backed up cut short. Returns false if that did not happen within
+    // STABLE_WAIT_PERIODS quiet periods. Paths that are gone count as stable; backing them up
+    // reports that.
+    pub fn wait_until_stable(&self, path: &Path) -> bool {
+        if self.quiet_period.is_zero() {
+            return true;
+        }
+        let fs = self.fs.as_ref();
+        let started = Instant::now();
+        let mut last_state = None;
+        let mut unchanged_since = started;
+        loop {
+            let Some(state) = write_state(fs, path, &self.options) else {
+                return true;
+            };
+            let now = Instant::now();
+            // Files last written long ago need no watching
+            let untouched_for = state
+                .newest
+                .and_then(|newest| SystemTime::now().duration_since(newest).ok())
+                .unwrap_or_default();
+            let readable = state.readable;
+            if last_state.as_ref() != Some(&state) {
+                last_state = Some(state);
+                unchanged_since = now;
+            }
+            let quiet_for = untouched_for.max(now - unchanged_since);
+            if readable && quiet_for >= self.quiet_period {
+                return true;
+            }
+            if now - started >= self.quiet_period * STABLE_WAIT_PERIODS {
+                return false;
+            }
+            std::thread::sleep(STABLE_POLL_INTERVAL);
+        }
+    }
+
+    // Top-level source items changed since `since` that the manifest has no backup of, or only
+    // an older one, oldest first. Catches what the watcher missed; older items are left to
+    // the initial sync so matches removed by retention or the quota are not copied back.
+    pub fn unsynced_items(&self, since: SystemTime) -> std::io::Result<Vec<PathBuf>> {
+        let fs = self.fs.as_ref();
+        let manifest = Manifest::load(fs, &self.destination_folder())?;
+        let (items, _) = newest_source_items(fs, &self.source_folder, None, &self.options)?;
+        let mut unsynced = Vec::new();
+        for path in items.into_iter().rev() {
+            let Some(modified) = fs
+                .metadata(&path)
+                .ok()
+                .and_then(|metadata| metadata.modified)
+            else {
+                continue;
+            };
+            if modified < since {
+                continue;
+            }
+            let backed_up = stored_item(fs, &path, &self.destination_folder(), &self.options)
+                .and_then(|target| {
+                    let name = target.file_name()?.to_string_lossy().into_owned();
+                    manifest.entries.get(&name)
+                })
+                .map(|entry| SystemTime::UNIX_EPOCH + Duration::from_secs(entry.last_backup));
+            if backed_up.is_none_or(|backed_up| backed_up < modified) {
+                unsynced.push(path);
+            }
+        }
+        Ok(unsynced)
+    }
+
+    // Turns a burst of changed paths into the fewest backups that cover them, in order. With
+    // archive storage every path inside a match folder becomes that folder, as each would
+    // rebuild the same archive. A folder is copied with everything in it, so the paths inside
+    // it are dropped.
+    pub fn coalesce_paths(&self, paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+        let archives = self.options.storage_backend().archive_extension().is_some();
+        let jobs: BTreeSet<PathBuf> = paths
+            .into_iter()
+            .map(|path| {
+                let match_folder = path
+                    .strip_prefix(&self.source_folder)
+                    .ok()
+                    .and_then(|relative| relative.components().next())
+                    .map(|first| self.source_folder.join(first));
+                match match_folder {
+                    Some(match_folder) if archives => match_folder,
+                    _ => path,
+                }
+            })
+            .collect();
+        // Sorted, so a folder comes right before the paths inside it
+        let mut coalesced = Vec::new();
+        let mut folder: Option<PathBuf> = None;
+        for job in jobs {
+            if folder
+                .as_ref()
+                .is_some_and(|folder| job.starts_with(folder))
+            {
+                continue;
+            }
+            if job != self.source_folder {
+                folder = Some(job.clone());
+            }
+            coalesced.push(job);
+        }
+        coalesced
+    }
+
+    // The file or match folder `backup` wrote directly inside the destination
+    fn written_item(&self, backup: &PathBackup) -> Option<PathBuf> {
+        let detail = backup.message.detail()?;
+        let first = detail
+            .destination
+            .strip_prefix(self.destination_folder())
+            .ok()?
+            .components()
+            .next()?;
+        // Replay files directly in the destination may have been stored encrypted
+        let written = self.destination_folder().join(first);
+        if self.fs.metadata(&written).is_ok() {
+            Some(written)
+        } else {
+            Some(self.options.stored_path(&written))
+        }
+    }
+
+    // Copies what `backup` wrote to every remote destination. A failed upload is reported
+    // but does not fail the backup; it stays in the upload queue, if there is one.
+    fn upload(&self, backup: &PathBackup, status_tx: &Sender<StatusEvent>) {
+        if self.remotes.is_empty() {
+            return;
+        }
+        let Some(written) = self.written_item(backup) else {
+            return;
+        };
+        let name = written
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for remote in &self.remotes {
+            let event = match upload_item(
+                self.fs.as_ref(),
+                remote,
+                self.upload_queue.as_deref(),
+                &self.destination_folder(),
+                &written,
+            ) {
+                Ok(totals) => StatusEvent::Uploaded {
+                    name: name.clone(),
+                    remote: remote.label.clone(),
+                    files: totals.files,
+                    bytes: totals.bytes,
+                },
+                Err(e) => StatusEvent::Error(format!(
+                    "Error uploading {} to {}: {}",
+                    name, remote.label, e
+                )),
+            };
+            let _ = status_tx.send(event);
+        }
+    }
+
+    fn run_post_backup_command(&self, backup: &PathBackup, status_tx: &Sender<StatusEvent>) {
+        let (Some(command), Some(detail), Some(written)) = (
+            &self.post_backup_command,
+            backup.message.detail(),
+            self.written_item(backup),
+        ) else {
+            return;
+        };
+        let name = written
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let env = [
+            ("SIEGESAVER_MATCH_NAME", name.clone()),
+            ("SIEGESAVER_MATCH_DIR", written.display().to_string()),
+            ("SIEGESAVER_SOURCE_DIR", detail.source.display().to_string()),
+            (
+                "SIEGESAVER_DEST_DIR",
+                self.destination_folder().display().to_string(),
+            ),
+            ("SIEGESAVER_FILES", detail.files.unwrap_or(0).to_string()),
+            ("SIEGESAVER_BYTES", detail.bytes.unwrap_or(0).to_string()),
+        ];
+        if let Err(e) = run_hook(command, &env) {
+            let _ = status_tx.send(StatusEvent::Error(format!(
+                "Post-backup command for {} failed: {}",
+                name, e
+            )));
+        }
+    }
+
+    // The top-level item of the source folder that `path` is in, e.g. its match folder
+    fn source_item(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.source_folder)
+            .ok()
+            .and_then(|relative| relative.components().next())
+            .map(|first| self.source_folder.join(first))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    // Runs r6-dissect on the match `backup` came from, if `with_match_stats` is set, and
+    // records what it read in the manifest
+    fn record_match_stats(&self, backup: &PathBackup, status_tx: &Sender<StatusEvent>) {
+        let (Some(program), Some(detail), Some(written)) = (
+            &self.dissect,
+            backup.message.detail(),
+            self.written_item(backup),
+        ) else {
+            return;
+        };
+        let fs = self.fs.as_ref();
+        let source = self.source_item(&detail.source);
+        let Some(name) = written
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+        else {
+            return;
+        };
+        if !fs.metadata(&source).is_ok_and(|metadata| metadata.is_dir) || !has_replays(fs, &source)
+        {
+            return;
+        }
+        let recorded = dissect_match(program, &source).and_then(|stats| {
+            let destination = self.destination_folder();
+            let mut manifest = Manifest::load(fs, &destination)?;
+            if let Some(entry) = manifest.entries.get_mut(&name) {
+                entry.match_stats = Some(stats);
+                manifest.save(fs, &destination)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = recorded {
+            let _ = status_tx.send(StatusEvent::Warning(format!(
+                "Could not read the match statistics of {} with r6-dissect: {}",
+                name, e
+            )));
+        }
+    }
+
+    // Records what `backup` wrote in the destination's manifest. With a size limit, the least
+    // recently backed-up items are then deleted until the destination fits into it again.
+    fn update_manifest(
+        &self,
+        backup: &PathBackup,
+        status_tx: &Sender<StatusEvent>,
+    ) -> std::io::Result<()> {
+        let fs = self.fs.as_ref();
+        let (Some(detail), Some(written)) = (backup.message.detail(), self.written_item(backup))
+        else {
+            return Ok(());
+        };
+        let source = self.source_item(&detail.source);
+        let Some(name) = written
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+        else {
+            return Ok(());
+        };
+
+        let mut manifest = Manifest::load(fs, &self.destination_folder())?;
+        manifest.record_backup(
+            fs,
+            &self.destination_folder(),
+            &name,
+            &source,
+            SystemTime::now(),
+        )?;
+        manifest.write_checksums(fs, &self.destination_folder(), &name)?;
+        let evictions = match self.quota_bytes {
+            Some(quota_bytes) => select_evictions(&manifest, quota_bytes, &name),
+            None => Vec::new(),
+        };
+        let evicted_any = !evictions.is_empty();
+        for evicted in evictions {
+            let Some(entry) = manifest.entries.remove(&evicted) else {
+                continue;
+            };
+            match self.delete_backup(&self.destination_folder().join(&evicted), status_tx) {
+                Ok(()) => {
+                    let _ = status_tx.send(StatusEvent::Evicted {
+                        name: evicted,
+                        bytes: entry.bytes,
+                    });
+                }
+                // Deleted by hand in the meantime
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error deleting {} to stay under the size limit: {}",
+                        evicted, e
+                    )));
+                    manifest.entries.insert(evicted, entry);
+                }
+            }
+        }
+        manifest.save(fs, &self.destination_folder())?;
+        if evicted_any {
+            self.collect_garbage(&manifest, status_tx);
+        }
+        Ok(())
+    }
+
+    // Deletes the files in the dedupe store that no backup links to anymore
+    fn collect_garbage(&self, manifest: &Manifest, status_tx: &Sender<StatusEvent>) {
+        if !self.dedupe {
+            return;
+        }
+        match collect_garbage(self.fs.as_ref(), &self.destination_folder(), manifest) {
+            Ok((0, _)) => {}
+            Ok((blobs, bytes)) => {
+                let _ = status_tx.send(StatusEvent::Info(format!(
+                    "Removed {} unused files ({}) from the dedupe store",
+                    blobs,
+                    format_bytes(bytes)
+                )));
+            }
+            Err(e) => {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error cleaning up the dedupe store: {}",
+                    e
+                )));
+            }
+        }
+    }
+
+    // Re-hashes every file recorded in the destination's manifest, reporting each missing or
+    // damaged one on `status_tx` followed by a summary
+    pub fn verify(&self, status_tx: &Sender<StatusEvent>) -> VerifyReport {
+        let fs = self.fs.as_ref();
+        if !Manifest::exists(fs, &self.destination_folder()) {
+            let _ = status_tx.send(StatusEvent::Error(
+                "Error verifying backups: the destination has no manifest yet, back something \
+                 up first"
+                    .to_string(),
+            ));
+            return VerifyReport::default();
+        }
+        let manifest = match Manifest::load(fs, &self.destination_folder()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error verifying backups: {}",
+                    e
+                )));
+                return VerifyReport::default();
+            }
+        };
+
+        let report = manifest.verify(fs, &self.destination_folder());
+        for (path, problem) in &report.problems {
+            let _ = status_tx.send(StatusEvent::VerifyProblem {
+                path: path.clone(),
+                problem: *problem,
+            });
+        }
+        let _ = status_tx.send(StatusEvent::Verified(report.clone()));
+        report
+    }
+
+    // Copies the files `report` found missing or damaged again, from the source if they are
+    // still there. Match archives are rebuilt as a whole.
+    pub fn repair(&self, report: &VerifyReport, status_tx: &Sender<StatusEvent>) {
+        let fs = self.fs.as_ref();
+        let manifest = match Manifest::load(fs, &self.destination_folder()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error re-copying backups: {}",
+                    e
+                )));
+                return;
+            }
+        };
+        // Damaged copies look newer than their source, so nothing may be skipped as up to date
+        let options = BackupOptions {
+            incremental: false,
+            ..self.options.clone()
+        };
+
+        let mut repaired = Vec::new();
+        for (path, _) in &report.problems {
+            let Some(mut source) = manifest.source_of(path) else {
+                let _ = status_tx.send(StatusEvent::Warning(format!(
+                    "Cannot re-copy {}: its source is unknown",
+                    path
+                )));
+                continue;
+            };
+            if options.encryption.is_some()
+                && source
+                    .extension()
+                    .is_some_and(|ext| ext == ENCRYPTED_EXTENSION)
+            {
+                source.set_extension("");
+            }
+            if repaired.contains(&source) {
+                continue;
+            }
+            if fs.metadata(&source).is_err() {
+                let _ = status_tx.send(StatusEvent::Warning(format!(
+                    "Cannot re-copy {}: {} no longer exists",
+                    path,
+                    source.display()
+                )));
+                continue;
+            }
+            let Some(backup) = backup_path(
+                fs,
+                &source,
+                &self.source_folder,
+                &self.destination_folder(),
+                &options,
+            ) else {
+                let _ = status_tx.send(StatusEvent::Warning(format!(
+                    "Cannot re-copy {}: {} is not in the source folder",
+                    path,
+                    source.display()
+                )));
+                continue;
+            };
+            if backup.totals.is_some() {
+                if let Err(e) = self.update_manifest(&backup, status_tx) {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error updating the backup manifest: {}",
+                        e
+                    )));
+                }
+            }
+            let _ = status_tx.send(backup.message);
+            repaired.push(source);
+        }
+    }
+
+    // Deletes the matches in the destination the retention policy does not keep, reporting
+    // each one on `status_tx`. With `dry_run` nothing is deleted. Returns how many matches
+    // were (or would have been) deleted.
+    pub fn prune(&self, dry_run: bool, status_tx: &Sender<StatusEvent>) -> usize {
+        let fs = self.fs.as_ref();
+        let candidates = match prune_candidates(
+            fs,
+            &self.destination_folder(),
+            &self.retention,
+            SystemTime::now(),
+        ) {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error scanning destination folder for old backups: {}",
+                    e
+                )));
+                return 0;
+            }
+        };
+
+        let mut pruned = Vec::new();
+        for path in candidates {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if !dry_run {
+                if let Err(e) = self.delete_backup(&path, status_tx) {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error deleting old backup {}: {}",
+                        name, e
+                    )));
+                    continue;
+                }
+            }
+            pruned.push(name.clone());
+            let _ = status_tx.send(StatusEvent::Pruned { name, dry_run });
+        }
+
+        if !dry_run && !pruned.is_empty() {
+            let updated =
+                Manifest::load(fs, &self.destination_folder()).and_then(|mut manifest| {
+                    for name in &pruned {
+                        manifest.entries.remove(name);
+                    }
+                    manifest.save(fs, &self.destination_folder())?;
+                    self.collect_garbage(&manifest, status_tx);
+                    Ok(())
+                });
+            if let Err(e) = updated {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error updating the backup manifest: {}",
+                    e
+                )));
+            }
+        }
+        pruned.len()
+    }
+
+    // Deletes a pruned or evicted backup, moving it to the recycle bin if `with_recycle_bin`
+    // is set
+    fn delete_backup(&self, path: &Path, status_tx: &Sender<StatusEvent>) -> std::io::Result<()> {
+        if self.recycle_pruned && self.fs.metadata(path).is_ok() {
+            match self.fs.recycle(path) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Warning(format!(
+                        "Could not move {} to the recycle bin, deleting it instead: {}",
+                        path.display(),
+                        e
+                    )));
+                }
+            }
+        }
+        self.fs.remove(path)
+    }
+
+    // Deletes the finished top-level items from the source whose backup is verified, see
+    // `check_moved_item`. The newest item is never deleted, as the game may still be recording
+    // it. Returns how many were deleted.
+    pub fn move_verified_sources(&self, status_tx: &Sender<StatusEvent>) -> usize {
+        let fs = self.fs.as_ref();
+        let destination = self.destination_folder();
+        let (items, manifest) =
+            match newest_source_items(fs, &self.source_folder, None, &self.options)
+                .and_then(|(items, _)| Ok((items, Manifest::load(fs, &destination)?)))
+            {
+                Ok(found) => found,
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error looking for verified matches to delete from the source: {}",
+                        e
+                    )));
+                    return 0;
+                }
+            };
+        let mut moved = 0;
+        for path in items.iter().skip(1) {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if let Err(reason) = self.check_moved_item(&manifest, path) {
+                tracing::debug!("Keeping {} in the source: {}", name, reason);
+                continue;
+            }
+            match fs.remove(path) {
+                Ok(()) => {
+                    moved += 1;
+                    let _ = status_tx.send(StatusEvent::Info(format!(
+                        "Deleted {} from the source folder, its backup in {} is verified",
+                        name,
+                        destination.display()
+                    )));
+                }
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error deleting {} from the source folder: {}",
+                        name, e
+                    )));
+                }
+            }
+        }
+        moved
+    }
+
+    // Whether the top-level source item `path` may be deleted: every file in it is backed up,
+    // unchanged since, and reads back from the destination with its recorded checksum. For
+    // plain copies the source files are compared with those checksums too. Returns why not.
+    fn check_moved_item(&self, manifest: &Manifest, path: &Path) -> Result<(), String> {
+        let fs = self.fs.as_ref();
+        let destination = self.destination_folder();
+        let name = stored_item(fs, path, &destination, &self.options)
+            .and_then(|target| Some(target.file_name()?.to_string_lossy().into_owned()))
+            .ok_or("it has no name")?;
+        let entry = manifest
+            .entries
+            .get(&name)
+            .filter(|entry| entry.source.as_deref() == Some(path))
+            .ok_or("it is not backed up")?;
+
+        let mut files = Vec::new();
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let metadata = fs.metadata(&current).map_err(|e| e.to_string())?;
+            if metadata.is_symlink || self.options.is_excluded(&current) {
+                return Err(format!("{} is not backed up", current.display()));
+            }
+            if metadata.is_dir {
+                pending.extend(fs.read_dir(&current).map_err(|e| e.to_string())?);
+            } else if self.options.file_filter.allows(&current) {
+                files.push((current, metadata.modified));
+            } else {
+                return Err(format!("{} is not backed up", current.display()));
+            }
+        }
+        let last_backup = SystemTime::UNIX_EPOCH + Duration::from_secs(entry.last_backup);
+        if files
+            .iter()
+            .any(|(_, modified)| modified.is_none_or(|modified| modified > last_backup))
+        {
+            return Err("it changed since its last backup".to_string());
+        }
+
+        for (key, record) in &entry.files {
+            let data = fs
+                .read(&destination.join(key))
+                .map_err(|e| format!("{} cannot be read back: {}", key, e))?;
+            if sha256_hex(&data) != record.sha256 {
+                return Err(format!("{} does not match its checksum", key));
+            }
+        }
+        // Archives and encrypted files are stored as other bytes than the source's
+        let plain = self.options.encryption.is_none()
+            && self.options.storage_backend().archive_extension().is_none();
+        if plain {
+            for (file, _) in &files {
+                let key = file
+                    .strip_prefix(&self.source_folder)
+                    .map_err(|e| e.to_string())?
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let data = fs.read(file).map_err(|e| e.to_string())?;
+                if entry.files.get(&key).map(|record| &record.sha256) != Some(&sha256_hex(&data)) {
+                    return Err(format!("{} differs from its backup", key));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Queues the deletion of the backup of `path`, a top-level source item that was deleted,
+    // in mirror mode. Matches the game deletes to make room are kept, see `is_game_cleanup`.
+    pub fn source_removed(&self, path: &Path, status_tx: &Sender<StatusEvent>) {
+        let Some((queue, grace)) = &self.deletions else {
+            return;
+        };
+        let fs = self.fs.as_ref();
+        // Paths inside a match, and items that are back already, are left alone
+        if path.parent() != Some(self.source_folder.as_path()) || fs.metadata(path).is_ok() {
+            return;
+        }
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            return;
+        };
+        let Ok(remaining) = fs.read_dir(&self.source_folder) else {
+            return;
+        };
+        let remaining: Vec<String> = remaining
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        if is_game_cleanup(&name, &remaining) {
+            tracing::debug!("Keeping the backup of {}, the game cleaned it up", name);
+            return;
+        }
+        let destination = self.destination_folder();
+        let Ok(manifest) = Manifest::load(fs, &destination) else {
+            return;
+        };
+        let Some(stored) = manifest
+            .entries
+            .iter()
+            .find(|(_, entry)| entry.source.as_deref() == Some(path))
+            .map(|(stored, _)| stored.clone())
+        else {
+            return;
+        };
+        let _ = status_tx.send(StatusEvent::Info(format!(
+            "{} was deleted from the source; its backup in {} is moved to the trash in {} \
+             minutes unless you keep it",
+            name,
+            destination.display(),
+            grace.as_secs() / 60
+        )));
+        queue.push(PendingDeletion {
+            source_path: path.to_path_buf(),
+            destination_folder: self.destination_root(),
+            name: stored,
+            due: SystemTime::now() + *grace,
+        });
+    }
+
+    // Moves the backups whose deletion is due into the destination's trash
+    pub fn run_due_deletions(&self, status_tx: &Sender<StatusEvent>) {
+        let Some((queue, _)) = &self.deletions else {
+            return;
+        };
+        let fs = self.fs.as_ref();
+        // Queued by the configured destination, which stays the same across snapshots
+        let due = queue.take_due(&self.destination_root(), SystemTime::now());
+        if due.is_empty() {
+            return;
+        }
+        let destination = self.writable_destination();
+        for deletion in due {
+            // Restored or recreated in the meantime
+            if fs.metadata(&deletion.source_path).is_ok() {
+                continue;
+            }
+            match move_to_trash(fs, &destination, &deletion.name, SystemTime::now()) {
+                Ok(_) => {
+                    let _ = status_tx.send(StatusEvent::Info(format!(
+                        "Moved the backup {} to the trash in {}, as its match was deleted from \
+                         the source",
+                        deletion.name,
+                        destination.display()
+                    )));
+                }
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error deleting the backup {}: {}",
+                        deletion.name, e
+                    )));
+                }
+            }
+        }
+    }
+
+    // Backs up items that already existed in the source before watching started. Items already
+    // in the destination are skipped, or topped up with changed files in incremental mode. With a
+    // `limit`, only the newest items are considered so users with a large history only get
+    // recent matches instead of everything. Matches the retention policy would prune right
+    // away are skipped as well.
+    pub fn initial_sync(&self, limit: Option<usize>, status_tx: &Sender<StatusEvent>) {
+        let fs = self.fs.as_ref();
+        let options = &self.options;
+        // The destination may have changed while nothing was watching it
+        let refreshed = Manifest::load(fs, &self.destination_folder()).and_then(|mut manifest| {
+            manifest.refresh(fs, &self.destination_folder())?;
+            manifest.save(fs, &self.destination_folder())?;
+            self.collect_garbage(&manifest, status_tx);
+            Ok(())
+        });
+        if let Err(e) = refreshed {
+            let _ = status_tx.send(StatusEvent::Error(format!(
+                "Error updating the backup manifest: {}",
+                e
+            )));
+        }
+        match empty_trash(
+            fs,
+            &self.destination_folder(),
+            SystemTime::now(),
+            TRASH_KEEP,
+        ) {
+            Ok(0) => {}
+            Ok(emptied) => {
+                let _ = status_tx.send(StatusEvent::Info(format!(
+                    "Deleted {} backups that were in the trash for more than {} days",
+                    emptied, TRASH_KEEP_DAYS
+                )));
+            }
+            Err(e) => {
+                let _ = status_tx.send(StatusEvent::Error(format!(
+                    "Error emptying the trash: {}",
+                    e
+                )));
+            }
+        }
+        let limit = match (limit, self.retention.keep_last) {
+            (Some(limit), Some(keep_last)) => Some(limit.min(keep_last)),
+            (limit, keep_last) => limit.or(keep_last),
+        };
+        let (items, mut skipped) =
+            match newest_source_items(fs, &self.source_folder, limit, options) {
+                Ok(selection) => selection,
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Error(format!(
+                        "Error scanning source folder: {}",
+                        e
+                    )));
+                    return;
+                }
+            };
+
+        let mut synced = 0;
+        let mut already_present = 0;
+        let now = SystemTime::now();
+        for path in &items {
+            let modified = fs
+                .metadata(path)
+                .ok()
+                .and_then(|metadata| metadata.modified);
+            if modified.is_some_and(|modified| self.retention.is_expired(modified, now)) {
+                skipped += 1;
+                continue;
+            }
+            if !options.incremental
+                && is_already_backed_up(fs, path, &self.destination_folder(), options)
+            {
+                already_present += 1;
+                continue;
+            }
+            // Items are pre-filtered, so no backup here means everything was already up to date
+            let Some(backup) = self.backup(path, status_tx) else {
+                already_present += 1;
+                continue;
+            };
+            if backup.totals.is_some() {
+                synced += 1;
+            }
+            let _ = status_tx.send(backup.message);
+            // The rest would not fit either; rescans pick it up once there is room
+            if backup.low_space {
+                break;
+            }
+        }
+
+        let _ = status_tx.send(StatusEvent::InitialSync {
+            backed_up: synced,
+            already_present,
+            skipped,
+        });
+    }
+}
+
+// How often a copy is attempted before a checksum mismatch is reported as an error
+pub const VERIFY_ATTEMPTS: usize = 3;
+
+fn checksum_mismatch(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!(
+            "checksum mismatch in {} after {} attempts",
+            path.display(),
+            VERIFY_ATTEMPTS
+        ),
+    )
+}
+
+// Copies a single file, optionally forcing the data (and on Unix the new directory entry)
+// to disk so the backup survives a power loss
+pub fn copy_file(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+) -> std::io::Result<u64> {
+    copy_file_with_progress(fs, source, destination, options, &|_, _| {})
+}
+
+// Like `copy_file`, calling `progress` with (bytes copied, total bytes) while a large file
+// is copied
+pub fn copy_file_with_progress(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+    progress: &dyn Fn(u64, u64),
+) -> std::io::Result<u64> {
+    options.check_cancelled()?;
+    let bytes = put_file(fs, source, destination, options, progress)?;
+    // Backups keep the dates of their replays, so they sort the same way. Not every
+    // filesystem can store all of them, which is no reason to fail the backup.
+    let _ = fs.copy_times(source, &options.stored_path(destination));
+    Ok(bytes)
+}
+
+// Gets `source` into the destination by encrypting, deduplicating, linking or copying it
+fn put_file(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+    progress: &dyn Fn(u64, u64),
+) -> std::io::Result<u64> {
+    if options.encryption.is_some() {
+        let plaintext = fs.read(source)?;
+        write_file(fs, destination, &plaintext, options)?;
+        return Ok(plaintext.len() as u64);
+    }
+    if let Some(store) = &options.dedupe_store {
+        if let Some(bytes) = dedupe_file(fs, source, destination, store, options)? {
+            return Ok(bytes);
+        }
+    }
+    if let Some(bytes) = link_file(fs, source, destination, options) {
+        return Ok(bytes);
+    }
+
+    let source_hash = if options.verify_copies {
+        Some(sha256_hex(&fs.read(source)?))
+    } else {
+        None
+    };
+    let part = part_path(destination);
+    let copied = copy_verified(fs, source, &part, source_hash.as_deref(), options, progress);
+    commit_part(fs, &part, destination, options, copied)
+}
+
+fn copy_verified(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    source_hash: Option<&str>,
+    options: &BackupOptions,
+    progress: &dyn Fn(u64, u64),
+) -> std::io::Result<u64> {
+    let chunk_done = |copied, total| {
+        progress(copied, total);
+        options.check_cancelled()
+    };
+    for _ in 0..VERIFY_ATTEMPTS {
+        let bytes = fs.copy_with_progress(source, destination, &chunk_done)?;
+        if options.fsync_writes {
+            fs.sync(destination)?;
+        }
+        match source_hash {
+            Some(source_hash) if source_hash != sha256_hex(&fs.read(destination)?) => continue,
+            _ => return Ok(bytes),
+        }
+    }
+    Err(checksum_mismatch(destination))
+}
+
+// Links or clones `source` to `destination` as configured. Returns None if that is not
+// possible, so the caller copies the file instead.
+fn link_file(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+) -> Option<u64> {
+    let link: fn(&dyn FileSystem, &Path, &Path) -> std::io::Result<()> = match options.copy_mode {
+        CopyMode::Copy => return None,
+        CopyMode::Reflink => |fs, from, to| fs.reflink(from, to),
+        CopyMode::Hardlink => |fs, from, to| fs.hard_link(from, to),
+    };
+    let bytes = fs.metadata(source).ok()?.len;
+    let part = part_path(destination);
+    // Left over from an interrupted backup; linking does not replace it
+    let _ = fs.remove(&part);
+    link(fs, source, &part).ok()?;
+    let committed = commit_part(fs, &part, destination, options, Ok(bytes)).ok();
+    // Renaming onto another link to the same file leaves the part file in place
+    let _ = fs.remove(&part);
+    committed
+}
+
+// Links `destination` to the blob of `source` in the dedupe store, storing the blob first if
+// no file with the same contents was backed up yet. Returns None if linking is not possible,
+// e.g. on FAT drives, so the caller copies the file instead.
+fn dedupe_file(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    store: &Path,
+    options: &BackupOptions,
+) -> std::io::Result<Option<u64>> {
+    let data = fs.read(source)?;
+    let blob = blob_path(store, &sha256_hex(&data));
+    let stored = fs.metadata(&blob).is_err();
+    if stored {
+        if let Some(parent) = blob.parent() {
+            fs.create_dir_all(parent)?;
+        }
+        // Written from what was hashed, so the blob always matches its name
+        write_file(fs, &blob, &data, options)?;
+    }
+    let part = part_path(destination);
+    let _ = fs.remove(&part);
+    if fs.hard_link(&blob, &part).is_err() {
+        if stored {
+            let _ = fs.remove(&blob);
+        }
+        return Ok(None);
+    }
+    let committed = commit_part(fs, &part, destination, options, Ok(data.len() as u64));
+    let _ = fs.remove(&part);
+    committed.map(Some)
+}
+
+// Files are written under this extension first and only get their name once complete
+pub const PART_EXTENSION: &str = "part";
+
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(PART_EXTENSION);
+    PathBuf::from(name)
+}
+
+// Gives a completely written `.part` file its final name, so an interrupted copy (a crash,
+// a power loss or a full disk) never leaves a cut-off file under a name that looks like a
+// valid backup. The part file is removed if writing it failed.
+fn commit_part<T>(
+    fs: &dyn FileSystem,
+    part: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+    written: std::io::Result<T>,
+) -> std::io::Result<T> {
+    let committed = written.and_then(|value| {
+        fs.rename(part, destination)?;
+        if options.fsync_writes {
+            // The new directory entry
+            fs.sync(destination)?;
+        }
+        Ok(value)
+    });
+    if committed.is_err() {
+        let _ = fs.remove(part);
+    }
+    committed
+}
+
+// Writes data produced in memory to `destination`, encrypting and flushing it like `copy_file`
+fn write_file(
+    fs: &dyn FileSystem,
+    destination: &Path,
+    data: &[u8],
+    options: &BackupOptions,
+) -> std::io::Result<()> {
+    let written_path = options.stored_path(destination);
+    let stored = match &options.encryption {
+        Some(key) => key.encrypt(data).map_err(std::io::Error::other)?,
+        None => data.to_vec(),
+    };
+    let expected_hash = options.verify_copies.then(|| sha256_hex(&stored));
+    let part = part_path(&written_path);
+    let written = write_verified(fs, &part, &stored, expected_hash.as_deref(), options);
+    commit_part(fs, &part, &written_path, options, written)
+}
+
+fn write_verified(
+    fs: &dyn FileSystem,
+    destination: &Path,
+    data: &[u8],
+    expected_hash: Option<&str>,
+    options: &BackupOptions,
+) -> std::io::Result<()> {
+    for _ in 0..VERIFY_ATTEMPTS {
+        fs.write(destination, data)?;
+        if options.fsync_writes {
+            fs.sync(destination)?;
+        }
+        match expected_hash {
+            Some(expected) if expected != sha256_hex(&fs.read(destination)?) => continue,
+            _ => return Ok(()),
+        }
+    }
+    Err(checksum_mismatch(destination))
+}
+
+// Helper function to recursively copy a directory, returning how much was copied
+pub fn copy_directory_recursive(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+) -> std::io::Result<CopyTotals> {
+    copy_directory_with_progress(fs, source, destination, options, &|_, _, _| {})
+}
+
+fn copy_directory_with_progress(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+    progress: &(dyn Fn(&Path, u64, u64) + Sync),
+) -> std::io::Result<CopyTotals> {
+    let mut copies = Vec::new();
+    let mut folders = Vec::new();
+    collect_copies(
+        fs,
+        source,
+        destination,
+        options,
+        &mut copies,
+        &mut folders,
+        &mut Vec::new(),
+    )?;
+    let totals = copy_files(fs, &copies, options, progress)?;
+    // Only once nothing is written into them anymore, which would update their times again
+    for (source, destination) in folders {
+        let _ = fs.copy_times(&source, &destination);
+    }
+    Ok(totals)
+}
+
+// Upper limit for `BackupOptions::copy_threads`
+pub const MAX_COPY_THREADS: usize = 8;
+
+// Creates the folders below `destination` and lists the (source, destination) pairs of the
+// files that need copying
+fn collect_copies(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+    copies: &mut Vec<(PathBuf, PathBuf)>,
+    folders: &mut Vec<(PathBuf, PathBuf)>,
+    ancestors: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    fs.create_dir_all(destination)?;
+    folders.push((source.to_path_buf(), destination.to_path_buf()));
+    enter_folder(fs, source, options, ancestors)?;
+
+    for path in fs.read_dir(source)? {
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        if options.is_excluded(&path) {
+            continue;
+        }
+        let dest_path = destination.join(file_name);
+
+        let metadata = fs.metadata(&path)?;
+        if metadata.is_symlink && options.symlinks == SymlinkMode::Copy {
+            if metadata.is_dir || options.file_filter.allows(&path) {
+                copy_link(fs, &path, &dest_path, metadata.is_dir)?;
+            }
+        } else if !follows(fs, &path, &metadata, options, ancestors) {
+            continue;
+        } else if metadata.is_dir {
+            collect_copies(fs, &path, &dest_path, options, copies, folders, ancestors)?;
+        } else if options.file_filter.allows(&path)
+            && resolve_conflict(fs, &dest_path, options, || {
+                !is_unchanged(fs, &path, &dest_path, options)
+            })?
+        {
+            copies.push((path, dest_path));
+        }
+    }
+
+    leave_folder(options, ancestors);
+    Ok(())
+}
+
+// Copies `copies` with up to `options.copy_threads` workers taking the next file as soon as
+// they are done with one. Stops at the first error, like a serial copy would.
+fn copy_files(
+    fs: &dyn FileSystem,
+    copies: &[(PathBuf, PathBuf)],
+    options: &BackupOptions,
+    progress: &(dyn Fn(&Path, u64, u64) + Sync),
+) -> std::io::Result<CopyTotals> {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let worker = || -> std::io::Result<CopyTotals> {
+        let mut totals = CopyTotals::default();
+        while !failed.load(Ordering::Relaxed) {
+            let Some((source, destination)) = copies.get(next.fetch_add(1, Ordering::Relaxed))
+            else {
+                break;
+            };
+            if let Err(e) = options.check_cancelled() {
+                failed.store(true, Ordering::Relaxed);
+                return Err(e);
+            }
+            let file_progress = |copied, total| progress(source, copied, total);
+            match copy_file_with_progress(fs, source, destination, options, &file_progress) {
+                Ok(bytes) => {
+                    totals.files += 1;
+                    totals.bytes += bytes;
+                }
+                Err(e) => {
+                    failed.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(totals)
+    };
+
+    let threads = options
+        .copy_threads
+        .clamp(1, MAX_COPY_THREADS)
+        .min(copies.len());
+    if threads <= 1 {
+        return worker();
+    }
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads).map(|_| scope.spawn(worker)).collect();
+        let mut totals = CopyTotals::default();
+        let mut error = None;
+        for handle in workers {
+            match handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            {
+                Ok(copied) => {
+                    totals.files += copied.files;
+                    totals.bytes += copied.bytes;
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(totals),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror::trashed_backups;
+    use crate::versions::versions_of;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    enum FakeEntry {
+        Dir,
+        File(Vec<u8>),
+    }
+
+    // In-memory filesystem for hermetic, sleep-free tests of the copy engine
+    #[derive(Default)]
+    struct FakeFs {
+        entries: Mutex<BTreeMap<PathBuf, FakeEntry>>,
+        modified: Mutex<BTreeMap<PathBuf, SystemTime>>,
+        // Number of upcoming writes that silently store damaged data
+        corrupt_writes: Mutex<usize>,
+        // Files held open by someone else
+        locked: Mutex<BTreeSet<PathBuf>>,
+        // Free space of every folder; unknown if None
+        available_space: Mutex<Option<u64>>,
+    }
+
+    impl FakeFs {
+        fn add_file(&self, path: &str, contents: &str) {
+            let path = Path::new(path);
+            self.create_dir_all(path.parent().unwrap()).unwrap();
+            self.write(path, contents.as_bytes()).unwrap();
+        }
+
+        fn contents(&self, path: &str) -> Option<String> {
+            match self.entries.lock().unwrap().get(Path::new(path)) {
+                Some(FakeEntry::File(data)) => Some(String::from_utf8(data.clone()).unwrap()),
+                _ => None,
+            }
+        }
+
+        fn exists(&self, path: &str) -> bool {
+            self.entries.lock().unwrap().contains_key(Path::new(path))
+        }
+
+        fn set_locked(&self, path: &str, locked: bool) {
+            let mut locked_files = self.locked.lock().unwrap();
+            if locked {
+                locked_files.insert(PathBuf::from(path));
+            } else {
+                locked_files.remove(Path::new(path));
+            }
+        }
+
+        fn set_modified(&self, path: &str, modified: SystemTime) {
+            self.modified
+                .lock()
+                .unwrap()
+                .insert(PathBuf::from(path), modified);
+        }
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(io::ErrorKind::NotFound, path.display().to_string())
+    }
+
+    impl FileSystem for FakeFs {
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+            let entries = self.entries.lock().unwrap();
+            match entries.get(path) {
+                Some(FakeEntry::Dir) => Ok(entries
+                    .keys()
+                    .filter(|p| p.parent() == Some(path))
+                    .cloned()
+                    .collect()),
+                _ => Err(not_found(path)),
+            }
+        }
+
+        fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+            match self.entries.lock().unwrap().get(path) {
+                Some(entry) => Ok(FileMetadata {
+                    is_dir: matches!(entry, FakeEntry::Dir),
+                    modified: self.modified.lock().unwrap().get(path).copied(),
+                    len: match entry {
+                        FakeEntry::Dir => 0,
+                        FakeEntry::File(data) => data.len() as u64,
+                    },
+                    is_symlink: false,
+                }),
+                None => Err(not_found(path)),
+            }
+        }
+
+        fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            for ancestor in path.ancestors() {
+                match entries.get(ancestor) {
+                    Some(FakeEntry::File(_)) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::AlreadyExists,
+                            ancestor.display().to_string(),
+                        ))
+                    }
+                    Some(FakeEntry::Dir) => {}
+                    None => {
+                        entries.insert(ancestor.to_path_buf(), FakeEntry::Dir);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+            let data = self.read(from)?;
+            self.write(to, &data)?;
+            Ok(data.len() as u64)
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            if self.locked.lock().unwrap().contains(path) {
+                return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+            }
+            match self.entries.lock().unwrap().get(path) {
+                Some(FakeEntry::File(data)) => Ok(data.clone()),
+                _ => Err(not_found(path)),
+            }
+        }
+
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            let parent = path.parent().unwrap_or(Path::new(""));
+            if !matches!(entries.get(parent), Some(FakeEntry::Dir)) {
+                return Err(not_found(parent));
+            }
+            let mut contents = contents.to_vec();
+            let mut corrupt_writes = self.corrupt_writes.lock().unwrap();
+            if *corrupt_writes > 0 {
+                *corrupt_writes -= 1;
+                contents.push(b'!');
+            }
+            entries.insert(path.to_path_buf(), FakeEntry::File(contents));
+            Ok(())
+        }
+
+        fn sync(&self, path: &Path) -> io::Result<()> {
+            self.metadata(path).map(|_| ())
+        }
+
+        fn remove(&self, path: &Path) -> io::Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            if !entries.contains_key(path) {
+                return Err(not_found(path));
+            }
+            entries.retain(|entry, _| !entry.starts_with(path));
+            Ok(())
+        }
+
+        fn open_for_read(&self, path: &Path) -> io::Result<()> {
+            self.read(path).map(|_| ())
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            if !entries.contains_key(from) {
+                return Err(not_found(from));
+            }
+            // Folders are moved with everything in them
+            let moved: Vec<PathBuf> = entries
+                .keys()
+                .filter(|entry| entry.starts_with(from))
+                .cloned()
+                .collect();
+            for path in moved {
+                let entry = entries.remove(&path).unwrap();
+                let relative = path.strip_prefix(from).unwrap();
+                entries.insert(to.join(relative), entry);
+            }
+            let mut modified = self.modified.lock().unwrap();
+            if let Some(time) = modified.remove(from) {
+                modified.insert(to.to_path_buf(), time);
+            }
+            Ok(())
+        }
+
+        fn recycle(&self, path: &Path) -> io::Result<()> {
+            let name = path.file_name().ok_or_else(|| not_found(path))?;
+            self.rename(path, &Path::new("/recycle-bin").join(name))
+        }
+
+        fn available_space(&self, _path: &Path) -> io::Result<u64> {
+            let available = *self.available_space.lock().unwrap();
+            available.ok_or_else(|| io::ErrorKind::Unsupported.into())
+        }
+    }
+
+    #[test]
+    fn test_source_destinations() {
+        let dest = Path::new("/dest");
+        assert_eq!(
+            source_destinations(&[PathBuf::from("/a/MatchReplay")], dest),
+            [PathBuf::from("/dest")]
+        );
+        let sources = [
+            PathBuf::from("/c/Main/MatchReplay"),
+            PathBuf::from("/d/Smurf/MatchReplay"),
+            PathBuf::from("/e/Smurf/MatchReplay"),
+            PathBuf::from("/f/Other Game"),
+        ];
+        assert_eq!(
+            source_labels(&sources),
+            [
+                "Main_MatchReplay",
+                "Smurf_MatchReplay",
+                "Smurf_MatchReplay-2",
+                "Other Game"
+            ]
+        );
+        assert_eq!(
+            source_destinations(&sources[..2], dest),
+            [
+                PathBuf::from("/dest/Main_MatchReplay"),
+                PathBuf::from("/dest/Smurf_MatchReplay")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_folder_backup_with_fake_fs() {
+        let fs = FakeFs::default();
+        fs.add_file(
+            "/source/Match-2025-11-23-001/replay1.rec",
+            "replay content 1",
+        );
+        fs.add_file(
+            "/source/Match-2025-11-23-001/replay2.rec",
+            "replay content 2",
+        );
+        fs.add_file(
+            "/source/Match-2025-11-23-001/nested/replay3.rec",
+            "replay content 3",
+        );
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+
+        let backup = backup_path(
+            &fs,
+            Path::new("/source/Match-2025-11-23-001"),
+            Path::new("/source"),
+            Path::new("/dest"),
+            &BackupOptions::default(),
+        )
+        .expect("folders are backed up");
+
+        assert_eq!(
+            backup.totals,
+            Some(CopyTotals {
+                files: 3,
+                bytes: 48
+            })
+        );
+        assert_eq!(
+            backup.message.to_string(),
+            "Backed up folder: Match-2025-11-23-001"
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-2025-11-23-001/replay1.rec")
+                .as_deref(),
+            Some("replay content 1")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-2025-11-23-001/replay2.rec")
+                .as_deref(),
+            Some("replay content 2")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-2025-11-23-001/nested/replay3.rec")
+                .as_deref(),
+            Some("replay content 3")
+        );
+    }
+
+    #[test]
+    fn test_rec_extension_filter_with_fake_fs() {
+        let fs = FakeFs::default();
+        fs.add_file("/source/Match-2025-11-24-001/round1.rec", "round 1 data");
+        fs.add_file("/source/Match-2025-11-24-001/notes.txt", "not a replay");
+        fs.add_file("/elsewhere/round9.rec", "outside the source folder");
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let options = BackupOptions::default();
+        let backup = |path: &str| {
+            backup_path(
+                &fs,
+                Path::new(path),
+                Path::new("/source"),
+                Path::new("/dest"),
+                &options,
+            )
+        };
+
+        let copied = backup("/source/Match-2025-11-24-001/round1.rec").expect("replay copied");
+        assert_eq!(
+            copied.totals,
+            Some(CopyTotals {
+                files: 1,
+                bytes: 12
+            })
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-2025-11-24-001/round1.rec")
+                .as_deref(),
+            Some("round 1 data")
+        );
+
+        assert!(backup("/source/Match-2025-11-24-001/notes.txt").is_none());
+        assert!(!fs.exists("/dest/Match-2025-11-24-001/notes.txt"));
+        assert!(backup("/elsewhere/round9.rec").is_none());
+        assert!(backup("/source/missing.rec").is_none());
+    }
+
+    #[test]
+    fn test_copy_with_fsync() {
+        let test_dir = std::env::temp_dir().join("siegesaver_fsync_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("Match-2025-11-23-001");
+        let destination = test_dir.join("backup").join("Match-2025-11-23-001");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("round1.rec"), "round 1 data").unwrap();
+        fs::write(source.join("nested").join("round2.rec"), "round 2 data").unwrap();
+
+        let options = BackupOptions {
+            fsync_writes: true,
+            ..Default::default()
+        };
+        copy_directory_recursive(&RealFs, &source, &destination, &options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(destination.join("round1.rec")).unwrap(),
+            "round 1 data"
+        );
+        assert_eq!(
+            fs::read_to_string(destination.join("nested").join("round2.rec")).unwrap(),
+            "round 2 data"
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_large_files_are_copied_in_chunks() {
+        let test_dir = std::env::temp_dir().join("siegesaver_chunked_copy_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let destination = test_dir.join("backup");
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        let data: Vec<u8> = (0..CHUNKED_COPY_THRESHOLD + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let large = source.join("Match-A").join("round1.rec");
+        fs::write(&large, &data).unwrap();
+        fs::write(source.join("Match-A").join("round2.rec"), "small").unwrap();
+
+        let updates = Mutex::new(Vec::new());
+        let backup = backup_path_with_progress(
+            &RealFs,
+            &source.join("Match-A"),
+            &source,
+            &destination,
+            &BackupOptions::default(),
+            &|_, _| {},
+            &|file, copied, total| {
+                updates
+                    .lock()
+                    .unwrap()
+                    .push((file.to_path_buf(), copied, total))
+            },
+        )
+        .unwrap();
+        assert_eq!(backup.totals.unwrap().bytes, data.len() as u64 + 5);
+        assert_eq!(
+            fs::read(destination.join("Match-A").join("round1.rec")).unwrap(),
+            data
+        );
+
+        // Only the large file reports progress, once per chunk, ending with the whole file
+        let updates = updates.into_inner().unwrap();
+        assert_eq!(updates.len(), 9);
+        assert!(updates
+            .iter()
+            .all(|(file, _, total)| file == &large && *total == data.len() as u64));
+        assert!(updates.windows(2).all(|pair| pair[0].1 < pair[1].1));
+        assert_eq!(updates.last().unwrap().1, data.len() as u64);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_copies_keep_the_dates_of_their_source() {
+        let test_dir = std::env::temp_dir().join("siegesaver_times_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let destination = test_dir.join("backup");
+        let large = CHUNKED_COPY_THRESHOLD as usize + 1;
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        fs::write(source.join("Match-A").join("round1.rec"), "round 1").unwrap();
+        fs::write(source.join("Match-A").join("round2.rec"), vec![0; large]).unwrap();
+        let played = SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60);
+        for path in ["Match-A/round1.rec", "Match-A/round2.rec", "Match-A"] {
+            fs::File::open(source.join(path))
+                .unwrap()
+                .set_modified(played)
+                .unwrap();
+        }
+
+        let encrypted = BackupOptions {
+            encryption: Some(Arc::new(EncryptionKey::generate("secret").unwrap())),
+            ..Default::default()
+        };
+        for (options, folder) in [
+            (BackupOptions::default(), "plain"),
+            (encrypted, "encrypted"),
+        ] {
+            let destination = destination.join(folder);
+            backup_path(
+                &RealFs,
+                &source.join("Match-A"),
+                &source,
+                &destination,
+                &options,
+            )
+            .unwrap();
+            let modified = |path: &Path| fs::metadata(path).unwrap().modified().unwrap();
+            // Chunked copies and encrypted files too, and the match folder itself
+            let match_folder = destination.join("Match-A");
+            for file in ["round1.rec", "round2.rec"] {
+                let backed_up = options.stored_path(&match_folder.join(file));
+                assert_eq!(modified(&backed_up), played, "{}", backed_up.display());
+            }
+            assert_eq!(modified(&match_folder), played);
+            // Still counts as up to date, so it is not copied again
+            assert!(is_unchanged(
+                &RealFs,
+                &source.join("Match-A").join("round1.rec"),
+                &match_folder.join("round1.rec"),
+                &options
+            ));
+        }
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_handled_as_configured() {
+        use std::os::unix::fs::symlink;
+
+        let test_dir = std::env::temp_dir().join("siegesaver_symlink_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let match_folder = source.join("Match-A");
+        fs::create_dir_all(&match_folder).unwrap();
+        fs::write(match_folder.join("round1.rec"), "round 1").unwrap();
+        fs::write(test_dir.join("elsewhere.rec"), "elsewhere").unwrap();
+        symlink(
+            test_dir.join("elsewhere.rec"),
+            match_folder.join("round2.rec"),
+        )
+        .unwrap();
+        symlink(
+            test_dir.join("missing.rec"),
+            match_folder.join("round3.rec"),
+        )
+        .unwrap();
+        // Following this one would copy the match into itself forever
+        symlink(".", match_folder.join("loop")).unwrap();
+
+        for mode in [SymlinkMode::Skip, SymlinkMode::Copy, SymlinkMode::Follow] {
+            let destination = test_dir.join(format!("{:?}", mode));
+            let options = BackupOptions {
+                symlinks: mode,
+                ..Default::default()
+            };
+            let backup =
+                backup_path(&RealFs, &match_folder, &source, &destination, &options).unwrap();
+            assert!(backup.totals.is_some(), "{:?}", mode);
+            let backed_up = destination.join("Match-A");
+            assert_eq!(fs::read(backed_up.join("round1.rec")).unwrap(), b"round 1");
+            let is_link = |name: &str| {
+                fs::symlink_metadata(backed_up.join(name))
+                    .map(|metadata| metadata.file_type().is_symlink())
+                    .ok()
+            };
+            match mode {
+                SymlinkMode::Skip => {
+                    assert_eq!(is_link("round2.rec"), None);
+                    assert_eq!(is_link("round3.rec"), None);
+                    assert_eq!(is_link("loop"), None);
+                }
+                SymlinkMode::Copy => {
+                    assert_eq!(is_link("round2.rec"), Some(true));
+                    assert_eq!(is_link("round3.rec"), Some(true));
+                    assert_eq!(
+                        fs::read_link(backed_up.join("loop")).unwrap(),
+                        Path::new(".")
+                    );
+                }
+                SymlinkMode::Follow => {
+                    assert_eq!(is_link("round2.rec"), Some(false));
+                    assert_eq!(
+                        fs::read(backed_up.join("round2.rec")).unwrap(),
+                        b"elsewhere"
+                    );
+                    assert_eq!(is_link("round3.rec"), None);
+                    assert_eq!(is_link("loop"), None);
+                }
+            }
+        }
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_files_are_linked_where_possible() {
+        let test_dir = std::env::temp_dir().join("siegesaver_link_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        let replay = source.join("Match-A").join("round1.rec");
+        fs::write(&replay, "round 1").unwrap();
+
+        for (mode, folder) in [
+            (CopyMode::Hardlink, "hardlinked"),
+            // Falls back to a copy where the filesystem cannot clone
+            (CopyMode::Reflink, "cloned"),
+        ] {
+            let destination = test_dir.join(folder);
+            let options = BackupOptions {
+                copy_mode: mode,
+                ..Default::default()
+            };
+            let backup = backup_path(
+                &RealFs,
+                &source.join("Match-A"),
+                &source,
+                &destination,
+                &options,
+            )
+            .unwrap();
+            assert_eq!(backup.totals.unwrap().bytes, 7);
+            let backed_up = destination.join("Match-A").join("round1.rec");
+            assert_eq!(fs::read(&backed_up).unwrap(), b"round 1");
+            assert!(!part_path(&backed_up).exists());
+            #[cfg(unix)]
+            if mode == CopyMode::Hardlink {
+                use std::os::unix::fs::MetadataExt;
+                assert_eq!(
+                    fs::metadata(&backed_up).unwrap().ino(),
+                    fs::metadata(&replay).unwrap().ino()
+                );
+            }
+        }
+
+        // Encrypted backups are never links
+        let options = BackupOptions {
+            copy_mode: CopyMode::Hardlink,
+            encryption: Some(Arc::new(EncryptionKey::generate("secret").unwrap())),
+            ..Default::default()
+        };
+        let destination = test_dir.join("encrypted");
+        backup_path(&RealFs, &replay, &source, &destination, &options).unwrap();
+        let encrypted = destination.join("Match-A").join("round1.rec.enc");
+        assert_ne!(fs::read(encrypted).unwrap(), b"round 1");
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_unchanged_files_are_skipped() {
+        let fs = FakeFs::default();
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = epoch + Duration::from_secs(60);
+        for (path, contents, modified) in [
+            // Unchanged
+            ("/source/Match-A/round1.rec", "round 1", epoch),
+            ("/dest/Match-A/round1.rec", "ROUND 1", later),
+            // Modified after its backup
+            ("/source/Match-A/round2.rec", "round 2", later),
+            ("/dest/Match-A/round2.rec", "ROUND 2", epoch),
+            // Same time, different size
+            ("/source/Match-A/round3.rec", "round 3 continued", epoch),
+            ("/dest/Match-A/round3.rec", "ROUND 3", later),
+        ] {
+            fs.add_file(path, contents);
+            fs.set_modified(path, modified);
+        }
+        fs.add_file("/source/Match-A/round4.rec", "round 4");
+
+        let options = BackupOptions::default();
+        let backup = |path: &str| {
+            backup_path(
+                &fs,
+                Path::new(path),
+                Path::new("/source"),
+                Path::new("/dest"),
+                &options,
+            )
+        };
+        let totals = backup("/source/Match-A").unwrap().totals.unwrap();
+        assert_eq!(totals.files, 3);
+        assert_eq!(
+            fs.contents("/dest/Match-A/round1.rec").as_deref(),
+            Some("ROUND 1")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-A/round2.rec").as_deref(),
+            Some("round 2")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-A/round3.rec").as_deref(),
+            Some("round 3 continued")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-A/round4.rec").as_deref(),
+            Some("round 4")
+        );
+
+        // Single files too
+        assert!(backup("/source/Match-A/round1.rec").is_none());
+        assert!(backup("/source/Match-A/round4.rec").is_some());
+    }
+
+    #[test]
+    fn test_parallel_copies() {
+        let fs = FakeFs::default();
+        for round in 1..=20 {
+            fs.add_file(
+                &format!("/source/Match-A/Round-{:02}/round.rec", round),
+                &"x".repeat(round),
+            );
+        }
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let options = BackupOptions {
+            copy_threads: 4,
+            ..Default::default()
+        };
+
+        let totals = copy_directory_recursive(
+            &fs,
+            Path::new("/source/Match-A"),
+            Path::new("/dest/Match-A"),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(totals.files, 20);
+        assert_eq!(totals.bytes, (1..=20).sum::<u64>());
+        assert_eq!(
+            fs.contents("/dest/Match-A/Round-20/round.rec").as_deref(),
+            Some("x".repeat(20).as_str())
+        );
+
+        // One file failing fails the whole folder, as with a single thread
+        fs.set_locked("/source/Match-A/Round-07/round.rec", true);
+        let error = copy_directory_recursive(
+            &fs,
+            Path::new("/source/Match-A"),
+            Path::new("/dest/Match-A"),
+            &options,
+        )
+        .unwrap_err();
+        assert!(is_locked_error(&error));
+    }
+
+    #[test]
+    fn test_copies_are_verified_and_retried() {
+        let fs = FakeFs::default();
+        fs.add_file("/source/round1.rec", "round 1");
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let options = BackupOptions {
+            verify_copies: true,
+            ..Default::default()
+        };
+
+        // A damaged copy is detected and copied again
+        *fs.corrupt_writes.lock().unwrap() = 1;
+        let copy = |options: &BackupOptions| {
+            copy_file(
+                &fs,
+                Path::new("/source/round1.rec"),
+                Path::new("/dest/round1.rec"),
+                options,
+            )
+        };
+        assert_eq!(copy(&options).unwrap(), 7);
+        assert_eq!(fs.contents("/dest/round1.rec").as_deref(), Some("round 1"));
+
+        // A copy that keeps coming out damaged is reported instead of kept silently
+        *fs.corrupt_writes.lock().unwrap() = VERIFY_ATTEMPTS;
+        let error = copy(&options).unwrap_err();
+        assert!(error.to_string().contains("checksum mismatch"));
+        // The earlier good copy is left alone, and so is nothing half-written
+        assert_eq!(fs.contents("/dest/round1.rec").as_deref(), Some("round 1"));
+        assert!(!fs.exists("/dest/round1.rec.part"));
+
+        // A copy into a folder that is gone fails without leaving anything behind
+        let missing = copy_file(
+            &fs,
+            Path::new("/source/round1.rec"),
+            Path::new("/gone/round1.rec"),
+            &options,
+        );
+        assert!(missing.is_err());
+        assert!(!fs.exists("/gone/round1.rec.part"));
+        assert_eq!(
+            part_path(Path::new("/dest/round1.rec.enc")),
+            Path::new("/dest/round1.rec.enc.part")
+        );
+
+        // Without verification the damage goes unnoticed
+        *fs.corrupt_writes.lock().unwrap() = 1;
+        assert!(copy(&BackupOptions::default()).is_ok());
+        assert_eq!(fs.contents("/dest/round1.rec").as_deref(), Some("round 1!"));
+    }
+
+    #[test]
+    fn test_initial_sync_copies_only_newest_items() {
+        let fs = Arc::new(FakeFs::default());
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for i in 0..200 {
+            let folder = format!("/source/Match-{:03}", i);
+            fs.add_file(&format!("{}/round1.rec", folder), "data");
+            fs.set_modified(&folder, epoch + Duration::from_secs(i * 60));
+        }
+
+        let (status_tx, status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone());
+        engine.initial_sync(Some(5), &status_tx);
+        let stats = engine.stats();
+
+        for i in 195..200 {
+            assert!(fs.exists(&format!("/dest/Match-{:03}/round1.rec", i)));
+        }
+        for i in 0..195 {
+            assert!(!fs.exists(&format!("/dest/Match-{:03}", i)));
+        }
+        assert_eq!(stats.lock().unwrap().files_backed_up, 5);
+
+        let summary = status_rx.try_iter().last().unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "Initial sync: skipped 195 older items; synced newest 5"
+        );
+    }
+
+    #[test]
+    fn test_initial_sync_skips_items_already_in_destination() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "new");
+        fs.add_file("/source/Match-B/round1.rec", "new");
+        fs.add_file("/source/loose.rec", "new");
+        fs.add_file("/source/notes.txt", "ignored");
+        fs.add_file("/dest/Match-A/round1.rec", "old");
+
+        let (status_tx, status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone());
+        engine.initial_sync(None, &status_tx);
+        let stats = engine.stats();
+
+        assert_eq!(
+            fs.contents("/dest/Match-A/round1.rec").as_deref(),
+            Some("old")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-B/round1.rec").as_deref(),
+            Some("new")
+        );
+        assert_eq!(fs.contents("/dest/loose.rec").as_deref(), Some("new"));
+        assert!(!fs.exists("/dest/notes.txt"));
+        assert_eq!(stats.lock().unwrap().files_backed_up, 2);
+
+        let summary = status_rx.try_iter().last().unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "Initial sync: backed up 2 existing items (1 already in destination)"
+        );
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = epoch + Duration::from_secs(60);
+        for policy in ConflictPolicy::ALL {
+            let fs = FakeFs::default();
+            // Changed since its backup
+            fs.add_file("/source/Match-A/round1.rec", "round 1 v2");
+            fs.set_modified("/source/Match-A/round1.rec", later);
+            fs.add_file("/dest/Match-A/round1.rec", "round 1");
+            fs.set_modified("/dest/Match-A/round1.rec", epoch);
+            // Unchanged: same size, backed up after it was written
+            fs.add_file("/source/Match-A/round2.rec", "round 2");
+            fs.set_modified("/source/Match-A/round2.rec", epoch);
+            fs.add_file("/dest/Match-A/round2.rec", "ROUND 2");
+            fs.set_modified("/dest/Match-A/round2.rec", later);
+            // Not backed up yet
+            fs.add_file("/source/Match-A/round3.rec", "round 3");
+            // A single file, with a numbered copy already taken
+            fs.add_file("/source/loose.rec", "loose v2");
+            fs.set_modified("/source/loose.rec", later);
+            fs.add_file("/dest/loose.rec", "loose");
+            fs.set_modified("/dest/loose.rec", epoch);
+            fs.add_file("/dest/loose (1).rec", "loose v0");
+
+            let options = BackupOptions {
+                conflicts: policy,
+                ..Default::default()
+            };
+            let source = Path::new("/source");
+            let dest = Path::new("/dest");
+            for path in ["/source/Match-A", "/source/loose.rec"] {
+                backup_path(&fs, Path::new(path), source, dest, &options);
+            }
+
+            let contents = |path| fs.contents(path);
+            assert_eq!(
+                contents("/dest/Match-A/round3.rec").as_deref(),
+                Some("round 3"),
+                "{:?}",
+                policy
+            );
+            let (round1, round2, loose) = match policy {
+                ConflictPolicy::Merge | ConflictPolicy::Rename => {
+                    ("round 1 v2", "ROUND 2", "loose v2")
+                }
+                ConflictPolicy::Skip => ("round 1", "ROUND 2", "loose"),
+                ConflictPolicy::Overwrite => ("round 1 v2", "round 2", "loose v2"),
+            };
+            assert_eq!(
+                contents("/dest/Match-A/round1.rec").as_deref(),
+                Some(round1)
+            );
+            assert_eq!(
+                contents("/dest/Match-A/round2.rec").as_deref(),
+                Some(round2)
+            );
+            assert_eq!(contents("/dest/loose.rec").as_deref(), Some(loose));
+            // Only changed backups are kept under a number, next to the ones already there
+            let renamed = policy == ConflictPolicy::Rename;
+            assert_eq!(
+                contents("/dest/Match-A/round1 (1).rec").as_deref(),
+                renamed.then_some("round 1")
+            );
+            assert!(!fs.exists("/dest/Match-A/round2 (1).rec"));
+            assert_eq!(contents("/dest/loose (1).rec").as_deref(), Some("loose v0"));
+            assert_eq!(
+                contents("/dest/loose (2).rec").as_deref(),
+                renamed.then_some("loose")
+            );
+        }
+
+        assert_eq!(
+            numbered_path(Path::new("/dest/Match-A.tar.zst"), 3),
+            Path::new("/dest/Match-A (3).tar.zst")
+        );
+        assert_eq!(
+            numbered_path(Path::new("/dest/.hidden"), 1),
+            Path::new("/dest/.hidden (1)")
+        );
+    }
+
+    #[test]
+    fn test_source_deletions_are_mirrored_after_the_grace_period() {
+        let fs = Arc::new(FakeFs::default());
+        for name in ["Match-A", "Match-B", "Match-C"] {
+            fs.add_file(&format!("/source/{}/round1.rec", name), name);
+        }
+        let queue = Arc::new(DeletionQueue::default());
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone())
+        .with_mirror_deletions(Arc::clone(&queue), Duration::ZERO);
+        let (status_tx, _status_rx) = channel();
+        for name in ["Match-A", "Match-B", "Match-C"] {
+            let path = Path::new("/source").join(name);
+            assert!(engine.backup(&path, &status_tx).is_some());
+        }
+
+        // The oldest match is what the game deletes to make room, so its backup stays
+        fs.remove(Path::new("/source/Match-A")).unwrap();
+        engine.source_removed(Path::new("/source/Match-A"), &status_tx);
+        assert!(queue.pending().is_empty());
+
+        // Deleting a newer one by hand deletes its backup once the grace period is over
+        fs.remove(Path::new("/source/Match-C")).unwrap();
+        engine.source_removed(Path::new("/source/Match-C"), &status_tx);
+        engine.source_removed(Path::new("/source/Match-B/round1.rec"), &status_tx);
+        assert_eq!(queue.pending().len(), 1);
+        assert_eq!(queue.pending()[0].name, "Match-C");
+        engine.run_due_deletions(&status_tx);
+        assert!(queue.pending().is_empty());
+        assert!(fs.exists("/dest/Match-A/round1.rec"));
+        assert!(!fs.exists("/dest/Match-C"));
+        let trashed = trashed_backups(fs.as_ref(), Path::new("/dest")).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(fs.exists(&format!(
+            "{}/Match-C/round1.rec",
+            trashed[0].folder.display()
+        )));
+        let manifest = Manifest::load(fs.as_ref(), Path::new("/dest")).unwrap();
+        assert!(!manifest.entries.contains_key("Match-C"));
+    }
+
+    #[test]
+    fn test_verified_matches_are_moved_out_of_the_source() {
+        let fs = Arc::new(FakeFs::default());
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for (index, name) in ["Match-A", "Match-B", "Match-C"].iter().enumerate() {
+            let file = format!("/source/{}/round1.rec", name);
+            let modified = epoch + Duration::from_secs(index as u64 * 60);
+            fs.add_file(&file, name);
+            fs.set_modified(&file, modified);
+            fs.set_modified(&format!("/source/{}", name), modified);
+        }
+        let engine = |move_sources| {
+            BackupEngine::new(
+                PathBuf::from("/source"),
+                PathBuf::from("/dest"),
+                BackupOptions::default(),
+            )
+            .with_fs(fs.clone())
+            .with_move_after_verify(move_sources)
+        };
+        let (status_tx, _status_rx) = channel();
+
+        // A finished match is deleted from the source right after its verified backup
+        engine(true).backup(Path::new("/source/Match-A"), &status_tx);
+        assert!(fs.exists("/dest/Match-A/round1.rec"));
+        assert!(!fs.exists("/source/Match-A"));
+
+        for name in ["/source/Match-B", "/source/Match-C"] {
+            engine(false).backup(Path::new(name), &status_tx);
+        }
+        let engine = engine(true);
+        // Not while its backup is damaged
+        fs.add_file("/dest/Match-B/round1.rec", "damaged");
+        assert_eq!(engine.move_verified_sources(&status_tx), 0);
+        // Nor after it changed since its backup
+        fs.add_file("/dest/Match-B/round1.rec", "Match-B");
+        let later = SystemTime::now() + Duration::from_secs(60 * 60);
+        fs.set_modified("/source/Match-B/round1.rec", later);
+        assert_eq!(engine.move_verified_sources(&status_tx), 0);
+        fs.set_modified("/source/Match-B/round1.rec", epoch);
+        assert_eq!(engine.move_verified_sources(&status_tx), 1);
+        assert!(!fs.exists("/source/Match-B"));
+        // The newest match may still be recorded
+        assert!(fs.exists("/source/Match-C/round1.rec"));
+    }
+
+    #[test]
+    fn test_snapshot_runs_keep_earlier_versions() {
+        let fs = Arc::new(FakeFs::default());
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.set_modified("/source/Match-A/round1.rec", epoch);
+        fs.add_file("/source/Match-B/round1.rec", "round 1");
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let engine = |snapshot: &str| {
+            BackupEngine::new(
+                PathBuf::from("/source"),
+                PathBuf::from("/dest"),
+                BackupOptions::default(),
+            )
+            .with_fs(fs.clone())
+            .with_snapshot(snapshot.to_string())
+        };
+        let (status_tx, _status_rx) = channel();
+
+        let first = engine("2025-11-24T14-32");
+        assert_eq!(
+            first.destination_folder(),
+            Path::new("/dest/2025-11-24T14-32")
+        );
+        for name in ["/source/Match-A", "/source/Match-B"] {
+            first.backup(Path::new(name), &status_tx);
+        }
+
+        // The next run starts from the previous one and only replaces what changed
+        fs.add_file("/source/Match-A/round1.rec", "round 1 v2");
+        fs.set_modified(
+            "/source/Match-A/round1.rec",
+            SystemTime::now() + Duration::from_secs(60),
+        );
+        engine("2025-11-25T09-00").backup(Path::new("/source/Match-A"), &status_tx);
+        let contents = |path| fs.contents(path);
+        assert_eq!(
+            contents("/dest/2025-11-25T09-00/Match-A/round1.rec").as_deref(),
+            Some("round 1 v2")
+        );
+        assert_eq!(
+            contents("/dest/2025-11-25T09-00/Match-B/round1.rec").as_deref(),
+            Some("round 1")
+        );
+        assert_eq!(
+            contents("/dest/2025-11-24T14-32/Match-A/round1.rec").as_deref(),
+            Some("round 1")
+        );
+        let manifest = Manifest::load(fs.as_ref(), Path::new("/dest/2025-11-25T09-00")).unwrap();
+        assert_eq!(manifest.entries.len(), 2);
+        assert!(!Manifest::exists(fs.as_ref(), Path::new("/dest")));
+    }
+
+    #[test]
+    fn test_changed_files_keep_earlier_versions() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round2.rec", "round 2");
+        fs.set_modified(
+            "/source/Match-A/round2.rec",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        );
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone())
+        .with_versions(Some(1));
+        let (status_tx, _status_rx) = channel();
+        engine.backup(Path::new("/source/Match-A"), &status_tx);
+        fs.set_modified("/dest/Match-A/round2.rec", SystemTime::now());
+
+        let folder = Path::new("/dest")
+            .join(VERSIONS_FOLDER_NAME)
+            .join("Match-A");
+        for contents in ["round 1 v2", "round 1 v3"] {
+            fs.add_file("/source/Match-A/round1.rec", contents);
+            fs.set_modified(
+                "/source/Match-A/round1.rec",
+                SystemTime::now() + Duration::from_secs(60),
+            );
+            engine.backup(Path::new("/source/Match-A"), &status_tx);
+            assert_eq!(
+                fs.contents("/dest/Match-A/round1.rec").as_deref(),
+                Some(contents)
+            );
+        }
+
+        // Only the newest earlier version is kept, and unchanged files have none
+        let versions = versions_of(fs.as_ref(), &folder, "round1.rec").unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(fs.read(&versions[0]).unwrap(), b"round 1 v2");
+        assert!(versions_of(fs.as_ref(), &folder, "round2.rec")
+            .unwrap()
+            .is_empty());
+        let manifest = Manifest::load(fs.as_ref(), Path::new("/dest")).unwrap();
+        assert_eq!(manifest.entries.keys().collect::<Vec<_>>(), ["Match-A"]);
+    }
+
+    #[test]
+    fn test_incremental_sync_adds_new_rounds_to_existing_match() {
+        let fs = Arc::new(FakeFs::default());
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round2.rec", "round 2");
+        fs.add_file("/source/Match-A/nested/round3.rec", "round 3");
+        fs.set_modified("/source/Match-A/round1.rec", epoch);
+        // Same sizes as the sources, different contents to tell whether they were copied
+        fs.add_file("/dest/Match-A/round1.rec", "ROUND 1");
+        fs.set_modified("/dest/Match-A/round1.rec", epoch + Duration::from_secs(60));
+        fs.add_file("/source/Match-B/round1.rec", "other match");
+        fs.set_modified("/source/Match-B/round1.rec", epoch);
+        fs.add_file("/dest/Match-B/round1.rec", "OTHER MATCH");
+        fs.set_modified("/dest/Match-B/round1.rec", epoch);
+
+        let options = BackupOptions {
+            incremental: true,
+            ..Default::default()
+        };
+        let (status_tx, status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            options.clone(),
+        )
+        .with_fs(fs.clone());
+        engine.initial_sync(None, &status_tx);
+        let stats = engine.stats();
+
+        // Round 1 is unchanged since its backup and must not be copied again
+        assert_eq!(
+            fs.contents("/dest/Match-A/round1.rec").as_deref(),
+            Some("ROUND 1")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-A/round2.rec").as_deref(),
+            Some("round 2")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-A/nested/round3.rec").as_deref(),
+            Some("round 3")
+        );
+        assert_eq!(stats.lock().unwrap().files_backed_up, 2);
+        let summary = status_rx.try_iter().last().unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "Initial sync: backed up 1 existing items (1 already in destination)"
+        );
+
+        // A nested folder event lands below its match folder, not in the destination root
+        let backup = backup_path(
+            fs.as_ref(),
+            Path::new("/source/Match-A/nested"),
+            Path::new("/source"),
+            Path::new("/dest2"),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(backup.totals.unwrap().files, 1);
+        assert!(fs.exists("/dest2/Match-A/nested/round3.rec"));
+        assert!(!fs.exists("/dest2/nested"));
+    }
+
+    #[test]
+    fn test_file_filter_applies_to_folders_and_single_files() {
+        let fs = FakeFs::default();
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round2.REC", "round 2");
+        fs.add_file("/source/Match-A/round3.rec.tmp", "partial");
+        fs.add_file("/source/Match-A/match.json", "metadata");
+        fs.add_file("/source/Match-A/skip-me.rec", "excluded");
+
+        let options = BackupOptions {
+            file_filter: FileFilter::new(
+                &parse_patterns("*.rec, *.json"),
+                &parse_patterns("skip-*"),
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let backup = |path: &str| {
+            backup_path(
+                &fs,
+                Path::new(path),
+                Path::new("/source"),
+                Path::new("/dest"),
+                &options,
+            )
+        };
+
+        let folder = backup("/source/Match-A").unwrap();
+        assert_eq!(folder.totals.unwrap().files, 3);
+        assert!(fs.exists("/dest/Match-A/round1.rec"));
+        assert!(fs.exists("/dest/Match-A/round2.REC"));
+        assert!(fs.exists("/dest/Match-A/match.json"));
+        assert!(!fs.exists("/dest/Match-A/round3.rec.tmp"));
+        assert!(!fs.exists("/dest/Match-A/skip-me.rec"));
+
+        assert!(backup("/source/Match-A/round3.rec.tmp").is_none());
+        assert!(backup("/source/Match-A/skip-me.rec").is_none());
+        assert!(backup("/source/Match-A/match.json").is_some());
+
+        assert!(FileFilter::new(&["[".to_string()], &[]).is_err());
+        assert!(FileFilter::default().allows(Path::new("/source/Match-A/round1.rec")));
+        assert!(!FileFilter::default().allows(Path::new("/source/Match-A/match.json")));
+
+        // The temporary files are left out even when everything else is backed up
+        let everything = FileFilter::new(&["*".to_string()], &default_exclude_patterns()).unwrap();
+        assert!(everything.allows(Path::new("/source/Match-A/match.json")));
+        assert!(!everything.allows(Path::new("/source/Match-A/round3.rec.tmp")));
+        assert!(!everything.allows(Path::new("/source/Match-A/round3.REC.PARTIAL")));
+    }
+
+    #[test]
+    fn test_match_folders_are_archived_as_zip() {
+        use std::io::Read;
+
+        let fs = FakeFs::default();
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/nested/round2.rec", "round 2");
+        fs.add_file("/source/Match-A/notes.txt", "not a replay");
+        fs.add_file("/source/loose.rec", "loose replay");
+        let recorded = chrono::NaiveDate::from_ymd_opt(2025, 11, 24)
+            .unwrap()
+            .and_hms_opt(14, 32, 10)
+            .unwrap();
+        let modified = recorded.and_local_timezone(chrono::Local).unwrap();
+        fs.set_modified("/source/Match-A/round1.rec", modified.into());
+        let options = BackupOptions {
+            storage: StorageFormat::Zip,
+            ..Default::default()
+        };
+        let progress = Mutex::new(Vec::new());
+        let backup = |path: &str| {
+            backup_path_with_progress(
+                &fs,
+                Path::new(path),
+                Path::new("/source"),
+                Path::new("/dest"),
+                &options,
+                &|done, total| progress.lock().unwrap().push((done, total)),
+                &|_, _, _| {},
+            )
+        };
+
+        // A new round inside the match rebuilds the whole archive
+        let archived = backup("/source/Match-A/nested/round2.rec").unwrap();
+        assert_eq!(archived.totals.unwrap().files, 2);
+        assert_eq!(archived.message.to_string(), "Archived folder: Match-A.zip");
+        assert_eq!(*progress.lock().unwrap(), vec![(1, 2), (2, 2)]);
+        assert!(!fs.exists("/dest/Match-A"));
+
+        let data = fs.read(Path::new("/dest/Match-A.zip")).unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        let mut names: Vec<_> = zip.file_names().map(String::from).collect();
+        names.sort();
+        assert_eq!(names, ["nested/round2.rec", "round1.rec"]);
+        let mut contents = String::new();
+        zip.by_name("round1.rec")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "round 1");
+        // Dated like the replay, not like the backup
+        let dated = zip.by_name("round1.rec").unwrap().last_modified().unwrap();
+        assert_eq!(chrono::NaiveDateTime::try_from(dated).unwrap(), recorded);
+
+        // Replays directly in the source folder are still copied as they are
+        backup("/source/loose.rec").unwrap();
+        assert_eq!(
+            fs.contents("/dest/loose.rec").as_deref(),
+            Some("loose replay")
+        );
+        assert!(is_already_backed_up(
+            &fs,
+            Path::new("/source/Match-A"),
+            Path::new("/dest"),
+            &options
+        ));
+    }
+
+    #[test]
+    fn test_match_folders_are_archived_as_tar_zst() {
+        let fs = FakeFs::default();
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        let options = BackupOptions {
+            storage: StorageFormat::TarZst,
+            zstd_level: 19,
+            ..Default::default()
+        };
+
+        let archived = backup_path(
+            &fs,
+            Path::new("/source/Match-A"),
+            Path::new("/source"),
+            Path::new("/dest"),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            archived.message.to_string(),
+            "Archived folder: Match-A.tar.zst"
+        );
+
+        let data = fs.read(Path::new("/dest/Match-A.tar.zst")).unwrap();
+        let tar_data = zstd::decode_all(data.as_slice()).unwrap();
+        let mut archive = tar::Archive::new(tar_data.as_slice());
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect();
+        assert_eq!(names, ["round1.rec"]);
+        assert!(is_already_backed_up(
+            &fs,
+            Path::new("/source/Match-A"),
+            Path::new("/dest"),
+            &options
+        ));
+    }
+
+    #[test]
+    fn test_retention_prunes_oldest_matches() {
+        let fs = Arc::new(FakeFs::default());
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        fs.add_file("/source/Match-C/round1.rec", "round 1");
+        fs.add_file("/dest/Match-A/round1.rec", "old");
+        fs.add_file("/dest/Match-B.zip", "older archive");
+        fs.add_file("/dest/loose.rec", "loose replay");
+        fs.set_modified("/dest/Match-A", now - day * 3);
+        fs.set_modified("/dest/Match-B.zip", now - day * 2);
+        fs.set_modified("/dest/loose.rec", now - day * 10);
+        fs.set_modified("/source/Match-C", now);
+        fs.set_modified("/dest/Match-C", now);
+
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone())
+        .with_retention(RetentionPolicy {
+            keep_last: Some(2),
+            max_age_days: None,
+        });
+        let (status_tx, status_rx) = channel();
+
+        // Nothing to prune yet, and a dry run never deletes anything
+        assert_eq!(engine.prune(true, &status_tx), 0);
+
+        // Backing up a third match pushes the oldest one out
+        let backup = engine
+            .backup(Path::new("/source/Match-C"), &status_tx)
+            .unwrap();
+        assert!(backup.totals.is_some());
+        assert!(fs.exists("/dest/Match-C/round1.rec"));
+        assert!(!fs.exists("/dest/Match-A"));
+        assert!(!fs.exists("/dest/Match-A/round1.rec"));
+        assert!(fs.exists("/dest/Match-B.zip"));
+        assert!(fs.exists("/dest/loose.rec"));
+
+        let events: Vec<StatusEvent> = status_rx.try_iter().collect();
+        assert!(events.contains(&StatusEvent::Pruned {
+            name: "Match-A".to_string(),
+            dry_run: false,
+        }));
+    }
+
+    #[test]
+    fn test_pruned_backups_go_to_the_recycle_bin() {
+        let fs = Arc::new(FakeFs::default());
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        fs.add_file("/dest/Match-A/round1.rec", "old");
+        fs.add_file("/dest/Match-B/round1.rec", "new");
+        fs.set_modified("/dest/Match-A", now - day * 2);
+        fs.set_modified("/dest/Match-B", now - day);
+        let engine = |recycle: bool| {
+            BackupEngine::new(
+                PathBuf::from("/source"),
+                PathBuf::from("/dest"),
+                BackupOptions::default(),
+            )
+            .with_fs(fs.clone())
+            .with_retention(RetentionPolicy {
+                keep_last: Some(1),
+                max_age_days: None,
+            })
+            .with_recycle_bin(recycle)
+        };
+        let (status_tx, _status_rx) = channel();
+
+        assert_eq!(engine(true).prune(false, &status_tx), 1);
+        assert!(!fs.exists("/dest/Match-A"));
+        assert_eq!(
+            fs.contents("/recycle-bin/Match-A/round1.rec").as_deref(),
+            Some("old")
+        );
+
+        // Deleted for good otherwise
+        fs.add_file("/dest/Match-C/round1.rec", "newest");
+        fs.set_modified("/dest/Match-C", now);
+        assert_eq!(engine(false).prune(false, &status_tx), 1);
+        assert!(!fs.exists("/dest/Match-B"));
+        assert!(!fs.exists("/recycle-bin/Match-B"));
+    }
+
+    #[test]
+    fn test_quota_evicts_least_recently_backed_up_matches() {
+        let fs = Arc::new(FakeFs::default());
+        let now = SystemTime::now();
+        let hour = Duration::from_secs(60 * 60);
+        fs.add_file("/source/Match-C/round1.rec", "0123456789");
+        fs.add_file("/dest/Match-A/round1.rec", "0123456789");
+        fs.add_file("/dest/Match-B/round1.rec", "0123456789");
+        fs.set_modified("/dest/Match-A", now - hour * 2);
+        fs.set_modified("/dest/Match-B", now - hour);
+
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone())
+        .with_quota(Some(25));
+        let (status_tx, status_rx) = channel();
+
+        let backup = engine
+            .backup(Path::new("/source/Match-C"), &status_tx)
+            .unwrap();
+        assert!(backup.totals.is_some());
+        assert!(!fs.exists("/dest/Match-A"));
+        assert!(fs.exists("/dest/Match-B/round1.rec"));
+        assert!(fs.exists("/dest/Match-C/round1.rec"));
+
+        let manifest = Manifest::load(fs.as_ref(), Path::new("/dest")).unwrap();
+        assert!(fs.exists("/dest/siegesaver-manifest.json"));
+        assert_eq!(manifest.total_bytes(), 20);
+        assert!(!manifest.entries.contains_key("Match-A"));
+        let entry = &manifest.entries["Match-C"];
+        assert_eq!(entry.source, Some(PathBuf::from("/source/Match-C")));
+        assert_eq!(
+            entry.files["Match-C/round1.rec"].sha256,
+            crate::manifest::sha256_hex(b"0123456789")
+        );
+
+        let events: Vec<StatusEvent> = status_rx.try_iter().collect();
+        assert!(events.contains(&StatusEvent::Evicted {
+            name: "Match-A".to_string(),
+            bytes: 10,
+        }));
+    }
+
+    #[test]
+    fn test_verify_and_repair_backups() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round2.rec", "round 2");
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions {
+                incremental: true,
+                ..Default::default()
+            },
+        )
+        .with_fs(fs.clone());
+        let (status_tx, status_rx) = channel();
+
+        // Nothing to verify against before the first backup
+        assert_eq!(engine.verify(&status_tx), VerifyReport::default());
+
+        engine.backup(Path::new("/source/Match-A"), &status_tx);
+        assert!(engine.verify(&status_tx).problems.is_empty());
+
+        fs.write(Path::new("/dest/Match-A/round1.rec"), b"bit rot")
+            .unwrap();
+        fs.remove(Path::new("/dest/Match-A/round2.rec")).unwrap();
+        let report = engine.verify(&status_tx);
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.count(crate::manifest::FileProblem::Corrupted), 1);
+        assert_eq!(report.count(crate::manifest::FileProblem::Missing), 1);
+
+        engine.repair(&report, &status_tx);
+        assert_eq!(
+            fs.contents("/dest/Match-A/round1.rec").as_deref(),
+            Some("round 1")
+        );
+        assert_eq!(
+            fs.contents("/dest/Match-A/round2.rec").as_deref(),
+            Some("round 2")
+        );
+        assert!(engine.verify(&status_tx).problems.is_empty());
+
+        let messages: Vec<String> = status_rx.try_iter().map(|m| m.to_string()).collect();
+        assert!(messages.contains(&"Damaged in backup: Match-A/round1.rec".to_string()));
+        assert!(messages.contains(&"Verified 2 files: 1 missing, 1 damaged".to_string()));
+    }
+
+    #[test]
+    fn test_excluded_subfolders_are_not_backed_up() {
+        let fs = FakeFs::default();
+        fs.add_file("/source/Match-2025-11-23-001/round1.rec", "round 1 data");
+        fs.add_file("/source/Match-2025-11-23-001/temp/partial.rec", "partial");
+        fs.add_file("/source/cache/blob.rec", "cache data");
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+
+        let sep = std::path::MAIN_SEPARATOR;
+        let excluded = parse_exclude_dirs(" cache \n\n/Match-2025-11-23-001/temp\n");
+        assert_eq!(
+            excluded,
+            vec![
+                "cache".to_string(),
+                format!("Match-2025-11-23-001{sep}temp")
+            ]
+        );
+        let options = BackupOptions {
+            excluded_dirs: excluded
+                .iter()
+                .map(|dir| Path::new("/source").join(dir))
+                .collect(),
+            ..Default::default()
+        };
+        let backup = |path: &str| {
+            backup_path(
+                &fs,
+                Path::new(path),
+                Path::new("/source"),
+                Path::new("/dest"),
+                &options,
+            )
+        };
+
+        // Events inside excluded folders are dropped
+        assert!(backup("/source/cache").is_none());
+        assert!(backup("/source/cache/blob.rec").is_none());
+        assert!(backup("/source/Match-2025-11-23-001/temp/partial.rec").is_none());
+
+        // Folder copies skip the excluded subtree
+        let copied = backup("/source/Match-2025-11-23-001").unwrap();
+        assert_eq!(copied.totals.unwrap().files, 1);
+        assert!(fs.exists("/dest/Match-2025-11-23-001/round1.rec"));
+        assert!(!fs.exists("/dest/Match-2025-11-23-001/temp"));
+        assert!(!fs.exists("/dest/cache"));
+
+        // The initial scan ignores them too
+        let (items, _) = newest_source_items(&fs, Path::new("/source"), None, &options).unwrap();
+        assert_eq!(items, vec![PathBuf::from("/source/Match-2025-11-23-001")]);
+    }
+    #[test]
+    fn test_locked_files_are_retried_without_an_error() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.set_locked("/source/Match-A/round1.rec", true);
+        let (status_tx, _status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone());
+
+        let backup = engine
+            .backup(Path::new("/source/Match-A/round1.rec"), &status_tx)
+            .unwrap();
+        assert!(backup.totals.is_none());
+        assert!(backup.locked);
+        assert_eq!(engine.stats().lock().unwrap().errors, 0);
+
+        fs.set_locked("/source/Match-A/round1.rec", false);
+        let backup = engine
+            .backup(Path::new("/source/Match-A/round1.rec"), &status_tx)
+            .unwrap();
+        assert!(backup.totals.is_some());
+        assert!(!backup.locked);
+
+        assert!(!is_locked_error(&io::Error::from(io::ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn test_cancelled_backups_stop_without_an_error() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round2.rec", "round 2");
+        let (status_tx, _status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone());
+
+        let cancel = Arc::new(AtomicBool::new(true));
+        let backup = engine
+            .backup_cancellable(Path::new("/source/Match-A"), &status_tx, Some(&cancel))
+            .unwrap();
+        assert!(backup.cancelled);
+        assert!(backup.totals.is_none());
+        assert!(!fs.exists("/dest/Match-A/round1.rec"));
+        assert_eq!(engine.stats().lock().unwrap().errors, 0);
+        assert_eq!(backup.message.to_string(), "Cancelled backing up Match-A");
+
+        cancel.store(false, Ordering::Relaxed);
+        let backup = engine
+            .backup_cancellable(Path::new("/source/Match-A"), &status_tx, Some(&cancel))
+            .unwrap();
+        assert_eq!(backup.totals.unwrap().files, 2);
+    }
+
+    #[test]
+    fn test_backups_are_held_while_the_destination_is_low_on_space() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round2.rec", "round 2");
+        *fs.available_space.lock().unwrap() = Some(110);
+        let (status_tx, status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone())
+        .with_min_free_space(Some(100));
+
+        // 14 bytes of rounds would leave less than 100 free
+        let backup = engine
+            .backup(Path::new("/source/Match-A"), &status_tx)
+            .unwrap();
+        assert!(backup.low_space);
+        assert!(backup.totals.is_none());
+        assert!(!fs.exists("/dest/Match-A"));
+        assert!(backup
+            .message
+            .to_string()
+            .starts_with("Only 110 B free in /dest"));
+        assert_eq!(engine.stats().lock().unwrap().errors, 0);
+        assert!(engine.is_low_on_space());
+        assert!(!engine.check_free_space(&status_tx));
+
+        *fs.available_space.lock().unwrap() = Some(114);
+        assert!(engine.check_free_space(&status_tx));
+        assert!(!engine.is_low_on_space());
+        assert!(status_rx
+            .try_iter()
+            .any(|event| event.to_string().ends_with("resuming backups")));
+        let backup = engine
+            .backup(Path::new("/source/Match-A"), &status_tx)
+            .unwrap();
+        assert_eq!(backup.totals.unwrap().files, 2);
+
+        // Files already backed up take no room
+        let now = SystemTime::now();
+        for folder in ["/source", "/dest"] {
+            for round in ["round1.rec", "round2.rec"] {
+                fs.set_modified(&format!("{}/Match-A/{}", folder, round), now);
+            }
+        }
+        *fs.available_space.lock().unwrap() = Some(100);
+        fs.add_file("/source/Match-A/round3.rec", "round 3");
+        assert!(
+            engine
+                .backup(Path::new("/source/Match-A"), &status_tx)
+                .unwrap()
+                .low_space
+        );
+        *fs.available_space.lock().unwrap() = Some(107);
+        assert!(engine.check_free_space(&status_tx));
+        assert!(
+            !engine
+                .backup(Path::new("/source/Match-A"), &status_tx)
+                .unwrap()
+                .low_space
+        );
+    }
+
+    #[test]
+    fn test_wait_until_stable() {
+        let fs = Arc::new(FakeFs::default());
+        fs.add_file("/source/Match-A/round1.rec", "round 1");
+        fs.add_file("/source/Match-A/round1.rec.tmp", "ignored");
+        let quiet_period = Duration::from_millis(300);
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone())
+        .with_quiet_period(quiet_period);
+
+        // Written long ago: no waiting
+        let long_ago = SystemTime::now() - Duration::from_secs(3600);
+        fs.set_modified("/source/Match-A/round1.rec", long_ago);
+        let started = Instant::now();
+        assert!(engine.wait_until_stable(Path::new("/source/Match-A")));
+        assert!(started.elapsed() < quiet_period);
+
+        // Just written: waits out the quiet period
+        fs.set_modified("/source/Match-A/round1.rec", SystemTime::now());
+        let started = Instant::now();
+        assert!(engine.wait_until_stable(Path::new("/source/Match-A/round1.rec")));
+        assert!(started.elapsed() >= quiet_period);
+
+        // Still growing while it is watched: waits until it stops
+        let write = |fs: &FakeFs, bytes: usize| {
+            fs.add_file("/source/Match-A/round2.rec", &"x".repeat(bytes));
+            fs.set_modified("/source/Match-A/round2.rec", SystemTime::now());
+        };
+        write(&fs, 1);
+        let writer = {
+            let fs = fs.clone();
+            std::thread::spawn(move || {
+                for bytes in 2..6 {
+                    std::thread::sleep(Duration::from_millis(150));
+                    write(&fs, bytes);
+                }
+            })
+        };
+        let started = Instant::now();
+        assert!(engine.wait_until_stable(Path::new("/source/Match-A")));
+        assert!(started.elapsed() >= Duration::from_millis(600) + quiet_period);
+        writer.join().unwrap();
+
+        // Locked by the game the whole time: gives up
+        fs.set_locked("/source/Match-A/round1.rec", true);
+        fs.set_modified("/source/Match-A/round1.rec", long_ago);
+        assert!(!engine.wait_until_stable(Path::new("/source/Match-A/round1.rec")));
+        assert!(engine.wait_until_stable(Path::new("/source/Match-B")));
+    }
+
+    #[test]
+    fn test_unsynced_items() {
+        let fs = Arc::new(FakeFs::default());
+        fs.create_dir_all(Path::new("/dest")).unwrap();
+        let since = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for (name, offset) in [("Match-A", 0), ("Match-B", 120), ("Match-C", 60)] {
+            fs.add_file(&format!("/source/{}/round1.rec", name), "data");
+            fs.set_modified(
+                &format!("/source/{}", name),
+                since + Duration::from_secs(offset),
+            );
+        }
+        // From before watching started
+        fs.set_modified("/source/Match-A", since - Duration::from_secs(60));
+
+        let (status_tx, _status_rx) = channel();
+        let engine = BackupEngine::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            BackupOptions::default(),
+        )
+        .with_fs(fs.clone());
+        assert_eq!(
+            engine.unsynced_items(since).unwrap(),
+            [
+                PathBuf::from("/source/Match-C"),
+                PathBuf::from("/source/Match-B")
+            ]
+        );
+
+        engine.backup(Path::new("/source/Match-B"), &status_tx);
+        assert_eq!(
+            engine.unsynced_items(since).unwrap(),
+            [PathBuf::from("/source/Match-C")]
+        );
+        // Changed again after its backup
+        fs.set_modified(
+            "/source/Match-B",
+            SystemTime::now() + Duration::from_secs(3600),
+        );
+        assert_eq!(engine.unsynced_items(since).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_coalesce_paths() {
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let burst = paths(&[
+            "/source/Match-B/round2.rec",
+            "/source/Match-A/round1.rec",
+            "/source/Match-B",
+            "/source/Match-B/round1.rec",
+            "/source/Match-A/round2.rec",
+            "/source/Match-A/round1.rec",
+        ]);
+
+        // Folder copies: each file once, and nothing inside a folder that is copied anyway
+        let engine = BackupEngine::new("/source".into(), "/dest".into(), Default::default());
+        assert_eq!(
+            engine.coalesce_paths(burst.clone()),
+            paths(&[
+                "/source/Match-A/round1.rec",
+                "/source/Match-A/round2.rec",
+                "/source/Match-B",
+            ])
+        );
+        // The source folder itself does not swallow the rest
+        assert_eq!(
+            engine.coalesce_paths(paths(&["/source/Match-A/round1.rec", "/source"])),
+            paths(&["/source", "/source/Match-A/round1.rec"])
+        );
+
+        // Archives: one rebuild per match
+        let options = BackupOptions {
+            storage: StorageFormat::TarZst,
+            ..Default::default()
+        };
+        let engine = BackupEngine::new("/source".into(), "/dest".into(), options);
+        assert_eq!(
+            engine.coalesce_paths(burst),
+            paths(&["/source/Match-A", "/source/Match-B"])
+        );
+        assert_eq!(
+            engine.coalesce_paths(paths(&["/source/loose.rec"])),
+            paths(&["/source/loose.rec"])
+        );
+    }
+}
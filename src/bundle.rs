@@ -0,0 +1,165 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{source_destinations, FileSystem};
+use crate::config::AppConfig;
+use crate::manifest::Manifest;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Bumped when a bundle can no longer be read by older versions
+pub const BUNDLE_VERSION: u32 = 1;
+
+// The whole configuration, and optionally the destination manifests, in one file for setting
+// up another machine
+#[derive(Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub version: u32,
+    pub config: AppConfig,
+    // Keyed by the destination folder relative to the main destination, with forward slashes;
+    // empty for the main destination itself
+    #[serde(default)]
+    pub manifests: BTreeMap<String, Manifest>,
+}
+
+// The destination folder of every source, with its key in `manifests`
+fn manifest_folders(config: &AppConfig) -> Vec<(String, PathBuf)> {
+    let destination = PathBuf::from(&config.destination_folder);
+    let sources: Vec<PathBuf> = config.source_folders.iter().map(PathBuf::from).collect();
+    if config.destination_folder.is_empty() || sources.is_empty() {
+        return Vec::new();
+    }
+    source_destinations(&sources, &destination)
+        .into_iter()
+        .map(|folder| {
+            let key = folder
+                .strip_prefix(&destination)
+                .unwrap_or(Path::new(""))
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (key, folder)
+        })
+        .collect()
+}
+
+impl SettingsBundle {
+    // Bundles `config`, with the manifests of its destination folders if `with_manifests`
+    pub fn new(
+        fs: &dyn FileSystem,
+        config: AppConfig,
+        with_manifests: bool,
+    ) -> std::io::Result<Self> {
+        let mut manifests = BTreeMap::new();
+        if with_manifests {
+            for (key, folder) in manifest_folders(&config) {
+                if Manifest::exists(fs, &folder) {
+                    manifests.insert(key, Manifest::load(fs, &folder)?);
+                }
+            }
+        }
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            config,
+            manifests,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let bundle: Self = serde_json::from_str(&contents).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("not a SiegeSaver settings file: {}", e),
+            )
+        })?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the settings were exported by a newer SiegeSaver",
+            ));
+        }
+        Ok(bundle)
+    }
+
+    // Writes the bundled manifests into the destination folders of the bundled config,
+    // leaving folders that already have one alone. Returns how many were written.
+    pub fn restore_manifests(&self, fs: &dyn FileSystem) -> std::io::Result<usize> {
+        let mut restored = 0;
+        for (key, folder) in manifest_folders(&self.config) {
+            let Some(manifest) = self.manifests.get(&key) else {
+                continue;
+            };
+            if Manifest::exists(fs, &folder) {
+                continue;
+            }
+            fs.create_dir_all(&folder)?;
+            manifest.save(fs, &folder)?;
+            restored += 1;
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use crate::manifest::ManifestEntry;
+
+    #[test]
+    fn test_settings_bundle_round_trip() {
+        let test_dir = std::env::temp_dir().join("siegesaver_bundle_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let old_destination = test_dir.join("old");
+        let config = AppConfig {
+            source_folders: vec![
+                "/games/a/replays".to_string(),
+                "/games/b/replays".to_string(),
+            ],
+            destination_folder: old_destination.display().to_string(),
+            keep_last_matches: Some(20),
+            ..Default::default()
+        };
+        let folders = manifest_folders(&config);
+        assert_eq!(folders.len(), 2);
+        let mut manifest = Manifest::default();
+        manifest
+            .entries
+            .insert("Match-A".to_string(), ManifestEntry::default());
+        fs::create_dir_all(&folders[0].1).unwrap();
+        manifest.save(&RealFs, &folders[0].1).unwrap();
+
+        let bundle = SettingsBundle::new(&RealFs, config, true).unwrap();
+        assert_eq!(bundle.manifests.len(), 1);
+        assert!(!folders[0].0.contains('\\'));
+        let path = test_dir.join("settings.json");
+        bundle.save(&path).unwrap();
+
+        // On the new machine the backups go somewhere else
+        let mut imported = SettingsBundle::load(&path).unwrap();
+        assert_eq!(imported.config.keep_last_matches, Some(20));
+        let new_destination = test_dir.join("new");
+        imported.config.destination_folder = new_destination.display().to_string();
+        assert_eq!(imported.restore_manifests(&RealFs).unwrap(), 1);
+        let (_, folder) = &manifest_folders(&imported.config)[0];
+        assert!(folder.starts_with(&new_destination));
+        assert_eq!(Manifest::load(&RealFs, folder).unwrap(), manifest);
+        // Existing manifests are kept
+        assert_eq!(imported.restore_manifests(&RealFs).unwrap(), 0);
+
+        let without = SettingsBundle::new(&RealFs, AppConfig::default(), false).unwrap();
+        assert!(without.manifests.is_empty());
+        fs::write(&path, r#"{"version": 99, "config": {}}"#).unwrap();
+        assert!(SettingsBundle::load(&path).is_err());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
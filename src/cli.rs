@@ -0,0 +1,369 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::RealFs;
+use crate::config::AppConfig;
+use crate::crypto::Decryptor;
+use crate::headless::{
+    backup_engines, backup_targets, run_headless, HeadlessArgs, PASSPHRASE_VARIABLE,
+};
+use crate::logging::log_event;
+use crate::restore::{restore_candidates, restore_match, RestoreCandidate};
+use crate::service::{install_service, run_service, service_log_path, uninstall_service};
+use crate::snapshot::latest_snapshot;
+use crate::status::{Severity, StatusEvent, StatusLine};
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
+
+// Command line of the binary. Without a subcommand or --headless the GUI starts.
+#[derive(Debug, Parser)]
+#[command(
+    name = "siegesaver",
+    version,
+    about = "Backs up Rainbow Six Siege replays"
+)]
+pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Use this config file instead of the default one"
+    )]
+    pub config: Option<PathBuf>,
+    #[arg(long, help = "Watch and back up without opening the window")]
+    pub headless: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "headless",
+        help = "Also append the status log to this file"
+    )]
+    pub log: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    #[command(about = "Back up everything in the source folders that is not backed up yet")]
+    BackupNow,
+    #[command(about = "List the backed-up matches, newest first")]
+    List,
+    #[command(about = "Check the backups against the hashes recorded when they were made")]
+    Verify {
+        #[arg(long, help = "Copy missing or damaged files again from the source")]
+        repair: bool,
+    },
+    #[command(about = "Copy a backed-up match back into its source folder")]
+    Restore {
+        #[arg(value_name = "MATCH", help = "Name of the match, as shown by list")]
+        name: String,
+        #[arg(long, value_name = "FOLDER", help = "Restore into this folder instead")]
+        to: Option<PathBuf>,
+        #[arg(long, help = "Replace the match if it is still in the folder")]
+        overwrite: bool,
+    },
+    #[command(about = "Run the watcher as a Windows service, without anyone logged in")]
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum ServiceAction {
+    #[command(about = "Install and start the service with the current config (needs admin)")]
+    Install,
+    #[command(about = "Stop and remove the service (needs admin)")]
+    Uninstall,
+    #[command(about = "Used by Windows to start the service")]
+    Run,
+}
+
+impl Cli {
+    // Runs what the command line asks for without the GUI, or returns None to start it
+    pub fn run_without_gui(&self, config_path: &Path) -> Option<Result<(), String>> {
+        if let Some(command) = &self.command {
+            return Some(run_command(command, config_path));
+        }
+        self.headless.then(|| {
+            run_headless(
+                config_path,
+                &HeadlessArgs {
+                    log_file: self.log.clone(),
+                },
+                &AtomicBool::new(false),
+            )
+        })
+    }
+}
+
+// The GUI build has no console of its own; print into the one it was started from
+pub fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+// Prints status events as they arrive until every sender is dropped. Returns how many were
+// errors.
+fn print_status(status_rx: Receiver<StatusEvent>) -> JoinHandle<usize> {
+    std::thread::spawn(move || {
+        let mut errors = 0;
+        for event in status_rx {
+            log_event(&event);
+            if matches!(
+                event,
+                StatusEvent::Progress { .. }
+                    | StatusEvent::CopyProgress { .. }
+                    | StatusEvent::RestoreProgress { .. }
+            ) {
+                continue;
+            }
+            if event.severity() == Severity::Error {
+                errors += 1;
+            }
+            println!("{}", StatusLine::new(event, chrono::Local::now()).text);
+        }
+        errors
+    })
+}
+
+pub fn run_command(command: &Command, config_path: &Path) -> Result<(), String> {
+    if let Command::Service { action } = command {
+        return run_service_action(action, config_path);
+    }
+    let config = AppConfig::load(config_path);
+    let (status_tx, status_rx) = channel();
+    let printer = print_status(status_rx);
+    let result = match command {
+        Command::BackupNow => backup_now(&config, config_path, &status_tx),
+        Command::List => list(&config),
+        Command::Verify { repair } => verify(&config, config_path, *repair, &status_tx),
+        Command::Restore {
+            name,
+            to,
+            overwrite,
+        } => restore(&config, name, to.as_deref(), *overwrite, &status_tx),
+        Command::Service { .. } => unreachable!("handled above"),
+    };
+    drop(status_tx);
+    let errors = printer.join().unwrap_or_default();
+    result?;
+    match errors {
+        0 => Ok(()),
+        1 => Err("1 error occurred".to_string()),
+        errors => Err(format!("{} errors occurred", errors)),
+    }
+}
+
+fn run_service_action(action: &ServiceAction, config_path: &Path) -> Result<(), String> {
+    match action {
+        ServiceAction::Install => {
+            install_service(config_path)?;
+            println!(
+                "Installed and started the SiegeSaver service; it logs to {}",
+                service_log_path(config_path).display()
+            );
+        }
+        ServiceAction::Uninstall => {
+            uninstall_service()?;
+            println!("Removed the SiegeSaver service");
+        }
+        ServiceAction::Run => run_service()?,
+    }
+    Ok(())
+}
+
+fn backup_now(
+    config: &AppConfig,
+    config_path: &Path,
+    status_tx: &Sender<StatusEvent>,
+) -> Result<(), String> {
+    for (_, engines) in backup_engines(config, config_path, status_tx)? {
+        for engine in &engines {
+            engine.initial_sync(None, status_tx);
+        }
+    }
+    Ok(())
+}
+
+// Every backed-up match with the folder it is restored into, newest first per source
+fn backed_up(config: &AppConfig) -> Result<Vec<(RestoreCandidate, PathBuf)>, String> {
+    let mut matches = Vec::new();
+    for (source, destination) in backup_targets(config)? {
+        if !destination.is_dir() {
+            continue;
+        }
+        // The latest snapshot holds every match of the last run
+        let destination = if config.snapshot_runs {
+            latest_snapshot(&RealFs, &destination).unwrap_or(destination)
+        } else {
+            destination
+        };
+        let candidates = restore_candidates(&destination).map_err(|e| {
+            format!(
+                "Error listing backed-up matches in {}: {}",
+                destination.display(),
+                e
+            )
+        })?;
+        matches.extend(
+            candidates
+                .into_iter()
+                .map(|candidate| (candidate, source.clone())),
+        );
+    }
+    Ok(matches)
+}
+
+fn list(config: &AppConfig) -> Result<(), String> {
+    for (candidate, _) in backed_up(config)? {
+        let modified: chrono::DateTime<chrono::Local> = candidate.modified.into();
+        println!("{}  {}", modified.format("%Y-%m-%d %H:%M"), candidate.name);
+    }
+    Ok(())
+}
+
+fn verify(
+    config: &AppConfig,
+    config_path: &Path,
+    repair: bool,
+    status_tx: &Sender<StatusEvent>,
+) -> Result<(), String> {
+    for (_, engines) in backup_engines(config, config_path, status_tx)? {
+        for engine in &engines {
+            let report = engine.verify(status_tx);
+            if repair && !report.problems.is_empty() {
+                engine.repair(&report, status_tx);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn restore(
+    config: &AppConfig,
+    name: &str,
+    to: Option<&Path>,
+    overwrite: bool,
+    status_tx: &Sender<StatusEvent>,
+) -> Result<(), String> {
+    let (candidate, source) = backed_up(config)?
+        .into_iter()
+        .find(|(candidate, _)| {
+            candidate.name == name || candidate.backup.file_name() == Some(name.as_ref())
+        })
+        .ok_or_else(|| format!("No backed-up match named {}", name))?;
+    let target = to.map_or(source, Path::to_path_buf);
+    if !overwrite && target.join(&candidate.name).exists() {
+        return Err(format!(
+            "{} is still in {}; pass --overwrite to replace it",
+            candidate.name,
+            target.display()
+        ));
+    }
+    let mut decryptor = std::env::var(PASSPHRASE_VARIABLE)
+        .ok()
+        .map(|passphrase| Decryptor::new(&passphrase));
+    let files = restore_match(&candidate.backup, &target, &mut decryptor)
+        .map_err(|e| format!("Error restoring {}: {}", candidate.name, e))?;
+    let _ = status_tx.send(StatusEvent::Restored {
+        name: candidate.name,
+        files,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::service_arguments;
+    use std::fs;
+
+    #[test]
+    fn test_parse_command_line() {
+        let cli = Cli::try_parse_from(["siegesaver", "--config", "a.json"]).unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("a.json")));
+        assert!(cli.run_without_gui(Path::new("a.json")).is_none());
+
+        let cli = Cli::try_parse_from(["siegesaver", "--headless", "--log=siegesaver.log"]);
+        let cli = cli.unwrap();
+        assert!(cli.headless);
+        assert_eq!(cli.log, Some(PathBuf::from("siegesaver.log")));
+        assert!(Cli::try_parse_from(["siegesaver", "--log", "siegesaver.log"]).is_err());
+
+        let cli = Cli::try_parse_from(["siegesaver", "restore", "Match-A", "--config=b.json"]);
+        let cli = cli.unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("b.json")));
+        assert_eq!(
+            cli.command,
+            Some(Command::Restore {
+                name: "Match-A".to_string(),
+                to: None,
+                overwrite: false,
+            })
+        );
+        assert!(Cli::try_parse_from(["siegesaver", "restore"]).is_err());
+
+        // The service is registered with a command line this parses back
+        let config_path = Path::new("service.json");
+        let args = std::iter::once("siegesaver".into()).chain(service_arguments(config_path));
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.config.as_deref(), Some(config_path));
+        assert_eq!(
+            cli.command,
+            Some(Command::Service {
+                action: ServiceAction::Run
+            })
+        );
+    }
+
+    #[test]
+    fn test_backup_and_restore_commands() {
+        let test_dir = std::env::temp_dir().join("siegesaver_cli_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        fs::write(source.join("Match-A").join("round1.rec"), b"round 1").unwrap();
+        let config_path = test_dir.join("config.json");
+        let config = AppConfig {
+            source_folders: vec![source.display().to_string()],
+            destination_folder: test_dir.join("backup").display().to_string(),
+            ..Default::default()
+        };
+        fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        run_command(&Command::BackupNow, &config_path).unwrap();
+        assert!(test_dir.join("backup/Match-A/round1.rec").is_file());
+        run_command(&Command::Verify { repair: false }, &config_path).unwrap();
+
+        let restore = |name: &str, overwrite| {
+            run_command(
+                &Command::Restore {
+                    name: name.to_string(),
+                    to: None,
+                    overwrite,
+                },
+                &config_path,
+            )
+        };
+        assert!(restore("Match-A", false)
+            .unwrap_err()
+            .contains("--overwrite"));
+        fs::remove_dir_all(source.join("Match-A")).unwrap();
+        restore("Match-A", false).unwrap();
+        assert_eq!(
+            fs::read(source.join("Match-A").join("round1.rec")).unwrap(),
+            b"round 1"
+        );
+        restore("Match-A", true).unwrap();
+        assert!(restore("Match-B", false).unwrap_err().contains("Match-B"));
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
@@ -0,0 +1,735 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::autostart::AutostartMode;
+use crate::backup::{
+    default_exclude_patterns, default_include_patterns, ConflictPolicy, CopyMode, SymlinkMode,
+};
+use crate::i18n::Language;
+use crate::logging::LogLevel;
+use crate::notifications::DesktopNotifications;
+use crate::remote::RemoteConfig;
+use crate::storage::{StorageFormat, DEFAULT_ZSTD_LEVEL};
+use crate::throttle::IoLimits;
+use crate::ui::{Theme, WindowGeometry};
+use crate::watcher::{parse_time_of_day, RescanSchedule, WatchOptions};
+use auto_launch::{AutoLaunch, AutoLaunchBuilder};
+use chrono::NaiveTime;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const DEFAULT_PROFILE_NAME: &str = "Default";
+
+// The settings that differ between games or accounts. The active profile's settings are the
+// top-level fields of AppConfig; only the inactive profiles are stored as Profile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub source_folders: Vec<String>,
+    pub polling_sources: Vec<String>,
+    pub destination_folder: String,
+    pub mirror_folders: Vec<String>,
+    pub remote_destinations: Vec<RemoteConfig>,
+    pub watch_exclude_dirs: Vec<String>,
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    pub storage_format: StorageFormat,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            source_folders: Vec::new(),
+            polling_sources: Vec::new(),
+            destination_folder: String::new(),
+            mirror_folders: Vec::new(),
+            remote_destinations: Vec::new(),
+            watch_exclude_dirs: Vec::new(),
+            include_patterns: default_include_patterns(),
+            exclude_patterns: default_exclude_patterns(),
+            storage_format: StorageFormat::Folder,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub start_on_boot: bool,
+    pub autostart_mode: AutostartMode,
+    pub active_profile: String,
+    pub profiles: Vec<Profile>,
+    // Older configs have a single `source_folder` string
+    #[serde(alias = "source_folder", deserialize_with = "string_or_list")]
+    pub source_folders: Vec<String>,
+    // Sources that are rescanned every `poll_interval_secs` instead of watched, for network
+    // shares and drives the native watch misses events on
+    pub polling_sources: Vec<String>,
+    pub poll_interval_secs: u64,
+    pub destination_folder: String,
+    // Extra destinations that receive a copy of every backup, e.g. a second drive or a NAS
+    pub mirror_folders: Vec<String>,
+    // Object storage every backup to the main destination is uploaded to
+    pub remote_destinations: Vec<RemoteConfig>,
+    pub stale_warning_enabled: bool,
+    pub stale_after_minutes: u64,
+    pub fsync_writes: bool,
+    // Read every copy back and compare checksums with the source
+    pub verify_copies: bool,
+    pub encrypt_backups: bool,
+    pub prometheus_textfile: Option<String>,
+    // Port of the HTTP API on 127.0.0.1; None to not run it
+    pub api_port: Option<u16>,
+    // Finished matches and errors are posted here
+    pub discord_webhook_url: Option<String>,
+    // Every finished or failed backup is POSTed here as JSON
+    pub webhook_url: Option<String>,
+    pub desktop_notifications: DesktopNotifications,
+    // Shell command run after each successful backup to the main destination
+    pub post_backup_command: Option<String>,
+    // The r6-dissect program that reads the statistics of every backed-up match
+    pub r6_dissect_path: Option<String>,
+    // Show the watcher status in Discord, as the Rich Presence of this Discord application
+    pub discord_presence: bool,
+    pub discord_application_id: String,
+    pub initial_sync_limit: Option<usize>,
+    // How long the watcher waits for a burst of changes to settle before backing them up
+    pub debounce_ms: u64,
+    // Files are only backed up once their size and modification time stayed the same this
+    // long, so replays still being written are not copied cut short; 0 to not wait
+    pub quiet_period_secs: u64,
+    // How many files of a match are copied at the same time, up to MAX_COPY_THREADS
+    pub copy_threads: usize,
+    // Caps on the disk I/O of backups, so copying mid-match does not cause frame drops
+    pub io_limit_mb_per_sec: Option<u64>,
+    pub io_limit_ops_per_sec: Option<u32>,
+    // Copy with background I/O priority (Windows only)
+    pub background_io_priority: bool,
+    // Link or clone files instead of copying them where the destination allows it
+    pub copy_mode: CopyMode,
+    // Keep identical files once in a store folder in the destination, hard-linked into matches
+    pub dedupe_files: bool,
+    // Skip, recreate or follow symbolic links in the source
+    pub symlinks: SymlinkMode,
+    // Update, keep, replace or keep both of files that are already backed up
+    pub conflict_policy: ConflictPolicy,
+    // Mirror mode: backups of matches deleted from the source, other than by the game's own
+    // cleanup, go to a trash folder in the destination once the grace period is over
+    pub mirror_deletions: bool,
+    pub mirror_deletion_grace_minutes: u64,
+    // Delete finished matches from the source once their backup is verified, to free space on
+    // the game's drive; rules out mirror mode
+    pub move_after_verify: bool,
+    // Back up every run into a new dated folder in the destination, with the files that did
+    // not change hard-linked from the previous one
+    pub snapshot_runs: bool,
+    // Keep this many earlier versions of each backed-up file that changed, in a .versions
+    // folder in the destination, instead of replacing them
+    pub keep_file_versions: Option<usize>,
+    // Move pruned backups to the system's recycle bin instead of deleting them for good
+    pub prune_to_recycle_bin: bool,
+    // How much goes into the log files in the log folder
+    pub log_level: LogLevel,
+    // Dark or light window, or the one the system is set to
+    pub theme: Theme,
+    // Language of the window and the tray menu
+    pub language: Language,
+    // Size and position of the window when it was last closed
+    pub window: Option<WindowGeometry>,
+    // The source folders are compared with the manifest this often, to catch missed changes
+    pub rescan_interval_minutes: Option<u64>,
+    // Time of day (HH:MM) at which everything in the source folders is backed up, whether
+    // or not watching; None to not schedule it
+    pub scheduled_sync_time: Option<String>,
+    // When the scheduled sync last started, in seconds since the Unix epoch
+    pub last_scheduled_sync: Option<i64>,
+    // Times of day (HH:MM) between which changes are queued but not backed up, e.g. while
+    // streaming; None to back up around the clock
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    // Subfolders of the source, relative to it, that are never backed up
+    pub watch_exclude_dirs: Vec<String>,
+    // Merge new and changed files into matches that were already backed up
+    pub incremental_backups: bool,
+    // Folder copies or one archive per match, and the tar.zst compression level
+    pub storage_format: StorageFormat,
+    pub zstd_level: i32,
+    // Glob patterns for the file names that are (not) backed up
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    // Retention policy; old matches are pruned from the destination after each backup
+    pub keep_last_matches: Option<usize>,
+    pub max_backup_age_days: Option<u64>,
+    // Size limit of the destination in GB; the oldest matches are deleted to stay below it
+    pub destination_quota_gb: Option<u64>,
+    // Free space in GB to leave on the destination's drive; backups that would go below it
+    // are held until there is room
+    pub min_free_space_gb: Option<u64>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            start_on_boot: false,
+            autostart_mode: AutostartMode::Desktop,
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            profiles: Vec::new(),
+            source_folders: Vec::new(),
+            polling_sources: Vec::new(),
+            poll_interval_secs: 10,
+            destination_folder: String::new(),
+            mirror_folders: Vec::new(),
+            remote_destinations: Vec::new(),
+            stale_warning_enabled: false,
+            stale_after_minutes: 60,
+            fsync_writes: false,
+            verify_copies: true,
+            encrypt_backups: false,
+            prometheus_textfile: None,
+            api_port: None,
+            discord_webhook_url: None,
+            webhook_url: None,
+            desktop_notifications: DesktopNotifications::default(),
+            post_backup_command: None,
+            r6_dissect_path: None,
+            discord_presence: false,
+            discord_application_id: String::new(),
+            initial_sync_limit: None,
+            debounce_ms: 500,
+            quiet_period_secs: 3,
+            copy_threads: 2,
+            io_limit_mb_per_sec: None,
+            io_limit_ops_per_sec: None,
+            background_io_priority: false,
+            copy_mode: CopyMode::Copy,
+            dedupe_files: false,
+            symlinks: SymlinkMode::Skip,
+            conflict_policy: ConflictPolicy::Merge,
+            mirror_deletions: false,
+            mirror_deletion_grace_minutes: 60,
+            move_after_verify: false,
+            snapshot_runs: false,
+            keep_file_versions: None,
+            prune_to_recycle_bin: true,
+            log_level: LogLevel::Info,
+            theme: Theme::System,
+            language: Language::System,
+            window: None,
+            rescan_interval_minutes: Some(15),
+            scheduled_sync_time: None,
+            last_scheduled_sync: None,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            watch_exclude_dirs: Vec::new(),
+            incremental_backups: true,
+            storage_format: StorageFormat::Folder,
+            zstd_level: DEFAULT_ZSTD_LEVEL,
+            include_patterns: default_include_patterns(),
+            exclude_patterns: default_exclude_patterns(),
+            keep_last_matches: None,
+            max_backup_age_days: None,
+            destination_quota_gb: None,
+            min_free_space_gb: Some(1),
+        }
+    }
+}
+
+impl AppConfig {
+    // Resolves the config file for this process from the command line and the environment
+    pub fn resolve_path() -> PathBuf {
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        resolve_config_path(
+            config_override_from_args(std::env::args().skip(1)),
+            dirs::config_dir(),
+            exe_dir,
+        )
+    }
+
+    pub fn load(config_path: &Path) -> Self {
+        Self::read(config_path).unwrap_or_default()
+    }
+
+    // Like load, but fails on a missing or broken file instead of falling back to defaults
+    pub fn read(config_path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(config_path)
+            .map_err(|e| format!("Error reading {}: {}", config_path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| format!("Error in {}: {}", config_path.display(), e))
+    }
+
+    // How the source folder `source` is watched
+    pub fn watch_options(&self, source: &Path) -> WatchOptions {
+        WatchOptions {
+            debounce: Duration::from_millis(self.debounce_ms),
+            polling: self
+                .polling_sources
+                .iter()
+                .any(|folder| Path::new(&normalize_path_input(folder)) == source),
+            poll_interval: Duration::from_secs(self.poll_interval_secs.max(1)),
+        }
+    }
+
+    pub fn rescan_schedule(&self) -> RescanSchedule {
+        RescanSchedule {
+            interval: self
+                .rescan_interval_minutes
+                .map(|minutes| Duration::from_secs(minutes.max(1) * 60)),
+            ..Default::default()
+        }
+    }
+
+    // Start and end of the quiet hours, if both are set and valid
+    pub fn quiet_hours(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let start = parse_time_of_day(self.quiet_hours_start.as_deref()?)?;
+        let end = parse_time_of_day(self.quiet_hours_end.as_deref()?)?;
+        Some((start, end))
+    }
+
+    // How long mirror mode waits before deleting a backup, if it is on
+    pub fn mirror_deletion_grace(&self) -> Option<Duration> {
+        (self.mirror_deletions && !self.move_after_verify)
+            .then(|| Duration::from_secs(self.mirror_deletion_grace_minutes * 60))
+    }
+
+    pub fn io_limits(&self) -> IoLimits {
+        IoLimits {
+            bytes_per_sec: self.io_limit_mb_per_sec.map(|mb| mb.max(1) * 1024 * 1024),
+            ops_per_sec: self.io_limit_ops_per_sec.map(|ops| ops.max(1)),
+            background_priority: self.background_io_priority,
+        }
+    }
+
+    // Every profile name, the active one included, sorted
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .profiles
+            .iter()
+            .map(|profile| profile.name.clone())
+            .chain([self.active_profile.clone()])
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn active_settings(&self) -> Profile {
+        Profile {
+            name: self.active_profile.clone(),
+            source_folders: self.source_folders.clone(),
+            polling_sources: self.polling_sources.clone(),
+            destination_folder: self.destination_folder.clone(),
+            mirror_folders: self.mirror_folders.clone(),
+            remote_destinations: self.remote_destinations.clone(),
+            watch_exclude_dirs: self.watch_exclude_dirs.clone(),
+            include_patterns: self.include_patterns.clone(),
+            exclude_patterns: self.exclude_patterns.clone(),
+            storage_format: self.storage_format,
+        }
+    }
+
+    fn activate(&mut self, profile: Profile) {
+        self.profiles.push(self.active_settings());
+        self.active_profile = profile.name;
+        self.source_folders = profile.source_folders;
+        self.polling_sources = profile.polling_sources;
+        self.destination_folder = profile.destination_folder;
+        self.mirror_folders = profile.mirror_folders;
+        self.remote_destinations = profile.remote_destinations;
+        self.watch_exclude_dirs = profile.watch_exclude_dirs;
+        self.include_patterns = profile.include_patterns;
+        self.exclude_patterns = profile.exclude_patterns;
+        self.storage_format = profile.storage_format;
+    }
+
+    // Stores the active settings under their profile and loads those of `name`
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| format!("No profile named '{}'", name))?;
+        let profile = self.profiles.remove(index);
+        self.activate(profile);
+        Ok(())
+    }
+
+    // Creates an empty profile and switches to it
+    pub fn add_profile(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Enter a name for the new profile".to_string());
+        }
+        if self.profile_names().iter().any(|known| known == name) {
+            return Err(format!("A profile named '{}' already exists", name));
+        }
+        self.activate(Profile {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    // Deletes the active profile and switches to the first remaining one
+    pub fn remove_active_profile(&mut self) -> Result<(), String> {
+        if self.profiles.is_empty() {
+            return Err("The last profile cannot be deleted".to_string());
+        }
+        let next = self.profiles.remove(0);
+        self.activate(next);
+        self.profiles.pop();
+        Ok(())
+    }
+
+    pub fn save(&self, config_path: &Path) {
+        if let Some(config_dir) = config_path.parent() {
+            if fs::create_dir_all(config_dir).is_err() {
+                return;
+            }
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(config_path, json);
+        }
+    }
+}
+
+fn string_or_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::String(folder) if folder.is_empty() => Vec::new(),
+        StringOrList::String(folder) => vec![folder],
+        StringOrList::List(folders) => folders,
+    })
+}
+
+// Picks the config file location: an explicit `--config <path>` wins, then the platform
+// config directory, then `config.json` next to the executable for minimal environments
+// where no config directory can be determined
+pub fn resolve_config_path(
+    override_path: Option<PathBuf>,
+    config_dir: Option<PathBuf>,
+    exe_dir: Option<PathBuf>,
+) -> PathBuf {
+    if let Some(path) = override_path {
+        return path;
+    }
+    if let Some(config_dir) = config_dir {
+        return config_dir.join("siegesaver").join("config.json");
+    }
+    exe_dir.unwrap_or_default().join("config.json")
+}
+
+// Extracts the value of `--config <path>` or `--config=<path>` from the command line
+pub fn config_override_from_args(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+// Cleans up a folder path typed or pasted by the user: trims whitespace, strips surrounding
+// quotes, converts both slash styles to the platform separator and collapses repeated separators
+pub fn normalize_path_input(input: &str) -> String {
+    let mut trimmed = input.trim();
+    for quote in ['"', '\''] {
+        if trimmed.len() >= 2 && trimmed.starts_with(quote) && trimmed.ends_with(quote) {
+            trimmed = trimmed[1..trimmed.len() - 1].trim();
+        }
+    }
+
+    let separator = std::path::MAIN_SEPARATOR;
+    let mut normalized = String::with_capacity(trimmed.len());
+    for (i, c) in trimmed.chars().enumerate() {
+        let c = if c == '/' || c == '\\' { separator } else { c };
+        // Keep the leading double separator of Windows UNC paths (\\server\share)
+        let is_unc_prefix = cfg!(windows) && i == 1;
+        if c == separator && normalized.ends_with(separator) && !is_unc_prefix {
+            continue;
+        }
+        normalized.push(c);
+    }
+    normalized
+}
+
+// What a folder field points to, shown next to it while it is typed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FolderCheck {
+    Ready,
+    Missing,
+    // A file, or anything else that is not a folder
+    NotAFolder,
+    // The folder exists, but a file could not be created in it
+    ReadOnly,
+}
+
+// Checks the folder a field points to. With `writable`, a file is created in it and removed
+// again, as permissions alone do not tell, e.g. for read-only shares or ACLs on Windows.
+pub fn check_folder(input: &str, writable: bool) -> FolderCheck {
+    let path = PathBuf::from(normalize_path_input(input));
+    match fs::metadata(&path) {
+        Err(_) => FolderCheck::Missing,
+        Ok(metadata) if !metadata.is_dir() => FolderCheck::NotAFolder,
+        Ok(_) if !writable => FolderCheck::Ready,
+        Ok(_) => {
+            let probe = path.join(".siegesaver-write-test");
+            match fs::File::create(&probe) {
+                Ok(_) => {
+                    let _ = fs::remove_file(&probe);
+                    FolderCheck::Ready
+                }
+                Err(_) => FolderCheck::ReadOnly,
+            }
+        }
+    }
+}
+
+pub fn get_auto_launch() -> Result<AutoLaunch, String> {
+    let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
+
+    AutoLaunchBuilder::new()
+        .set_app_name("SiegeSaver")
+        .set_app_path(&exe_path.to_string_lossy())
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_config_serialization() {
+        let config = AppConfig {
+            start_on_boot: true,
+            source_folders: vec!["/test/source".to_string(), "/test/other".to_string()],
+            destination_folder: "/test/dest".to_string(),
+            theme: Theme::Light,
+            window: Some(WindowGeometry {
+                width: 800.0,
+                height: 600.0,
+                x: Some(-1280.0),
+                y: None,
+                maximized: true,
+            }),
+            ..Default::default()
+        };
+
+        // Test serialization
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("start_on_boot"));
+        assert!(json.contains("true"));
+        assert!(json.contains("/test/source"));
+        assert!(json.contains("/test/dest"));
+
+        // Test deserialization
+        let deserialized: AppConfig = serde_json::from_str(&json).unwrap();
+        assert!(deserialized.start_on_boot);
+        assert_eq!(deserialized.source_folders, ["/test/source", "/test/other"]);
+        assert_eq!(deserialized.destination_folder, "/test/dest");
+        assert_eq!(deserialized.theme, Theme::Light);
+        assert_eq!(deserialized.window, config.window);
+    }
+
+    #[test]
+    fn test_app_config_default() {
+        let config = AppConfig::default();
+        assert!(!config.start_on_boot);
+        assert!(config.source_folders.is_empty());
+        assert_eq!(config.destination_folder, "");
+        assert_eq!(config.theme, Theme::System);
+        assert_eq!(config.window, None);
+        assert_eq!(config.quiet_hours(), None);
+        assert_eq!(config.mirror_deletion_grace(), None);
+        let config = AppConfig {
+            quiet_hours_start: Some("22:00".to_string()),
+            quiet_hours_end: Some("2 am".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.quiet_hours(), None);
+    }
+
+    #[test]
+    fn test_watch_options() {
+        let config = AppConfig {
+            source_folders: vec!["/games/a".to_string(), "/mnt/share/b".to_string()],
+            polling_sources: vec![" \"/mnt/share/b\" ".to_string()],
+            poll_interval_secs: 30,
+            ..Default::default()
+        };
+        let options = config.watch_options(Path::new("/games/a"));
+        assert!(!options.polling);
+        assert_eq!(options.debounce, Duration::from_millis(500));
+        let options = config.watch_options(Path::new("/mnt/share/b"));
+        assert!(options.polling);
+        assert_eq!(options.poll_interval, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_read_config() {
+        let test_dir = std::env::temp_dir().join("siegesaver_read_config_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let path = test_dir.join("config.json");
+        assert!(AppConfig::read(&path).is_err());
+        // A half-written file is an error, not a reset to defaults
+        fs::write(&path, r#"{"destination_folder": "/backup"#).unwrap();
+        assert!(AppConfig::read(&path).is_err());
+        assert_eq!(AppConfig::load(&path).destination_folder, "");
+        fs::write(&path, r#"{"destination_folder": "/backup"}"#).unwrap();
+        assert_eq!(
+            AppConfig::read(&path).unwrap().destination_folder,
+            "/backup"
+        );
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut config = AppConfig {
+            source_folders: vec!["/siege/replays".to_string()],
+            destination_folder: "/backup/siege".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.profile_names(), [DEFAULT_PROFILE_NAME]);
+        assert!(config.add_profile(" ").is_err());
+        assert!(config.add_profile(DEFAULT_PROFILE_NAME).is_err());
+
+        config.add_profile("Other Game").unwrap();
+        assert_eq!(config.active_profile, "Other Game");
+        assert!(config.source_folders.is_empty());
+        assert_eq!(config.include_patterns, default_include_patterns());
+        assert_eq!(config.exclude_patterns, default_exclude_patterns());
+        config.destination_folder = "/backup/other".to_string();
+
+        // Switching stores the active settings and restores the other profile's
+        config.switch_profile(DEFAULT_PROFILE_NAME).unwrap();
+        assert_eq!(config.source_folders, ["/siege/replays"]);
+        assert_eq!(config.destination_folder, "/backup/siege");
+        assert!(config.switch_profile("Missing").is_err());
+
+        let json = serde_json::to_string(&config).unwrap();
+        let mut config: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.profile_names(), [DEFAULT_PROFILE_NAME, "Other Game"]);
+        config.switch_profile("Other Game").unwrap();
+        assert_eq!(config.destination_folder, "/backup/other");
+
+        config.remove_active_profile().unwrap();
+        assert_eq!(config.active_profile, DEFAULT_PROFILE_NAME);
+        assert_eq!(config.profile_names(), [DEFAULT_PROFILE_NAME]);
+        assert!(config.remove_active_profile().is_err());
+    }
+
+    #[test]
+    fn test_single_source_folder_config_is_migrated() {
+        let config: AppConfig =
+            serde_json::from_str(r#"{"source_folder": "/test/source"}"#).unwrap();
+        assert_eq!(config.source_folders, ["/test/source"]);
+        let config: AppConfig = serde_json::from_str(r#"{"source_folder": ""}"#).unwrap();
+        assert!(config.source_folders.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_path_input() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let expected = format!("C:{sep}Games{sep}Replays");
+
+        assert_eq!(normalize_path_input("  C:/Games/Replays  "), expected);
+        assert_eq!(normalize_path_input("\"C:\\Games\\Replays\""), expected);
+        assert_eq!(normalize_path_input("'C://Games\\\\Replays'"), expected);
+        assert_eq!(
+            normalize_path_input("/home/user/My Replays"),
+            format!("{sep}home{sep}user{sep}My Replays")
+        );
+        assert_eq!(normalize_path_input("   "), "");
+        assert_eq!(normalize_path_input("\"\""), "");
+    }
+
+    #[test]
+    fn test_check_folder() {
+        let test_dir = std::env::temp_dir().join("siegesaver_check_folder_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let file = test_dir.join("replay.rec");
+        fs::write(&file, b"replay").unwrap();
+        let folder = test_dir.display().to_string();
+
+        assert_eq!(check_folder(&folder, false), FolderCheck::Ready);
+        assert_eq!(
+            check_folder(&format!(" \"{}\" ", folder), true),
+            FolderCheck::Ready
+        );
+        // The probe file is removed again
+        assert_eq!(fs::read_dir(&test_dir).unwrap().count(), 1);
+        assert_eq!(
+            check_folder(&file.display().to_string(), true),
+            FolderCheck::NotAFolder
+        );
+        assert_eq!(
+            check_folder(&test_dir.join("missing").display().to_string(), false),
+            FolderCheck::Missing
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_config_path_resolution() {
+        let config_dir = Some(PathBuf::from("/home/user/.config"));
+        let exe_dir = Some(PathBuf::from("/opt/siegesaver"));
+
+        // An explicit override always wins
+        let args = ["--config", "/tmp/custom.json"].map(String::from);
+        assert_eq!(
+            resolve_config_path(
+                config_override_from_args(args),
+                config_dir.clone(),
+                exe_dir.clone()
+            ),
+            PathBuf::from("/tmp/custom.json")
+        );
+        let args = ["--minimized", "--config=/tmp/other.json"].map(String::from);
+        assert_eq!(
+            config_override_from_args(args),
+            Some(PathBuf::from("/tmp/other.json"))
+        );
+        assert_eq!(config_override_from_args(["--config".to_string()]), None);
+
+        // Platform config directory by default
+        assert_eq!(
+            resolve_config_path(None, config_dir, exe_dir.clone()),
+            PathBuf::from("/home/user/.config/siegesaver/config.json")
+        );
+
+        // Next to the executable when no config directory exists
+        assert_eq!(
+            resolve_config_path(None, None, exe_dir),
+            PathBuf::from("/opt/siegesaver/config.json")
+        );
+        assert_eq!(
+            resolve_config_path(None, None, None),
+            PathBuf::from("config.json")
+        );
+    }
+
+    #[test]
+    fn test_get_auto_launch() {
+        // Test that we can create an AutoLaunch instance
+        let result = get_auto_launch();
+        // This should succeed as long as we can get the current exe path
+        assert!(result.is_ok());
+    }
+}
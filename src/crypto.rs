@@ -0,0 +1,228 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::storage::{StorageFormat, DEFAULT_ZSTD_LEVEL};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use std::fs;
+use std::path::Path;
+
+// Encrypted backups start with this magic, followed by the Argon2 salt and the AES-GCM nonce
+pub const ENCRYPTION_MAGIC: &[u8; 8] = b"SSENC001";
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 12;
+pub const HEADER_LEN: usize = ENCRYPTION_MAGIC.len() + SALT_LEN + NONCE_LEN;
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+// AES-256-GCM key derived from the user's passphrase with Argon2.
+// The passphrase and key only ever live in memory; neither is saved nor logged.
+pub struct EncryptionKey {
+    salt: [u8; SALT_LEN],
+    cipher: Aes256Gcm,
+}
+
+impl EncryptionKey {
+    pub fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, String> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+        Ok(Self {
+            salt,
+            cipher: Aes256Gcm::new(&key.into()),
+        })
+    }
+
+    // Derives a key with a fresh random salt, used for one watching session
+    pub fn generate(passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt)
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "Encryption failed".to_string())?;
+
+        let mut output = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        output.extend_from_slice(ENCRYPTION_MAGIC);
+        output.extend_from_slice(&self.salt);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+}
+
+// Decrypts backups written by `EncryptionKey::encrypt`, re-deriving the key only when the salt changes
+pub struct Decryptor {
+    passphrase: String,
+    key: Option<EncryptionKey>,
+}
+
+impl Decryptor {
+    pub fn new(passphrase: &str) -> Self {
+        Self {
+            passphrase: passphrase.to_string(),
+            key: None,
+        }
+    }
+
+    pub fn decrypt(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() < HEADER_LEN || &data[..ENCRYPTION_MAGIC.len()] != ENCRYPTION_MAGIC {
+            return Err("Not a SiegeSaver encrypted file".to_string());
+        }
+        let (salt, rest) = data[ENCRYPTION_MAGIC.len()..].split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let salt: [u8; SALT_LEN] = salt.try_into().expect("salt has a fixed length");
+
+        if self.key.as_ref().map(|k| k.salt) != Some(salt) {
+            self.key = Some(EncryptionKey::derive(&self.passphrase, salt)?);
+        }
+        let key = self.key.as_ref().expect("key was just derived");
+
+        key.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Decryption failed: wrong passphrase or corrupted file".to_string())
+    }
+}
+
+// Recursively decrypts every `.enc` file in `source` into `destination`, returning how many were
+// restored. Encrypted match archives are unpacked back into their match folder.
+pub fn decrypt_directory(
+    source: &Path,
+    destination: &Path,
+    decryptor: &mut Decryptor,
+) -> std::io::Result<usize> {
+    fs::create_dir_all(destination)?;
+
+    let mut restored = 0;
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(folder_name) = path.file_name() {
+                restored += decrypt_directory(&path, &destination.join(folder_name), decryptor)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == ENCRYPTED_EXTENSION)
+        {
+            if let Some(file_name) = path.file_stem() {
+                let data = fs::read(&path)?;
+                let plaintext = decryptor
+                    .decrypt(&data)
+                    .map_err(|e| std::io::Error::other(format!("{}: {}", path.display(), e)))?;
+                match StorageFormat::from_archive_name(&file_name.to_string_lossy()) {
+                    Some((format, folder_name)) => {
+                        restored += format
+                            .backend(DEFAULT_ZSTD_LEVEL)
+                            .extract(&plaintext, &destination.join(folder_name))
+                            .map_err(|e| {
+                                std::io::Error::other(format!("{}: {}", path.display(), e))
+                            })?;
+                    }
+                    None => {
+                        fs::write(destination.join(file_name), plaintext)?;
+                        restored += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{backup_path, copy_directory_recursive, BackupOptions, RealFs};
+    use crate::config::AppConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_encryption_round_trip() {
+        let key = EncryptionKey::generate("correct horse battery staple").unwrap();
+        let plaintext = b"replay round data".repeat(100);
+        let encrypted = key.encrypt(&plaintext).unwrap();
+        assert_ne!(&encrypted[HEADER_LEN..], plaintext.as_slice());
+
+        let mut decryptor = Decryptor::new("correct horse battery staple");
+        assert_eq!(decryptor.decrypt(&encrypted).unwrap(), plaintext);
+
+        let mut wrong = Decryptor::new("wrong passphrase");
+        assert!(wrong.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_backup_and_decrypt_directory() {
+        let test_dir = std::env::temp_dir().join("siegesaver_encryption_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("Match-2025-11-23-001");
+        let backup = test_dir.join("backup").join("Match-2025-11-23-001");
+        let restored = test_dir.join("restored");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("round1.rec"), "round 1 data").unwrap();
+
+        let options = BackupOptions {
+            encryption: Some(Arc::new(EncryptionKey::generate("secret").unwrap())),
+            ..Default::default()
+        };
+        copy_directory_recursive(&RealFs, &source, &backup, &options).unwrap();
+        assert!(!backup.join("round1.rec").exists());
+        assert!(backup.join("round1.rec.enc").exists());
+
+        let mut decryptor = Decryptor::new("secret");
+        let count = decrypt_directory(&backup, &restored, &mut decryptor).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            fs::read_to_string(restored.join("round1.rec")).unwrap(),
+            "round 1 data"
+        );
+
+        // The passphrase must never end up in the saved configuration
+        let config = AppConfig {
+            encrypt_backups: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("secret"));
+        assert!(!json.contains("passphrase"));
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_encrypted_archives_are_restored_as_folders() {
+        let test_dir = std::env::temp_dir().join("siegesaver_encrypted_archive_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("source");
+        let backup = test_dir.join("backup");
+        let restored = test_dir.join("restored");
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        fs::create_dir_all(&backup).unwrap();
+        fs::write(source.join("Match-A").join("round1.rec"), "round 1 data").unwrap();
+
+        let options = BackupOptions {
+            encryption: Some(Arc::new(EncryptionKey::generate("secret").unwrap())),
+            storage: StorageFormat::TarZst,
+            ..Default::default()
+        };
+        backup_path(&RealFs, &source.join("Match-A"), &source, &backup, &options).unwrap();
+        assert!(backup.join("Match-A.tar.zst.enc").exists());
+        assert!(!backup.join("Match-A.tar.zst").exists());
+
+        let mut decryptor = Decryptor::new("secret");
+        let count = decrypt_directory(&backup, &restored, &mut decryptor).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            fs::read_to_string(restored.join("Match-A").join("round1.rec")).unwrap(),
+            "round 1 data"
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{FileSystem, PART_EXTENSION};
+use crate::manifest::Manifest;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+// Folder in the destination that keeps every deduplicated file once, named by its SHA-256.
+// The files in the match folders are hard links to these blobs.
+pub const DEDUPE_STORE_NAME: &str = "siegesaver-store";
+
+// Where the file with the SHA-256 `sha256` is kept in `store`. Blobs are spread over
+// subfolders named by the first two hex digits, so no folder gets too large.
+pub fn blob_path(store: &Path, sha256: &str) -> PathBuf {
+    store.join(sha256.get(..2).unwrap_or(sha256)).join(sha256)
+}
+
+// Deletes the blobs that no file in the manifest has the hash of anymore, e.g. after the
+// matches linking to them were pruned. Returns how many blobs and bytes were freed.
+pub fn collect_garbage(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    manifest: &Manifest,
+) -> std::io::Result<(usize, u64)> {
+    let store = destination_folder.join(DEDUPE_STORE_NAME);
+    if fs.metadata(&store).is_err() {
+        return Ok((0, 0));
+    }
+    let referenced: BTreeSet<&str> = manifest
+        .entries
+        .values()
+        .flat_map(|entry| entry.files.values())
+        .map(|file| file.sha256.as_str())
+        .collect();
+    let (mut blobs, mut bytes) = (0, 0);
+    for folder in fs.read_dir(&store)? {
+        if !fs.metadata(&folder)?.is_dir {
+            continue;
+        }
+        for blob in fs.read_dir(&folder)? {
+            let Some(name) = blob.file_name().map(|name| name.to_string_lossy()) else {
+                continue;
+            };
+            // Part files may belong to a backup that is still running
+            if referenced.contains(name.as_ref())
+                || blob.extension().is_some_and(|ext| ext == PART_EXTENSION)
+            {
+                continue;
+            }
+            let len = fs.metadata(&blob)?.len;
+            fs.remove(&blob)?;
+            blobs += 1;
+            bytes += len;
+        }
+    }
+    Ok((blobs, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{BackupEngine, RealFs};
+    use crate::retention::RetentionPolicy;
+    use std::fs;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_identical_files_are_stored_once() {
+        let test_dir = std::env::temp_dir().join("siegesaver_dedupe_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let destination = test_dir.join("backup");
+        for name in ["Match-A", "Match-B"] {
+            fs::create_dir_all(source.join(name)).unwrap();
+            fs::write(source.join(name).join("round1.rec"), "same round").unwrap();
+        }
+        fs::write(source.join("Match-B").join("round2.rec"), "round 2").unwrap();
+        fs::create_dir_all(&destination).unwrap();
+
+        let engine = BackupEngine::new(source.clone(), destination.clone(), Default::default())
+            .with_dedupe(true);
+        let (status_tx, status_rx) = channel();
+        engine.backup(&source.join("Match-A"), &status_tx);
+        engine.backup(&source.join("Match-B"), &status_tx);
+        assert_eq!(
+            fs::read(destination.join("Match-B").join("round1.rec")).unwrap(),
+            b"same round"
+        );
+
+        // One blob per distinct file, and the store is not mistaken for a match
+        let manifest = Manifest::load(&RealFs, &destination).unwrap();
+        assert_eq!(
+            manifest.entries.keys().collect::<Vec<_>>(),
+            ["Match-A", "Match-B"]
+        );
+        let store = destination.join(DEDUPE_STORE_NAME);
+        let shared = &manifest.entries["Match-A"].files["Match-A/round1.rec"].sha256;
+        assert_eq!(fs::read(blob_path(&store, shared)).unwrap(), b"same round");
+        let blobs = fs::read_dir(&store)
+            .unwrap()
+            .map(|folder| fs::read_dir(folder.unwrap().path()).unwrap().count())
+            .sum::<usize>();
+        assert_eq!(blobs, 2);
+
+        // Blobs of pruned matches are deleted, the ones still linked to are kept
+        let stray = blob_path(&store, "00stray");
+        fs::create_dir_all(stray.parent().unwrap()).unwrap();
+        fs::write(&stray, "left over").unwrap();
+        let engine = engine.with_retention(RetentionPolicy {
+            keep_last: Some(1),
+            max_age_days: None,
+        });
+        assert_eq!(engine.prune(false, &status_tx), 1);
+        let manifest = Manifest::load(&RealFs, &destination).unwrap();
+        assert!(blob_path(&store, shared).exists());
+        assert!(!stray.exists());
+        assert!(status_rx
+            .try_iter()
+            .any(|event| event.to_string().starts_with("Removed 1 unused files")));
+        assert_eq!(
+            collect_garbage(&RealFs, &destination, &manifest).unwrap(),
+            (0, 0)
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::build_info;
+use crate::config::{normalize_path_input, AppConfig};
+use crate::logging::LogRecord;
+use crate::status::Severity;
+use std::fmt::Write;
+
+// Replaces the configured folders, and the home folder, with placeholders such as
+// `<source 1>`, so a report can be shared without showing account or folder names
+struct Anonymizer {
+    // Longest first, so a folder inside another one is replaced as a whole
+    replacements: Vec<(String, String)>,
+}
+
+impl Anonymizer {
+    fn new(config: &AppConfig) -> Self {
+        let folders = |folders: &[String], name: &str| -> Vec<(String, String)> {
+            folders
+                .iter()
+                .map(|folder| normalize_path_input(folder))
+                .filter(|folder| !folder.is_empty())
+                .enumerate()
+                .map(|(index, folder)| (folder, format!("<{} {}>", name, index + 1)))
+                .collect()
+        };
+        let mut replacements = folders(&config.source_folders, "source");
+        replacements.extend(folders(&config.mirror_folders, "mirror"));
+        let destination = normalize_path_input(&config.destination_folder);
+        if !destination.is_empty() {
+            replacements.push((destination, "<destination>".to_string()));
+        }
+        // A home folder of `/` would replace every separator
+        if let Some(home) = dirs::home_dir().filter(|home| home.parent().is_some()) {
+            replacements.push((home.display().to_string(), "~".to_string()));
+        }
+        replacements.sort_by_key(|(folder, _)| std::cmp::Reverse(folder.len()));
+        Self { replacements }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(text.to_string(), |text, (folder, placeholder)| {
+                text.replace(folder.as_str(), placeholder)
+            })
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// Text to attach to a bug report: the version, a summary of the settings without passwords
+// or keys, and `records` from the log. With `anonymize`, folder names are left out.
+pub fn diagnostics_report(config: &AppConfig, records: &[&LogRecord], anonymize: bool) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "{}", build_info());
+    let _ = writeln!(
+        report,
+        "System: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let list = |folders: &[String]| match folders.len() {
+        0 => "none".to_string(),
+        _ => folders.join(", "),
+    };
+    let _ = writeln!(report, "Sources: {}", list(&config.source_folders));
+    let _ = writeln!(report, "Destination: {}", config.destination_folder);
+    let _ = writeln!(report, "Mirrors: {}", list(&config.mirror_folders));
+    // Remote labels name buckets and servers
+    let remotes = if anonymize {
+        config.remote_destinations.len().to_string()
+    } else {
+        let labels: Vec<String> = config
+            .remote_destinations
+            .iter()
+            .map(|remote| remote.label())
+            .collect();
+        list(&labels)
+    };
+    let _ = writeln!(report, "Remote destinations: {}", remotes);
+    let _ = writeln!(
+        report,
+        "Storage: {}, encryption {}, incremental {}, copy mode {}, dedupe {}, symbolic links {}, \
+         existing files {}",
+        config.storage_format.label(),
+        on_off(config.encrypt_backups),
+        on_off(config.incremental_backups),
+        config.copy_mode.label(),
+        on_off(config.dedupe_files),
+        config.symlinks.label(),
+        config.conflict_policy.label()
+    );
+    let _ = writeln!(
+        report,
+        "Checks: verify copies {}, fsync {}, quiet period {} s, debounce {} ms, copy threads {}",
+        on_off(config.verify_copies),
+        on_off(config.fsync_writes),
+        config.quiet_period_secs,
+        config.debounce_ms,
+        config.copy_threads
+    );
+    let limit = |value: Option<u64>, unit: &str| match value {
+        Some(value) => format!("{} {}", value, unit),
+        None => "none".to_string(),
+    };
+    let _ = writeln!(
+        report,
+        "Limits: keep last {}, max age {}, quota {}, min free space {}",
+        limit(config.keep_last_matches.map(|keep| keep as u64), "matches"),
+        limit(config.max_backup_age_days, "days"),
+        limit(config.destination_quota_gb, "GB"),
+        limit(config.min_free_space_gb, "GB")
+    );
+    let _ = writeln!(report, "Log level: {}", config.log_level.label());
+    let _ = writeln!(report);
+    for record in records {
+        let level = match record.severity {
+            Severity::Info | Severity::Success => "INFO",
+            Severity::Warning => "WARN",
+            Severity::Error => "ERROR",
+        };
+        let _ = writeln!(
+            report,
+            "{} {:>5} {}",
+            record.time.format("%Y-%m-%d %H:%M:%S"),
+            level,
+            record.text
+        );
+    }
+    if anonymize {
+        report = Anonymizer::new(config).apply(&report);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_report() {
+        let config = AppConfig {
+            source_folders: vec!["/home/jane/Replays".to_string()],
+            destination_folder: "/media/jane/USB/Backups".to_string(),
+            ..Default::default()
+        };
+        let records = [LogRecord {
+            time: chrono::NaiveDate::from_ymd_opt(2025, 11, 23)
+                .unwrap()
+                .and_hms_opt(21, 4, 5)
+                .unwrap(),
+            severity: Severity::Error,
+            text: "Error copying /home/jane/Replays/Match-A to /media/jane/USB/Backups".to_string(),
+            detail: None,
+        }];
+        let records: Vec<&LogRecord> = records.iter().collect();
+
+        let report = diagnostics_report(&config, &records, false);
+        assert!(report.starts_with(&build_info()));
+        assert!(report.contains("Sources: /home/jane/Replays\n"));
+        assert!(report.contains(
+            "2025-11-23 21:04:05 ERROR Error copying /home/jane/Replays/Match-A to \
+             /media/jane/USB/Backups\n"
+        ));
+
+        let report = diagnostics_report(&config, &records, true);
+        assert!(!report.contains("jane"));
+        assert!(report.contains("Sources: <source 1>\n"));
+        assert!(report.contains("Destination: <destination>\n"));
+        assert!(report.contains("Error copying <source 1>/Match-A to <destination>\n"));
+    }
+}
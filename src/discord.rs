@@ -0,0 +1,164 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::status::{format_bytes, format_count, Severity, StatusEvent};
+use serde_json::{json, Value};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+// At most one message per interval; events in between are posted together with the next one
+pub const MIN_INTERVAL: Duration = Duration::from_secs(10);
+
+// Discord accepts up to 10 embeds per message
+const MAX_EMBEDS: usize = 10;
+
+const GREEN: u32 = 0x2e_cc_71;
+const RED: u32 = 0xe7_4c_3c;
+
+// The Discord embed for a finished match or an error; other events are not posted
+pub fn embed(event: &StatusEvent) -> Option<Value> {
+    match event {
+        StatusEvent::FolderBackedUp { name, detail }
+        | StatusEvent::FolderArchived { name, detail } => {
+            let mut fields = Vec::new();
+            if let Some(bytes) = detail.bytes {
+                fields
+                    .push(json!({ "name": "Size", "value": format_bytes(bytes), "inline": true }));
+            }
+            if let Some(files) = detail.files {
+                fields.push(
+                    json!({ "name": "Files", "value": format_count(files as usize), "inline": true }),
+                );
+            }
+            if let Some(duration) = detail.duration {
+                fields.push(json!({
+                    "name": "Took",
+                    "value": format!("{:.1} s", duration.as_secs_f64()),
+                    "inline": true,
+                }));
+            }
+            Some(json!({
+                "title": format!("Backed up {}", name),
+                "description": detail.destination.display().to_string(),
+                "color": GREEN,
+                "fields": fields,
+            }))
+        }
+        event if event.severity() == Severity::Error => Some(json!({
+            "title": "Backup error",
+            "description": event.to_string(),
+            "color": RED,
+        })),
+        _ => None,
+    }
+}
+
+// The webhook message for a batch of embeds; beyond what fits in one message, the last embed
+// counts the rest
+pub fn payload(embeds: &[Value]) -> Value {
+    let mut shown = embeds.to_vec();
+    if shown.len() > MAX_EMBEDS {
+        let hidden = shown.len() - (MAX_EMBEDS - 1);
+        shown.truncate(MAX_EMBEDS - 1);
+        shown.push(json!({
+            "description": format!("…and {} more events, see the status log", hidden),
+        }));
+    }
+    json!({ "username": "SiegeSaver", "embeds": shown })
+}
+
+// Posts backups and errors to a Discord webhook from a background thread, rate limited so
+// bursts end up in a few messages
+pub struct DiscordNotifier {
+    tx: Sender<Value>,
+}
+
+impl DiscordNotifier {
+    // Failed posts are reported on `status_tx` as warnings
+    pub fn spawn(url: String, status_tx: Sender<StatusEvent>) -> Self {
+        let (tx, rx) = channel();
+        std::thread::spawn(move || post_batches(&url, &rx, &status_tx));
+        Self { tx }
+    }
+
+    pub fn notify(&self, event: &StatusEvent) {
+        if let Some(embed) = embed(event) {
+            let _ = self.tx.send(embed);
+        }
+    }
+}
+
+// Runs until the notifier is dropped
+fn post_batches(url: &str, rx: &Receiver<Value>, status_tx: &Sender<StatusEvent>) {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build();
+    let mut last_post: Option<Instant> = None;
+    while let Ok(first) = rx.recv() {
+        if let Some(wait) = last_post.and_then(|posted| MIN_INTERVAL.checked_sub(posted.elapsed()))
+        {
+            std::thread::sleep(wait);
+        }
+        let mut embeds = vec![first];
+        embeds.extend(rx.try_iter());
+        let message = payload(&embeds);
+        let mut result = agent.post(url).send_json(&message);
+        // Discord's own rate limit says how long to wait before trying again
+        if let Err(ureq::Error::Status(429, response)) = &result {
+            let retry_after = response
+                .header("Retry-After")
+                .and_then(|seconds| seconds.parse::<f64>().ok())
+                .unwrap_or(5.0);
+            std::thread::sleep(Duration::from_secs_f64(retry_after.clamp(0.0, 60.0)));
+            result = agent.post(url).send_json(&message);
+        }
+        last_post = Some(Instant::now());
+        if let Err(e) = result {
+            let _ = status_tx.send(StatusEvent::Warning(format!(
+                "Discord notification failed: {}",
+                e
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::EventDetail;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_discord_embeds() {
+        let event = StatusEvent::FolderArchived {
+            name: "Match-2025-01-01_20-00-00".to_string(),
+            detail: EventDetail {
+                source: PathBuf::from("replays/Match-2025-01-01_20-00-00"),
+                destination: PathBuf::from("backup/Match-2025-01-01_20-00-00.tar.zst"),
+                files: Some(1200),
+                bytes: Some(128 * 1024 * 1024),
+                duration: None,
+                error: None,
+            },
+        };
+        let embed = embed(&event).unwrap();
+        assert_eq!(embed["title"], "Backed up Match-2025-01-01_20-00-00");
+        assert_eq!(embed["fields"][0]["value"], "128.0 MB");
+        assert_eq!(embed["fields"][1]["value"], "1,200");
+        assert_eq!(embed["fields"].as_array().unwrap().len(), 2);
+
+        let error = super::embed(&StatusEvent::Error("Disk full".to_string())).unwrap();
+        assert_eq!(error["description"], "Disk full");
+        assert!(super::embed(&StatusEvent::Info("Watching".to_string())).is_none());
+        assert!(super::embed(&StatusEvent::Warning("Slow".to_string())).is_none());
+
+        // A burst of events is cut to one message
+        let message = payload(&vec![error; 25]);
+        let embeds = message["embeds"].as_array().unwrap();
+        assert_eq!(embeds.len(), MAX_EMBEDS);
+        assert_eq!(
+            embeds[MAX_EMBEDS - 1]["description"],
+            "…and 16 more events, see the status log"
+        );
+        assert_eq!(payload(&[json!({})])["embeds"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use serde_json::Value;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Numbers the output files of runs, so engines running at the same time do not share one
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+// Reads the match folder `match_folder` with the r6-dissect program at `program` and returns
+// its JSON: every round with its players, teams and kill feed, and the scoreboard of the match
+pub fn dissect_match(program: &Path, match_folder: &Path) -> std::io::Result<Value> {
+    let output = std::env::temp_dir().join(format!(
+        "siegesaver-dissect-{}-{}.json",
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut command = Command::new(program);
+    command
+        .arg(match_folder)
+        .arg("-o")
+        .arg(&output)
+        .stdin(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // Keep it from opening a console window next to the GUI
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let result = command.output().and_then(|finished| {
+        if !finished.status.success() {
+            let stderr = String::from_utf8_lossy(&finished.stderr);
+            let last_line = stderr
+                .lines()
+                .rfind(|line| !line.trim().is_empty())
+                .unwrap_or_default();
+            return Err(std::io::Error::other(format!(
+                "{} {}",
+                finished.status,
+                last_line.trim()
+            )));
+        }
+        let json = std::fs::read(&output)?;
+        serde_json::from_slice(&json).map_err(std::io::Error::other)
+    });
+    let _ = std::fs::remove_file(&output);
+    result
+}
+
+// Whether `folder` has replays r6-dissect can read
+pub fn has_replays(fs: &dyn FileSystem, folder: &Path) -> bool {
+    fs.read_dir(folder).is_ok_and(|paths| {
+        paths.iter().any(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("rec"))
+        })
+    })
+}
+
+// How the match went for the player who recorded it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MatchSummary {
+    pub username: String,
+    pub rounds_won: u64,
+    pub rounds_lost: u64,
+    pub kills: u64,
+    pub deaths: u64,
+}
+
+impl MatchSummary {
+    // None for a draw
+    pub fn won(&self) -> Option<bool> {
+        (self.rounds_won != self.rounds_lost).then_some(self.rounds_won > self.rounds_lost)
+    }
+
+    // Kills per death, counting no deaths as one
+    pub fn kd(&self) -> f64 {
+        self.kills as f64 / self.deaths.max(1) as f64
+    }
+}
+
+// The player who recorded `round`, as their entry in its player list
+pub fn recording_player(round: &Value) -> Option<&Value> {
+    let players = round["players"].as_array()?;
+    players
+        .iter()
+        .find(|player| {
+            round["recordingProfileID"].is_string()
+                && player["profileID"] == round["recordingProfileID"]
+        })
+        .or_else(|| {
+            players.iter().find(|player| {
+                round["recordingPlayerID"].is_number() && player["id"] == round["recordingPlayerID"]
+            })
+        })
+}
+
+// Sums up the r6-dissect JSON of a match for the player who recorded it. None if it does not
+// say who that was.
+pub fn summarize(stats: &Value) -> Option<MatchSummary> {
+    let rounds = stats["rounds"].as_array()?;
+    let mut summary = MatchSummary::default();
+    for round in rounds {
+        let Some(player) = recording_player(round) else {
+            continue;
+        };
+        summary.username = player["username"].as_str().unwrap_or_default().to_string();
+        // Looked up for every round, in case the teams are listed in another order
+        let Some(team) = player["teamIndex"].as_u64() else {
+            continue;
+        };
+        for (index, other) in round["teams"].as_array().into_iter().flatten().enumerate() {
+            if other["won"].as_bool() == Some(true) {
+                if index as u64 == team {
+                    summary.rounds_won += 1;
+                } else {
+                    summary.rounds_lost += 1;
+                }
+            }
+        }
+    }
+    if summary.username.is_empty() {
+        return None;
+    }
+
+    let scoreboard = stats["stats"].as_array().and_then(|players| {
+        players
+            .iter()
+            .find(|player| player["username"].as_str() == Some(&summary.username))
+    });
+    match scoreboard {
+        Some(player) => {
+            summary.kills = player["kills"].as_u64().unwrap_or(0);
+            summary.deaths = player["deaths"].as_u64().unwrap_or(0);
+        }
+        // Older versions only have the scoreboard of each round
+        None => {
+            for player in rounds
+                .iter()
+                .flat_map(|round| round["stats"].as_array().into_iter().flatten())
+                .filter(|player| player["username"].as_str() == Some(&summary.username))
+            {
+                summary.kills += player["kills"].as_u64().unwrap_or(0);
+                summary.deaths += u64::from(player["died"].as_bool() == Some(true));
+            }
+        }
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round(won: [bool; 2], team: u64) -> Value {
+        json!({
+            "recordingProfileID": "b0b",
+            "players": [
+                { "profileID": "a11ce", "username": "Alice", "teamIndex": 1 - team },
+                { "profileID": "b0b", "username": "Bob", "teamIndex": team },
+            ],
+            "teams": [
+                { "name": "YOUR TEAM", "won": won[0] },
+                { "name": "OPPONENTS", "won": won[1] },
+            ],
+            "stats": [
+                { "username": "Bob", "kills": 2, "died": !won[team as usize] },
+            ],
+        })
+    }
+
+    #[test]
+    fn test_summarize() {
+        let mut stats = json!({
+            "rounds": [round([true, false], 0), round([false, true], 0), round([true, false], 1)],
+            "stats": [
+                { "username": "Alice", "kills": 1, "deaths": 4 },
+                { "username": "Bob", "kills": 7, "deaths": 2 },
+            ],
+        });
+        let summary = summarize(&stats).unwrap();
+        assert_eq!(
+            summary,
+            MatchSummary {
+                username: "Bob".to_string(),
+                rounds_won: 1,
+                rounds_lost: 2,
+                kills: 7,
+                deaths: 2,
+            }
+        );
+        assert_eq!(summary.won(), Some(false));
+        assert_eq!(summary.kd(), 3.5);
+
+        // Without a match scoreboard, the rounds are added up
+        stats.as_object_mut().unwrap().remove("stats");
+        let summary = summarize(&stats).unwrap();
+        assert_eq!((summary.kills, summary.deaths), (6, 2));
+
+        assert_eq!(summarize(&json!({ "rounds": [] })), None);
+        assert_eq!(summarize(&json!("not a match")), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dissect_match() {
+        use crate::backup::RealFs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let folder = std::env::temp_dir().join("siegesaver_dissect_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        let match_folder = folder.join("Match-A");
+        std::fs::create_dir_all(&match_folder).unwrap();
+        assert!(!has_replays(&RealFs, &match_folder));
+        std::fs::write(match_folder.join("round1.rec"), "round 1").unwrap();
+        assert!(has_replays(&RealFs, &match_folder));
+
+        // Stands in for r6-dissect: writes its arguments as JSON to the output path
+        let program = folder.join("r6-dissect");
+        std::fs::write(
+            &program,
+            "#!/bin/sh\n[ \"$2\" = -o ] || { echo bad arguments >&2; exit 2; }\n\
+             printf '{\"folder\": \"%s\"}' \"$1\" > \"$3\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let stats = dissect_match(&program, &match_folder).unwrap();
+        assert_eq!(stats["folder"], match_folder.display().to_string());
+
+        let error = dissect_match(&folder.join("missing"), &match_folder).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::remote::RemoteStore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+const UPLOAD_URL: &str = "https://www.googleapis.com/upload/drive/v3/files";
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+// Only files and folders created by SiegeSaver are visible to it
+const SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+pub const DEFAULT_DRIVE_FOLDER: &str = "SiegeSaver";
+
+// A folder in the user's Google Drive. The client ID and secret belong to an OAuth client of
+// type "TVs and Limited Input devices" in the user's Google Cloud project.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GoogleDriveConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    // Path of the folder in "My Drive", created if missing, e.g. "Backups/Replays"
+    pub folder: String,
+    // Set once the account was connected; empty until then
+    pub refresh_token: String,
+}
+
+impl Default for GoogleDriveConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: String::new(),
+            folder: DEFAULT_DRIVE_FOLDER.to_string(),
+            refresh_token: String::new(),
+        }
+    }
+}
+
+impl GoogleDriveConfig {
+    pub fn label(&self) -> String {
+        format!("Google Drive/{}", self.folder.trim_matches('/'))
+    }
+}
+
+fn http_error(e: ureq::Error) -> std::io::Error {
+    match e {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|error| {
+                    let message = &error["error"]["message"];
+                    let description = &error["error_description"];
+                    message
+                        .as_str()
+                        .or(description.as_str())
+                        .map(str::to_string)
+                })
+                .unwrap_or(body);
+            std::io::Error::other(format!("Google returned HTTP {}: {}", code, message))
+        }
+        e => std::io::Error::other(e.to_string()),
+    }
+}
+
+fn json_response(result: Result<ureq::Response, ureq::Error>) -> std::io::Result<Value> {
+    let body = result.map_err(http_error)?.into_string()?;
+    serde_json::from_str(&body).map_err(std::io::Error::other)
+}
+
+// A pending "Connect" of a Google account: the user enters `user_code` at `verification_url`
+// while SiegeSaver waits for the grant
+#[derive(Clone, Debug)]
+pub struct DeviceAuthorization {
+    pub user_code: String,
+    pub verification_url: String,
+    device_code: String,
+    interval: Duration,
+    expires_at: Instant,
+}
+
+// Starts connecting a Google account with the device authorization flow
+pub fn start_authorization(config: &GoogleDriveConfig) -> Result<DeviceAuthorization, String> {
+    if config.client_id.trim().is_empty() {
+        return Err("Enter the OAuth client ID first".to_string());
+    }
+    let response = json_response(
+        ureq::post(DEVICE_CODE_URL)
+            .send_form(&[("client_id", config.client_id.trim()), ("scope", SCOPE)]),
+    )
+    .map_err(|e| e.to_string())?;
+    let text = |name: &str| response[name].as_str().unwrap_or_default().to_string();
+    Ok(DeviceAuthorization {
+        user_code: text("user_code"),
+        verification_url: text("verification_url"),
+        device_code: text("device_code"),
+        interval: Duration::from_secs(response["interval"].as_u64().unwrap_or(5)),
+        expires_at: Instant::now()
+            + Duration::from_secs(response["expires_in"].as_u64().unwrap_or(1800)),
+    })
+}
+
+// Waits until the user granted access and returns the refresh token to store in the config
+pub fn finish_authorization(
+    config: &GoogleDriveConfig,
+    authorization: &DeviceAuthorization,
+) -> Result<String, String> {
+    let mut interval = authorization.interval;
+    while Instant::now() < authorization.expires_at {
+        std::thread::sleep(interval);
+        let result = ureq::post(TOKEN_URL).send_form(&[
+            ("client_id", config.client_id.trim()),
+            ("client_secret", config.client_secret.trim()),
+            ("device_code", &authorization.device_code),
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ]);
+        let body = match result {
+            Ok(response) => response.into_string().map_err(|e| e.to_string())?,
+            Err(ureq::Error::Status(_, response)) => response.into_string().unwrap_or_default(),
+            Err(e) => return Err(e.to_string()),
+        };
+        let response: Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+        if let Some(token) = response["refresh_token"].as_str() {
+            return Ok(token.to_string());
+        }
+        match response["error"].as_str() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval += Duration::from_secs(5),
+            Some("access_denied") => return Err("Access to Google Drive was denied".to_string()),
+            _ => {
+                return Err(format!(
+                    "Connecting to Google Drive failed: {}",
+                    body.trim()
+                ))
+            }
+        }
+    }
+    Err("The Google Drive code expired, connect again".to_string())
+}
+
+// Escapes a name for a Drive search query
+fn query_literal(name: &str) -> String {
+    format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// Uploads files into a Drive folder, replacing files of the same name, and refreshes the
+// access token whenever it runs out
+pub struct GoogleDriveStore {
+    agent: ureq::Agent,
+    client_id: String,
+    client_secret: String,
+    refresh_token: String,
+    folder: String,
+    access_token: Mutex<Option<(String, Instant)>>,
+    // IDs of folders already looked up or created, by path below "My Drive"
+    folders: Mutex<BTreeMap<String, String>>,
+}
+
+impl GoogleDriveStore {
+    pub fn new(config: &GoogleDriveConfig) -> Result<Self, String> {
+        if config.client_id.trim().is_empty() || config.refresh_token.is_empty() {
+            return Err(format!(
+                "Connect {} to a Google account first",
+                config.label()
+            ));
+        }
+        Ok(Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(30))
+                .timeout_read(Duration::from_secs(120))
+                .build(),
+            client_id: config.client_id.trim().to_string(),
+            client_secret: config.client_secret.trim().to_string(),
+            refresh_token: config.refresh_token.clone(),
+            folder: config.folder.trim_matches('/').to_string(),
+            access_token: Mutex::new(None),
+            folders: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    fn access_token(&self) -> std::io::Result<String> {
+        let mut cached = self.access_token.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + Duration::from_secs(60) < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+        let response = json_response(self.agent.post(TOKEN_URL).send_form(&[
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("refresh_token", &self.refresh_token),
+            ("grant_type", "refresh_token"),
+        ]))?;
+        let token = response["access_token"]
+            .as_str()
+            .ok_or_else(|| std::io::Error::other("Google did not return an access token"))?
+            .to_string();
+        let expires_in = Duration::from_secs(response["expires_in"].as_u64().unwrap_or(3600));
+        *cached = Some((token.clone(), Instant::now() + expires_in));
+        Ok(token)
+    }
+
+    fn request(&self, method: &str, url: &str) -> std::io::Result<ureq::Request> {
+        Ok(self
+            .agent
+            .request(method, url)
+            .set("Authorization", &format!("Bearer {}", self.access_token()?)))
+    }
+
+    // ID of the file or folder called `name` directly inside `parent`, if there is one
+    fn find(&self, parent: &str, name: &str, folder: bool) -> std::io::Result<Option<String>> {
+        let mut query = format!(
+            "name = {} and {} in parents and trashed = false",
+            query_literal(name),
+            query_literal(parent)
+        );
+        if folder {
+            query.push_str(&format!(" and mimeType = '{}'", FOLDER_MIME_TYPE));
+        }
+        let response = json_response(
+            self.request("GET", FILES_URL)?
+                .query("q", &query)
+                .query("fields", "files(id)")
+                .call(),
+        )?;
+        Ok(response["files"][0]["id"].as_str().map(str::to_string))
+    }
+
+    // ID of a folder below "My Drive", created along with its parents if missing
+    fn folder_id(&self, path: &str) -> std::io::Result<String> {
+        let mut id = "root".to_string();
+        let mut known = String::new();
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            if !known.is_empty() {
+                known.push('/');
+            }
+            known.push_str(name);
+            let cached = self
+                .folders
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&known)
+                .cloned();
+            id = match cached {
+                Some(cached) => cached,
+                None => {
+                    let found = match self.find(&id, name, true)? {
+                        Some(found) => found,
+                        None => {
+                            let created = json_response(
+                                self.request("POST", FILES_URL)?
+                                    .query("fields", "id")
+                                    .send_json(json!({
+                                        "name": name,
+                                        "mimeType": FOLDER_MIME_TYPE,
+                                        "parents": [id],
+                                    })),
+                            )?;
+                            created["id"]
+                                .as_str()
+                                .ok_or_else(|| {
+                                    std::io::Error::other("Google did not return a folder ID")
+                                })?
+                                .to_string()
+                        }
+                    };
+                    self.folders
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(known.clone(), found.clone());
+                    found
+                }
+            };
+        }
+        Ok(id)
+    }
+}
+
+impl RemoteStore for GoogleDriveStore {
+    fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()> {
+        let (folder, name) = match key.rsplit_once('/') {
+            Some((parents, name)) => (format!("{}/{}", self.folder, parents), name),
+            None => (self.folder.clone(), key),
+        };
+        let parent = self.folder_id(&folder)?;
+        // A resumable upload session takes the metadata first and the contents in one PUT
+        let session = match self.find(&parent, name, false)? {
+            Some(id) => self
+                .request("PATCH", &format!("{}/{}", UPLOAD_URL, id))?
+                .query("uploadType", "resumable")
+                .set("X-Upload-Content-Length", &data.len().to_string())
+                .send_json(json!({})),
+            None => self
+                .request("POST", UPLOAD_URL)?
+                .query("uploadType", "resumable")
+                .set("X-Upload-Content-Length", &data.len().to_string())
+                .send_json(json!({ "name": name, "parents": [parent] })),
+        }
+        .map_err(http_error)?;
+        let location = session
+            .header("Location")
+            .ok_or_else(|| std::io::Error::other("Google did not return an upload URL"))?;
+        self.agent
+            .put(location)
+            .send_bytes(data)
+            .map_err(http_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_google_drive_settings() {
+        let mut config = GoogleDriveConfig {
+            client_id: "client".to_string(),
+            folder: "/Backups/Replays/".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.label(), "Google Drive/Backups/Replays");
+        assert!(GoogleDriveStore::new(&config).is_err());
+        config.refresh_token = "token".to_string();
+        assert_eq!(
+            GoogleDriveStore::new(&config).unwrap().folder,
+            "Backups/Replays"
+        );
+        assert_eq!(query_literal("Match 'A'"), "'Match \\'A\\''");
+    }
+}
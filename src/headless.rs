@@ -0,0 +1,441 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{
+    source_destinations, source_labels, BackupEngine, BackupOptions, FileFilter, RealFs,
+};
+use crate::config::{normalize_path_input, AppConfig};
+use crate::crypto::EncryptionKey;
+use crate::discord::DiscordNotifier;
+use crate::journal::{BackupJournal, JOURNAL_FILE_NAME};
+use crate::logging::log_event;
+use crate::mirror::DeletionQueue;
+use crate::queue::BackupQueue;
+use crate::quota::gigabytes_to_bytes;
+use crate::remote::{retry_uploads, Remote, UploadQueue, UPLOAD_QUEUE_FILE_NAME};
+use crate::retention::RetentionPolicy;
+use crate::snapshot::snapshot_name;
+use crate::status::{StatusEvent, StatusLine};
+use crate::throttle::Throttle;
+use crate::watcher::{
+    daily_run_due, handle_mirrored_file_events, in_quiet_hours, parse_time_of_day, watch_folder,
+};
+use crate::webhook::WebhookNotifier;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Without the GUI there is no passphrase field; encrypted backups read it from here
+pub const PASSPHRASE_VARIABLE: &str = "SIEGESAVER_PASSPHRASE";
+
+// Options of a headless run from the command line
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeadlessArgs {
+    // Status lines are also appended to this file
+    pub log_file: Option<PathBuf>,
+}
+
+// The non-empty folders of a list from the config, cleaned up like typed paths
+fn folder_paths(folders: &[String]) -> Vec<PathBuf> {
+    folders
+        .iter()
+        .map(|folder| normalize_path_input(folder))
+        .filter(|folder| !folder.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+// Every source folder of `config` with its folder in the main destination, whether or not
+// anything was backed up yet
+pub fn backup_targets(config: &AppConfig) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let sources = folder_paths(&config.source_folders);
+    let destination = normalize_path_input(&config.destination_folder);
+    if sources.is_empty() || destination.is_empty() {
+        return Err("No source or destination folder configured".to_string());
+    }
+    let destinations = source_destinations(&sources, Path::new(&destination));
+    Ok(sources.into_iter().zip(destinations).collect())
+}
+
+// Every source folder of `config` with the engines backing it up into the main destination
+// and each mirror, the main one first. Checks the settings and creates the destination
+// folders, like starting to watch in the GUI does.
+pub fn backup_engines(
+    config: &AppConfig,
+    config_path: &Path,
+    status_tx: &Sender<StatusEvent>,
+) -> Result<Vec<(PathBuf, Vec<BackupEngine>)>, String> {
+    let sources = folder_paths(&config.source_folders);
+    let destination = normalize_path_input(&config.destination_folder);
+    if sources.is_empty() || destination.is_empty() {
+        return Err("No source or destination folder configured".to_string());
+    }
+    let destination = PathBuf::from(destination);
+    let file_filter = FileFilter::new(&config.include_patterns, &config.exclude_patterns)?;
+    for (index, source) in sources.iter().enumerate() {
+        if !source.exists() {
+            return Err(format!(
+                "Source folder does not exist: {}",
+                source.display()
+            ));
+        }
+        if sources[..index].contains(source) {
+            return Err(format!(
+                "Source folder is listed twice: {}",
+                source.display()
+            ));
+        }
+    }
+    let mut stores = Vec::new();
+    for remote in &config.remote_destinations {
+        stores.push((remote.label(), remote.store(status_tx)?));
+    }
+    let encryption = if config.encrypt_backups {
+        let passphrase = std::env::var(PASSPHRASE_VARIABLE).map_err(|_| {
+            format!(
+                "Backups are encrypted; set {} to the passphrase",
+                PASSPHRASE_VARIABLE
+            )
+        })?;
+        Some(Arc::new(EncryptionKey::generate(&passphrase)?))
+    } else {
+        None
+    };
+    let mirrors = folder_paths(&config.mirror_folders);
+    let upload_queue = Arc::new(UploadQueue::load(
+        &config_path.with_file_name(UPLOAD_QUEUE_FILE_NAME),
+    ));
+    let throttle = Arc::new(Throttle::new(config.io_limits()));
+    let journal = Arc::new(BackupJournal::load(
+        &config_path.with_file_name(JOURNAL_FILE_NAME),
+    ));
+    let retention = RetentionPolicy {
+        keep_last: config.keep_last_matches,
+        max_age_days: config.max_backup_age_days,
+    };
+    let quota_bytes = config.destination_quota_gb.map(gigabytes_to_bytes);
+    let labels = source_labels(&sources);
+    let deletions = Arc::new(DeletionQueue::default());
+    let snapshot = config
+        .snapshot_runs
+        .then(|| snapshot_name(chrono::Local::now().naive_local()));
+
+    let mut targets = Vec::new();
+    for (index, source) in sources.iter().enumerate() {
+        let options = BackupOptions {
+            fsync_writes: config.fsync_writes,
+            verify_copies: config.verify_copies,
+            encryption: encryption.clone(),
+            excluded_dirs: config
+                .watch_exclude_dirs
+                .iter()
+                .map(|dir| source.join(dir))
+                .collect(),
+            incremental: config.incremental_backups,
+            file_filter: file_filter.clone(),
+            storage: config.storage_format,
+            zstd_level: config.zstd_level,
+            copy_threads: config.copy_threads,
+            cancel: None,
+            copy_mode: config.copy_mode,
+            symlinks: config.symlinks,
+            conflicts: config.conflict_policy,
+            dedupe_store: None,
+            versions: None,
+        };
+        let folder = if sources.len() < 2 {
+            String::new()
+        } else {
+            labels[index].clone()
+        };
+        let remotes = stores
+            .iter()
+            .map(|(label, store)| Remote {
+                label: label.clone(),
+                store: Arc::clone(store),
+                folder: folder.clone(),
+            })
+            .collect();
+        let main = source_destinations(&sources, &destination).swap_remove(index);
+        let mirror_folders: Vec<PathBuf> = mirrors
+            .iter()
+            .map(|mirror| source_destinations(&sources, mirror).swap_remove(index))
+            .collect();
+        for folder in std::iter::once(&main).chain(&mirror_folders) {
+            fs::create_dir_all(folder).map_err(|e| {
+                format!(
+                    "Error creating destination folder {}: {}",
+                    folder.display(),
+                    e
+                )
+            })?;
+        }
+        let engine = |folder: PathBuf| {
+            let mut engine = BackupEngine::new(source.clone(), folder, options.clone())
+                .with_retention(retention)
+                .with_quota(quota_bytes)
+                .with_min_free_space(config.min_free_space_gb.map(gigabytes_to_bytes))
+                .with_quiet_period(Duration::from_secs(config.quiet_period_secs))
+                .with_journal(Arc::clone(&journal))
+                .with_throttle(Arc::clone(&throttle))
+                .with_dedupe(config.dedupe_files)
+                .with_versions(config.keep_file_versions)
+                .with_recycle_bin(config.prune_to_recycle_bin)
+                .with_volume_tracking();
+            if let Some(grace) = config.mirror_deletion_grace() {
+                engine = engine.with_mirror_deletions(Arc::clone(&deletions), grace);
+            }
+            if let Some(name) = &snapshot {
+                engine = engine.with_snapshot(name.clone());
+            }
+            engine
+        };
+        // Only the main destination uploads and runs the post-backup command
+        let mut engines = vec![engine(main)
+            .with_remotes(remotes)
+            .with_upload_queue(Arc::clone(&upload_queue))
+            .with_move_after_verify(config.move_after_verify)
+            .with_post_backup_command(config.post_backup_command.clone())
+            .with_match_stats(config.r6_dissect_path.as_ref().map(PathBuf::from))];
+        engines.extend(mirror_folders.into_iter().map(engine));
+        targets.push((source.clone(), engines));
+    }
+    Ok(targets)
+}
+
+// Backs up everything in the source folders in the background, for the scheduled full sync.
+// The start is recorded in the config, where the GUI shows it.
+fn start_scheduled_sync(config_path: &Path, status_tx: &Sender<StatusEvent>) {
+    let mut config = AppConfig::load(config_path);
+    config.last_scheduled_sync = Some(chrono::Local::now().timestamp());
+    config.save(config_path);
+    let _ = status_tx.send(StatusEvent::Info(
+        "Starting the scheduled full sync".to_string(),
+    ));
+    match backup_engines(&config, config_path, status_tx) {
+        Ok(targets) => {
+            let status_tx = status_tx.clone();
+            std::thread::spawn(move || {
+                for (_, engines) in targets {
+                    for engine in &engines {
+                        engine.initial_sync(None, &status_tx);
+                    }
+                }
+            });
+        }
+        Err(e) => {
+            let _ = status_tx.send(StatusEvent::Error(format!(
+                "Error starting the scheduled full sync: {}",
+                e
+            )));
+        }
+    }
+}
+
+// Watches the configured source folders and backs them up until `stop` is set or the process
+// is stopped, printing the status log to stdout and, if given, to a log file
+pub fn run_headless(
+    config_path: &Path,
+    args: &HeadlessArgs,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let config = AppConfig::load(config_path);
+    let mut log_file = match &args.log_file {
+        Some(path) => Some(
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("Error opening log file {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let (status_tx, status_rx) = channel();
+    let targets = backup_engines(&config, config_path, &status_tx)?;
+
+    let mut watchers = Vec::new();
+    let queue = Arc::new(BackupQueue::default());
+    for (source, engines) in targets {
+        let (tx, rx) = channel();
+        let options = config.watch_options(&source);
+        watchers.push(watch_folder(&source, &options, tx, status_tx.clone())?);
+        let _ = status_tx.send(StatusEvent::Started {
+            source: source.clone(),
+        });
+        let status_tx = status_tx.clone();
+        let initial_sync_limit = config.initial_sync_limit;
+        let rescan = config.rescan_schedule();
+        let queue = Arc::clone(&queue);
+        std::thread::spawn(move || {
+            for engine in &engines {
+                engine.resume_interrupted(&status_tx);
+                engine.initial_sync(initial_sync_limit, &status_tx);
+            }
+            // Never paused; there is no tray to pause from
+            handle_mirrored_file_events(
+                rx,
+                &engines,
+                &status_tx,
+                &AtomicBool::new(false),
+                &rescan,
+                &queue,
+            );
+        });
+    }
+
+    let upload_queue = UploadQueue::load(&config_path.with_file_name(UPLOAD_QUEUE_FILE_NAME));
+    if !upload_queue.pending().is_empty() {
+        let remotes: Vec<Remote> = config
+            .remote_destinations
+            .iter()
+            .filter_map(|remote| {
+                Some(Remote {
+                    label: remote.label(),
+                    store: remote.store(&status_tx).ok()?,
+                    folder: String::new(),
+                })
+            })
+            .collect();
+        let status_tx = status_tx.clone();
+        std::thread::spawn(move || {
+            let (uploaded, error) = retry_uploads(&RealFs, &remotes, &upload_queue);
+            if uploaded > 0 {
+                let _ = status_tx.send(StatusEvent::Info(format!(
+                    "Uploaded {} files left over from earlier",
+                    uploaded
+                )));
+            }
+            if let Some(e) = error {
+                let _ = status_tx.send(StatusEvent::Warning(format!(
+                    "{} uploads are still queued: {}",
+                    upload_queue.pending().len(),
+                    e
+                )));
+            }
+        });
+    }
+
+    let discord = config
+        .discord_webhook_url
+        .clone()
+        .map(|url| DiscordNotifier::spawn(url, status_tx.clone()));
+    let webhook = config
+        .webhook_url
+        .clone()
+        .map(|url| WebhookNotifier::spawn(url, status_tx.clone()));
+    let scheduled_sync = config
+        .scheduled_sync_time
+        .as_deref()
+        .and_then(parse_time_of_day);
+    let started = chrono::Local::now().naive_local();
+    let mut last_scheduled_sync = config
+        .last_scheduled_sync
+        .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+        .map(|time| time.with_timezone(&chrono::Local).naive_local());
+    let quiet_hours = config.quiet_hours();
+    while !stop.load(Ordering::Relaxed) {
+        if let Some((start, end)) = quiet_hours {
+            let quiet = in_quiet_hours(start, end, chrono::Local::now().time());
+            if quiet != queue.is_held() {
+                queue.hold(quiet);
+                let _ = status_tx.send(StatusEvent::Info(if quiet {
+                    format!(
+                        "Quiet hours started, changes are queued until {}",
+                        end.format("%H:%M")
+                    )
+                } else {
+                    "Quiet hours ended, running the queued backups".to_string()
+                }));
+            }
+        }
+        if let Some(time) = scheduled_sync {
+            let now = chrono::Local::now().naive_local();
+            if daily_run_due(time, now, last_scheduled_sync, started) {
+                last_scheduled_sync = Some(now);
+                start_scheduled_sync(config_path, &status_tx);
+            }
+        }
+        let event = match status_rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        log_event(&event);
+        if matches!(
+            event,
+            StatusEvent::Progress { .. }
+                | StatusEvent::CopyProgress { .. }
+                | StatusEvent::RestoreProgress { .. }
+        ) {
+            continue;
+        }
+        if let Some(discord) = &discord {
+            discord.notify(&event);
+        }
+        if let Some(webhook) = &webhook {
+            webhook.notify(&event);
+        }
+        let line = StatusLine::new(event, chrono::Local::now());
+        println!("{}", line.text);
+        if let Some(file) = log_file.as_mut() {
+            if let Err(e) = writeln!(file, "{}", line.text) {
+                tracing::error!("Error writing the log file: {}", e);
+            }
+        }
+    }
+    drop(watchers);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_engines_from_config() {
+        let test_dir = std::env::temp_dir().join("siegesaver_headless_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        fs::create_dir_all(&source).unwrap();
+        let (tx, _rx) = channel();
+        let config_path = test_dir.join("config.json");
+        let mut config = AppConfig {
+            source_folders: vec![source.display().to_string()],
+            destination_folder: test_dir.join("backup").display().to_string(),
+            mirror_folders: vec![test_dir.join("mirror").display().to_string()],
+            ..Default::default()
+        };
+        let targets = backup_engines(&config, &config_path, &tx).unwrap();
+        assert_eq!(targets.len(), 1);
+        let destinations: Vec<_> = targets[0]
+            .1
+            .iter()
+            .map(|engine| engine.destination_folder().to_path_buf())
+            .collect();
+        assert_eq!(
+            destinations,
+            [test_dir.join("backup"), test_dir.join("mirror")]
+        );
+        assert!(test_dir.join("mirror").is_dir());
+
+        config.encrypt_backups = true;
+        if std::env::var(PASSPHRASE_VARIABLE).is_err() {
+            assert!(backup_engines(&config, &config_path, &tx)
+                .err()
+                .unwrap()
+                .contains(PASSPHRASE_VARIABLE));
+        }
+        config
+            .source_folders
+            .push(test_dir.join("missing").display().to_string());
+        assert!(backup_engines(&config, &config_path, &tx)
+            .err()
+            .unwrap()
+            .starts_with("Source folder does not exist"));
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
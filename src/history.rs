@@ -0,0 +1,230 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::status::StatusEvent;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Stored next to config.json
+pub const HISTORY_FILE_NAME: &str = "history.json";
+
+// Older operations are dropped once the history grows beyond this
+pub const MAX_HISTORY_ENTRIES: usize = 1000;
+
+// One backup operation as shown in the History tab
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    // Match folder (or replay file) the operation was about
+    pub name: String,
+    // Seconds since the Unix epoch
+    pub time: i64,
+    pub files: u64,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    // None if the backup succeeded
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    // The history entry for a status event, if it reports a finished backup
+    pub fn from_event(event: &StatusEvent, time: chrono::DateTime<chrono::Local>) -> Option<Self> {
+        let name = match event {
+            StatusEvent::FileBackedUp { path, .. } | StatusEvent::CopyError { path, .. } => path
+                .components()
+                .next()?
+                .as_os_str()
+                .to_string_lossy()
+                .into_owned(),
+            StatusEvent::FolderBackedUp { name, .. } | StatusEvent::FolderArchived { name, .. } => {
+                name.clone()
+            }
+            _ => return None,
+        };
+        let detail = event.detail();
+        let error = match event {
+            StatusEvent::CopyError { source, .. } => Some(source.clone()),
+            _ => None,
+        };
+        Some(Self {
+            name,
+            time: time.timestamp(),
+            files: detail.and_then(|detail| detail.files).unwrap_or(0),
+            bytes: detail.and_then(|detail| detail.bytes).unwrap_or(0),
+            duration_ms: detail
+                .and_then(|detail| detail.duration)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0),
+            error,
+        })
+    }
+}
+
+// Column the History tab is sorted by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryColumn {
+    Name,
+    Time,
+    Files,
+    Bytes,
+    Duration,
+    Result,
+}
+
+impl HistoryColumn {
+    pub const ALL: [HistoryColumn; 6] = [
+        HistoryColumn::Time,
+        HistoryColumn::Name,
+        HistoryColumn::Files,
+        HistoryColumn::Bytes,
+        HistoryColumn::Duration,
+        HistoryColumn::Result,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HistoryColumn::Name => "Match",
+            HistoryColumn::Time => "Time",
+            HistoryColumn::Files => "Files",
+            HistoryColumn::Bytes => "Size",
+            HistoryColumn::Duration => "Duration",
+            HistoryColumn::Result => "Result",
+        }
+    }
+}
+
+// Every backup operation, oldest first, persisted across restarts
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupHistory {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl BackupHistory {
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) {
+        self.entries.push(entry);
+        let excess = self.entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        self.entries.drain(..excess);
+    }
+
+    // Indices into `entries` in display order
+    pub fn sorted(&self, column: HistoryColumn, ascending: bool) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.entries[a], &self.entries[b]);
+            let ordering = match column {
+                HistoryColumn::Name => a.name.cmp(&b.name),
+                HistoryColumn::Time => a.time.cmp(&b.time),
+                HistoryColumn::Files => a.files.cmp(&b.files),
+                HistoryColumn::Bytes => a.bytes.cmp(&b.bytes),
+                HistoryColumn::Duration => a.duration_ms.cmp(&b.duration_ms),
+                HistoryColumn::Result => a.error.is_some().cmp(&b.error.is_some()),
+            };
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::EventDetail;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_history_entries_from_events() {
+        let time = chrono::Local::now();
+        let detail = EventDetail {
+            source: PathBuf::from("/source/Match-A/round1.rec"),
+            destination: PathBuf::from("/dest/Match-A/round1.rec"),
+            files: Some(1),
+            bytes: Some(2048),
+            duration: Some(Duration::from_millis(15)),
+            error: None,
+        };
+        let entry = HistoryEntry::from_event(
+            &StatusEvent::FileBackedUp {
+                path: PathBuf::from("Match-A/round1.rec"),
+                detail,
+            },
+            time,
+        )
+        .unwrap();
+        assert_eq!(
+            entry,
+            HistoryEntry {
+                name: "Match-A".to_string(),
+                time: time.timestamp(),
+                files: 1,
+                bytes: 2048,
+                duration_ms: 15,
+                error: None,
+            }
+        );
+
+        let failed = HistoryEntry::from_event(
+            &StatusEvent::CopyError {
+                path: PathBuf::from("Match-B"),
+                source: "disk full".to_string(),
+                detail: None,
+            },
+            time,
+        )
+        .unwrap();
+        assert_eq!(failed.error.as_deref(), Some("disk full"));
+        assert!(HistoryEntry::from_event(&StatusEvent::Stopped, time).is_none());
+    }
+
+    #[test]
+    fn test_history_persists_and_sorts() {
+        let path = std::env::temp_dir()
+            .join("siegesaver_history_test")
+            .join(HISTORY_FILE_NAME);
+        let _ = fs::remove_file(&path);
+
+        let mut history = BackupHistory::load(&path);
+        for (name, bytes) in [("Match-B", 10), ("Match-A", 30), ("Match-C", 20)] {
+            history.record(HistoryEntry {
+                name: name.to_string(),
+                time: 0,
+                files: 1,
+                bytes,
+                duration_ms: 0,
+                error: None,
+            });
+        }
+        history.save(&path).unwrap();
+        let history = BackupHistory::load(&path);
+        assert_eq!(history.entries.len(), 3);
+        assert_eq!(history.sorted(HistoryColumn::Name, true), [1, 0, 2]);
+        assert_eq!(history.sorted(HistoryColumn::Bytes, false), [1, 2, 0]);
+
+        let mut full = BackupHistory::default();
+        for _ in 0..MAX_HISTORY_ENTRIES + 5 {
+            full.record(history.entries[0].clone());
+        }
+        assert_eq!(full.entries.len(), MAX_HISTORY_ENTRIES);
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use std::process::{Command, Stdio};
+
+// A command line run through the system shell, so users can write it as they would in a
+// terminal, including pipes and quoting
+fn shell_command(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // Keep the command from opening a console window next to the GUI
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").raw_arg(command);
+        shell.creation_flags(CREATE_NO_WINDOW);
+        shell
+    }
+    #[cfg(not(windows))]
+    {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+// Runs the user's post-backup command with `env` set, waiting for it to finish. Fails if it
+// cannot be started or exits unsuccessfully, with the last line it printed.
+pub fn run_hook(command: &str, env: &[(&str, String)]) -> std::io::Result<()> {
+    let output = shell_command(command)
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .stdin(Stdio::null())
+        .output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last_line = stderr
+        .lines()
+        .chain(stdout.lines())
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    Err(std::io::Error::other(format!(
+        "{} {}",
+        output.status,
+        last_line.trim()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_run_hook() {
+        let test_dir = std::env::temp_dir().join("siegesaver_hook_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let output = test_dir.join("match.txt");
+        let command = if cfg!(windows) {
+            format!("echo %SIEGESAVER_MATCH_NAME%> \"{}\"", output.display())
+        } else {
+            format!("echo \"$SIEGESAVER_MATCH_NAME\" > '{}'", output.display())
+        };
+        run_hook(
+            &command,
+            &[("SIEGESAVER_MATCH_NAME", "Match-A".to_string())],
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap().trim(), "Match-A");
+
+        let error = run_hook("echo upload failed 1>&2 && exit 3", &[]).unwrap_err();
+        assert!(error.to_string().ends_with("upload failed"));
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+// Texts of the window and the tray menu are written in English in the code and looked up in
+// a gettext catalog (locales/<code>.po) of the chosen language, falling back to English for
+// anything not translated. Status messages stay in English, as they go into the log file.
+
+// Languages the window can be shown in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    // The language of the system if it is translated, otherwise English
+    #[default]
+    System,
+    English,
+    German,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::System,
+        Language::English,
+        Language::German,
+        Language::French,
+    ];
+
+    // In the language itself, so it can be found without understanding the current one
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::System => "Same as system",
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::French => "Français",
+        }
+    }
+
+    fn catalog_source(self) -> Option<&'static str> {
+        match self {
+            Language::German => Some(include_str!("../locales/de.po")),
+            Language::French => Some(include_str!("../locales/fr.po")),
+            Language::System | Language::English => None,
+        }
+    }
+
+    // The language for a locale name such as de_DE.UTF-8 or fr-CA
+    fn from_locale(locale: &str) -> Option<Self> {
+        let code = locale.get(..2)?.to_ascii_lowercase();
+        match code.as_str() {
+            "en" => Some(Language::English),
+            "de" => Some(Language::German),
+            "fr" => Some(Language::French),
+            _ => None,
+        }
+    }
+
+    // `System` resolved to the language the texts are shown in
+    pub fn resolve(self) -> Self {
+        match self {
+            Language::System => system_locale()
+                .as_deref()
+                .and_then(Language::from_locale)
+                .unwrap_or(Language::English),
+            language => language,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    let mut name = [0u16; 85];
+    // SAFETY: `name` is valid and writable for `name.len()` UTF-16 units, the size passed in
+    let len = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    // The length includes the terminating null
+    (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]))
+}
+
+#[cfg(not(windows))]
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+// Index into `Language::ALL` of the language texts are shown in. Starts out as English, so
+// tests do not depend on the language of the machine they run on.
+static CURRENT: AtomicUsize = AtomicUsize::new(1);
+
+// Switches the language of the window and the tray menu
+pub fn set_language(language: Language) {
+    let language = language.resolve();
+    let index = Language::ALL.iter().position(|l| *l == language);
+    CURRENT.store(index.unwrap_or(1), Ordering::Relaxed);
+}
+
+fn current_language() -> Language {
+    Language::ALL[CURRENT.load(Ordering::Relaxed)]
+}
+
+// Parses the entries of a gettext catalog. Only what translation tools write is supported:
+// comments, and msgid and msgstr strings continued on the following lines.
+fn parse_po(source: &str) -> HashMap<String, String> {
+    let mut entries = HashMap::new();
+    let mut msgid = String::new();
+    let mut msgstr = String::new();
+    // Which of the two a continued string belongs to
+    let mut in_msgstr = false;
+    let mut finish = |msgid: &mut String, msgstr: &mut String| {
+        if !msgid.is_empty() && !msgstr.is_empty() {
+            entries.insert(std::mem::take(msgid), std::mem::take(msgstr));
+        }
+        msgid.clear();
+        msgstr.clear();
+    };
+    for line in source.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            finish(&mut msgid, &mut msgstr);
+            msgid = unquote(rest);
+            in_msgstr = false;
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            msgstr = unquote(rest);
+            in_msgstr = true;
+        } else if line.starts_with('"') {
+            let target = if in_msgstr { &mut msgstr } else { &mut msgid };
+            target.push_str(&unquote(line));
+        }
+    }
+    finish(&mut msgid, &mut msgstr);
+    entries
+}
+
+fn unquote(quoted: &str) -> String {
+    let inner = quoted
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .unwrap_or_default();
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
+
+fn catalog(language: Language) -> Option<&'static HashMap<String, String>> {
+    static CATALOGS: OnceLock<Vec<Option<HashMap<String, String>>>> = OnceLock::new();
+    let catalogs = CATALOGS.get_or_init(|| {
+        Language::ALL
+            .iter()
+            .map(|language| language.catalog_source().map(parse_po))
+            .collect()
+    });
+    let index = Language::ALL.iter().position(|l| *l == language)?;
+    catalogs[index].as_ref()
+}
+
+// `text` in `language`, or as it is if it is not translated
+pub fn translate(language: Language, text: &str) -> &str {
+    catalog(language)
+        .and_then(|catalog| catalog.get(text))
+        .map_or(text, String::as_str)
+}
+
+// `text` in the current language
+pub fn tr(text: &str) -> &str {
+    translate(current_language(), text)
+}
+
+// Fills the `{}` placeholders of `text` with `args` in order. Translations that need another
+// order refer to them by position instead, e.g. `{1}`.
+pub fn fill_placeholders(text: &str, args: &[&dyn Display]) -> String {
+    let mut formatted = String::with_capacity(text.len());
+    let mut next = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        formatted.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            rest = &rest[start..];
+            break;
+        };
+        let index = match &rest[start + 1..end] {
+            "" => {
+                next += 1;
+                Some(next - 1)
+            }
+            position => position.parse::<usize>().ok(),
+        };
+        match index.and_then(|index| args.get(index)) {
+            Some(arg) => formatted.push_str(&arg.to_string()),
+            None => formatted.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+// `text` in the current language with its placeholders filled in
+pub fn tr_fmt(text: &str, args: &[&dyn Display]) -> String {
+    fill_placeholders(tr(text), args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autostart::AutostartMode;
+    use crate::backup::{ConflictPolicy, CopyMode, SymlinkMode};
+    use crate::history::HistoryColumn;
+    use crate::logging::LogLevel;
+    use crate::service::ServiceState;
+    use crate::storage::StorageFormat;
+    use crate::ui::Theme;
+    use std::collections::BTreeSet;
+
+    // The string literals passed to `tr` and `tr_fmt` in `source`
+    fn translated_literals(source: &str) -> Vec<String> {
+        let mut literals = Vec::new();
+        for call in ["tr(", "tr_fmt("] {
+            for (start, _) in source.match_indices(call) {
+                let before = source[..start].chars().next_back();
+                if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+                let argument = source[start + call.len()..].trim_start();
+                let Some(argument) = argument.strip_prefix('"') else {
+                    continue;
+                };
+                let mut literal = String::new();
+                let mut chars = argument.chars().peekable();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => literal.push('\n'),
+                            // A line continuation skips the indentation of the next line
+                            Some('\n') => {
+                                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                                    chars.next();
+                                }
+                            }
+                            Some(other) => literal.push(other),
+                            None => {}
+                        },
+                        c => literal.push(c),
+                    }
+                }
+                literals.push(literal);
+            }
+        }
+        literals
+    }
+
+    fn placeholders(text: &str) -> usize {
+        text.matches('{').count()
+    }
+
+    #[test]
+    fn test_catalogs_translate_every_text() {
+        let folder = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut texts = BTreeSet::new();
+        for file in ["ui.rs", "tray.rs"] {
+            let source = std::fs::read_to_string(folder.join(file)).unwrap();
+            texts.extend(translated_literals(&source));
+        }
+        // Labels are translated where they are shown
+        texts.extend(Theme::ALL.map(|theme| theme.label().to_string()));
+        texts.extend(LogLevel::ALL.map(|level| level.label().to_string()));
+        texts.extend(HistoryColumn::ALL.map(|column| column.label().to_string()));
+        texts.extend(
+            [
+                StorageFormat::Folder,
+                StorageFormat::Zip,
+                StorageFormat::TarZst,
+            ]
+            .map(|format| format.label().to_string()),
+        );
+        texts.extend(
+            [CopyMode::Copy, CopyMode::Reflink, CopyMode::Hardlink]
+                .map(|mode| mode.label().to_string()),
+        );
+        texts.extend(
+            [SymlinkMode::Skip, SymlinkMode::Copy, SymlinkMode::Follow]
+                .map(|mode| mode.label().to_string()),
+        );
+        texts.extend(ConflictPolicy::ALL.map(|policy| policy.label().to_string()));
+        texts.extend(
+            [AutostartMode::Desktop, AutostartMode::Systemd].map(|mode| mode.label().to_string()),
+        );
+        texts.extend(
+            [
+                ServiceState::NotInstalled,
+                ServiceState::Stopped,
+                ServiceState::Starting,
+                ServiceState::Running,
+                ServiceState::Stopping,
+            ]
+            .map(|state| state.label().to_string()),
+        );
+        texts.insert(Language::System.label().to_string());
+        assert!(texts.contains("Start Watching"));
+
+        for language in [Language::German, Language::French] {
+            let catalog = catalog(language).unwrap();
+            for text in &texts {
+                let translation = catalog
+                    .get(text)
+                    .unwrap_or_else(|| panic!("{:?} has no translation of {:?}", language, text));
+                assert_eq!(
+                    placeholders(translation),
+                    placeholders(text),
+                    "{:?}: {:?}",
+                    language,
+                    translation
+                );
+            }
+            for text in catalog.keys() {
+                assert!(
+                    texts.contains(text),
+                    "{:?} is not used: {:?}",
+                    language,
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_translation() {
+        assert_eq!(
+            translate(Language::German, "Start Watching"),
+            "Überwachung starten"
+        );
+        assert_eq!(
+            translate(Language::English, "Start Watching"),
+            "Start Watching"
+        );
+        assert_eq!(
+            translate(Language::French, "Not in the catalog"),
+            "Not in the catalog"
+        );
+        assert_eq!(Language::from_locale("de_AT.UTF-8"), Some(Language::German));
+        assert_eq!(Language::from_locale("fr-CA"), Some(Language::French));
+        assert_eq!(Language::from_locale("pt_BR"), None);
+
+        let entries = parse_po(
+            "# Comment\nmsgid \"\"\nmsgstr \"Content-Type: text/plain\\n\"\n\n\
+             msgid \"Copy {} of {}\"\nmsgstr \"\"\n\"{1} \\\"{0}\\\"\"\n\nmsgid \"Untranslated\"\nmsgstr \"\"\n",
+        );
+        assert_eq!(entries.len(), 1);
+        let text = &entries["Copy {} of {}"];
+        assert_eq!(fill_placeholders(text, &[&3, &"Match-A"]), "Match-A \"3\"");
+        assert_eq!(
+            fill_placeholders("Backup queue ({})", &[&2]),
+            "Backup queue (2)"
+        );
+        assert_eq!(fill_placeholders("{} and {}", &[&1]), "1 and {}");
+    }
+}
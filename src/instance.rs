@@ -0,0 +1,119 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use eframe::egui;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SHOW_REQUEST: &str = "SIEGESAVER SHOW";
+const SHOW_REPLY: &str = "SIEGESAVER OK";
+
+// The local port instances using the config file `config_path` meet on. Copies started
+// with different config files run side by side.
+pub fn instance_port(config_path: &Path) -> u16 {
+    // FNV-1a, so every build agrees on the port
+    let hash = config_path
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    49152 + (hash % 16000) as u16
+}
+
+// Asks the instance listening on `port` to show its window. Returns false if there is none.
+fn request_show(port: u16) -> bool {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&address, Duration::from_secs(1)) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+    if writeln!(stream, "{}", SHOW_REQUEST).is_err() {
+        return false;
+    }
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).is_ok() && reply.trim() == SHOW_REPLY
+}
+
+// The running instance; later copies ask it to show its window instead of starting
+#[derive(Default)]
+pub struct PrimaryInstance {
+    show_requested: Arc<AtomicBool>,
+    // Woken when another copy asks for the window, even while it is hidden
+    context: Arc<Mutex<Option<egui::Context>>>,
+}
+
+impl PrimaryInstance {
+    pub fn set_context(&self, context: egui::Context) {
+        *self.context.lock().unwrap_or_else(|e| e.into_inner()) = Some(context);
+    }
+
+    // Whether another copy asked for the window since the last call
+    pub fn take_show_request(&self) -> bool {
+        self.show_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+// Becomes the running instance for `port`, or returns None after handing off to the one
+// that already runs. If the port is taken by something else, this copy runs anyway.
+pub fn claim_instance_on(port: u16) -> Option<PrimaryInstance> {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(listener) => listener,
+        Err(_) if request_show(port) => return None,
+        Err(e) => {
+            tracing::warn!(
+                "Cannot check for another running SiegeSaver on port {}: {}",
+                port,
+                e
+            );
+            return Some(PrimaryInstance::default());
+        }
+    };
+    let instance = PrimaryInstance::default();
+    let show_requested = Arc::clone(&instance.show_requested);
+    let context = Arc::clone(&instance.context);
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+            let mut request = String::new();
+            let Ok(reader) = stream.try_clone() else {
+                continue;
+            };
+            if BufReader::new(reader).read_line(&mut request).is_err()
+                || request.trim() != SHOW_REQUEST
+            {
+                continue;
+            }
+            show_requested.store(true, Ordering::Relaxed);
+            let _ = writeln!(stream, "{}", SHOW_REPLY);
+            if let Some(context) = context.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                context.request_repaint();
+            }
+        }
+    });
+    Some(instance)
+}
+
+// Claims the running instance for a config file
+pub fn claim_instance(config_path: &Path) -> Option<PrimaryInstance> {
+    claim_instance_on(instance_port(config_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_instance_hands_off() {
+        let port = instance_port(&std::env::temp_dir().join("siegesaver_instance_test.json"));
+        assert!((49152..65152).contains(&port));
+        let primary = claim_instance_on(port).unwrap();
+        assert!(!primary.take_show_request());
+        assert!(claim_instance_on(port).is_none());
+        assert!(primary.take_show_request());
+        assert!(!primary.take_show_request());
+    }
+}
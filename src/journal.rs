@@ -0,0 +1,132 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Stored next to config.json
+pub const JOURNAL_FILE_NAME: &str = "journal.json";
+
+// A backup that was started but has not finished yet
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingBackup {
+    // The created or modified path in the source folder
+    pub path: PathBuf,
+    pub destination_folder: PathBuf,
+}
+
+// Every backup that is running. A backup is added before it starts and removed once it
+// finished, successfully or not, so whatever is still listed on startup was cut short by a
+// crash, a power loss or a forced quit and is backed up again.
+pub struct BackupJournal {
+    path: PathBuf,
+    pending: Mutex<Vec<PendingBackup>>,
+}
+
+impl BackupJournal {
+    pub fn load(path: &Path) -> Self {
+        let pending = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            pending: Mutex::new(pending),
+        }
+    }
+
+    pub fn pending(&self) -> Vec<PendingBackup> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<PendingBackup>)) -> std::io::Result<()> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut pending);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&*pending).map_err(std::io::Error::other)?;
+        fs::write(&self.path, json)
+    }
+
+    pub fn add(&self, backup: &PendingBackup) -> std::io::Result<()> {
+        self.update(|pending| {
+            if !pending.contains(backup) {
+                pending.push(backup.clone());
+            }
+        })
+    }
+
+    pub fn remove(&self, backup: &PendingBackup) -> std::io::Result<()> {
+        self.update(|pending| pending.retain(|known| known != backup))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{BackupEngine, BackupOptions};
+    use crate::status::StatusEvent;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_interrupted_backups_are_resumed() {
+        let test_dir = std::env::temp_dir().join("siegesaver_journal_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let destination = test_dir.join("backup");
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        fs::write(source.join("Match-A").join("round1.rec"), "round 1").unwrap();
+        fs::create_dir_all(&destination).unwrap();
+        let journal_path = test_dir.join(JOURNAL_FILE_NAME);
+
+        // The app was killed while copying Match-A
+        let journal = BackupJournal::load(&journal_path);
+        let interrupted = PendingBackup {
+            path: source.join("Match-A"),
+            destination_folder: destination.clone(),
+        };
+        journal.add(&interrupted).unwrap();
+        journal.add(&interrupted).unwrap();
+        journal
+            .add(&PendingBackup {
+                path: source.join("Match-B"),
+                destination_folder: test_dir.join("elsewhere"),
+            })
+            .unwrap();
+        drop(journal);
+
+        let journal = Arc::new(BackupJournal::load(&journal_path));
+        assert_eq!(journal.pending().len(), 2);
+        let engine = BackupEngine::new(source.clone(), destination.clone(), Default::default())
+            .with_journal(Arc::clone(&journal));
+        let (status_tx, status_rx) = channel();
+        assert_eq!(engine.resume_interrupted(&status_tx), 1);
+        assert_eq!(
+            fs::read(destination.join("Match-A").join("round1.rec")).unwrap(),
+            b"round 1"
+        );
+        let messages: Vec<String> = status_rx.try_iter().map(|m| m.to_string()).collect();
+        assert!(messages.contains(&"Resumed 1 interrupted backup".to_string()));
+
+        // Only the other destination's backup is left, also on disk
+        let left = BackupJournal::load(&journal_path).pending();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].destination_folder, test_dir.join("elsewhere"));
+        assert_eq!(engine.resume_interrupted(&status_tx), 0);
+
+        // Backups that finish leave nothing behind
+        let other = BackupEngine::new(source, destination, BackupOptions::default())
+            .with_journal(Arc::clone(&journal));
+        other.backup(&test_dir.join("replays").join("Match-A"), &status_tx);
+        assert_eq!(journal.pending().len(), 1);
+        assert!(!matches!(status_rx.try_recv(), Ok(StatusEvent::Error(_))));
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
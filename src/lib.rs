@@ -0,0 +1,83 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+// Backup engine, file watching and GUI of SiegeSaver. The binary only wires these together,
+// so the copy and watch logic can be tested and reused without starting egui.
+
+pub mod analytics;
+pub mod api;
+pub mod autostart;
+pub mod backup;
+pub mod bundle;
+pub mod cli;
+pub mod config;
+pub mod crypto;
+pub mod dedupe;
+pub mod diagnostics;
+pub mod discord;
+pub mod dissect;
+pub mod gdrive;
+pub mod headless;
+pub mod history;
+pub mod hook;
+pub mod i18n;
+pub mod instance;
+pub mod journal;
+pub mod library;
+pub mod logging;
+pub mod longpath;
+pub mod manifest;
+pub mod metrics;
+pub mod mirror;
+pub mod notifications;
+pub mod presence;
+pub mod queue;
+pub mod quota;
+pub mod rclone;
+pub mod remote;
+pub mod restore;
+pub mod retention;
+pub mod s3;
+pub mod selftest;
+pub mod service;
+pub mod share;
+pub mod snapshot;
+pub mod stats;
+pub mod status;
+pub mod storage;
+pub mod throttle;
+pub mod tray;
+pub mod ui;
+pub mod versions;
+pub mod volume;
+pub mod watcher;
+pub mod webdav;
+pub mod webhook;
+
+pub use backup::BackupEngine;
+
+// Version, git commit and build date of this binary, e.g. "SiegeSaver v0.1.0 (3f2a1bc, built 2025-11-24)"
+pub fn build_info() -> String {
+    format!(
+        "SiegeSaver v{} ({}, built {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("SIEGESAVER_GIT_COMMIT"),
+        env!("SIEGESAVER_BUILD_DATE")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        let info = build_info();
+        let rest = info
+            .strip_prefix(&format!("SiegeSaver v{} (", env!("CARGO_PKG_VERSION")))
+            .expect("starts with the name and version");
+        let details = rest.strip_suffix(')').expect("ends with a parenthesis");
+        let (commit, date) = details.split_once(", built ").expect("commit and date");
+        assert!(!commit.is_empty());
+        assert!(chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::manifest::{FileRecord, Manifest, ManifestEntry};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+// What the Library tab shows; empty fields match everything
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LibraryFilter {
+    // Case-insensitive, matched against the backup name, its source, its file names and notes
+    pub text: String,
+    // Only items carrying this tag, compared case-insensitively
+    pub tag: Option<String>,
+    // Inclusive range of local dates the match was first backed up on
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl LibraryFilter {
+    pub fn matches(&self, name: &str, entry: &ManifestEntry) -> bool {
+        let text = self.text.trim().to_lowercase();
+        if !text.is_empty() {
+            let source = entry
+                .source
+                .as_ref()
+                .map(|source| source.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let found = name.to_lowercase().contains(&text)
+                || source.contains(&text)
+                || entry.notes.to_lowercase().contains(&text)
+                || entry
+                    .files
+                    .keys()
+                    .any(|file| file.to_lowercase().contains(&text));
+            if !found {
+                return false;
+            }
+        }
+
+        if let Some(tag) = &self.tag {
+            if !entry
+                .tags
+                .iter()
+                .any(|found| found.eq_ignore_ascii_case(tag))
+            {
+                return false;
+            }
+        }
+
+        if self.from.is_none() && self.to.is_none() {
+            return true;
+        }
+        let Some(date) = backup_date(entry.first_backup) else {
+            return false;
+        };
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+// Splits a comma-separated tag list as typed in the UI, e.g. "Clutch, ranked". Tags are
+// stored in lowercase so the same tag is not listed twice.
+pub fn parse_tags(text: &str) -> BTreeSet<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// File format of a library export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+// One backed-up item as written to a JSON export
+#[derive(Serialize)]
+struct ExportedItem<'a> {
+    name: &'a str,
+    source: Option<&'a PathBuf>,
+    first_backup: String,
+    last_backup: String,
+    bytes: u64,
+    tags: &'a BTreeSet<String>,
+    notes: &'a str,
+    files: &'a BTreeMap<String, FileRecord>,
+}
+
+fn format_timestamp(unix_seconds: u64) -> String {
+    i64::try_from(unix_seconds)
+        .ok()
+        .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+        .map(|time| time.with_timezone(&chrono::Local).to_rfc3339())
+        .unwrap_or_default()
+}
+
+// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Writes library items for spreadsheets or other tools. CSV has one row per backed-up file,
+// JSON one object per item with its files nested.
+pub fn export_library(
+    items: &[(&str, &ManifestEntry)],
+    format: ExportFormat,
+) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => {
+            let exported: Vec<_> = items
+                .iter()
+                .map(|(name, entry)| ExportedItem {
+                    name,
+                    source: entry.source.as_ref(),
+                    first_backup: format_timestamp(entry.first_backup),
+                    last_backup: format_timestamp(entry.last_backup),
+                    bytes: entry.bytes,
+                    tags: &entry.tags,
+                    notes: &entry.notes,
+                    files: &entry.files,
+                })
+                .collect();
+            serde_json::to_string_pretty(&exported).map_err(|e| e.to_string())
+        }
+        ExportFormat::Csv => {
+            let mut csv = String::from(
+                "match,source,first_backup,last_backup,tags,notes,file,bytes,sha256\n",
+            );
+            for (name, entry) in items {
+                let tags = entry
+                    .tags
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let item = [
+                    name.to_string(),
+                    entry
+                        .source
+                        .as_ref()
+                        .map(|source| source.display().to_string())
+                        .unwrap_or_default(),
+                    format_timestamp(entry.first_backup),
+                    format_timestamp(entry.last_backup),
+                    tags,
+                    entry.notes.clone(),
+                ];
+                for (file, record) in &entry.files {
+                    let row: Vec<String> = item
+                        .iter()
+                        .map(String::as_str)
+                        .chain([file.as_str(), &record.bytes.to_string(), &record.sha256])
+                        .map(csv_field)
+                        .collect();
+                    csv.push_str(&row.join(","));
+                    csv.push('\n');
+                }
+            }
+            Ok(csv)
+        }
+    }
+}
+
+// Local date of a manifest timestamp
+pub fn backup_date(unix_seconds: u64) -> Option<NaiveDate> {
+    let time = chrono::DateTime::from_timestamp(i64::try_from(unix_seconds).ok()?, 0)?;
+    Some(time.with_timezone(&chrono::Local).date_naive())
+}
+
+// Parses a `YYYY-MM-DD` date from a filter field; empty means no bound
+pub fn parse_date(text: &str) -> Result<Option<NaiveDate>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", text))
+}
+
+// The manifest entries passing the filter, most recently backed up first
+pub fn filter_library<'a>(
+    manifest: &'a Manifest,
+    filter: &LibraryFilter,
+) -> Vec<(&'a str, &'a ManifestEntry)> {
+    let mut items: Vec<_> = manifest
+        .entries
+        .iter()
+        .filter(|(name, entry)| filter.matches(name, entry))
+        .map(|(name, entry)| (name.as_str(), entry))
+        .collect();
+    items.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_backup));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::FileRecord;
+    use std::path::PathBuf;
+
+    fn timestamp(date: &str) -> u64 {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .unwrap()
+            .timestamp() as u64
+    }
+
+    #[test]
+    fn test_filter_library() {
+        let mut manifest = Manifest::default();
+        for (name, date, file, tags, notes) in [
+            (
+                "Match-A",
+                "2025-11-01",
+                "Match-A/round1.rec",
+                "ranked, review later",
+                "",
+            ),
+            (
+                "Match-B.zip",
+                "2025-11-10",
+                "Match-B.zip",
+                "",
+                "ace on Bank",
+            ),
+            (
+                "Match-C",
+                "2025-11-20",
+                "Match-C/clutch.rec",
+                "Ranked, , clutch",
+                "",
+            ),
+        ] {
+            let mut entry = ManifestEntry {
+                source: Some(PathBuf::from("/replays").join(name)),
+                first_backup: timestamp(date),
+                last_backup: timestamp(date),
+                tags: parse_tags(tags),
+                notes: notes.to_string(),
+                ..Default::default()
+            };
+            entry.files.insert(file.to_string(), FileRecord::default());
+            manifest.entries.insert(name.to_string(), entry);
+        }
+        let names = |filter: &LibraryFilter| {
+            filter_library(&manifest, filter)
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&LibraryFilter::default()),
+            ["Match-C", "Match-B.zip", "Match-A"]
+        );
+        let text = LibraryFilter {
+            text: " CLUTCH ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(names(&text), ["Match-C"]);
+        let range = LibraryFilter {
+            from: parse_date("2025-11-01").unwrap(),
+            to: parse_date("2025-11-10").unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(names(&range), ["Match-B.zip", "Match-A"]);
+        let notes = LibraryFilter {
+            text: "bank".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(names(&notes), ["Match-B.zip"]);
+        let tagged = LibraryFilter {
+            tag: Some("ranked".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(names(&tagged), ["Match-C", "Match-A"]);
+        assert_eq!(
+            manifest.tags().into_iter().collect::<Vec<_>>(),
+            ["clutch", "ranked", "review later"]
+        );
+
+        let items = filter_library(&manifest, &tagged);
+        let csv = export_library(&items, ExportFormat::Csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("Match-C,/replays/Match-C,"));
+        assert!(lines[1].ends_with(",\"clutch, ranked\",,Match-C/clutch.rec,0,"));
+        let json: serde_json::Value =
+            serde_json::from_str(&export_library(&items, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[1]["name"], "Match-A");
+        assert_eq!(json[1]["tags"][1], "review later");
+        assert!(json[1]["files"]["Match-A/round1.rec"]["sha256"].is_string());
+
+        assert_eq!(parse_date("  ").unwrap(), None);
+        assert!(parse_date("11/01/2025").is_err());
+    }
+}
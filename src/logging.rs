@@ -0,0 +1,347 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::status::{EventDetail, Severity, StatusEvent};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::field::display;
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::time::ChronoLocal;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+// Folder next to the config file with one log file per day, e.g. siegesaver.2025-11-23.log
+pub const LOG_FOLDER_NAME: &str = "logs";
+const LOG_FILE_PREFIX: &str = "siegesaver";
+const LOG_FILE_SUFFIX: &str = "log";
+// Days of logs kept; older files are deleted when a new one is started
+const KEPT_LOG_FILES: usize = 14;
+// Lines kept by the log view
+pub const LOG_VIEW_LINES: usize = 2000;
+// Log lines start with the local time in this format
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// How much goes into the log file. Debug and Trace add every queued job and file event,
+// which is what is needed to find out why a backup was missed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "Errors only",
+            LogLevel::Warn => "Warnings",
+            LogLevel::Info => "Normal",
+            LogLevel::Debug => "Debug",
+            LogLevel::Trace => "Everything (trace)",
+        }
+    }
+
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+// Changes the level of the running logger, see `set_log_level`
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+pub fn log_folder(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(LOG_FOLDER_NAME)
+}
+
+// Sends everything logged through `tracing` to a daily log file in the log folder next to
+// the config file. Lines are written on a background thread; the returned guard writes out
+// what is left when it is dropped, so it has to live until the process exits.
+pub fn init_logging(config_path: &Path, level: LogLevel) -> Result<WorkerGuard, String> {
+    let folder = log_folder(config_path);
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(KEPT_LOG_FILES)
+        .build(&folder)
+        .map_err(|e| format!("Error opening the log folder {}: {}", folder.display(), e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (filter, handle) = reload::Layer::new(level.filter());
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_target(false)
+                .with_timer(ChronoLocal::new(TIME_FORMAT.to_string())),
+        )
+        .try_init()
+        .map_err(|e| format!("Error starting the log: {}", e))?;
+    let _ = LEVEL.set(handle);
+    Ok(guard)
+}
+
+// Applies a changed log level setting without a restart
+pub fn set_log_level(level: LogLevel) {
+    if let Some(handle) = LEVEL.get() {
+        let _ = handle.modify(|filter| *filter = level.filter());
+    }
+}
+
+// Writes a status event to the log at the level of its severity, with the source,
+// destination and size of the backup it describes as fields
+pub fn log_event(event: &StatusEvent) {
+    let level = match event {
+        // Several times a second while copying
+        StatusEvent::Progress { .. }
+        | StatusEvent::CopyProgress { .. }
+        | StatusEvent::RestoreProgress { .. } => Level::TRACE,
+        _ => match event.severity() {
+            Severity::Error => Level::ERROR,
+            Severity::Warning => Level::WARN,
+            Severity::Info | Severity::Success => Level::INFO,
+        },
+    };
+    let detail = event.detail();
+    let source = detail.map(|detail| display(detail.source.display()));
+    let destination = detail.map(|detail| display(detail.destination.display()));
+    let files = detail.and_then(|detail| detail.files);
+    let bytes = detail.and_then(|detail| detail.bytes);
+    let millis = detail
+        .and_then(|detail| detail.duration)
+        .map(|duration| duration.as_millis() as u64);
+    // The level of a tracing event has to be known where it is written
+    macro_rules! log_at {
+        ($level:expr) => {
+            tracing::event!(
+                $level,
+                source,
+                destination,
+                files,
+                bytes,
+                millis,
+                "{}",
+                event
+            )
+        };
+    }
+    match level {
+        Level::ERROR => log_at!(Level::ERROR),
+        Level::WARN => log_at!(Level::WARN),
+        Level::INFO => log_at!(Level::INFO),
+        Level::DEBUG => log_at!(Level::DEBUG),
+        _ => log_at!(Level::TRACE),
+    }
+}
+
+// A line of the log as shown in the log view
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub time: NaiveDateTime,
+    pub severity: Severity,
+    pub text: String,
+    // Only events of this session have details; lines read back from the log file do not
+    pub detail: Option<EventDetail>,
+}
+
+impl LogRecord {
+    // Parses a line of the log file. Debug and trace lines are left out, they are only
+    // meant for bug reports.
+    pub fn parse(line: &str) -> Option<Self> {
+        let time = NaiveDateTime::parse_and_remainder(line, TIME_FORMAT).ok()?;
+        let (level, text) = time.1.trim_start().split_once(' ')?;
+        let severity = match level {
+            "ERROR" => Severity::Error,
+            "WARN" => Severity::Warning,
+            "INFO" => Severity::Info,
+            _ => return None,
+        };
+        Some(Self {
+            time: time.0,
+            severity,
+            text: text.trim_start().to_string(),
+            detail: None,
+        })
+    }
+}
+
+// Which lines the log view shows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFilter {
+    pub info: bool,
+    pub warnings: bool,
+    pub errors: bool,
+    // Shows only lines containing this, ignoring case
+    pub search: String,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            info: true,
+            warnings: true,
+            errors: true,
+            search: String::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        let shown = match record.severity {
+            Severity::Info | Severity::Success => self.info,
+            Severity::Warning => self.warnings,
+            Severity::Error => self.errors,
+        };
+        let search = self.search.trim().to_lowercase();
+        shown && (search.is_empty() || record.text.to_lowercase().contains(&search))
+    }
+}
+
+// The last `limit` info, warning and error lines of the log files in the log folder, oldest
+// first
+pub fn read_log(config_path: &Path, limit: usize) -> std::io::Result<Vec<LogRecord>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(log_folder(config_path))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect();
+    // The names end in the date, so they sort by age
+    files.sort();
+    let mut records = Vec::new();
+    for file in files.iter().rev() {
+        let text = std::fs::read_to_string(file)?;
+        let mut older: Vec<LogRecord> = text.lines().filter_map(LogRecord::parse).collect();
+        older.append(&mut records);
+        records = older;
+        if records.len() >= limit {
+            break;
+        }
+    }
+    let skip = records.len().saturating_sub(limit);
+    records.drain(..skip);
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::EventDetail;
+    use std::fs;
+
+    #[test]
+    fn test_status_events_are_logged_to_a_file() {
+        let test_dir = std::env::temp_dir().join("siegesaver_logging_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let config_path = test_dir.join("config.json");
+        let guard = init_logging(&config_path, LogLevel::Info).unwrap();
+
+        log_event(&StatusEvent::FolderBackedUp {
+            name: "Match-A".to_string(),
+            detail: EventDetail {
+                source: PathBuf::from("/replays/Match-A"),
+                destination: PathBuf::from("/backup"),
+                files: Some(3),
+                bytes: Some(1024),
+                duration: None,
+                error: None,
+            },
+        });
+        log_event(&StatusEvent::Progress {
+            name: "Match-B".to_string(),
+            done: 1,
+            total: 2,
+        });
+        // Lowered while running
+        set_log_level(LogLevel::Warn);
+        log_event(&StatusEvent::Info(
+            "Started the periodic rescan".to_string(),
+        ));
+        log_event(&StatusEvent::Error("Error rescanning".to_string()));
+        drop(guard);
+
+        let files: Vec<_> = fs::read_dir(log_folder(&config_path))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        let name = files[0].file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("siegesaver.") && name.ends_with(".log"));
+        // Other tests running at the same time log into the same file
+        let log = fs::read_to_string(&files[0]).unwrap();
+        let line = |text: &str| log.lines().find(|line| line.contains(text));
+        let backed_up = line("Backed up folder: Match-A").unwrap();
+        assert!(backed_up.contains("INFO"));
+        assert!(backed_up.contains("source=/replays/Match-A"));
+        assert!(backed_up.contains("files=3 bytes=1024"));
+        assert!(line("Error rescanning").unwrap().contains("ERROR"));
+        assert!(line("Archiving Match-B").is_none());
+        assert!(line("Started the periodic rescan").is_none());
+
+        // Read back for the log view
+        let records = read_log(&config_path, 1000).unwrap();
+        let backed_up = records
+            .iter()
+            .find(|record| record.text.starts_with("Backed up folder: Match-A"))
+            .unwrap();
+        assert_eq!(backed_up.severity, Severity::Info);
+        assert!(records
+            .iter()
+            .any(|record| record.severity == Severity::Error));
+        assert_eq!(read_log(&config_path, 1).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_log_lines_are_parsed_and_filtered() {
+        let record = LogRecord::parse(
+            "2025-11-23 21:04:05  WARN watch{source=/replays}: Rescan found 2 changed items",
+        )
+        .unwrap();
+        assert_eq!(record.time.to_string(), "2025-11-23 21:04:05");
+        assert_eq!(record.severity, Severity::Warning);
+        assert_eq!(
+            record.text,
+            "watch{source=/replays}: Rescan found 2 changed items"
+        );
+        assert!(LogRecord::parse("2025-11-23 21:04:05 DEBUG Queued /replays/Match-A").is_none());
+        assert!(LogRecord::parse("not a log line").is_none());
+
+        let mut filter = LogFilter::default();
+        assert!(filter.matches(&record));
+        filter.search = "RESCAN".to_string();
+        assert!(filter.matches(&record));
+        filter.search = "Match-A".to_string();
+        assert!(!filter.matches(&record));
+        filter.search.clear();
+        filter.warnings = false;
+        assert!(!filter.matches(&record));
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use std::borrow::Cow;
+use std::path::Path;
+
+// Windows refuses paths longer than MAX_PATH (260 characters) unless they are written in the
+// extended-length `\\?\` form, and deep destination trees with long match names easily get
+// there. The rest of the app works with regular paths; they are only turned into the
+// extended form right before they are handed to the operating system.
+
+// The extended-length form of an absolute path on Windows. Relative paths, paths already in
+// that form and every path on other systems are returned as they are.
+#[cfg(windows)]
+pub fn extended_path(path: &Path) -> Cow<'_, Path> {
+    use std::path::{Component, PathBuf, Prefix};
+
+    if !path.is_absolute() {
+        return Cow::Borrowed(path);
+    }
+    let mut components = path.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) => PathBuf::from(format!(r"\\?\{}:\", letter as char)),
+            Prefix::UNC(server, share) => PathBuf::from(format!(
+                r"\\?\UNC\{}\{}\",
+                server.to_string_lossy(),
+                share.to_string_lossy()
+            )),
+            // Already extended, or a device path
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+    // Windows takes extended paths literally, so `.` and `..` are resolved here and every
+    // `/` becomes a `\`
+    for component in components {
+        match component {
+            Component::ParentDir => {
+                extended.pop();
+            }
+            Component::Normal(name) => extended.push(name),
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    Cow::Owned(extended)
+}
+
+#[cfg(not(windows))]
+pub fn extended_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+// Undoes `extended_path`, e.g. for paths the file watcher reports below an extended path
+#[cfg(windows)]
+pub fn regular_path(path: &Path) -> Cow<'_, Path> {
+    use std::path::{Component, PathBuf, Prefix};
+
+    let mut components = path.components();
+    let mut regular = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::VerbatimDisk(letter) => PathBuf::from(format!(r"{}:\", letter as char)),
+            Prefix::VerbatimUNC(server, share) => PathBuf::from(format!(
+                r"\\{}\{}\",
+                server.to_string_lossy(),
+                share.to_string_lossy()
+            )),
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+    regular.extend(components.filter(|component| !matches!(component, Component::RootDir)));
+    Cow::Owned(regular)
+}
+
+#[cfg(not(windows))]
+pub fn regular_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(windows)]
+    #[test]
+    fn test_extended_paths() {
+        let cases = [
+            (r"C:\Replays\Match-A", r"\\?\C:\Replays\Match-A"),
+            (r"C:/Replays/./Old/../Match-A", r"\\?\C:\Replays\Match-A"),
+            (r"\\nas\backups\Match-A", r"\\?\UNC\nas\backups\Match-A"),
+            (r"\\?\C:\Replays", r"\\?\C:\Replays"),
+            (r"Replays\Match-A", r"Replays\Match-A"),
+        ];
+        for (path, extended) in cases {
+            assert_eq!(extended_path(Path::new(path)), Path::new(extended));
+        }
+        for (regular, extended) in [
+            (r"C:\Replays\Match-A", r"\\?\C:\Replays\Match-A"),
+            (r"\\nas\backups\Match-A", r"\\?\UNC\nas\backups\Match-A"),
+        ] {
+            assert_eq!(regular_path(Path::new(extended)), Path::new(regular));
+        }
+    }
+
+    #[test]
+    fn test_paths_longer_than_max_path() {
+        use crate::backup::{backup_path, BackupOptions, RealFs};
+        use std::fs;
+
+        let test_dir = std::env::temp_dir().join("siegesaver_longpath_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let match_name = format!("Match-{}", "0123456789".repeat(10));
+        fs::create_dir_all(source.join(&match_name)).unwrap();
+        fs::write(source.join(&match_name).join("round1.rec"), "round 1").unwrap();
+        let destination = (0..3).fold(test_dir.join("backup"), |path, level| {
+            path.join(format!("{}-{}", level, "abcdefghij".repeat(6)))
+        });
+
+        let backup = backup_path(
+            &RealFs,
+            &source.join(&match_name),
+            &source,
+            &destination,
+            &BackupOptions::default(),
+        )
+        .unwrap();
+        assert!(backup.totals.is_some());
+        let backed_up = destination.join(&match_name).join("round1.rec");
+        assert!(backed_up.as_os_str().len() > 260);
+        assert_eq!(fs::read(extended_path(&backed_up)).unwrap(), b"round 1");
+
+        let _ = fs::remove_dir_all(extended_path(&test_dir));
+    }
+}
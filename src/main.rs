@@ -1,201 +1,55 @@
 // Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
 
 #![windows_subsystem = "windows"]
-use auto_launch::{AutoLaunch, AutoLaunchBuilder};
+use clap::Parser;
 use eframe::egui;
-use notify::{Event, EventKind, RecursiveMode, Watcher};
-use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, FileIdMap};
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::fs;
-use std::path::PathBuf;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tray_icon::Icon;
-use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem},
-    MouseButton, TrayIconBuilder, TrayIconEvent,
-};
+use siegesaver::cli::{attach_console, Cli};
+use siegesaver::config::AppConfig;
+use siegesaver::i18n::set_language;
+use siegesaver::instance::claim_instance;
+use siegesaver::logging::init_logging;
+use siegesaver::tray::{load_icon_for_window, AppTray};
+use siegesaver::ui::{window_viewport, SiegeSaverApp};
 
-#[derive(Serialize, Deserialize, Default)]
-struct AppConfig {
-    start_on_boot: bool,
-    source_folder: String,
-    destination_folder: String,
-}
-
-impl AppConfig {
-    fn load() -> Self {
-        if let Some(config_dir) = dirs::config_dir() {
-            let config_path = config_dir.join("siegesaver").join("config.json");
-            if let Ok(contents) = fs::read_to_string(&config_path) {
-                if let Ok(config) = serde_json::from_str(&contents) {
-                    return config;
-                }
-            }
-        }
-        Self::default()
-    }
-
-    fn save(&self) {
-        if let Some(config_dir) = dirs::config_dir() {
-            let config_dir = config_dir.join("siegesaver");
-            if fs::create_dir_all(&config_dir).is_ok() {
-                let config_path = config_dir.join("config.json");
-                if let Ok(json) = serde_json::to_string_pretty(self) {
-                    let _ = fs::write(&config_path, json);
-                }
-            }
-        }
-    }
-}
-
-fn get_auto_launch() -> Result<AutoLaunch, String> {
-    let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
-
-    AutoLaunchBuilder::new()
-        .set_app_name("SiegeSaver")
-        .set_app_path(&exe_path.to_string_lossy())
-        .build()
-        .map_err(|e| e.to_string())
-}
-
-fn load_icon_for_window() -> std::sync::Arc<egui::IconData> {
-    match load_icon_data_from_file() {
-        Ok(icon_data) => std::sync::Arc::new(icon_data),
+fn main() -> Result<(), eframe::Error> {
+    // Help, errors and subcommand output go to the console the binary was started from
+    if std::env::args_os().len() > 1 {
+        attach_console();
+    }
+    let cli = Cli::parse();
+    let config_path = AppConfig::resolve_path();
+    // Kept until the process exits, so the last log lines are written out
+    let _log_guard = match init_logging(&config_path, AppConfig::load(&config_path).log_level) {
+        Ok(guard) => Some(guard),
         Err(e) => {
-            eprintln!(
-                "Warning: Failed to load icon.ico for window: {}. Using fallback icon.",
-                e
-            );
-            std::sync::Arc::new(create_fallback_icon_data())
-        }
-    }
-}
-
-fn load_icon_bytes_from_file() -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    // Load the icon.ico file
-    let icon_path = std::env::current_exe()?
-        .parent()
-        .ok_or("Failed to get parent directory")?
-        .join("icon.ico");
-
-    // Try current exe directory first, then try current working directory
-    if icon_path.exists() {
-        Ok(std::fs::read(&icon_path)?)
-    } else {
-        Ok(std::fs::read("icon.ico")?)
-    }
-}
-
-fn load_icon_data_from_file() -> Result<egui::IconData, Box<dyn std::error::Error>> {
-    let icon_data = load_icon_bytes_from_file()?;
-
-    // Load the ICO file using the image crate
-    let img = image::load_from_memory(&icon_data)?;
-
-    // Convert to RGBA8
-    let rgba_image = img.to_rgba8();
-    let (width, height) = rgba_image.dimensions();
-
-    Ok(egui::IconData {
-        rgba: rgba_image.into_raw(),
-        width,
-        height,
-    })
-}
-
-fn generate_fallback_rgba() -> Vec<u8> {
-    // Create a simple 16x16 icon (blue/white pattern)
-    // RGBA format: each pixel is 4 bytes (R, G, B, A)
-    let mut rgba = Vec::with_capacity(16 * 16 * 4);
-
-    for y in 0..16 {
-        for x in 0..16 {
-            // Create a simple pattern - blue circle on transparent background
-            let dx = x as f32 - 7.5;
-            let dy = y as f32 - 7.5;
-            let dist = (dx * dx + dy * dy).sqrt();
-
-            if dist < 6.0 {
-                // Blue color inside the circle
-                rgba.extend_from_slice(&[33, 150, 243, 255]); // Blue (#2196F3)
-            } else {
-                // Transparent outside
-                rgba.extend_from_slice(&[0, 0, 0, 0]);
-            }
+            eprintln!("Warning: {}", e);
+            None
         }
-    }
-
-    rgba
-}
-
-fn create_fallback_icon_data() -> egui::IconData {
-    egui::IconData {
-        rgba: generate_fallback_rgba(),
-        width: 16,
-        height: 16,
-    }
-}
-
-fn load_icon() -> Icon {
-    // Try to load the icon from icon.ico file
-    // If it fails, fall back to a programmatically generated icon
-    match load_icon_from_file() {
-        Ok(icon) => icon,
-        Err(e) => {
-            eprintln!(
-                "Warning: Failed to load icon.ico: {}. Using fallback icon.",
-                e
-            );
-            create_fallback_icon()
+    };
+    if let Some(result) = cli.run_without_gui(&config_path) {
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
+        return Ok(());
     }
-}
-
-fn load_icon_from_file() -> Result<Icon, Box<dyn std::error::Error>> {
-    let icon_data = load_icon_bytes_from_file()?;
-
-    // Load the ICO file using the image crate
-    let img = image::load_from_memory(&icon_data)?;
 
-    // Convert to RGBA8
-    let rgba_image = img.to_rgba8();
-    let (width, height) = rgba_image.dimensions();
-
-    // Create Icon from RGBA data
-    let icon = Icon::from_rgba(rgba_image.into_raw(), width, height)?;
-    Ok(icon)
-}
-
-fn create_fallback_icon() -> Icon {
-    Icon::from_rgba(generate_fallback_rgba(), 16, 16).expect("Failed to create fallback icon")
-}
-
-fn main() -> Result<(), eframe::Error> {
-    // Initialize tray icon menu
-    let menu = Menu::new();
-    let quit_item = MenuItem::new("Quit", true, None);
-    menu.append(&quit_item)
-        .expect("Failed to append quit item to menu");
-
-    // Create the tray icon
-    let tray_icon_data = load_icon();
-    let tray_icon = TrayIconBuilder::new()
-        .with_menu(Box::new(menu))
-        .with_tooltip("SiegeSaver")
-        .with_icon(tray_icon_data)
-        .build()
-        .expect("Failed to create tray icon");
+    // A second copy only brings up the window of the one already running
+    let Some(instance) = claim_instance(&config_path) else {
+        return Ok(());
+    };
+    // The tray menu is built before the window, so its language is set first
+    set_language(AppConfig::load(&config_path).language);
+    let tray = AppTray::create();
 
     // Load icon for window
     let window_icon = load_icon_for_window();
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([600.0, 400.0])
-            .with_icon(window_icon),
+        viewport: window_viewport(
+            egui::ViewportBuilder::default().with_icon(window_icon),
+            AppConfig::load(&config_path).window,
+        ),
         ..Default::default()
     };
 
@@ -203,457 +57,8 @@ fn main() -> Result<(), eframe::Error> {
         "SiegeSaver - Replay File Backup Utility",
         options,
         Box::new(move |cc| {
-            // Keep tray_icon alive by moving it into the closure
-            let _tray = tray_icon;
-            Ok(Box::new(SiegeSaverApp::new(cc, quit_item.id().clone())))
+            // The app owns the tray icon so it stays alive and can update its tooltip
+            Ok(Box::new(SiegeSaverApp::new(cc, tray, instance)))
         }),
     )
 }
-
-struct SiegeSaverApp {
-    source_folder: String,
-    destination_folder: String,
-    watcher: Option<Arc<Mutex<Debouncer<notify::RecommendedWatcher, FileIdMap>>>>,
-    status_messages: VecDeque<String>,
-    is_watching: bool,
-    status_receiver: Option<Receiver<String>>,
-    start_on_boot: bool,
-    quit_item_id: tray_icon::menu::MenuId,
-    should_exit: bool,
-}
-
-impl SiegeSaverApp {
-    fn new(_cc: &eframe::CreationContext<'_>, quit_item_id: tray_icon::menu::MenuId) -> Self {
-        let config = AppConfig::load();
-        Self {
-            source_folder: config.source_folder,
-            destination_folder: config.destination_folder,
-            watcher: None,
-            status_messages: VecDeque::new(),
-            is_watching: false,
-            status_receiver: None,
-            start_on_boot: config.start_on_boot,
-            quit_item_id,
-            should_exit: false,
-        }
-    }
-
-    fn save_config(&self) {
-        let config = AppConfig {
-            start_on_boot: self.start_on_boot,
-            source_folder: self.source_folder.clone(),
-            destination_folder: self.destination_folder.clone(),
-        };
-        config.save();
-    }
-
-    fn add_status(&mut self, message: String) {
-        self.status_messages.push_back(format!(
-            "[{}] {}",
-            chrono::Local::now().format("%H:%M:%S"),
-            message
-        ));
-        // Keep only the last 100 messages
-        if self.status_messages.len() > 100 {
-            self.status_messages.pop_front();
-        }
-    }
-
-    fn start_watching(&mut self) {
-        if self.source_folder.is_empty() || self.destination_folder.is_empty() {
-            self.add_status("Error: Please select both source and destination folders".to_string());
-            return;
-        }
-
-        let source_path = PathBuf::from(&self.source_folder);
-        let destination_path = PathBuf::from(&self.destination_folder);
-
-        if !source_path.exists() {
-            self.add_status(format!(
-                "Error: Source folder does not exist: {}",
-                self.source_folder
-            ));
-            return;
-        }
-
-        if !destination_path.exists() {
-            if let Err(e) = fs::create_dir_all(&destination_path) {
-                self.add_status(format!("Error creating destination folder: {}", e));
-                return;
-            }
-            self.add_status(format!(
-                "Created destination folder: {}",
-                self.destination_folder
-            ));
-        }
-
-        let dest_clone = destination_path.clone();
-        let (tx, rx) = channel();
-        let (status_tx, status_rx) = channel();
-        let status_tx_clone = status_tx.clone();
-
-        let mut debouncer = match new_debouncer(
-            Duration::from_millis(500),
-            None,
-            move |result: DebounceEventResult| match result {
-                Ok(events) => {
-                    for event in events {
-                        if let Err(e) = tx.send(event.event) {
-                            let _ = status_tx_clone.send(format!("Error sending event: {}", e));
-                        }
-                    }
-                }
-                Err(errors) => {
-                    for error in errors {
-                        let _ = status_tx_clone.send(format!("File watch error: {:?}", error));
-                    }
-                }
-            },
-        ) {
-            Ok(d) => d,
-            Err(e) => {
-                self.add_status(format!("Error creating file watcher: {}", e));
-                return;
-            }
-        };
-
-        if let Err(e) = debouncer
-            .watcher()
-            .watch(&source_path, RecursiveMode::Recursive)
-        {
-            self.add_status(format!("Error watching folder: {}", e));
-            return;
-        }
-
-        self.watcher = Some(Arc::new(Mutex::new(debouncer)));
-        self.is_watching = true;
-        self.status_receiver = Some(status_rx);
-        self.add_status(format!("Started watching: {}", self.source_folder));
-
-        // Spawn a thread to handle file events
-        let dest_for_thread = dest_clone;
-        let source_for_thread = source_path.clone();
-        std::thread::spawn(move || {
-            handle_file_events(rx, source_for_thread, dest_for_thread, status_tx);
-        });
-    }
-
-    fn stop_watching(&mut self) {
-        self.watcher = None;
-        self.is_watching = false;
-        self.add_status("Stopped watching".to_string());
-    }
-
-    fn set_start_on_boot(&mut self, enabled: bool) {
-        match get_auto_launch() {
-            Ok(auto_launch) => {
-                let result = if enabled {
-                    auto_launch.enable()
-                } else {
-                    auto_launch.disable()
-                };
-
-                match result {
-                    Ok(_) => {
-                        self.start_on_boot = enabled;
-                        self.save_config();
-                        let status = if enabled { "enabled" } else { "disabled" };
-                        self.add_status(format!("Start on system boot {}", status));
-                    }
-                    Err(e) => {
-                        self.add_status(format!("Error setting start on boot: {}", e));
-                    }
-                }
-            }
-            Err(e) => {
-                self.add_status(format!("Error accessing auto-launch: {}", e));
-            }
-        }
-    }
-}
-
-fn handle_file_events(
-    rx: Receiver<Event>,
-    source_folder: PathBuf,
-    destination_folder: PathBuf,
-    status_tx: Sender<String>,
-) {
-    while let Ok(event) = rx.recv() {
-        match event.kind {
-            EventKind::Create(_) | EventKind::Modify(_) => {
-                for path in event.paths {
-                    // Check if the path is a file (not a directory)
-                    if path.is_file() {
-                        // Check if the file has a .rec extension
-                        if let Some(extension) = path.extension() {
-                            if extension == "rec" {
-                                // Calculate relative path from source to get the destination path
-                                if let Ok(relative_path) = path.strip_prefix(&source_folder) {
-                                    let dest_path = destination_folder.join(relative_path);
-
-                                    // Ensure the parent directory exists
-                                    if let Some(parent) = dest_path.parent() {
-                                        if let Err(e) = fs::create_dir_all(parent) {
-                                            let msg = format!(
-                                                "Error creating parent directory for {}: {}",
-                                                relative_path.display(),
-                                                e
-                                            );
-                                            let _ = status_tx.send(msg);
-                                            continue;
-                                        }
-                                    }
-
-                                    // Copy the file (overwrite if it exists)
-                                    match fs::copy(&path, &dest_path) {
-                                        Ok(_) => {
-                                            let msg = format!(
-                                                "Backed up file: {}",
-                                                relative_path.display()
-                                            );
-                                            let _ = status_tx.send(msg);
-                                        }
-                                        Err(e) => {
-                                            let msg = format!(
-                                                "Error copying file {}: {}",
-                                                relative_path.display(),
-                                                e
-                                            );
-                                            let _ = status_tx.send(msg);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    } else if path.is_dir() {
-                        // Handle directory creation events (for initial folder backup)
-                        if let Some(folder_name) = path.file_name() {
-                            let dest_path = destination_folder.join(folder_name);
-
-                            // Always merge folders - copy all files to destination
-                            // This ensures new files are backed up even if the folder exists
-                            match copy_directory_recursive(&path, &dest_path) {
-                                Ok(()) => {
-                                    let msg = format!(
-                                        "Backed up folder: {}",
-                                        folder_name.to_string_lossy()
-                                    );
-                                    let _ = status_tx.send(msg);
-                                }
-                                Err(e) => {
-                                    let msg = format!(
-                                        "Error copying folder {}: {}",
-                                        folder_name.to_string_lossy(),
-                                        e
-                                    );
-                                    let _ = status_tx.send(msg);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                // Ignore all other events including deletions
-            }
-        }
-    }
-}
-
-// Helper function to recursively copy a directory
-fn copy_directory_recursive(source: &PathBuf, destination: &PathBuf) -> std::io::Result<()> {
-    // Create the destination directory
-    fs::create_dir_all(destination)?;
-
-    // Read all entries in the source directory
-    for entry in fs::read_dir(source)? {
-        let entry = entry?;
-        let path = entry.path();
-        let file_name = entry.file_name();
-        let dest_path = destination.join(&file_name);
-
-        if path.is_dir() {
-            // Recursively copy subdirectories
-            copy_directory_recursive(&path, &dest_path)?;
-        } else {
-            // Copy files
-            fs::copy(&path, &dest_path)?;
-        }
-    }
-
-    Ok(())
-}
-
-impl eframe::App for SiegeSaverApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Handle tray icon click events
-        let tray_channel = TrayIconEvent::receiver();
-        if let Ok(TrayIconEvent::Click { button, .. }) = tray_channel.try_recv() {
-            if button == MouseButton::Left {
-                // Show and focus window on left click
-                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
-                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
-            } else if button == MouseButton::Right {
-                // Close the application on right click
-                self.should_exit = true;
-                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-            }
-        }
-
-        // Handle tray menu events
-        let menu_channel = MenuEvent::receiver();
-        if let Ok(event) = menu_channel.try_recv() {
-            if event.id == self.quit_item_id {
-                // Set should_exit to true and then close
-                self.should_exit = true;
-                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-            }
-        }
-
-        // Intercept close requests - hide window instead of closing unless should_exit is true
-        if ctx.input(|i| i.viewport().close_requested()) && !self.should_exit {
-            // Hide the window instead of closing
-            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
-            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
-        }
-        // When should_exit is true, the close request will proceed normally and terminate the application
-
-        // Check for status messages from the background thread
-        let mut messages = Vec::new();
-        if let Some(receiver) = &self.status_receiver {
-            while let Ok(msg) = receiver.try_recv() {
-                messages.push(msg);
-            }
-        }
-        for msg in messages {
-            self.add_status(msg);
-        }
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("SiegeSaver - Replay File Backup Utility");
-            ui.add_space(10.0);
-
-            ui.group(|ui| {
-                ui.label("Source Folder (to watch for new match folders):");
-                ui.horizontal(|ui| {
-                    if ui.text_edit_singleline(&mut self.source_folder).changed() {
-                        self.save_config();
-                    }
-                    if ui.button("Browse").clicked() {
-                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                            self.source_folder = path.display().to_string();
-                            self.save_config();
-                        }
-                    }
-                });
-            });
-
-            ui.add_space(10.0);
-
-            ui.group(|ui| {
-                ui.label("Destination Folder (where backups will be saved):");
-                ui.horizontal(|ui| {
-                    if ui
-                        .text_edit_singleline(&mut self.destination_folder)
-                        .changed()
-                    {
-                        self.save_config();
-                    }
-                    if ui.button("Browse").clicked() {
-                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                            self.destination_folder = path.display().to_string();
-                            self.save_config();
-                        }
-                    }
-                });
-            });
-
-            ui.add_space(20.0);
-
-            ui.horizontal(|ui| {
-                if !self.is_watching {
-                    if ui.button("Start Watching").clicked() {
-                        self.start_watching();
-                    }
-                } else if ui.button("Stop Watching").clicked() {
-                    self.stop_watching();
-                }
-
-                if self.is_watching {
-                    ui.colored_label(egui::Color32::GREEN, "● Watching");
-                } else {
-                    ui.colored_label(egui::Color32::GRAY, "○ Not Watching");
-                }
-            });
-
-            ui.add_space(20.0);
-
-            ui.horizontal(|ui| {
-                let mut start_on_boot = self.start_on_boot;
-                if ui
-                    .checkbox(&mut start_on_boot, "Start on system boot")
-                    .changed()
-                {
-                    self.set_start_on_boot(start_on_boot);
-                }
-            });
-
-            ui.add_space(20.0);
-
-            ui.separator();
-            ui.label("Status Messages:");
-
-            egui::ScrollArea::vertical()
-                .max_height(200.0)
-                .stick_to_bottom(true)
-                .show(ui, |ui| {
-                    for message in &self.status_messages {
-                        ui.label(message);
-                    }
-                });
-        });
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_app_config_serialization() {
-        let config = AppConfig {
-            start_on_boot: true,
-            source_folder: "/test/source".to_string(),
-            destination_folder: "/test/dest".to_string(),
-        };
-
-        // Test serialization
-        let json = serde_json::to_string(&config).unwrap();
-        assert!(json.contains("start_on_boot"));
-        assert!(json.contains("true"));
-        assert!(json.contains("/test/source"));
-        assert!(json.contains("/test/dest"));
-
-        // Test deserialization
-        let deserialized: AppConfig = serde_json::from_str(&json).unwrap();
-        assert_eq!(deserialized.start_on_boot, true);
-        assert_eq!(deserialized.source_folder, "/test/source");
-        assert_eq!(deserialized.destination_folder, "/test/dest");
-    }
-
-    #[test]
-    fn test_app_config_default() {
-        let config = AppConfig::default();
-        assert_eq!(config.start_on_boot, false);
-        assert_eq!(config.source_folder, "");
-        assert_eq!(config.destination_folder, "");
-    }
-
-    #[test]
-    fn test_get_auto_launch() {
-        // Test that we can create an AutoLaunch instance
-        let result = get_auto_launch();
-        // This should succeed as long as we can get the current exe path
-        assert!(result.is_ok());
-    }
-}
@@ -0,0 +1,466 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{FileSystem, PART_EXTENSION};
+use crate::dedupe::DEDUPE_STORE_NAME;
+use crate::mirror::TRASH_FOLDER_NAME;
+use crate::snapshot::is_snapshot_name;
+use crate::versions::VERSIONS_FOLDER_NAME;
+use crate::volume::VOLUME_ID_FILE_NAME;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Written into the destination folder next to the backed-up matches
+pub const MANIFEST_FILE_NAME: &str = "siegesaver-manifest.json";
+// Written into every backed-up match folder: the SHA-256 of each file in it, in the format of
+// `sha256sum`, so the backup can be checked without SiegeSaver by running
+// `sha256sum -c SHA256SUMS` in the folder
+pub const CHECKSUMS_FILE_NAME: &str = "SHA256SUMS";
+
+// Lowercase hex SHA-256 of `data`
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub(crate) fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+// One file as it is stored in the destination
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub bytes: u64,
+    // Modification time when it was hashed, in seconds since the Unix epoch
+    pub modified: u64,
+    // Hash of the stored bytes, i.e. of the ciphertext for encrypted backups
+    pub sha256: String,
+}
+
+// One top-level item in the destination: a match folder, a match archive or a loose replay
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManifestEntry {
+    // Where it was backed up from; None for items found in the destination without a record
+    pub source: Option<PathBuf>,
+    // Seconds since the Unix epoch
+    pub first_backup: u64,
+    pub last_backup: u64,
+    pub bytes: u64,
+    // Keyed by the path relative to the destination folder, with forward slashes
+    pub files: BTreeMap<String, FileRecord>,
+    // Set by the user in the Library tab
+    pub tags: BTreeSet<String>,
+    pub notes: String,
+    // What r6-dissect read from the match's replays, see `dissect::summarize`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_stats: Option<serde_json::Value>,
+}
+
+impl ManifestEntry {
+    // The contents of the checksums file of the match folder `name`: a `<hash>  <path>` line
+    // for each file, with the path relative to the folder
+    pub fn checksums(&self, name: &str) -> String {
+        let prefix = format!("{}/", name);
+        let mut checksums = String::new();
+        for (key, record) in &self.files {
+            let Some(path) = key.strip_prefix(&prefix) else {
+                continue;
+            };
+            // Like sha256sum, paths with a backslash or line break are escaped, which a
+            // backslash at the start of the line announces
+            if path.contains(['\\', '\n']) {
+                let escaped = path.replace('\\', "\\\\").replace('\n', "\\n");
+                checksums.push_str(&format!("\\{}  {}\n", record.sha256, escaped));
+            } else {
+                checksums.push_str(&format!("{}  {}\n", record.sha256, path));
+            }
+        }
+        checksums
+    }
+}
+
+// What is wrong with a backed-up file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileProblem {
+    Missing,
+    // Its contents no longer match the hash recorded when it was backed up
+    Corrupted,
+}
+
+// Outcome of checking a destination against its manifest
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    // The destination folder that was checked
+    pub destination: PathBuf,
+    pub checked: usize,
+    // Paths relative to the destination folder, with forward slashes
+    pub problems: Vec<(String, FileProblem)>,
+}
+
+impl VerifyReport {
+    pub fn count(&self, problem: FileProblem) -> usize {
+        self.problems
+            .iter()
+            .filter(|(_, found)| *found == problem)
+            .count()
+    }
+}
+
+// Index of everything in a destination folder, kept up to date after every backup so sizes,
+// history and hashes are known without walking and re-reading the whole destination
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    // Keyed by the file or folder name in the destination
+    pub entries: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    // Reads the manifest of a destination, building it from what is on disk if it is missing
+    // or broken
+    pub fn load(fs: &dyn FileSystem, destination_folder: &Path) -> std::io::Result<Self> {
+        let manifest = fs
+            .read(&destination_folder.join(MANIFEST_FILE_NAME))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok());
+        match manifest {
+            Some(manifest) => Ok(manifest),
+            None => {
+                let mut manifest = Self::default();
+                manifest.refresh(fs, destination_folder)?;
+                Ok(manifest)
+            }
+        }
+    }
+
+    pub fn exists(fs: &dyn FileSystem, destination_folder: &Path) -> bool {
+        fs.metadata(&destination_folder.join(MANIFEST_FILE_NAME))
+            .is_ok()
+    }
+
+    pub fn save(&self, fs: &dyn FileSystem, destination_folder: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        fs.write(&destination_folder.join(MANIFEST_FILE_NAME), &json)
+    }
+
+    // Brings the manifest in line with what is actually in the destination: items deleted by
+    // hand are dropped, untracked items are added and changed files are hashed again
+    pub fn refresh(
+        &mut self,
+        fs: &dyn FileSystem,
+        destination_folder: &Path,
+    ) -> std::io::Result<()> {
+        let mut present = BTreeSet::new();
+        for path in fs.read_dir(destination_folder)? {
+            let Some(name) = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            if [
+                MANIFEST_FILE_NAME,
+                DEDUPE_STORE_NAME,
+                TRASH_FOLDER_NAME,
+                VERSIONS_FOLDER_NAME,
+                VOLUME_ID_FILE_NAME,
+            ]
+            .contains(&name.as_str())
+                || is_snapshot_name(&name)
+            {
+                continue;
+            }
+            let files = self.scan_item(fs, destination_folder, &name)?;
+            let entry = self.entries.entry(name.clone()).or_insert_with(|| {
+                let modified = fs
+                    .metadata(&path)
+                    .ok()
+                    .and_then(|metadata| metadata.modified)
+                    .map(unix_seconds)
+                    .unwrap_or(0);
+                ManifestEntry {
+                    first_backup: modified,
+                    last_backup: modified,
+                    ..Default::default()
+                }
+            });
+            entry.bytes = files.values().map(|file| file.bytes).sum();
+            entry.files = files;
+            present.insert(name);
+        }
+        self.entries.retain(|name, _| present.contains(name));
+        Ok(())
+    }
+
+    // Records that the top-level item `name` was just backed up from `source`
+    pub fn record_backup(
+        &mut self,
+        fs: &dyn FileSystem,
+        destination_folder: &Path,
+        name: &str,
+        source: &Path,
+        backed_up: SystemTime,
+    ) -> std::io::Result<()> {
+        let files = self.scan_item(fs, destination_folder, name)?;
+        let backed_up = unix_seconds(backed_up);
+        let entry = self
+            .entries
+            .entry(name.to_string())
+            .or_insert_with(|| ManifestEntry {
+                first_backup: backed_up,
+                ..Default::default()
+            });
+        entry.source = Some(source.to_path_buf());
+        entry.last_backup = backed_up;
+        entry.bytes = files.values().map(|file| file.bytes).sum();
+        entry.files = files;
+        Ok(())
+    }
+
+    // Writes the checksums file into the backed-up item `name` if it is a match folder
+    pub fn write_checksums(
+        &self,
+        fs: &dyn FileSystem,
+        destination_folder: &Path,
+        name: &str,
+    ) -> std::io::Result<()> {
+        let folder = destination_folder.join(name);
+        let Some(entry) = self.entries.get(name) else {
+            return Ok(());
+        };
+        if !fs.metadata(&folder).is_ok_and(|metadata| metadata.is_dir) {
+            return Ok(());
+        }
+        // Replaced instead of written in place, which would also change it in the earlier
+        // snapshots that link to it
+        let part = folder.join(format!("{}.{}", CHECKSUMS_FILE_NAME, PART_EXTENSION));
+        fs.write(&part, entry.checksums(name).as_bytes())?;
+        fs.rename(&part, &folder.join(CHECKSUMS_FILE_NAME))
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.values().map(|entry| entry.bytes).sum()
+    }
+
+    // Every tag used on any item, sorted
+    pub fn tags(&self) -> BTreeSet<&str> {
+        self.entries
+            .values()
+            .flat_map(|entry| &entry.tags)
+            .map(String::as_str)
+            .collect()
+    }
+
+    // Reads every recorded file again and compares it with its recorded hash
+    pub fn verify(&self, fs: &dyn FileSystem, destination_folder: &Path) -> VerifyReport {
+        let mut report = VerifyReport {
+            destination: destination_folder.to_path_buf(),
+            ..Default::default()
+        };
+        for (key, record) in self.entries.values().flat_map(|entry| &entry.files) {
+            report.checked += 1;
+            let problem = match fs.read(&destination_folder.join(key)) {
+                Ok(data) if sha256_hex(&data) == record.sha256 => continue,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => FileProblem::Missing,
+                _ => FileProblem::Corrupted,
+            };
+            report.problems.push((key.clone(), problem));
+        }
+        report
+    }
+
+    // Where the file recorded under `key` was backed up from, if known. Stored names such as
+    // `.enc` suffixes are not undone.
+    pub fn source_of(&self, key: &str) -> Option<PathBuf> {
+        let mut components = key.split('/');
+        let entry = self.entries.get(components.next()?)?;
+        let mut source = entry.source.clone()?;
+        source.extend(components);
+        Some(source)
+    }
+
+    // Lists every file of a top-level item, only re-reading files whose size or modification
+    // time changed since they were last hashed
+    fn scan_item(
+        &self,
+        fs: &dyn FileSystem,
+        destination_folder: &Path,
+        name: &str,
+    ) -> std::io::Result<BTreeMap<String, FileRecord>> {
+        let known = self.entries.get(name).map(|entry| &entry.files);
+        let mut files = BTreeMap::new();
+        let mut pending = vec![destination_folder.join(name)];
+        while let Some(path) = pending.pop() {
+            let metadata = fs.metadata(&path)?;
+            // Links copied from the source are not backed-up data
+            if metadata.is_symlink {
+                continue;
+            }
+            if metadata.is_dir {
+                pending.extend(fs.read_dir(&path)?);
+                continue;
+            }
+            // Written from the manifest itself
+            if path
+                .file_name()
+                .is_some_and(|name| name == CHECKSUMS_FILE_NAME)
+            {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(destination_folder) else {
+                continue;
+            };
+            let key = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let modified = metadata.modified.map(unix_seconds).unwrap_or(0);
+            let record = match known.and_then(|known| known.get(&key)) {
+                Some(record)
+                    if record.bytes == metadata.len
+                        && record.modified == modified
+                        && metadata.modified.is_some() =>
+                {
+                    record.clone()
+                }
+                _ => FileRecord {
+                    bytes: metadata.len,
+                    modified,
+                    sha256: sha256_hex(&fs.read(&path)?),
+                },
+            };
+            files.insert(key, record);
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use std::fs;
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_manifest_tracks_destination() {
+        let test_dir = std::env::temp_dir().join("siegesaver_manifest_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let destination = test_dir.join("dest");
+        fs::create_dir_all(destination.join("Match-A").join("nested")).unwrap();
+        fs::write(destination.join("Match-A").join("round1.rec"), "round 1").unwrap();
+        fs::write(
+            destination
+                .join("Match-A")
+                .join("nested")
+                .join("round2.rec"),
+            "round 2",
+        )
+        .unwrap();
+        fs::write(destination.join("loose.rec"), "loose").unwrap();
+
+        let mut manifest = Manifest::load(&RealFs, &destination).unwrap();
+        assert_eq!(manifest.entries["Match-A"].source, None);
+        manifest
+            .record_backup(
+                &RealFs,
+                &destination,
+                "Match-A",
+                &test_dir.join("source").join("Match-A"),
+                SystemTime::now(),
+            )
+            .unwrap();
+        manifest.refresh(&RealFs, &destination).unwrap();
+        manifest.save(&RealFs, &destination).unwrap();
+
+        let manifest = Manifest::load(&RealFs, &destination).unwrap();
+        assert_eq!(manifest.total_bytes(), 19);
+        let entry = &manifest.entries["Match-A"];
+        assert_eq!(entry.source, Some(test_dir.join("source").join("Match-A")));
+        assert_eq!(
+            entry.files.keys().collect::<Vec<_>>(),
+            ["Match-A/nested/round2.rec", "Match-A/round1.rec"]
+        );
+        assert_eq!(
+            entry.files["Match-A/round1.rec"].sha256,
+            sha256_hex(b"round 1")
+        );
+        assert_eq!(manifest.entries["loose.rec"].source, None);
+
+        // Every match folder gets a checksums file, which is no backed-up file itself
+        manifest
+            .write_checksums(&RealFs, &destination, "Match-A")
+            .unwrap();
+        manifest
+            .write_checksums(&RealFs, &destination, "loose.rec")
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(destination.join("Match-A").join(CHECKSUMS_FILE_NAME)).unwrap(),
+            format!(
+                "{}  nested/round2.rec\n{}  round1.rec\n",
+                sha256_hex(b"round 2"),
+                sha256_hex(b"round 1")
+            )
+        );
+        assert!(!destination.join(CHECKSUMS_FILE_NAME).exists());
+        let mut rescanned = Manifest::load(&RealFs, &destination).unwrap();
+        rescanned.refresh(&RealFs, &destination).unwrap();
+        assert_eq!(rescanned, manifest);
+
+        // Damaged and deleted files are found by re-hashing
+        fs::write(destination.join("Match-A").join("round1.rec"), "round 9").unwrap();
+        fs::remove_file(
+            destination
+                .join("Match-A")
+                .join("nested")
+                .join("round2.rec"),
+        )
+        .unwrap();
+        let report = manifest.verify(&RealFs, &destination);
+        assert_eq!(report.checked, 3);
+        assert_eq!(
+            report.problems,
+            [
+                (
+                    "Match-A/nested/round2.rec".to_string(),
+                    FileProblem::Missing
+                ),
+                ("Match-A/round1.rec".to_string(), FileProblem::Corrupted),
+            ]
+        );
+        assert_eq!(
+            manifest.source_of("Match-A/nested/round2.rec"),
+            Some(
+                test_dir
+                    .join("source")
+                    .join("Match-A")
+                    .join("nested")
+                    .join("round2.rec")
+            )
+        );
+        assert_eq!(manifest.source_of("loose.rec"), None);
+
+        // Items deleted by hand disappear from the manifest
+        let mut manifest = manifest;
+        fs::remove_dir_all(destination.join("Match-A")).unwrap();
+        manifest.refresh(&RealFs, &destination).unwrap();
+        assert_eq!(manifest.entries.keys().collect::<Vec<_>>(), ["loose.rec"]);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
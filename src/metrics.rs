@@ -0,0 +1,133 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::BackupStats;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// How often the Prometheus textfile is rewritten
+pub const METRICS_INTERVAL: Duration = Duration::from_secs(15);
+
+// Escapes a label value for the Prometheus text exposition format
+pub fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Renders the backup counters in the Prometheus text exposition format, labelled with the source folder
+pub fn render_prometheus_metrics(stats: &BackupStats, source: &str, watching: bool) -> String {
+    let labels = format!("{{source=\"{}\"}}", escape_label_value(source));
+    let last_backup = stats
+        .last_backup
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+
+    let metrics: [(&str, &str, &str, u64); 5] = [
+        (
+            "siegesaver_files_backed_up_total",
+            "counter",
+            "Number of files copied to the destination.",
+            stats.files_backed_up,
+        ),
+        (
+            "siegesaver_bytes_backed_up_total",
+            "counter",
+            "Number of bytes copied to the destination.",
+            stats.bytes_backed_up,
+        ),
+        (
+            "siegesaver_errors_total",
+            "counter",
+            "Number of failed backup operations.",
+            stats.errors,
+        ),
+        (
+            "siegesaver_last_backup_timestamp_seconds",
+            "gauge",
+            "Unix time of the last successful backup, 0 if none.",
+            last_backup,
+        ),
+        (
+            "siegesaver_watching",
+            "gauge",
+            "Whether the source folder is currently being watched.",
+            u64::from(watching),
+        ),
+    ];
+
+    let mut output = String::new();
+    for (name, kind, help, value) in metrics {
+        output.push_str(&format!("# HELP {} {}\n", name, help));
+        output.push_str(&format!("# TYPE {} {}\n", name, kind));
+        output.push_str(&format!("{}{} {}\n", name, labels, value));
+    }
+    output
+}
+
+// Writes the metrics next to the target first and renames them into place,
+// so the node_exporter textfile collector never reads a half-written file
+pub fn write_prometheus_textfile(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_metrics_format() {
+        let stats = BackupStats {
+            last_backup: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            files_backed_up: 12,
+            bytes_backed_up: 4096,
+            errors: 1,
+        };
+        let text = render_prometheus_metrics(&stats, r#"C:\Replays "main""#, true);
+
+        // Minimal exposition-format parser: every sample must be preceded by its HELP and TYPE
+        // lines, use a valid metric name and label syntax, and carry a numeric value
+        let is_name = |name: &str| {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+        };
+        let mut described = Vec::new();
+        let mut samples = std::collections::HashMap::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# HELP ") {
+                described.push(rest.split(' ').next().unwrap().to_string());
+            } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let mut parts = rest.split(' ');
+                let name = parts.next().unwrap();
+                assert!(described.iter().any(|d| d == name));
+                assert!(matches!(parts.next(), Some("counter") | Some("gauge")));
+            } else {
+                let (series, value) = line.rsplit_once(' ').unwrap();
+                let (name, labels) = series.split_once('{').unwrap();
+                assert!(is_name(name), "invalid metric name {}", name);
+                assert!(described.iter().any(|d| d == name));
+                assert!(labels.starts_with("source=\"") && labels.ends_with("\"}"));
+                let value: f64 = value.parse().unwrap();
+                samples.insert(name.to_string(), value);
+            }
+        }
+
+        assert_eq!(samples["siegesaver_files_backed_up_total"], 12.0);
+        assert_eq!(samples["siegesaver_bytes_backed_up_total"], 4096.0);
+        assert_eq!(samples["siegesaver_errors_total"], 1.0);
+        assert_eq!(
+            samples["siegesaver_last_backup_timestamp_seconds"],
+            1_700_000_000.0
+        );
+        assert_eq!(samples["siegesaver_watching"], 1.0);
+        assert!(text.contains(r#"source="C:\\Replays \"main\"""#));
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use crate::manifest::{Manifest, ManifestEntry};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+// Folder in the destination that mirror mode moves the backups of deleted matches into, so
+// they can be brought back. Every deleted backup gets a folder `<unix seconds>_<name>` in it
+// with the backup and its manifest entry.
+pub const TRASH_FOLDER_NAME: &str = "siegesaver-trash";
+// Manifest entry of a backup in the trash, with its tags and notes
+const TRASHED_ENTRY_FILE_NAME: &str = "entry.json";
+// How long deleted backups stay in the trash before they are deleted for good
+pub const TRASH_KEEP_DAYS: u64 = 30;
+pub const TRASH_KEEP: Duration = Duration::from_secs(TRASH_KEEP_DAYS * 24 * 60 * 60);
+
+// Whether deleting the match `name` from the source looks like the game's own cleanup. The
+// game names match folders by date and deletes the oldest ones to make room for new matches,
+// so only deleting a match that is older than every match left is taken for it.
+pub fn is_game_cleanup(name: &str, remaining: &[String]) -> bool {
+    remaining.iter().all(|other| name < other.as_str())
+}
+
+// A backup whose match was deleted from the source, waiting for its grace period to end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingDeletion {
+    pub source_path: PathBuf,
+    pub destination_folder: PathBuf,
+    // Name of the backup in the destination, e.g. `Match-A.zip`
+    pub name: String,
+    pub due: SystemTime,
+}
+
+// Backups mirror mode is about to delete. Shared with the GUI, which shows them and lets the
+// user keep any of them.
+#[derive(Default)]
+pub struct DeletionQueue {
+    pending: Mutex<Vec<PendingDeletion>>,
+}
+
+impl DeletionQueue {
+    fn pending_mut(&self) -> MutexGuard<'_, Vec<PendingDeletion>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Queues `deletion` unless the same backup is already waiting
+    pub fn push(&self, deletion: PendingDeletion) {
+        let mut pending = self.pending_mut();
+        if !pending.iter().any(|other| {
+            other.destination_folder == deletion.destination_folder && other.name == deletion.name
+        }) {
+            pending.push(deletion);
+        }
+    }
+
+    // Soonest due first
+    pub fn pending(&self) -> Vec<PendingDeletion> {
+        let mut pending = self.pending_mut().clone();
+        pending.sort_by_key(|deletion| deletion.due);
+        pending
+    }
+
+    // Drops a pending deletion, keeping the backup
+    pub fn keep(&self, destination_folder: &Path, name: &str) {
+        self.pending_mut().retain(|deletion| {
+            deletion.destination_folder != destination_folder || deletion.name != name
+        });
+    }
+
+    // Takes the deletions for `destination_folder` whose grace period is over at `now`
+    pub fn take_due(&self, destination_folder: &Path, now: SystemTime) -> Vec<PendingDeletion> {
+        let mut pending = self.pending_mut();
+        let (due, waiting) = std::mem::take(&mut *pending)
+            .into_iter()
+            .partition(|deletion| {
+                deletion.destination_folder == destination_folder && deletion.due <= now
+            });
+        *pending = waiting;
+        due
+    }
+}
+
+// A backup in the trash of a destination
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrashedBackup {
+    pub destination_folder: PathBuf,
+    pub name: String,
+    pub deleted: SystemTime,
+    // Its folder in the trash
+    pub folder: PathBuf,
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+// Moves the backup `name` into the trash of `destination_folder` and takes it out of the
+// manifest
+pub fn move_to_trash(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    name: &str,
+    now: SystemTime,
+) -> std::io::Result<TrashedBackup> {
+    let folder =
+        destination_folder
+            .join(TRASH_FOLDER_NAME)
+            .join(format!("{}_{}", unix_seconds(now), name));
+    fs.create_dir_all(&folder)?;
+    let mut manifest = Manifest::load(fs, destination_folder)?;
+    if let Some(entry) = manifest.entries.get(name) {
+        let json = serde_json::to_vec_pretty(entry).map_err(std::io::Error::other)?;
+        fs.write(&folder.join(TRASHED_ENTRY_FILE_NAME), &json)?;
+    }
+    fs.rename(&destination_folder.join(name), &folder.join(name))?;
+    if manifest.entries.remove(name).is_some() {
+        manifest.save(fs, destination_folder)?;
+    }
+    Ok(TrashedBackup {
+        destination_folder: destination_folder.to_path_buf(),
+        name: name.to_string(),
+        deleted: now,
+        folder,
+    })
+}
+
+// The backups in the trash of `destination_folder`, most recently deleted first
+pub fn trashed_backups(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+) -> std::io::Result<Vec<TrashedBackup>> {
+    let trash = destination_folder.join(TRASH_FOLDER_NAME);
+    if fs.metadata(&trash).is_err() {
+        return Ok(Vec::new());
+    }
+    let mut trashed = Vec::new();
+    for folder in fs.read_dir(&trash)? {
+        let Some(folder_name) = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+        let Some((seconds, name)) = folder_name.split_once('_') else {
+            continue;
+        };
+        let Ok(seconds) = seconds.parse() else {
+            continue;
+        };
+        trashed.push(TrashedBackup {
+            destination_folder: destination_folder.to_path_buf(),
+            name: name.to_string(),
+            deleted: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+            folder,
+        });
+    }
+    trashed.sort_by_key(|backup| std::cmp::Reverse(backup.deleted));
+    Ok(trashed)
+}
+
+// Moves a backup out of the trash back to where it was, with its tags and notes
+pub fn restore_from_trash(fs: &dyn FileSystem, trashed: &TrashedBackup) -> std::io::Result<()> {
+    let destination = trashed.destination_folder.join(&trashed.name);
+    if fs.metadata(&destination).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} is already in the destination", trashed.name),
+        ));
+    }
+    fs.rename(&trashed.folder.join(&trashed.name), &destination)?;
+    let mut manifest = Manifest::load(fs, &trashed.destination_folder)?;
+    let entry: Option<ManifestEntry> = fs
+        .read(&trashed.folder.join(TRASHED_ENTRY_FILE_NAME))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok());
+    match entry {
+        Some(entry) => {
+            manifest.entries.insert(trashed.name.clone(), entry);
+        }
+        None => manifest.refresh(fs, &trashed.destination_folder)?,
+    }
+    manifest.save(fs, &trashed.destination_folder)?;
+    fs.remove(&trashed.folder)
+}
+
+// Deletes the backups that have been in the trash for longer than `keep`. Returns how many
+// were deleted.
+pub fn empty_trash(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    now: SystemTime,
+    keep: Duration,
+) -> std::io::Result<usize> {
+    let mut emptied = 0;
+    for trashed in trashed_backups(fs, destination_folder)? {
+        if now.duration_since(trashed.deleted).unwrap_or_default() > keep {
+            fs.remove(&trashed.folder)?;
+            emptied += 1;
+        }
+    }
+    Ok(emptied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+
+    #[test]
+    fn test_game_cleanup() {
+        let remaining = [
+            "Match-2025-11-20_18-00-00".to_string(),
+            "Match-2025-11-24_20-00-00".to_string(),
+        ];
+        assert!(is_game_cleanup("Match-2025-11-19_21-00-00", &remaining));
+        assert!(!is_game_cleanup("Match-2025-11-22_19-00-00", &remaining));
+        assert!(is_game_cleanup("Match-2025-11-22_19-00-00", &[]));
+    }
+
+    #[test]
+    fn test_deletion_queue() {
+        let queue = DeletionQueue::default();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let deletion = |destination: &str, name: &str, due: u64| PendingDeletion {
+            source_path: Path::new("/replays").join(name),
+            destination_folder: PathBuf::from(destination),
+            name: name.to_string(),
+            due: now + Duration::from_secs(due),
+        };
+        queue.push(deletion("/backup", "Match-B", 60));
+        queue.push(deletion("/backup", "Match-A", 0));
+        queue.push(deletion("/mirror", "Match-A", 0));
+        // Already waiting
+        queue.push(deletion("/backup", "Match-A", 30));
+        assert_eq!(queue.pending().len(), 3);
+        assert_eq!(queue.pending()[2].name, "Match-B");
+
+        let due = queue.take_due(Path::new("/backup"), now);
+        assert_eq!(due, [deletion("/backup", "Match-A", 0)]);
+        queue.keep(Path::new("/backup"), "Match-B");
+        assert!(queue
+            .take_due(Path::new("/backup"), now + Duration::from_secs(60))
+            .is_empty());
+        assert_eq!(queue.pending(), [deletion("/mirror", "Match-A", 0)]);
+    }
+
+    #[test]
+    fn test_trash() {
+        let destination = std::env::temp_dir().join("siegesaver_trash_test");
+        let _ = std::fs::remove_dir_all(&destination);
+        std::fs::create_dir_all(destination.join("Match-A")).unwrap();
+        std::fs::write(destination.join("Match-A").join("round1.rec"), "round 1").unwrap();
+        let fs = RealFs;
+        let mut manifest = Manifest::load(&fs, &destination).unwrap();
+        manifest
+            .entries
+            .get_mut("Match-A")
+            .unwrap()
+            .tags
+            .insert("clutch".to_string());
+        manifest.save(&fs, &destination).unwrap();
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let trashed = move_to_trash(&fs, &destination, "Match-A", now).unwrap();
+        assert!(!destination.join("Match-A").exists());
+        assert!(Manifest::load(&fs, &destination)
+            .unwrap()
+            .entries
+            .is_empty());
+        let listed = trashed_backups(&fs, &destination).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0], trashed);
+
+        restore_from_trash(&fs, &trashed).unwrap();
+        assert!(destination.join("Match-A").join("round1.rec").exists());
+        let manifest = Manifest::load(&fs, &destination).unwrap();
+        assert!(manifest.entries["Match-A"].tags.contains("clutch"));
+        assert!(trashed_backups(&fs, &destination).unwrap().is_empty());
+
+        // Only what has been in the trash for longer than it is kept is deleted
+        move_to_trash(&fs, &destination, "Match-A", now).unwrap();
+        assert_eq!(empty_trash(&fs, &destination, now, TRASH_KEEP).unwrap(), 0);
+        let later = now + TRASH_KEEP + Duration::from_secs(1);
+        assert_eq!(
+            empty_trash(&fs, &destination, later, TRASH_KEEP).unwrap(),
+            1
+        );
+        assert!(trashed_backups(&fs, &destination).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&destination);
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::status::{format_bytes, Severity, StatusEvent};
+use serde::{Deserialize, Serialize};
+
+// Which events pop up a desktop notification, since the window is usually hidden in the tray
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopNotifications {
+    // A match folder was backed up or archived
+    pub backups: bool,
+    // A match was uploaded to a remote destination
+    pub uploads: bool,
+    pub warnings: bool,
+    pub errors: bool,
+}
+
+impl Default for DesktopNotifications {
+    fn default() -> Self {
+        Self {
+            backups: true,
+            uploads: false,
+            warnings: true,
+            errors: true,
+        }
+    }
+}
+
+impl DesktopNotifications {
+    // Title and text of the notification for an event, if its type is switched on
+    pub fn notification(&self, event: &StatusEvent) -> Option<(String, String)> {
+        match event {
+            StatusEvent::FolderBackedUp { name, detail }
+            | StatusEvent::FolderArchived { name, detail }
+                if self.backups =>
+            {
+                let body = match detail.bytes {
+                    Some(bytes) => format!("{} backed up ({})", name, format_bytes(bytes)),
+                    None => format!("{} backed up", name),
+                };
+                Some(("Backup finished".to_string(), body))
+            }
+            StatusEvent::Uploaded { .. } if self.uploads => {
+                Some(("Upload finished".to_string(), event.to_string()))
+            }
+            event if event.severity() == Severity::Warning && self.warnings => {
+                Some(("SiegeSaver warning".to_string(), event.to_string()))
+            }
+            event if event.severity() == Severity::Error && self.errors => {
+                Some(("SiegeSaver error".to_string(), event.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    // Shows the notification for an event without blocking the caller
+    pub fn notify(&self, event: &StatusEvent) {
+        let Some((summary, body)) = self.notification(event) else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = notify_rust::Notification::new()
+                .appname("SiegeSaver")
+                .summary(&summary)
+                .body(&body)
+                .show()
+            {
+                tracing::warn!("Failed to show a desktop notification: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::EventDetail;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_notification_toggles() {
+        let backed_up = StatusEvent::FolderArchived {
+            name: "Match-2025-01-01_20-00-00".to_string(),
+            detail: EventDetail {
+                source: PathBuf::from("replays"),
+                destination: PathBuf::from("backup"),
+                files: Some(3),
+                bytes: Some(128 * 1024 * 1024),
+                duration: None,
+                error: None,
+            },
+        };
+        let uploaded = StatusEvent::Uploaded {
+            name: "Match-A".to_string(),
+            remote: "s3://replays".to_string(),
+            files: 1,
+            bytes: 10,
+        };
+        let error = StatusEvent::Error("Disk full".to_string());
+
+        let settings = DesktopNotifications::default();
+        assert_eq!(
+            settings.notification(&backed_up),
+            Some((
+                "Backup finished".to_string(),
+                "Match-2025-01-01_20-00-00 backed up (128.0 MB)".to_string()
+            ))
+        );
+        assert_eq!(settings.notification(&uploaded), None);
+        assert_eq!(
+            settings.notification(&error),
+            Some(("SiegeSaver error".to_string(), "Disk full".to_string()))
+        );
+        assert_eq!(settings.notification(&StatusEvent::Stopped), None);
+
+        let settings = DesktopNotifications {
+            backups: false,
+            uploads: true,
+            warnings: false,
+            errors: false,
+        };
+        assert_eq!(settings.notification(&backed_up), None);
+        assert!(settings.notification(&uploaded).is_some());
+        assert_eq!(settings.notification(&error), None);
+        assert_eq!(
+            settings.notification(&StatusEvent::Warning("Slow".to_string())),
+            None
+        );
+    }
+}
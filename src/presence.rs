@@ -0,0 +1,116 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::RealFs;
+use crate::manifest::Manifest;
+use discord_rich_presence::activity::{Activity, Timestamps};
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// How often connecting is retried while Discord is not running
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+// The two lines shown under the application name in Discord
+pub fn activity_text(watching: bool, matches: usize) -> (String, String) {
+    let details = if watching {
+        "Watching replays"
+    } else {
+        "Not watching"
+    };
+    let state = match matches {
+        1 => "1 match archived".to_string(),
+        matches => format!("{} matches archived", matches),
+    };
+    (details.to_string(), state)
+}
+
+// Number of matches recorded in the destination's manifest, without building one if it is
+// missing
+fn archived_matches(destination: &Path) -> usize {
+    if !Manifest::exists(&RealFs, destination) {
+        return 0;
+    }
+    Manifest::load(&RealFs, destination)
+        .map(|manifest| manifest.entries.len())
+        .unwrap_or(0)
+}
+
+// Shows the watcher status as Discord Rich Presence of the user's Discord application, from
+// a background thread that connects whenever Discord is running
+pub struct RichPresence {
+    tx: Sender<(bool, PathBuf)>,
+}
+
+impl RichPresence {
+    pub fn spawn(application_id: String) -> Self {
+        let (tx, rx) = channel();
+        std::thread::spawn(move || show_presence(&application_id, &rx));
+        Self { tx }
+    }
+
+    pub fn update(&self, watching: bool, destination: &Path) {
+        let _ = self.tx.send((watching, destination.to_path_buf()));
+    }
+}
+
+// Runs until the presence is dropped, then clears it
+fn show_presence(application_id: &str, rx: &Receiver<(bool, PathBuf)>) {
+    let mut client = DiscordIpcClient::new(application_id);
+    let mut connected = false;
+    let since = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64);
+    let mut latest: Option<(bool, PathBuf)> = None;
+    loop {
+        match rx.recv_timeout(RECONNECT_INTERVAL) {
+            // Only the newest status matters
+            Ok(update) => latest = Some(rx.try_iter().last().unwrap_or(update)),
+            Err(RecvTimeoutError::Timeout) if !connected => {}
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        let Some((watching, destination)) = &latest else {
+            continue;
+        };
+        let (details, state) = activity_text(*watching, archived_matches(destination));
+        if !connected {
+            connected = client.connect().is_ok();
+        }
+        if connected {
+            let activity = Activity::new()
+                .details(&details)
+                .state(&state)
+                .timestamps(Timestamps::new().start(since));
+            if client.set_activity(activity).is_err() {
+                let _ = client.close();
+                connected = false;
+            }
+        }
+    }
+    if connected {
+        let _ = client.clear_activity();
+        let _ = client.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_text() {
+        assert_eq!(
+            activity_text(true, 42),
+            (
+                "Watching replays".to_string(),
+                "42 matches archived".to_string()
+            )
+        );
+        assert_eq!(activity_text(false, 1).1, "1 match archived");
+        assert_eq!(
+            archived_matches(&std::env::temp_dir().join("siegesaver_no_such_destination")),
+            0
+        );
+    }
+}
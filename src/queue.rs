@@ -0,0 +1,238 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// One path waiting to be, or being, backed up into one destination
+#[derive(Clone, Debug)]
+pub struct BackupJob {
+    pub id: u64,
+    pub source_folder: PathBuf,
+    pub destination_folder: PathBuf,
+    pub path: PathBuf,
+    pub running: bool,
+    // Set to stop the job while it runs
+    pub cancel: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_id: u64,
+    running: Vec<BackupJob>,
+    waiting: VecDeque<BackupJob>,
+}
+
+// Backups the event handlers are about to run, in the order they run them. Shared with the
+// GUI, which shows it and can cancel or reorder jobs. Every handler only takes the jobs of
+// its own source folder.
+#[derive(Default)]
+pub struct BackupQueue {
+    state: Mutex<QueueState>,
+    // Set during quiet hours, when changes are queued but no job is started
+    held: AtomicBool,
+}
+
+impl BackupQueue {
+    fn state(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Queues `path` unless it is already waiting for the same destination. A path that is
+    // being backed up right now is queued again, as it changed since the backup started.
+    pub fn push(&self, source_folder: &Path, destination_folder: &Path, path: &Path) {
+        let mut state = self.state();
+        if state
+            .waiting
+            .iter()
+            .any(|job| job.path == path && job.destination_folder == destination_folder)
+        {
+            return;
+        }
+        state.next_id += 1;
+        let job = BackupJob {
+            id: state.next_id,
+            source_folder: source_folder.to_path_buf(),
+            destination_folder: destination_folder.to_path_buf(),
+            path: path.to_path_buf(),
+            running: false,
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+        state.waiting.push_back(job);
+    }
+
+    // Marks the first waiting job of `source_folder` as running and returns it. Jobs for the
+    // `held` destinations, e.g. ones on an unplugged drive, stay where they are. Nothing is
+    // started while the whole queue is held.
+    pub fn start_next(&self, source_folder: &Path, held: &[PathBuf]) -> Option<BackupJob> {
+        if self.is_held() {
+            return None;
+        }
+        let mut state = self.state();
+        let index = state.waiting.iter().position(|job| {
+            job.source_folder == source_folder && !held.contains(&job.destination_folder)
+        })?;
+        let mut job = state.waiting.remove(index)?;
+        job.running = true;
+        state.running.push(job.clone());
+        Some(job)
+    }
+
+    pub fn finish(&self, id: u64) {
+        self.state().running.retain(|job| job.id != id);
+    }
+
+    // Drops a waiting job, or asks a running one to stop
+    pub fn cancel(&self, id: u64) {
+        let mut state = self.state();
+        state.waiting.retain(|job| job.id != id);
+        if let Some(job) = state.running.iter().find(|job| job.id == id) {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Moves a waiting job `offset` places towards the back of the queue, or towards the front
+    // for a negative offset
+    pub fn move_job(&self, id: u64, offset: isize) {
+        let mut state = self.state();
+        let Some(index) = state.waiting.iter().position(|job| job.id == id) else {
+            return;
+        };
+        let target = index
+            .saturating_add_signed(offset)
+            .min(state.waiting.len() - 1);
+        if let Some(job) = state.waiting.remove(index) {
+            state.waiting.insert(target, job);
+        }
+    }
+
+    // Running jobs first, then the waiting ones in the order they will run
+    pub fn jobs(&self) -> Vec<BackupJob> {
+        let state = self.state();
+        state
+            .running
+            .iter()
+            .chain(&state.waiting)
+            .cloned()
+            .collect()
+    }
+
+    // Points the waiting jobs for `from` to `to`, after the destination's drive came back
+    // under another path
+    pub fn relocate(&self, from: &Path, to: &Path) {
+        for job in self.state().waiting.iter_mut() {
+            if job.destination_folder == from {
+                job.destination_folder = to.to_path_buf();
+            }
+        }
+    }
+
+    // Drops the waiting jobs of `source_folder`, e.g. once it is no longer watched
+    pub fn clear(&self, source_folder: &Path) {
+        self.state()
+            .waiting
+            .retain(|job| job.source_folder != source_folder);
+    }
+
+    // Holds every waiting job, or lets the handlers run them again
+    pub fn hold(&self, held: bool) {
+        self.held.store(held, Ordering::Relaxed);
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let state = self.state();
+        state.running.is_empty() && state.waiting.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(jobs: &[BackupJob]) -> Vec<&str> {
+        jobs.iter().map(|job| job.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_backup_queue() {
+        let queue = BackupQueue::default();
+        let (source, other_source) = (Path::new("/replays"), Path::new("/other"));
+        let destination = Path::new("/backup");
+        for path in ["/replays/Match-A", "/replays/Match-B", "/replays/Match-C"] {
+            queue.push(source, destination, Path::new(path));
+        }
+        queue.push(other_source, destination, Path::new("/other/Match-D"));
+        // Already waiting
+        queue.push(source, destination, Path::new("/replays/Match-B"));
+        assert_eq!(queue.jobs().len(), 4);
+
+        // Handlers only take their own source's jobs
+        let running = queue.start_next(source, &[]).unwrap();
+        assert_eq!(running.path, Path::new("/replays/Match-A"));
+        assert!(queue.jobs()[0].running);
+
+        // Reprioritized jobs run first; moves are clamped to the queue
+        let jobs = queue.jobs();
+        queue.move_job(jobs[2].id, -5);
+        assert_eq!(
+            paths(&queue.jobs()),
+            [
+                "/replays/Match-A",
+                "/replays/Match-C",
+                "/replays/Match-B",
+                "/other/Match-D"
+            ]
+        );
+        queue.move_job(jobs[2].id, 1);
+        queue.move_job(jobs[2].id, 1);
+        assert_eq!(
+            paths(&queue.jobs()),
+            [
+                "/replays/Match-A",
+                "/replays/Match-B",
+                "/other/Match-D",
+                "/replays/Match-C"
+            ]
+        );
+
+        // Waiting jobs are dropped, running ones are asked to stop
+        queue.cancel(jobs[1].id);
+        queue.cancel(running.id);
+        assert!(running.cancel.load(Ordering::Relaxed));
+        queue.finish(running.id);
+        assert_eq!(paths(&queue.jobs()), ["/other/Match-D", "/replays/Match-C"]);
+
+        // Jobs for a held destination wait, and follow it when it moves
+        let moved = Path::new("/moved");
+        assert!(queue
+            .start_next(source, &[destination.to_path_buf()])
+            .is_none());
+        queue.relocate(destination, moved);
+        assert!(queue
+            .jobs()
+            .iter()
+            .all(|job| job.destination_folder == moved));
+
+        // Nothing starts while the whole queue is held
+        queue.hold(true);
+        assert!(queue.start_next(source, &[]).is_none());
+        queue.hold(false);
+
+        // A running path that changes again is queued behind itself
+        let running = queue.start_next(source, &[]).unwrap();
+        queue.push(source, destination, &running.path);
+        assert_eq!(queue.jobs().len(), 3);
+
+        queue.clear(source);
+        queue.finish(running.id);
+        assert_eq!(paths(&queue.jobs()), ["/other/Match-D"]);
+        assert!(queue.start_next(source, &[]).is_none());
+        queue.clear(other_source);
+        assert!(queue.is_empty());
+    }
+}
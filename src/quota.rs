@@ -0,0 +1,69 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::manifest::Manifest;
+
+const BYTES_PER_GB: u64 = 1024 * 1024 * 1024;
+
+pub fn gigabytes_to_bytes(gigabytes: u64) -> u64 {
+    gigabytes.saturating_mul(BYTES_PER_GB)
+}
+
+// The least recently backed-up items to delete so the destination fits into `quota_bytes`.
+// `keep` is the item that was just backed up and is never evicted.
+pub fn select_evictions(manifest: &Manifest, quota_bytes: u64, keep: &str) -> Vec<String> {
+    let mut oldest_first: Vec<_> = manifest
+        .entries
+        .iter()
+        .filter(|(name, _)| name.as_str() != keep)
+        .collect();
+    oldest_first.sort_by_key(|(_, entry)| entry.last_backup);
+
+    let mut total = manifest.total_bytes();
+    let mut evictions = Vec::new();
+    for (name, entry) in oldest_first {
+        if total <= quota_bytes {
+            break;
+        }
+        total -= entry.bytes;
+        evictions.push(name.clone());
+    }
+    evictions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::ManifestEntry;
+
+    #[test]
+    fn test_select_evictions() {
+        let mut manifest = Manifest::default();
+        for (name, bytes, last_backup) in [
+            ("Match-1", 40, 100),
+            ("Match-2", 30, 200),
+            ("Match-3", 20, 300),
+        ] {
+            manifest.entries.insert(
+                name.to_string(),
+                ManifestEntry {
+                    bytes,
+                    last_backup,
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(manifest.total_bytes(), 90);
+
+        assert!(select_evictions(&manifest, 100, "Match-3").is_empty());
+        assert_eq!(select_evictions(&manifest, 60, "Match-3"), ["Match-1"]);
+        assert_eq!(
+            select_evictions(&manifest, 40, "Match-3"),
+            ["Match-1", "Match-2"]
+        );
+        // The item just backed up stays even if it alone exceeds the limit
+        assert_eq!(
+            select_evictions(&manifest, 10, "Match-1"),
+            ["Match-2", "Match-3"]
+        );
+    }
+}
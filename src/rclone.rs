@@ -0,0 +1,215 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use crate::remote::RemoteStore;
+use crate::status::StatusEvent;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+
+// Any of the clouds rclone supports, through a remote set up with `rclone config`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RcloneConfig {
+    // Path of the rclone executable, or just "rclone" if it is on the PATH
+    pub program: String,
+    // Remote and folder backups are copied into, e.g. "onedrive:Replays"
+    pub destination: String,
+    // Passed to every rclone call, separated by spaces, e.g. "--config D:\rclone.conf"
+    pub extra_args: String,
+}
+
+impl Default for RcloneConfig {
+    fn default() -> Self {
+        Self {
+            program: "rclone".to_string(),
+            destination: String::new(),
+            extra_args: String::new(),
+        }
+    }
+}
+
+impl RcloneConfig {
+    pub fn label(&self) -> String {
+        format!("rclone {}", self.destination.trim())
+    }
+}
+
+// Uploads by running rclone, reporting its transfer statistics in the status log
+pub struct RcloneStore {
+    program: String,
+    destination: String,
+    extra_args: Vec<String>,
+    label: String,
+    status_tx: Sender<StatusEvent>,
+}
+
+impl RcloneStore {
+    pub fn new(config: &RcloneConfig, status_tx: Sender<StatusEvent>) -> Result<Self, String> {
+        let destination = config.destination.trim();
+        if !destination.contains(':') {
+            return Err(format!(
+                "Invalid rclone destination '{}', expected e.g. onedrive:Replays",
+                destination
+            ));
+        }
+        let program = config.program.trim();
+        Ok(Self {
+            program: if program.is_empty() {
+                "rclone".to_string()
+            } else {
+                program.to_string()
+            },
+            destination: destination.to_string(),
+            extra_args: config
+                .extra_args
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            label: config.label(),
+            status_tx,
+        })
+    }
+
+    // Where rclone puts the object `key`
+    fn target(&self, key: &str) -> String {
+        if self.destination.ends_with(':') {
+            format!("{}{}", self.destination, key)
+        } else {
+            format!("{}/{}", self.destination.trim_end_matches('/'), key)
+        }
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(args).args(&self.extra_args);
+        #[cfg(windows)]
+        {
+            // Keep rclone from opening a console window next to the GUI
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        command
+    }
+
+    fn spawn_error(&self, e: std::io::Error) -> std::io::Error {
+        std::io::Error::other(format!("Could not run {}: {}", self.program, e))
+    }
+}
+
+fn exit_error(status: std::process::ExitStatus, output: &str) -> std::io::Error {
+    let last_line = output.lines().last().unwrap_or_default();
+    std::io::Error::other(format!("rclone failed ({}): {}", status, last_line))
+}
+
+impl RemoteStore for RcloneStore {
+    // Streams the data into `rclone rcat`
+    fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()> {
+        let mut child = self
+            .command(&["rcat", &self.target(key)])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| self.spawn_error(e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(data)?;
+        }
+        let output = child.wait_with_output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(exit_error(
+                output.status,
+                &String::from_utf8_lossy(&output.stderr),
+            ))
+        }
+    }
+
+    // Lets rclone read the file itself and forwards its one-line statistics as they come in
+    fn upload(&self, fs: &dyn FileSystem, key: &str, file: &Path) -> std::io::Result<u64> {
+        let bytes = fs.metadata(file)?.len;
+        let file = file.to_string_lossy();
+        let mut child = self
+            .command(&[
+                "copyto",
+                &file,
+                &self.target(key),
+                "--stats=2s",
+                "--stats-one-line",
+                "--stats-log-level=NOTICE",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| self.spawn_error(e))?;
+        let mut last_line = String::new();
+        if let Some(stderr) = child.stderr.take() {
+            for line in BufReader::new(stderr).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let _ = self.status_tx.send(StatusEvent::Info(format!(
+                    "{}: {}",
+                    self.label,
+                    line.trim()
+                )));
+                last_line = line;
+            }
+        }
+        let status = child.wait()?;
+        if status.success() {
+            Ok(bytes)
+        } else {
+            Err(exit_error(status, &last_line))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_rclone_targets() {
+        let (tx, _rx) = channel();
+        let config = RcloneConfig {
+            destination: "onedrive:Replays/".to_string(),
+            extra_args: " --config  rclone.conf ".to_string(),
+            ..Default::default()
+        };
+        let store = RcloneStore::new(&config, tx.clone()).unwrap();
+        assert_eq!(
+            store.target("Match-A/round1.rec"),
+            "onedrive:Replays/Match-A/round1.rec"
+        );
+        assert_eq!(store.extra_args, ["--config", "rclone.conf"]);
+        let root = RcloneConfig {
+            destination: "b2:".to_string(),
+            ..Default::default()
+        };
+        let store = RcloneStore::new(&root, tx.clone()).unwrap();
+        assert_eq!(store.target("Match-A.zip"), "b2:Match-A.zip");
+        assert!(RcloneStore::new(&RcloneConfig::default(), tx.clone()).is_err());
+
+        let missing = RcloneConfig {
+            program: "siegesaver-no-such-rclone".to_string(),
+            destination: "b2:".to_string(),
+            ..Default::default()
+        };
+        let store = RcloneStore::new(&missing, tx).unwrap();
+        let error = store
+            .upload(&RealFs, "Cargo.toml", Path::new("Cargo.toml"))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Could not run siegesaver-no-such-rclone"));
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{CopyTotals, FileSystem};
+use crate::gdrive::{GoogleDriveConfig, GoogleDriveStore};
+use crate::rclone::{RcloneConfig, RcloneStore};
+use crate::s3::{S3Config, S3Store};
+use crate::status::StatusEvent;
+use crate::webdav::{WebDavConfig, WebDavStore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+// Stored next to config.json
+pub const UPLOAD_QUEUE_FILE_NAME: &str = "upload-queue.json";
+
+// Storage outside the local filesystem that receives a copy of every backup once it was
+// written to the destination folder
+pub trait RemoteStore: Send + Sync {
+    // Stores one object; `key` is relative to the remote's configured prefix and uses `/`
+    fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()>;
+
+    // Uploads a file from the destination and returns its size. Stores that can read the
+    // file themselves override this instead of getting its contents.
+    fn upload(&self, fs: &dyn FileSystem, key: &str, file: &Path) -> std::io::Result<u64> {
+        let data = fs.read(file)?;
+        self.put(key, &data)?;
+        Ok(data.len() as u64)
+    }
+}
+
+// A remote destination as stored in the config
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RemoteConfig {
+    S3(S3Config),
+    WebDav(WebDavConfig),
+    GoogleDrive(GoogleDriveConfig),
+    Rclone(RcloneConfig),
+}
+
+impl RemoteConfig {
+    // How the remote is named in the status log, e.g. "s3://bucket/prefix"
+    pub fn label(&self) -> String {
+        match self {
+            RemoteConfig::S3(config) => config.label(),
+            RemoteConfig::WebDav(config) => config.label(),
+            RemoteConfig::GoogleDrive(config) => config.label(),
+            RemoteConfig::Rclone(config) => config.label(),
+        }
+    }
+
+    // Connects to the remote; fails if the settings are incomplete. Stores that report
+    // progress of their own send it on `status_tx`.
+    pub fn store(&self, status_tx: &Sender<StatusEvent>) -> Result<Arc<dyn RemoteStore>, String> {
+        match self {
+            RemoteConfig::S3(config) => Ok(Arc::new(S3Store::new(config)?)),
+            RemoteConfig::WebDav(config) => Ok(Arc::new(WebDavStore::new(config)?)),
+            RemoteConfig::GoogleDrive(config) => Ok(Arc::new(GoogleDriveStore::new(config)?)),
+            RemoteConfig::Rclone(config) => {
+                Ok(Arc::new(RcloneStore::new(config, status_tx.clone())?))
+            }
+        }
+    }
+}
+
+// Percent-encodes everything but unreserved characters (and `/` in paths)
+pub fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// A connected remote as used by the backup engine
+#[derive(Clone)]
+pub struct Remote {
+    pub label: String,
+    pub store: Arc<dyn RemoteStore>,
+    // Prepended to the keys of this engine's uploads, so several source folders backing up
+    // into one remote do not overwrite each other; empty for a single source
+    pub folder: String,
+}
+
+// A file that still has to be uploaded to a remote
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingUpload {
+    // Label of the remote
+    pub remote: String,
+    pub key: String,
+    pub file: PathBuf,
+}
+
+// Every upload that has not finished yet. It is saved before an upload starts and after it
+// succeeded, so uploads cut short by an error, a crash or a restart can be tried again.
+pub struct UploadQueue {
+    path: PathBuf,
+    pending: Mutex<Vec<PendingUpload>>,
+}
+
+impl UploadQueue {
+    pub fn load(path: &Path) -> Self {
+        let pending = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            pending: Mutex::new(pending),
+        }
+    }
+
+    pub fn pending(&self) -> Vec<PendingUpload> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<PendingUpload>)) -> std::io::Result<()> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut pending);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&*pending).map_err(std::io::Error::other)?;
+        fs::write(&self.path, json)
+    }
+
+    pub fn add(&self, uploads: &[PendingUpload]) -> std::io::Result<()> {
+        self.update(|pending| {
+            for upload in uploads {
+                if !pending.contains(upload) {
+                    pending.push(upload.clone());
+                }
+            }
+        })
+    }
+
+    pub fn remove(&self, upload: &PendingUpload) -> std::io::Result<()> {
+        self.update(|pending| pending.retain(|known| known != upload))
+    }
+}
+
+// Uploads one file, keeping it queued until it arrived. Returns its size.
+fn upload_file(
+    fs: &dyn FileSystem,
+    remote: &Remote,
+    queue: Option<&UploadQueue>,
+    upload: &PendingUpload,
+) -> std::io::Result<u64> {
+    let bytes = remote.store.upload(fs, &upload.key, &upload.file)?;
+    if let Some(queue) = queue {
+        queue.remove(upload)?;
+    }
+    Ok(bytes)
+}
+
+// Uploads a file or match folder written directly inside `destination_folder`, keyed by its
+// path relative to it. With a queue, every file stays queued until it was uploaded.
+pub fn upload_item(
+    fs: &dyn FileSystem,
+    remote: &Remote,
+    queue: Option<&UploadQueue>,
+    destination_folder: &Path,
+    item: &Path,
+) -> std::io::Result<CopyTotals> {
+    let mut uploads = Vec::new();
+    let mut pending = vec![item.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let metadata = fs.metadata(&path)?;
+        if metadata.is_symlink {
+            continue;
+        }
+        if metadata.is_dir {
+            pending.extend(fs.read_dir(&path)?);
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(destination_folder) else {
+            continue;
+        };
+        let mut key: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if !remote.folder.is_empty() {
+            key.insert(0, remote.folder.clone());
+        }
+        uploads.push(PendingUpload {
+            remote: remote.label.clone(),
+            key: key.join("/"),
+            file: path,
+        });
+    }
+    if let Some(queue) = queue {
+        queue.add(&uploads)?;
+    }
+
+    let mut totals = CopyTotals::default();
+    for upload in &uploads {
+        totals.bytes += upload_file(fs, remote, queue, upload)?;
+        totals.files += 1;
+    }
+    Ok(totals)
+}
+
+// Tries every queued upload to one of `remotes` again. Files deleted from the destination in
+// the meantime, e.g. by the retention policy, are dropped from the queue. Returns how many
+// files were uploaded and the first error, if any upload failed again.
+pub fn retry_uploads(
+    fs: &dyn FileSystem,
+    remotes: &[Remote],
+    queue: &UploadQueue,
+) -> (usize, Option<std::io::Error>) {
+    let mut uploaded = 0;
+    let mut first_error = None;
+    for upload in queue.pending() {
+        let Some(remote) = remotes.iter().find(|remote| remote.label == upload.remote) else {
+            continue;
+        };
+        if fs.metadata(&upload.file).is_err() {
+            let _ = queue.remove(&upload);
+            continue;
+        }
+        match upload_file(fs, remote, Some(queue), &upload) {
+            Ok(_) => uploaded += 1,
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    (uploaded, first_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[derive(Default)]
+    struct MemoryStore {
+        objects: Mutex<BTreeMap<String, Vec<u8>>>,
+        offline: AtomicBool,
+    }
+
+    impl RemoteStore for MemoryStore {
+        fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()> {
+            if self.offline.load(Ordering::SeqCst) {
+                return Err(std::io::Error::other("offline"));
+            }
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_upload_item() {
+        let dest = std::env::temp_dir().join("siegesaver_remote_test");
+        let _ = fs::remove_dir_all(&dest);
+        fs::create_dir_all(dest.join("Match-A").join("nested")).unwrap();
+        fs::write(dest.join("Match-A").join("round1.rec"), "A1").unwrap();
+        fs::write(
+            dest.join("Match-A").join("nested").join("round2.rec"),
+            "A22",
+        )
+        .unwrap();
+
+        let store = Arc::new(MemoryStore::default());
+        let remote = Remote {
+            label: "memory".to_string(),
+            store: Arc::clone(&store) as Arc<dyn RemoteStore>,
+            folder: "Replays".to_string(),
+        };
+        let totals = upload_item(&RealFs, &remote, None, &dest, &dest.join("Match-A")).unwrap();
+        assert_eq!(totals, CopyTotals { files: 2, bytes: 5 });
+        let objects = store.objects.lock().unwrap();
+        assert_eq!(
+            objects.keys().collect::<Vec<_>>(),
+            [
+                "Replays/Match-A/nested/round2.rec",
+                "Replays/Match-A/round1.rec"
+            ]
+        );
+        assert_eq!(objects["Replays/Match-A/round1.rec"], b"A1");
+        drop(objects);
+
+        let json = r#"{"kind":"s3","endpoint":"http://nas:9000","bucket":"replays"}"#;
+        let config: RemoteConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.label(), "s3://replays");
+        // No credentials configured or in the environment
+        if std::env::var("AWS_ACCESS_KEY_ID").is_err() {
+            assert!(config.store(&std::sync::mpsc::channel().0).is_err());
+        }
+
+        let _ = fs::remove_dir_all(&dest);
+    }
+
+    #[test]
+    fn test_failed_uploads_stay_queued_across_restarts() {
+        let dest = std::env::temp_dir().join("siegesaver_upload_queue_test");
+        let _ = fs::remove_dir_all(&dest);
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("Match-A.zip"), "A").unwrap();
+        fs::write(dest.join("Match-B.zip"), "B").unwrap();
+        let queue_path = dest.join("config").join(UPLOAD_QUEUE_FILE_NAME);
+
+        let store = Arc::new(MemoryStore::default());
+        let remote = Remote {
+            label: "memory".to_string(),
+            store: Arc::clone(&store) as Arc<dyn RemoteStore>,
+            folder: String::new(),
+        };
+        store.offline.store(true, Ordering::SeqCst);
+        let queue = UploadQueue::load(&queue_path);
+        for name in ["Match-A.zip", "Match-B.zip"] {
+            assert!(upload_item(&RealFs, &remote, Some(&queue), &dest, &dest.join(name)).is_err());
+        }
+        assert_eq!(queue.pending().len(), 2);
+
+        // After a restart, with the remote reachable again
+        fs::remove_file(dest.join("Match-B.zip")).unwrap();
+        store.offline.store(false, Ordering::SeqCst);
+        let queue = UploadQueue::load(&queue_path);
+        let (uploaded, error) = retry_uploads(&RealFs, std::slice::from_ref(&remote), &queue);
+        assert_eq!(uploaded, 1);
+        assert!(error.is_none());
+        assert!(queue.pending().is_empty());
+        assert!(UploadQueue::load(&queue_path).pending().is_empty());
+        assert_eq!(
+            store.objects.lock().unwrap().keys().collect::<Vec<_>>(),
+            ["Match-A.zip"]
+        );
+
+        let _ = fs::remove_dir_all(&dest);
+    }
+}
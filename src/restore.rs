@@ -0,0 +1,215 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::RealFs;
+use crate::crypto::{Decryptor, ENCRYPTED_EXTENSION};
+use crate::manifest::CHECKSUMS_FILE_NAME;
+use crate::retention::backed_up_matches;
+use crate::storage::{StorageFormat, DEFAULT_ZSTD_LEVEL};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// A backed-up match that can be copied back into the game's replay folder
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestoreCandidate {
+    // The folder or archive in the destination
+    pub backup: PathBuf,
+    // Name of the match folder once restored
+    pub name: String,
+    pub modified: SystemTime,
+}
+
+// Lists the matches in the destination that can be restored, newest first
+pub fn restore_candidates(destination_folder: &Path) -> std::io::Result<Vec<RestoreCandidate>> {
+    Ok(backed_up_matches(&RealFs, destination_folder)?
+        .into_iter()
+        .filter_map(|(modified, backup)| {
+            let name = restored_name(&backup.file_name()?.to_string_lossy());
+            Some(RestoreCandidate {
+                backup,
+                name,
+                modified,
+            })
+        })
+        .collect())
+}
+
+fn strip_encrypted_extension(name: &str) -> Option<&str> {
+    name.strip_suffix(ENCRYPTED_EXTENSION)?.strip_suffix('.')
+}
+
+// The name a backed-up item gets back in the source, e.g. `Match-A.zip.enc` -> `Match-A`
+pub fn restored_name(backup_name: &str) -> String {
+    let name = strip_encrypted_extension(backup_name).unwrap_or(backup_name);
+    match StorageFormat::from_archive_name(name) {
+        Some((_, folder_name)) => folder_name.to_string(),
+        None => name.to_string(),
+    }
+}
+
+// Copies a backed-up match back into `target_folder`, unpacking archives and decrypting `.enc`
+// files on the way. `decryptor` is only needed for encrypted backups. Returns how many files
+// were restored.
+pub fn restore_match(
+    backup: &Path,
+    target_folder: &Path,
+    decryptor: &mut Option<Decryptor>,
+) -> std::io::Result<usize> {
+    let Some(file_name) = backup
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+    else {
+        return Ok(0);
+    };
+    if backup.is_dir() {
+        return restore_folder(backup, &target_folder.join(&file_name), decryptor);
+    }
+
+    fs::create_dir_all(target_folder)?;
+    let (data, name) = read_backup_file(backup, &file_name, decryptor)?;
+    match StorageFormat::from_archive_name(&name) {
+        Some((format, folder_name)) => format
+            .backend(DEFAULT_ZSTD_LEVEL)
+            .extract(&data, &target_folder.join(folder_name)),
+        None => {
+            fs::write(target_folder.join(name), data)?;
+            Ok(1)
+        }
+    }
+}
+
+fn restore_folder(
+    source: &Path,
+    destination: &Path,
+    decryptor: &mut Option<Decryptor>,
+) -> std::io::Result<usize> {
+    fs::create_dir_all(destination)?;
+    let mut restored = 0;
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let Some(file_name) = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+        // Written by the backup, not by the game
+        if file_name == CHECKSUMS_FILE_NAME {
+            continue;
+        }
+        if path.is_dir() {
+            restored += restore_folder(&path, &destination.join(&file_name), decryptor)?;
+        } else {
+            let (data, name) = read_backup_file(&path, &file_name, decryptor)?;
+            fs::write(destination.join(name), data)?;
+            restored += 1;
+        }
+    }
+    Ok(restored)
+}
+
+// Reads a stored file, decrypting it if needed. Returns its contents and original name.
+fn read_backup_file(
+    path: &Path,
+    file_name: &str,
+    decryptor: &mut Option<Decryptor>,
+) -> std::io::Result<(Vec<u8>, String)> {
+    let data = fs::read(path)?;
+    let Some(name) = strip_encrypted_extension(file_name) else {
+        return Ok((data, file_name.to_string()));
+    };
+    let Some(decryptor) = decryptor else {
+        return Err(std::io::Error::other(format!(
+            "{} is encrypted, enter the passphrase first",
+            path.display()
+        )));
+    };
+    let plaintext = decryptor
+        .decrypt(&data)
+        .map_err(|e| std::io::Error::other(format!("{}: {}", path.display(), e)))?;
+    Ok((plaintext, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{backup_path, BackupOptions};
+    use crate::crypto::EncryptionKey;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_restored_name() {
+        assert_eq!(restored_name("Match-A"), "Match-A");
+        assert_eq!(restored_name("Match-A.zip"), "Match-A");
+        assert_eq!(restored_name("Match-A.tar.zst.enc"), "Match-A");
+        assert_eq!(restored_name("round1.rec.enc"), "round1.rec");
+    }
+
+    #[test]
+    fn test_restore_folders_and_archives() {
+        let test_dir = std::env::temp_dir().join("siegesaver_restore_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("source");
+        let backup = test_dir.join("backup");
+        let game = test_dir.join("game");
+        fs::create_dir_all(source.join("Match-A").join("nested")).unwrap();
+        fs::create_dir_all(source.join("Match-B")).unwrap();
+        fs::create_dir_all(&backup).unwrap();
+        fs::write(
+            source.join("Match-A").join("nested").join("round1.rec"),
+            "A1",
+        )
+        .unwrap();
+        fs::write(source.join("Match-B").join("round1.rec"), "B1").unwrap();
+
+        let encrypted = BackupOptions {
+            encryption: Some(Arc::new(EncryptionKey::generate("secret").unwrap())),
+            ..Default::default()
+        };
+        backup_path(
+            &RealFs,
+            &source.join("Match-A"),
+            &source,
+            &backup,
+            &encrypted,
+        )
+        .unwrap();
+        let archived = BackupOptions {
+            storage: StorageFormat::Zip,
+            ..Default::default()
+        };
+        backup_path(
+            &RealFs,
+            &source.join("Match-B"),
+            &source,
+            &backup,
+            &archived,
+        )
+        .unwrap();
+
+        let candidates = restore_candidates(&backup).unwrap();
+        let mut names: Vec<_> = candidates.iter().map(|c| c.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["Match-A", "Match-B"]);
+
+        // Encrypted files need the passphrase
+        let folder = backup.join("Match-A");
+        assert!(restore_match(&folder, &game, &mut None).is_err());
+        let mut decryptor = Some(Decryptor::new("secret"));
+        assert_eq!(restore_match(&folder, &game, &mut decryptor).unwrap(), 1);
+        assert_eq!(
+            restore_match(&backup.join("Match-B.zip"), &game, &mut None).unwrap(),
+            1
+        );
+        assert_eq!(
+            fs::read_to_string(game.join("Match-A").join("nested").join("round1.rec")).unwrap(),
+            "A1"
+        );
+        assert_eq!(
+            fs::read_to_string(game.join("Match-B").join("round1.rec")).unwrap(),
+            "B1"
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
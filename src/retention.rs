@@ -0,0 +1,160 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use crate::crypto::ENCRYPTED_EXTENSION;
+use crate::dedupe::DEDUPE_STORE_NAME;
+use crate::mirror::TRASH_FOLDER_NAME;
+use crate::snapshot::is_snapshot_name;
+use crate::storage::StorageFormat;
+use crate::versions::VERSIONS_FOLDER_NAME;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Which backed-up matches are kept in the destination; everything else is pruned after a backup
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    // Keep only the N most recently modified matches
+    pub keep_last: Option<usize>,
+    // Delete matches that have not been modified for this many days
+    pub max_age_days: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.keep_last.is_some() || self.max_age_days.is_some()
+    }
+
+    pub fn is_expired(&self, modified: SystemTime, now: SystemTime) -> bool {
+        self.max_age_days.is_some_and(|days| {
+            now.duration_since(modified)
+                .is_ok_and(|age| age > Duration::from_secs(days * SECONDS_PER_DAY))
+        })
+    }
+
+    // Picks the matches to delete from `(modified, path)` pairs sorted newest first
+    pub fn select_expired(
+        &self,
+        matches: &[(SystemTime, PathBuf)],
+        now: SystemTime,
+    ) -> Vec<PathBuf> {
+        matches
+            .iter()
+            .enumerate()
+            .filter(|(index, (modified, _))| {
+                self.keep_last.is_some_and(|keep| *index >= keep) || self.is_expired(*modified, now)
+            })
+            .map(|(_, (_, path))| path.clone())
+            .collect()
+    }
+}
+
+// Whether a top-level destination entry holds a backed-up match: a match folder or a match
+// archive, possibly encrypted. Loose replay files are never pruned.
+pub fn is_match_backup(file_name: &str, is_dir: bool) -> bool {
+    if is_dir {
+        return file_name != DEDUPE_STORE_NAME
+            && file_name != TRASH_FOLDER_NAME
+            && file_name != VERSIONS_FOLDER_NAME
+            && !is_snapshot_name(file_name);
+    }
+    let name = file_name
+        .strip_suffix(ENCRYPTED_EXTENSION)
+        .and_then(|name| name.strip_suffix('.'))
+        .unwrap_or(file_name);
+    StorageFormat::from_archive_name(name).is_some()
+}
+
+// Lists the backed-up matches in the destination, newest first. Entries without a
+// modification time are left out so they are never pruned.
+pub fn backed_up_matches(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+) -> std::io::Result<Vec<(SystemTime, PathBuf)>> {
+    let mut matches = Vec::new();
+    for path in fs.read_dir(destination_folder)? {
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        let metadata = fs.metadata(&path)?;
+        if !is_match_backup(&file_name.to_string_lossy(), metadata.is_dir) {
+            continue;
+        }
+        if let Some(modified) = metadata.modified {
+            matches.push((modified, path));
+        }
+    }
+    matches.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(matches)
+}
+
+// The matches in the destination the policy does not keep
+pub fn prune_candidates(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+) -> std::io::Result<Vec<PathBuf>> {
+    if !policy.is_enabled() {
+        return Ok(Vec::new());
+    }
+    Ok(policy.select_expired(&backed_up_matches(fs, destination_folder)?, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_expired() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * SECONDS_PER_DAY);
+        let days_ago = |days: u64| now - Duration::from_secs(days * SECONDS_PER_DAY);
+        let matches = vec![
+            (days_ago(1), PathBuf::from("Match-3")),
+            (days_ago(5), PathBuf::from("Match-2")),
+            (days_ago(40), PathBuf::from("Match-1")),
+        ];
+
+        assert!(RetentionPolicy::default()
+            .select_expired(&matches, now)
+            .is_empty());
+        let keep_two = RetentionPolicy {
+            keep_last: Some(2),
+            max_age_days: None,
+        };
+        assert_eq!(
+            keep_two.select_expired(&matches, now),
+            [PathBuf::from("Match-1")]
+        );
+        let month = RetentionPolicy {
+            keep_last: None,
+            max_age_days: Some(30),
+        };
+        assert_eq!(
+            month.select_expired(&matches, now),
+            [PathBuf::from("Match-1")]
+        );
+        let both = RetentionPolicy {
+            keep_last: Some(1),
+            max_age_days: Some(3),
+        };
+        assert_eq!(
+            both.select_expired(&matches, now),
+            [PathBuf::from("Match-2"), PathBuf::from("Match-1")]
+        );
+    }
+
+    #[test]
+    fn test_is_match_backup() {
+        assert!(is_match_backup("Match-2025-11-23-001", true));
+        assert!(is_match_backup("Match-2025-11-23-001.zip", false));
+        assert!(is_match_backup("Match-2025-11-23-001.tar.zst.enc", false));
+        assert!(!is_match_backup("round1.rec", false));
+        assert!(!is_match_backup("round1.rec.enc", false));
+        assert!(!is_match_backup(DEDUPE_STORE_NAME, true));
+        assert!(!is_match_backup(TRASH_FOLDER_NAME, true));
+        assert!(!is_match_backup(VERSIONS_FOLDER_NAME, true));
+        assert!(!is_match_backup("2025-11-24T14-32", true));
+    }
+}
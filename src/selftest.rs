@@ -0,0 +1,307 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{FileSystem, RealFs};
+use crate::dedupe::collect_garbage;
+use crate::manifest::{sha256_hex, Manifest, ManifestEntry};
+use crate::snapshot::latest_snapshot;
+use crate::status::StatusEvent;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+// Test matches are named like the game's match folders, so every filter lets them through
+pub const TEST_MATCH_PREFIX: &str = "Match-TEST-";
+// Replays written into the test match
+const TEST_REPLAYS: [&str; 2] = ["round-01.rec", "round-02.rec"];
+const TEST_REPLAY_BYTES: usize = 64 * 1024;
+// How long a test backup may take, besides the time the watcher waits for changes to settle
+pub const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+// How often the destination is checked for the test match
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Where the test match is created and where the running watcher is expected to back it up
+#[derive(Clone, Debug)]
+pub struct SelfTest {
+    pub source_folder: PathBuf,
+    pub destination_folder: PathBuf,
+    // Whether matches are stored as one archive instead of a folder with one file per replay
+    pub archived: bool,
+    // How long to wait for the backup, which depends on the settle times of the watcher
+    pub timeout: Duration,
+    // Whether every backup run writes into its own snapshot in the destination folder
+    pub snapshots: bool,
+}
+
+impl SelfTest {
+    // Where the backups are now: the destination folder, or its latest snapshot
+    fn backups(&self, fs: &dyn FileSystem) -> PathBuf {
+        if self.snapshots {
+            if let Some(snapshot) = latest_snapshot(fs, &self.destination_folder) {
+                return snapshot;
+            }
+        }
+        self.destination_folder.clone()
+    }
+}
+
+// Contents of the `index`th test replay: recognizable, and different for every replay, so a
+// deduplicating destination does not merge them
+fn test_replay(index: usize) -> Vec<u8> {
+    let header = format!("SiegeSaver test replay {}\n", index + 1);
+    header
+        .bytes()
+        .chain((0..).map(|i: usize| (i.wrapping_mul(31) ^ index) as u8))
+        .take(TEST_REPLAY_BYTES)
+        .collect()
+}
+
+// Creates the test match `name` in `source_folder`. The replays are written under temporary
+// names that are never backed up and then renamed, so the watcher sees them finished at once.
+pub fn create_test_match(source_folder: &Path, name: &str) -> std::io::Result<PathBuf> {
+    let folder = source_folder.join(name);
+    std::fs::create_dir(&folder)?;
+    for (index, replay) in TEST_REPLAYS.iter().enumerate() {
+        let partial = folder.join(format!("{}.partial", replay));
+        std::fs::write(&partial, test_replay(index))?;
+        std::fs::rename(&partial, folder.join(replay))?;
+    }
+    Ok(folder)
+}
+
+// The name in the destination of what starts with `name`, e.g. the match folder itself or
+// its archive
+fn stored_name(fs: &dyn FileSystem, destination_folder: &Path, name: &str) -> Option<String> {
+    fs.read_dir(destination_folder)
+        .ok()?
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|stored| stored.to_string_lossy().into_owned())
+        .find(|stored| stored == name || stored.starts_with(&format!("{}.", name)))
+}
+
+// The manifest entry of the test match once all its files are recorded
+fn recorded_backup(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    stored: &str,
+    archived: bool,
+) -> Option<ManifestEntry> {
+    let expected_files = if archived { 1 } else { TEST_REPLAYS.len() };
+    if !Manifest::exists(fs, destination_folder) {
+        return None;
+    }
+    let manifest = Manifest::load(fs, destination_folder).ok()?;
+    manifest
+        .entries
+        .get(stored)
+        .filter(|entry| entry.files.len() >= expected_files)
+        .cloned()
+}
+
+// Re-reads every file of the backed-up test match and compares it with the checksum recorded
+// in the manifest, and for plain copies also with the replay that was written. Returns how
+// many files were checked.
+fn verify_test_backup(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    entry: &ManifestEntry,
+) -> Result<usize, String> {
+    for (key, record) in &entry.files {
+        let data = fs
+            .read(&destination_folder.join(key))
+            .map_err(|e| format!("{} cannot be read back: {}", key, e))?;
+        if sha256_hex(&data) != record.sha256 {
+            return Err(format!("{} does not match its recorded checksum", key));
+        }
+        let written = TEST_REPLAYS
+            .iter()
+            .position(|replay| key.ends_with(&format!("/{}", replay)));
+        if written.is_some_and(|index| data != test_replay(index)) {
+            return Err(format!("{} differs from the replay that was written", key));
+        }
+    }
+    Ok(entry.files.len())
+}
+
+// Deletes the test match from the source and, if it got there, from the destination and its
+// manifest
+fn remove_test_match(fs: &dyn FileSystem, test: &SelfTest, name: &str) -> std::io::Result<()> {
+    let source = test.source_folder.join(name);
+    if source.exists() {
+        std::fs::remove_dir_all(&source)?;
+    }
+    let destination = test.backups(fs);
+    let Some(stored) = stored_name(fs, &destination, name) else {
+        return Ok(());
+    };
+    fs.remove(&destination.join(&stored))?;
+    if Manifest::exists(fs, &destination) {
+        let mut manifest = Manifest::load(fs, &destination)?;
+        if manifest.entries.remove(&stored).is_some() {
+            manifest.save(fs, &destination)?;
+            collect_garbage(fs, &destination, &manifest)?;
+        }
+    }
+    Ok(())
+}
+
+// Creates the test match `name` and waits for it to go through the watcher, the copy, the
+// checksum verification and the manifest
+fn check_pipeline(
+    fs: &dyn FileSystem,
+    test: &SelfTest,
+    name: &str,
+    status_tx: &Sender<StatusEvent>,
+) -> Result<(), String> {
+    create_test_match(&test.source_folder, name).map_err(|e| {
+        format!(
+            "the test match could not be created in {}: {}",
+            test.source_folder.display(),
+            e
+        )
+    })?;
+    let _ = status_tx.send(StatusEvent::Info(format!(
+        "Test backup: created {} with {} replays in {}",
+        name,
+        TEST_REPLAYS.len(),
+        test.source_folder.display()
+    )));
+
+    let started = Instant::now();
+    let mut stored = None;
+    let mut destination = test.backups(fs);
+    let entry = loop {
+        if stored.is_none() {
+            // A new snapshot may have been started for the test match
+            destination = test.backups(fs);
+            stored = stored_name(fs, &destination, name);
+        }
+        if let Some(entry) = stored
+            .as_deref()
+            .and_then(|stored| recorded_backup(fs, &destination, stored, test.archived))
+        {
+            break entry;
+        }
+        if started.elapsed() >= test.timeout {
+            return Err(match stored {
+                None => format!(
+                    "it did not reach {} within {} seconds. Check that watching is on, the \
+                     source folder is where the game saves replays and the file filters let \
+                     .rec files through.",
+                    destination.display(),
+                    test.timeout.as_secs()
+                ),
+                Some(stored) => format!(
+                    "{} was copied but not recorded in the backup manifest within {} seconds",
+                    stored,
+                    test.timeout.as_secs()
+                ),
+            });
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    let _ = status_tx.send(StatusEvent::Info(format!(
+        "Test backup: backed up and recorded in the manifest after {:.1}s",
+        started.elapsed().as_secs_f64()
+    )));
+
+    let checked = verify_test_backup(fs, &destination, &entry)?;
+    let _ = status_tx.send(StatusEvent::Info(format!(
+        "Test backup: verified {} files against their checksums",
+        checked
+    )));
+    Ok(())
+}
+
+// Runs the test backup `name` and removes the test match again, whether it passed or not.
+// Every step is reported on `status_tx`.
+pub fn run_self_test(test: &SelfTest, name: &str, status_tx: &Sender<StatusEvent>) -> bool {
+    let fs = RealFs;
+    let result = check_pipeline(&fs, test, name, status_tx);
+    let cleaned = remove_test_match(&fs, test, name);
+    if let Err(e) = &cleaned {
+        let _ = status_tx.send(StatusEvent::Warning(format!(
+            "Test backup: could not remove {}, delete it by hand: {}",
+            name, e
+        )));
+    }
+    match result {
+        Ok(()) => {
+            let _ = status_tx.send(StatusEvent::Info(
+                "Test backup passed: new matches are picked up, copied and verified".to_string(),
+            ));
+            true
+        }
+        Err(e) => {
+            let _ = status_tx.send(StatusEvent::Error(format!("Test backup failed: {}", e)));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{BackupEngine, BackupOptions};
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_self_test() {
+        let folder = std::env::temp_dir().join("siegesaver_self_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        let source = folder.join("source");
+        let destination = folder.join("destination");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::create_dir_all(&destination).unwrap();
+        let test = SelfTest {
+            source_folder: source.clone(),
+            destination_folder: destination.clone(),
+            archived: false,
+            timeout: Duration::from_secs(10),
+            snapshots: false,
+        };
+        let (tx, rx) = channel();
+
+        // Nothing backs the test match up without a watcher
+        let name = "Match-TEST-unwatched";
+        assert!(!run_self_test(
+            &SelfTest {
+                timeout: Duration::ZERO,
+                ..test.clone()
+            },
+            name,
+            &tx
+        ));
+        assert!(!source.join(name).exists());
+        let messages: Vec<StatusEvent> = rx.try_iter().collect();
+        assert!(matches!(
+            messages.last(),
+            Some(StatusEvent::Error(message)) if message.contains("did not reach")
+        ));
+
+        // Backed up the way the watcher would, it passes and is removed everywhere
+        let name = "Match-TEST-watched";
+        let match_folder = create_test_match(&source, name).unwrap();
+        assert_eq!(std::fs::read_dir(&match_folder).unwrap().count(), 2);
+        let engine = BackupEngine::new(
+            source.clone(),
+            destination.clone(),
+            BackupOptions::default(),
+        );
+        assert!(engine.backup(&match_folder, &tx).is_some());
+        let fs = RealFs;
+        let entry = recorded_backup(&fs, &destination, name, false).unwrap();
+        assert_eq!(verify_test_backup(&fs, &destination, &entry), Ok(2));
+
+        std::fs::write(destination.join(name).join("round-01.rec"), b"damaged").unwrap();
+        assert!(verify_test_backup(&fs, &destination, &entry).is_err());
+
+        remove_test_match(&fs, &test, name).unwrap();
+        assert!(!match_folder.exists());
+        assert!(!destination.join(name).exists());
+        let manifest = Manifest::load(&fs, &destination).unwrap();
+        assert!(manifest.entries.is_empty());
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
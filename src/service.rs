@@ -0,0 +1,312 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const SERVICE_NAME: &str = "SiegeSaver";
+// Written next to the config file, as the service has no console
+pub const SERVICE_LOG_FILE_NAME: &str = "service.log";
+// How often the GUI looks up whether the service runs
+pub const SERVICE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// What the service control manager reports about the SiegeSaver service
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceState {
+    NotInstalled,
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+}
+
+impl ServiceState {
+    pub fn label(self) -> &'static str {
+        match self {
+            ServiceState::NotInstalled => "not installed",
+            ServiceState::Stopped => "stopped",
+            ServiceState::Starting => "starting",
+            ServiceState::Running => "running",
+            ServiceState::Stopping => "stopping",
+        }
+    }
+}
+
+pub fn service_log_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(SERVICE_LOG_FILE_NAME)
+}
+
+// The command line the service is started with. The service runs as the system account, so
+// it is pointed at the config of the user who installed it.
+pub fn service_arguments(config_path: &Path) -> Vec<OsString> {
+    vec![
+        "--config".into(),
+        config_path.as_os_str().to_owned(),
+        "service".into(),
+        "run".into(),
+    ]
+}
+
+#[cfg(windows)]
+pub use windows::{
+    install_service, run_service, service_state, start_service, stop_service, uninstall_service,
+};
+
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::headless::{run_headless, HeadlessArgs};
+    use std::io::Write;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use windows_service::service::{
+        self, ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl,
+        ServiceExitCode, ServiceInfo, ServiceStartType, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+    // The library's own messages leave out the Windows error
+    fn error_text(e: windows_service::Error) -> String {
+        match e {
+            windows_service::Error::Winapi(e) => e.to_string(),
+            e => e.to_string(),
+        }
+    }
+
+    fn open_service(access: ServiceAccess) -> Result<service::Service, String> {
+        let manager =
+            ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+                .map_err(|e| format!("Cannot connect to the service manager: {}", error_text(e)))?;
+        manager.open_service(SERVICE_NAME, access).map_err(|e| {
+            format!(
+                "Cannot open the {} service: {}",
+                SERVICE_NAME,
+                error_text(e)
+            )
+        })
+    }
+
+    // Registers the current executable as a service that starts with Windows and runs with
+    // the config at `config_path`, then starts it. Needs an elevated prompt.
+    pub fn install_service(config_path: &Path) -> Result<(), String> {
+        let config_path = std::path::absolute(config_path).map_err(|e| e.to_string())?;
+        let executable_path = std::env::current_exe().map_err(|e| e.to_string())?;
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )
+        .map_err(|e| format!("Cannot connect to the service manager: {}", error_text(e)))?;
+        let info = ServiceInfo {
+            name: SERVICE_NAME.into(),
+            display_name: "SiegeSaver replay backup".into(),
+            service_type: SERVICE_TYPE,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path,
+            launch_arguments: service_arguments(&config_path),
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        };
+        let service = manager
+            .create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+            .map_err(|e| format!("Cannot install the service: {}", error_text(e)))?;
+        let _ = service.set_description(
+            "Watches the replay folders and backs up new matches, without anyone logged in",
+        );
+        service.start::<&str>(&[]).map_err(|e| {
+            format!(
+                "Installed the service but cannot start it: {}",
+                error_text(e)
+            )
+        })
+    }
+
+    // Stops the service if it runs and removes it
+    pub fn uninstall_service() -> Result<(), String> {
+        let service = open_service(
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )?;
+        service
+            .delete()
+            .map_err(|e| format!("Cannot remove the service: {}", error_text(e)))?;
+        let stopped = service
+            .query_status()
+            .is_ok_and(|status| status.current_state == service::ServiceState::Stopped);
+        if !stopped {
+            let _ = service.stop();
+        }
+        Ok(())
+    }
+
+    pub fn service_state() -> ServiceState {
+        // Also when it cannot be looked at, as there is nothing to control then either
+        let Ok(service) = open_service(ServiceAccess::QUERY_STATUS) else {
+            return ServiceState::NotInstalled;
+        };
+        match service.query_status().map(|status| status.current_state) {
+            Ok(service::ServiceState::Running) => ServiceState::Running,
+            Ok(service::ServiceState::StartPending | service::ServiceState::ContinuePending) => {
+                ServiceState::Starting
+            }
+            Ok(service::ServiceState::StopPending | service::ServiceState::PausePending) => {
+                ServiceState::Stopping
+            }
+            Ok(_) | Err(_) => ServiceState::Stopped,
+        }
+    }
+
+    pub fn start_service() -> Result<(), String> {
+        open_service(ServiceAccess::START)?
+            .start::<&str>(&[])
+            .map_err(|e| format!("Cannot start the service: {}", error_text(e)))
+    }
+
+    pub fn stop_service() -> Result<(), String> {
+        open_service(ServiceAccess::STOP)?
+            .stop()
+            .map(|_| ())
+            .map_err(|e| format!("Cannot stop the service: {}", error_text(e)))
+    }
+
+    // Hands the process over to the service control manager until the service is stopped.
+    // Only works when started by it.
+    pub fn run_service() -> Result<(), String> {
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(|e| {
+            format!(
+                "Cannot run as a service ({}); use service install instead",
+                error_text(e)
+            )
+        })
+    }
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    fn service_status(state: service::ServiceState, failed: bool) -> ServiceStatus {
+        ServiceStatus {
+            service_type: SERVICE_TYPE,
+            current_state: state,
+            controls_accepted: if state == service::ServiceState::Running {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+            } else {
+                ServiceControlAccept::empty()
+            },
+            exit_code: if failed {
+                ServiceExitCode::ServiceSpecific(1)
+            } else {
+                ServiceExitCode::Win32(0)
+            },
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        }
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_requested = Arc::clone(&stop);
+        let handler = move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                stop_requested.store(true, Ordering::Relaxed);
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let Ok(status_handle) = service_control_handler::register(SERVICE_NAME, handler) else {
+            return;
+        };
+        let _ =
+            status_handle.set_service_status(service_status(service::ServiceState::Running, false));
+
+        let config_path = AppConfig::resolve_path();
+        let log_file = service_log_path(&config_path);
+        let result = run_headless(
+            &config_path,
+            &HeadlessArgs {
+                log_file: Some(log_file.clone()),
+            },
+            &stop,
+        );
+        if let Err(e) = &result {
+            if let Ok(mut file) = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&log_file)
+            {
+                let _ = writeln!(
+                    file,
+                    "[{}] Error: {}",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    e
+                );
+            }
+        }
+        let _ = status_handle.set_service_status(service_status(
+            service::ServiceState::Stopped,
+            result.is_err(),
+        ));
+    }
+}
+
+#[cfg(not(windows))]
+const NOT_SUPPORTED: &str = "Running as a service is only supported on Windows";
+
+#[cfg(not(windows))]
+pub fn install_service(_config_path: &Path) -> Result<(), String> {
+    Err(NOT_SUPPORTED.to_string())
+}
+
+#[cfg(not(windows))]
+pub fn uninstall_service() -> Result<(), String> {
+    Err(NOT_SUPPORTED.to_string())
+}
+
+#[cfg(not(windows))]
+pub fn run_service() -> Result<(), String> {
+    Err(NOT_SUPPORTED.to_string())
+}
+
+#[cfg(not(windows))]
+pub fn service_state() -> ServiceState {
+    ServiceState::NotInstalled
+}
+
+#[cfg(not(windows))]
+pub fn start_service() -> Result<(), String> {
+    Err(NOT_SUPPORTED.to_string())
+}
+
+#[cfg(not(windows))]
+pub fn stop_service() -> Result<(), String> {
+    Err(NOT_SUPPORTED.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_arguments() {
+        let config_path = Path::new("C:\\Users\\Player\\AppData\\Roaming\\siegesaver\\config.json");
+        assert_eq!(
+            service_arguments(config_path),
+            [
+                OsString::from("--config"),
+                config_path.as_os_str().to_owned(),
+                OsString::from("service"),
+                OsString::from("run"),
+            ]
+        );
+        assert_eq!(
+            service_log_path(config_path),
+            config_path.with_file_name("service.log")
+        );
+        assert_eq!(ServiceState::Running.label(), "running");
+    }
+}
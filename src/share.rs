@@ -0,0 +1,332 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use crate::manifest::{sha256_hex, unix_seconds, FileRecord, Manifest, ManifestEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+// Name of the manifest inside a shared archive, next to the match folders
+pub const SHARE_MANIFEST_NAME: &str = "siegesaver-share.json";
+// Bumped when a shared archive can no longer be read by older versions
+pub const SHARE_VERSION: u32 = 1;
+
+// What a shared archive holds: the manifest entries of its matches, whose files are stored
+// in the archive under the same paths as in the destination
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareManifest {
+    pub version: u32,
+    // Keyed by the file or folder name of the match
+    pub matches: BTreeMap<String, ManifestEntry>,
+}
+
+// Which matches of a shared archive were imported, and which were left out because the
+// destination already has an item of that name
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+// `key`, a file of the match `name` as recorded in a manifest, as a relative path, unless it
+// would end up outside the match
+fn match_file(name: &str, key: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(key);
+    let inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    let first = path.components().next()?;
+    (inside && first.as_os_str() == name).then_some(path)
+}
+
+// Whether `name`, the name of a match in a shared archive, is a single file or folder name,
+// so joining it onto the destination stays inside of it
+fn is_match_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Writes the matches `items`, each a destination folder, the match's name there and its
+// manifest entry, into the zip archive `archive`. Every file is checked against its recorded
+// checksum on the way. The folder the matches came from is left out, as it means nothing on
+// another machine. Returns how many matches were exported.
+pub fn export_matches(
+    fs: &dyn FileSystem,
+    items: &[(PathBuf, String, ManifestEntry)],
+    archive: &Path,
+) -> io::Result<usize> {
+    let result = write_archive(fs, items, archive);
+    if result.is_err() {
+        let _ = std::fs::remove_file(archive);
+    }
+    result
+}
+
+fn write_archive(
+    fs: &dyn FileSystem,
+    items: &[(PathBuf, String, ManifestEntry)],
+    archive: &Path,
+) -> io::Result<usize> {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(archive)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut manifest = ShareManifest {
+        version: SHARE_VERSION,
+        matches: BTreeMap::new(),
+    };
+    for (destination, name, entry) in items {
+        // The same match kept in several destinations is exported once
+        if manifest.matches.contains_key(name) {
+            continue;
+        }
+        for (key, record) in &entry.files {
+            let Some(file) = match_file(name, key) else {
+                continue;
+            };
+            let data = fs.read(&destination.join(file))?;
+            if sha256_hex(&data) != record.sha256 {
+                return Err(invalid(format!(
+                    "{} does not match its recorded checksum",
+                    key
+                )));
+            }
+            zip.start_file(key.as_str(), options)
+                .map_err(io::Error::other)?;
+            zip.write_all(&data)?;
+        }
+        manifest.matches.insert(
+            name.clone(),
+            ManifestEntry {
+                source: None,
+                ..entry.clone()
+            },
+        );
+    }
+    zip.start_file(SHARE_MANIFEST_NAME, options)
+        .map_err(io::Error::other)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?)?;
+    zip.finish().map_err(io::Error::other)?;
+    Ok(manifest.matches.len())
+}
+
+// Copies the matches of the shared archive `archive` into `destination`, checking every file
+// against its checksum, and records them with their tags, notes and match statistics in the
+// destination's manifest. Matches the destination already has are skipped.
+pub fn import_matches(
+    fs: &dyn FileSystem,
+    archive: &Path,
+    destination: &Path,
+) -> io::Result<ImportReport> {
+    let mut zip = zip::ZipArchive::new(std::fs::File::open(archive)?)
+        .map_err(|e| invalid(format!("not a zip archive: {}", e)))?;
+    let shared: ShareManifest = {
+        let mut file = zip
+            .by_name(SHARE_MANIFEST_NAME)
+            .map_err(|_| invalid("not a SiegeSaver match archive".to_string()))?;
+        let mut json = Vec::new();
+        file.read_to_end(&mut json)?;
+        serde_json::from_slice(&json)
+            .map_err(|e| invalid(format!("not a SiegeSaver match archive: {}", e)))?
+    };
+    if shared.version > SHARE_VERSION {
+        return Err(invalid(
+            "the matches were exported by a newer SiegeSaver".to_string(),
+        ));
+    }
+
+    fs.create_dir_all(destination)?;
+    let mut manifest = Manifest::load(fs, destination)?;
+    let mut report = ImportReport::default();
+    for (name, entry) in shared.matches {
+        if !is_match_name(&name) || entry.files.is_empty() {
+            return Err(invalid(format!("{} is not a match", name)));
+        }
+        if manifest.entries.contains_key(&name) || fs.metadata(&destination.join(&name)).is_ok() {
+            report.skipped.push(name);
+            continue;
+        }
+        let imported = match import_match(fs, &mut zip, destination, &name, &entry) {
+            Ok(imported) => imported,
+            Err(e) => {
+                let _ = fs.remove(&destination.join(&name));
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("{} could not be imported: {}", name, e),
+                ));
+            }
+        };
+        manifest.entries.insert(name.clone(), imported);
+        manifest.save(fs, destination)?;
+        manifest.write_checksums(fs, destination, &name)?;
+        report.imported.push(name);
+    }
+    Ok(report)
+}
+
+// Copies the files of the match `name` out of `zip` and returns its entry for the manifest
+// of `destination`
+fn import_match(
+    fs: &dyn FileSystem,
+    zip: &mut zip::ZipArchive<std::fs::File>,
+    destination: &Path,
+    name: &str,
+    entry: &ManifestEntry,
+) -> io::Result<ManifestEntry> {
+    let mut imported = ManifestEntry {
+        source: None,
+        files: BTreeMap::new(),
+        ..entry.clone()
+    };
+    for (key, record) in &entry.files {
+        let path = match_file(name, key)
+            .map(|file| destination.join(file))
+            .ok_or_else(|| invalid(format!("{} is outside of {}", key, name)))?;
+        let mut data = Vec::new();
+        zip.by_name(key)
+            .map_err(|e| invalid(format!("{} is missing: {}", key, e)))?
+            .read_to_end(&mut data)?;
+        if sha256_hex(&data) != record.sha256 {
+            return Err(invalid(format!(
+                "{} does not match its recorded checksum",
+                key
+            )));
+        }
+        if let Some(parent) = path.parent() {
+            fs.create_dir_all(parent)?;
+        }
+        fs.write(&path, &data)?;
+        // Taken from the copy, so the manifest does not hash it again
+        let modified = fs.metadata(&path)?.modified.map(unix_seconds).unwrap_or(0);
+        imported.files.insert(
+            key.clone(),
+            FileRecord {
+                modified,
+                ..record.clone()
+            },
+        );
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use crate::manifest::CHECKSUMS_FILE_NAME;
+
+    #[test]
+    fn test_share_round_trip() {
+        let folder = std::env::temp_dir().join("siegesaver_share_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        let sender = folder.join("sender");
+        let receiver = folder.join("receiver");
+        for name in ["Match-A", "Match-B"] {
+            std::fs::create_dir_all(sender.join(name)).unwrap();
+            std::fs::write(sender.join(name).join("round1.rec"), name).unwrap();
+        }
+        let fs = RealFs;
+        let mut manifest = Manifest::load(&fs, &sender).unwrap();
+        let entry = manifest.entries.get_mut("Match-A").unwrap();
+        entry.source = Some(PathBuf::from("/home/sender/replays/Match-A"));
+        entry.tags.insert("clutch".to_string());
+        entry.notes = "1v3 on the last round".to_string();
+        entry.match_stats = Some(serde_json::json!({ "rounds": [] }));
+        let entry = entry.clone();
+        manifest.save(&fs, &sender).unwrap();
+
+        let archive = folder.join("shared.zip");
+        let items = [(sender.clone(), "Match-A".to_string(), entry.clone())];
+        assert_eq!(export_matches(&fs, &items, &archive).unwrap(), 1);
+
+        let report = import_matches(&fs, &archive, &receiver).unwrap();
+        assert_eq!(report.imported, ["Match-A"]);
+        assert_eq!(
+            std::fs::read_to_string(receiver.join("Match-A").join("round1.rec")).unwrap(),
+            "Match-A"
+        );
+        assert!(receiver.join("Match-A").join(CHECKSUMS_FILE_NAME).exists());
+        assert!(!receiver.join("Match-B").exists());
+        let imported = Manifest::load(&fs, &receiver).unwrap().entries["Match-A"].clone();
+        assert_eq!(imported.source, None);
+        assert_eq!(imported.tags, entry.tags);
+        assert_eq!(imported.notes, entry.notes);
+        assert_eq!(imported.match_stats, entry.match_stats);
+        // Recorded the way a refresh of the destination would see it
+        let mut refreshed = Manifest::load(&fs, &receiver).unwrap();
+        refreshed.refresh(&fs, &receiver).unwrap();
+        assert_eq!(refreshed.entries["Match-A"], imported);
+
+        // Matches the destination already has are left alone
+        let report = import_matches(&fs, &archive, &receiver).unwrap();
+        assert_eq!(report.skipped, ["Match-A"]);
+
+        // A file changed since it was backed up is not passed on
+        std::fs::write(sender.join("Match-A").join("round1.rec"), "damaged").unwrap();
+        assert!(export_matches(&fs, &items, &archive).is_err());
+        assert!(!archive.exists());
+
+        assert!(
+            import_matches(&fs, &sender.join("Match-B").join("round1.rec"), &receiver).is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_match_file() {
+        assert_eq!(
+            match_file("Match-A", "Match-A/round1.rec"),
+            Some(PathBuf::from("Match-A/round1.rec"))
+        );
+        assert_eq!(
+            match_file("Match-A.zip", "Match-A.zip"),
+            Some(PathBuf::from("Match-A.zip"))
+        );
+        assert_eq!(match_file("Match-A", "Match-B/round1.rec"), None);
+        assert_eq!(match_file("Match-A", "Match-A/../../evil"), None);
+        assert_eq!(match_file("Match-A", "/Match-A/round1.rec"), None);
+    }
+
+    #[test]
+    fn test_match_name() {
+        assert!(is_match_name("Match-A"));
+        assert!(is_match_name("Match-A.zip"));
+        assert!(!is_match_name("../evil"));
+        assert!(!is_match_name(".."));
+        assert!(!is_match_name("/evil"));
+        assert!(!is_match_name("Match-A/round1.rec"));
+        assert!(!is_match_name(""));
+
+        // A match named to escape the destination is not imported
+        let folder = std::env::temp_dir().join("siegesaver_share_name_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let archive = folder.join("evil.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        zip.start_file(
+            SHARE_MANIFEST_NAME,
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        let shared = ShareManifest {
+            version: SHARE_VERSION,
+            matches: BTreeMap::from([("../evil".to_string(), ManifestEntry::default())]),
+        };
+        zip.write_all(&serde_json::to_vec(&shared).unwrap())
+            .unwrap();
+        zip.finish().unwrap();
+        let receiver = folder.join("receiver");
+        assert!(import_matches(&RealFs, &archive, &receiver).is_err());
+        assert!(Manifest::load(&RealFs, &receiver)
+            .unwrap()
+            .entries
+            .is_empty());
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{FileSystem, PART_EXTENSION};
+use crate::manifest::MANIFEST_FILE_NAME;
+use chrono::NaiveDateTime;
+use std::path::{Path, PathBuf};
+
+// Name of the dated folder a backup run writes into, e.g. `2025-11-24T14-32`. Names sort the
+// same way as the runs.
+pub const SNAPSHOT_NAME_FORMAT: &str = "%Y-%m-%dT%H-%M";
+
+pub fn snapshot_name(time: NaiveDateTime) -> String {
+    time.format(SNAPSHOT_NAME_FORMAT).to_string()
+}
+
+pub fn is_snapshot_name(name: &str) -> bool {
+    NaiveDateTime::parse_from_str(name, SNAPSHOT_NAME_FORMAT).is_ok()
+}
+
+// The snapshots in `destination_folder`, oldest first
+pub fn snapshots(fs: &dyn FileSystem, destination_folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut snapshots: Vec<PathBuf> = fs
+        .read_dir(destination_folder)?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| is_snapshot_name(&name.to_string_lossy()))
+                && fs.metadata(path).is_ok_and(|metadata| metadata.is_dir)
+        })
+        .collect();
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+pub fn latest_snapshot(fs: &dyn FileSystem, destination_folder: &Path) -> Option<PathBuf> {
+    snapshots(fs, destination_folder).ok()?.pop()
+}
+
+// Creates the snapshot `name` in `destination_folder`, starting out as a copy of the latest
+// one: its files are hard-linked, so those the run leaves alone take no space, except for the
+// manifest, which is copied as it is rewritten in place. Backups replace changed files
+// instead of writing into them, which keeps the earlier snapshots as they were. A snapshot
+// that already exists, e.g. from a run earlier in the same minute, is used as it is.
+pub fn start_snapshot(
+    fs: &dyn FileSystem,
+    destination_folder: &Path,
+    name: &str,
+) -> std::io::Result<PathBuf> {
+    let snapshot = destination_folder.join(name);
+    if fs.metadata(&snapshot).is_ok() {
+        return Ok(snapshot);
+    }
+    let previous = latest_snapshot(fs, destination_folder);
+    // Created under a temporary name, so an interrupted start never looks like a snapshot
+    let part = destination_folder.join(format!("{}.{}", name, PART_EXTENSION));
+    if fs.metadata(&part).is_ok() {
+        fs.remove(&part)?;
+    }
+    fs.create_dir_all(&part)?;
+    if let Some(previous) = previous {
+        for path in fs.read_dir(&previous)? {
+            let Some(file_name) = path.file_name() else {
+                continue;
+            };
+            if file_name == MANIFEST_FILE_NAME {
+                fs.write(&part.join(file_name), &fs.read(&path)?)?;
+            } else {
+                link_tree(fs, &path, &part.join(file_name))?;
+            }
+        }
+    }
+    fs.rename(&part, &snapshot)?;
+    Ok(snapshot)
+}
+
+// Hard-links every file under `from` to the same place under `to`, copying where the
+// destination cannot link, e.g. on FAT drives
+fn link_tree(fs: &dyn FileSystem, from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = fs.metadata(from)?;
+    if metadata.is_dir {
+        fs.create_dir_all(to)?;
+        for path in fs.read_dir(from)? {
+            if let Some(name) = path.file_name() {
+                link_tree(fs, &path, &to.join(name))?;
+            }
+        }
+        return Ok(());
+    }
+    if from
+        .extension()
+        .is_some_and(|extension| extension == PART_EXTENSION)
+    {
+        return Ok(());
+    }
+    if fs.hard_link(from, to).is_err() {
+        fs.copy(from, to)?;
+        let _ = fs.copy_times(from, to);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_snapshot_names() {
+        let time = NaiveDate::from_ymd_opt(2025, 11, 24)
+            .unwrap()
+            .and_hms_opt(14, 32, 10)
+            .unwrap();
+        assert_eq!(snapshot_name(time), "2025-11-24T14-32");
+        assert!(is_snapshot_name("2025-11-24T14-32"));
+        assert!(!is_snapshot_name("Match-2025-11-24_14-32-10"));
+        assert!(!is_snapshot_name("2025-11-24T14-32.part"));
+    }
+
+    #[test]
+    fn test_start_snapshot() {
+        let destination = std::env::temp_dir().join("siegesaver_snapshot_test");
+        let _ = std::fs::remove_dir_all(&destination);
+        let fs = RealFs;
+        std::fs::create_dir_all(&destination).unwrap();
+
+        // The first snapshot starts out empty
+        let first = start_snapshot(&fs, &destination, "2025-11-24T14-32").unwrap();
+        assert_eq!(std::fs::read_dir(&first).unwrap().count(), 0);
+        std::fs::create_dir_all(first.join("Match-A")).unwrap();
+        std::fs::write(first.join("Match-A").join("round1.rec"), "round 1").unwrap();
+        std::fs::write(first.join(MANIFEST_FILE_NAME), "{}").unwrap();
+
+        // The next one links the files of the previous one and copies its manifest
+        let second = start_snapshot(&fs, &destination, "2025-11-25T09-00").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(second.join("Match-A").join("round1.rec")).unwrap(),
+            "round 1"
+        );
+        assert!(second.join(MANIFEST_FILE_NAME).exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let linked = std::fs::metadata(second.join("Match-A").join("round1.rec")).unwrap();
+            assert_eq!(linked.nlink(), 2);
+            let copied = std::fs::metadata(second.join(MANIFEST_FILE_NAME)).unwrap();
+            assert_eq!(copied.nlink(), 1);
+        }
+
+        assert_eq!(
+            snapshots(&fs, &destination).unwrap(),
+            [first.clone(), second.clone()]
+        );
+        assert_eq!(latest_snapshot(&fs, &destination), Some(second.clone()));
+        // Started again in the same minute, it is left as it is
+        std::fs::write(second.join("loose.rec"), "loose").unwrap();
+        assert_eq!(
+            start_snapshot(&fs, &destination, "2025-11-25T09-00").unwrap(),
+            second
+        );
+        assert!(second.join("loose.rec").exists());
+
+        let _ = std::fs::remove_dir_all(&destination);
+    }
+}
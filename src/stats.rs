@@ -0,0 +1,136 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::history::BackupHistory;
+use crate::manifest::Manifest;
+use chrono::{DateTime, Days, Local, NaiveDate};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Days shown in the backups-per-day chart of the Stats tab
+pub const STATS_DAYS: u64 = 14;
+
+// Totals shown in the Stats tab. The matches and their size come from the destination's
+// manifest, so they cover every match kept there; backups per day and the last error come
+// from the backup history.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BackupStats {
+    pub matches: usize,
+    pub total_bytes: u64,
+    pub average_match_bytes: u64,
+    // Matches backed up on each of the last days, oldest first, including days without any
+    pub per_day: Vec<(NaiveDate, usize)>,
+    // Time, match name and error of the last backup that failed
+    pub last_error: Option<(i64, String, String)>,
+}
+
+pub fn local_date(timestamp: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp(timestamp, 0).map(|time| time.with_timezone(&Local).date_naive())
+}
+
+// The stats of `days` days up to `today`. A match backed up several times a day, e.g. once
+// per round, counts once for that day.
+pub fn backup_stats(
+    manifest: Option<&Manifest>,
+    history: &BackupHistory,
+    today: NaiveDate,
+    days: u64,
+) -> BackupStats {
+    let (matches, total_bytes) = manifest.map_or((0, 0), |manifest| {
+        (manifest.entries.len(), manifest.total_bytes())
+    });
+    let first_day = today.checked_sub_days(Days::new(days.saturating_sub(1)));
+    let mut backed_up: BTreeMap<NaiveDate, BTreeSet<&str>> = first_day
+        .into_iter()
+        .flat_map(|first| first.iter_days().take_while(|day| *day <= today))
+        .map(|day| (day, BTreeSet::new()))
+        .collect();
+    for entry in history.entries.iter().filter(|entry| entry.error.is_none()) {
+        if let Some(names) = local_date(entry.time).and_then(|day| backed_up.get_mut(&day)) {
+            names.insert(&entry.name);
+        }
+    }
+    let last_error = history
+        .entries
+        .iter()
+        .rev()
+        .find_map(|entry| Some((entry.time, entry.name.clone(), entry.error.clone()?)));
+    BackupStats {
+        matches,
+        total_bytes,
+        average_match_bytes: total_bytes.checked_div(matches as u64).unwrap_or(0),
+        per_day: backed_up
+            .into_iter()
+            .map(|(day, names)| (day, names.len()))
+            .collect(),
+        last_error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HistoryEntry;
+    use crate::manifest::ManifestEntry;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_backup_stats() {
+        let today = NaiveDate::from_ymd_opt(2025, 11, 23).unwrap();
+        let at = |day: u32, hour: u32| {
+            Local
+                .with_ymd_and_hms(2025, 11, day, hour, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+        let entry = |name: &str, time: i64, error: Option<&str>| HistoryEntry {
+            name: name.to_string(),
+            time,
+            files: 1,
+            bytes: 100,
+            duration_ms: 10,
+            error: error.map(String::from),
+        };
+        let history = BackupHistory {
+            entries: vec![
+                // Before the chart
+                entry("Match-Old", at(1, 12), None),
+                entry("Match-A", at(21, 20), None),
+                entry("Match-A", at(21, 21), None),
+                entry("Match-B", at(21, 22), Some("disk full")),
+                entry("Match-B", at(23, 9), None),
+                entry("Match-C", at(23, 10), None),
+            ],
+        };
+        let mut manifest = Manifest::default();
+        for (name, bytes) in [("Match-A", 300), ("Match-B", 100)] {
+            manifest.entries.insert(
+                name.to_string(),
+                ManifestEntry {
+                    bytes,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let stats = backup_stats(Some(&manifest), &history, today, 3);
+        assert_eq!(stats.matches, 2);
+        assert_eq!(stats.total_bytes, 400);
+        assert_eq!(stats.average_match_bytes, 200);
+        assert_eq!(
+            stats.per_day,
+            [
+                (NaiveDate::from_ymd_opt(2025, 11, 21).unwrap(), 1),
+                (NaiveDate::from_ymd_opt(2025, 11, 22).unwrap(), 0),
+                (today, 2),
+            ]
+        );
+        assert_eq!(
+            stats.last_error,
+            Some((at(21, 22), "Match-B".to_string(), "disk full".to_string()))
+        );
+
+        let empty = backup_stats(None, &BackupHistory::default(), today, STATS_DAYS);
+        assert_eq!(empty.average_match_bytes, 0);
+        assert_eq!(empty.per_day.len(), STATS_DAYS as usize);
+        assert_eq!(empty.last_error, None);
+    }
+}
@@ -0,0 +1,479 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::manifest::{FileProblem, VerifyReport};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Structured details about a single backup or error, kept alongside its status line
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventDetail {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub files: Option<u64>,
+    pub bytes: Option<u64>,
+    pub duration: Option<Duration>,
+    pub error: Option<String>,
+}
+
+// How important a status event is, used to color and filter the status log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+// Everything the watcher, the backup engine and the GUI report to the status log
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatusEvent {
+    // Watching started on a source folder
+    Started {
+        source: PathBuf,
+    },
+    Stopped,
+    // A single replay file was copied; `path` is relative to the source folder
+    FileBackedUp {
+        path: PathBuf,
+        detail: EventDetail,
+    },
+    // A match folder was copied or merged into the destination
+    FolderBackedUp {
+        name: String,
+        detail: EventDetail,
+    },
+    // A match folder was packed into the archive `name` in the destination
+    FolderArchived {
+        name: String,
+        detail: EventDetail,
+    },
+    // A match folder is being archived; shown in place instead of being logged
+    Progress {
+        name: String,
+        done: usize,
+        total: usize,
+    },
+    // A large file is being copied; `path` is relative to the source folder. Shown in place
+    // instead of being logged
+    CopyProgress {
+        path: PathBuf,
+        copied: u64,
+        total: u64,
+    },
+    // A backed-up match was copied back into the source folder
+    Restored {
+        name: String,
+        files: usize,
+    },
+    // Matches restored so far; shown in place instead of being logged
+    RestoreProgress {
+        done: usize,
+        total: usize,
+    },
+    // Backing up a file or folder failed; `path` is relative to the source folder
+    CopyError {
+        path: PathBuf,
+        source: String,
+        detail: Option<EventDetail>,
+    },
+    // Summary of backing up pre-existing items, when watching starts or on "Backup Now"
+    InitialSync {
+        backed_up: usize,
+        already_present: usize,
+        skipped: usize,
+    },
+    // An old match was deleted from the destination by the retention policy, or would have
+    // been in a dry run
+    Pruned {
+        name: String,
+        dry_run: bool,
+    },
+    // An item was deleted from the destination to stay under the size limit
+    Evicted {
+        name: String,
+        bytes: u64,
+    },
+    // A backed-up item was uploaded to a remote destination
+    Uploaded {
+        name: String,
+        remote: String,
+        files: u64,
+        bytes: u64,
+    },
+    // A file recorded in the manifest is missing or damaged in the destination; `path` is
+    // relative to the destination folder
+    VerifyProblem {
+        path: String,
+        problem: FileProblem,
+    },
+    // "Verify Backups" finished
+    Verified(VerifyReport),
+    // The destination folder cannot be reached, e.g. because its drive was unplugged.
+    // Backups are held until it is back
+    DestinationOffline {
+        destination: PathBuf,
+    },
+    DestinationOnline {
+        destination: PathBuf,
+    },
+    // The destination's drive came back under another drive letter or mount point
+    DestinationMoved {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    // The file watcher itself reported a problem
+    WatcherError(String),
+    Info(String),
+    Warning(String),
+    Error(String),
+}
+
+impl StatusEvent {
+    pub fn severity(&self) -> Severity {
+        match self {
+            StatusEvent::FileBackedUp { .. }
+            | StatusEvent::FolderBackedUp { .. }
+            | StatusEvent::FolderArchived { .. }
+            | StatusEvent::Restored { .. }
+            | StatusEvent::Uploaded { .. } => Severity::Success,
+            StatusEvent::Verified(report) if report.problems.is_empty() => Severity::Success,
+            StatusEvent::Warning(_)
+            | StatusEvent::Verified(_)
+            | StatusEvent::DestinationOffline { .. } => Severity::Warning,
+            StatusEvent::CopyError { .. }
+            | StatusEvent::VerifyProblem { .. }
+            | StatusEvent::WatcherError(_)
+            | StatusEvent::Error(_) => Severity::Error,
+            StatusEvent::Started { .. }
+            | StatusEvent::Stopped
+            | StatusEvent::InitialSync { .. }
+            | StatusEvent::Progress { .. }
+            | StatusEvent::CopyProgress { .. }
+            | StatusEvent::RestoreProgress { .. }
+            | StatusEvent::Pruned { .. }
+            | StatusEvent::Evicted { .. }
+            | StatusEvent::DestinationOnline { .. }
+            | StatusEvent::DestinationMoved { .. }
+            | StatusEvent::Info(_) => Severity::Info,
+        }
+    }
+
+    // Source, destination, size and timing of the backup this event describes, if any
+    pub fn detail(&self) -> Option<&EventDetail> {
+        match self {
+            StatusEvent::FileBackedUp { detail, .. }
+            | StatusEvent::FolderBackedUp { detail, .. }
+            | StatusEvent::FolderArchived { detail, .. } => Some(detail),
+            StatusEvent::CopyError { detail, .. } => detail.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StatusEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusEvent::Started { source } => write!(f, "Started watching: {}", source.display()),
+            StatusEvent::Stopped => write!(f, "Stopped watching"),
+            StatusEvent::FileBackedUp { path, .. } => {
+                write!(f, "Backed up file: {}", path.display())
+            }
+            StatusEvent::FolderBackedUp { name, .. } => write!(f, "Backed up folder: {}", name),
+            StatusEvent::FolderArchived { name, .. } => {
+                write!(f, "Archived folder: {}", name)
+            }
+            StatusEvent::Progress { name, done, total } => {
+                write!(f, "Archiving {}: {}/{} files", name, done, total)
+            }
+            StatusEvent::CopyProgress {
+                path,
+                copied,
+                total,
+            } => {
+                write!(
+                    f,
+                    "Copying {}: {}/{}",
+                    path.display(),
+                    format_bytes(*copied),
+                    format_bytes(*total)
+                )
+            }
+            StatusEvent::Restored { name, files } => {
+                write!(
+                    f,
+                    "Restored match: {} ({} files)",
+                    name,
+                    format_count(*files)
+                )
+            }
+            StatusEvent::RestoreProgress { done, total } => {
+                write!(f, "Restoring: {}/{} matches", done, total)
+            }
+            StatusEvent::CopyError { path, source, .. } => {
+                write!(f, "Error copying {}: {}", path.display(), source)
+            }
+            StatusEvent::InitialSync {
+                backed_up,
+                already_present,
+                skipped,
+            } => {
+                if *skipped > 0 {
+                    write!(
+                        f,
+                        "Initial sync: skipped {} older items; synced newest {}",
+                        format_count(*skipped),
+                        format_count(*backed_up)
+                    )?;
+                } else {
+                    write!(
+                        f,
+                        "Initial sync: backed up {} existing items",
+                        format_count(*backed_up)
+                    )?;
+                }
+                if *already_present > 0 {
+                    write!(
+                        f,
+                        " ({} already in destination)",
+                        format_count(*already_present)
+                    )?;
+                }
+                Ok(())
+            }
+            StatusEvent::Pruned { name, dry_run } => {
+                if *dry_run {
+                    write!(f, "Would delete old backup: {}", name)
+                } else {
+                    write!(f, "Deleted old backup: {}", name)
+                }
+            }
+            StatusEvent::Evicted { name, bytes } => write!(
+                f,
+                "Deleted {} ({}) to stay under the destination size limit",
+                name,
+                format_bytes(*bytes)
+            ),
+            StatusEvent::Uploaded {
+                name,
+                remote,
+                files,
+                bytes,
+            } => write!(
+                f,
+                "Uploaded {} to {} ({} files, {})",
+                name,
+                remote,
+                format_count(*files as usize),
+                format_bytes(*bytes)
+            ),
+            StatusEvent::VerifyProblem { path, problem } => match problem {
+                FileProblem::Missing => write!(f, "Missing from backup: {}", path),
+                FileProblem::Corrupted => write!(f, "Damaged in backup: {}", path),
+            },
+            StatusEvent::Verified(report) => {
+                write!(f, "Verified {} files: ", format_count(report.checked))?;
+                if report.problems.is_empty() {
+                    f.write_str("all intact")
+                } else {
+                    write!(
+                        f,
+                        "{} missing, {} damaged",
+                        format_count(report.count(FileProblem::Missing)),
+                        format_count(report.count(FileProblem::Corrupted))
+                    )
+                }
+            }
+            StatusEvent::DestinationOffline { destination } => write!(
+                f,
+                "Backup folder is not reachable, holding backups until it is back: {}",
+                destination.display()
+            ),
+            StatusEvent::DestinationOnline { destination } => write!(
+                f,
+                "Backup folder is back, resuming backups: {}",
+                destination.display()
+            ),
+            StatusEvent::DestinationMoved { from, to } => write!(
+                f,
+                "Backup drive found at a new location: {} is now {}",
+                from.display(),
+                to.display()
+            ),
+            StatusEvent::WatcherError(error) => write!(f, "File watch error: {}", error),
+            StatusEvent::Info(text) | StatusEvent::Warning(text) | StatusEvent::Error(text) => {
+                f.write_str(text)
+            }
+        }
+    }
+}
+
+// A timestamped line in the status log, remembering the event it was rendered from
+pub struct StatusLine {
+    pub text: String,
+    pub event: StatusEvent,
+}
+
+impl StatusLine {
+    pub fn new(event: StatusEvent, time: chrono::DateTime<chrono::Local>) -> Self {
+        Self {
+            text: format!("[{}] {}", time.format("%H:%M:%S"), event),
+            event,
+        }
+    }
+
+    // A line for an event from one of several source folders, tagged with the source's label
+    pub fn from_source(
+        event: StatusEvent,
+        time: chrono::DateTime<chrono::Local>,
+        source: &str,
+    ) -> Self {
+        Self {
+            text: format!("[{}] [{}] {}", time.format("%H:%M:%S"), source, event),
+            event,
+        }
+    }
+}
+
+// Formats a count with thousands separators, e.g. 4210 -> "4,210"
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let (head, tail) = digits.split_at(digits.len() % 3);
+    let mut groups: Vec<&str> = Vec::new();
+    if !head.is_empty() {
+        groups.push(head);
+    }
+    groups.extend(
+        tail.as_bytes()
+            .chunks(3)
+            .map(|group| std::str::from_utf8(group).expect("digits are ASCII")),
+    );
+    groups.join(",")
+}
+
+// Estimates how much longer a transfer takes from how long the part done so far took
+pub fn remaining_time(done: u64, total: u64, elapsed: Duration) -> Option<Duration> {
+    if done == 0 || done >= total {
+        return None;
+    }
+    Some(elapsed.mul_f64((total - done) as f64 / done as f64))
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining_time() {
+        let elapsed = Duration::from_secs(10);
+        assert_eq!(remaining_time(0, 100, elapsed), None);
+        assert_eq!(
+            remaining_time(25, 100, elapsed),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(remaining_time(100, 100, elapsed), None);
+    }
+
+    #[test]
+    fn test_status_line_keeps_event_detail() {
+        let detail = EventDetail {
+            source: PathBuf::from("/source/Match-2025-11-23-001/round1.rec"),
+            destination: PathBuf::from("/dest/Match-2025-11-23-001/round1.rec"),
+            files: Some(1),
+            bytes: Some(2048),
+            duration: Some(Duration::from_millis(15)),
+            error: None,
+        };
+        let event = StatusEvent::FileBackedUp {
+            path: PathBuf::from("Match-2025-11-23-001/round1.rec"),
+            detail: detail.clone(),
+        };
+        let time = chrono::Local::now();
+
+        let line = StatusLine::new(event, time);
+        assert_eq!(
+            line.text,
+            format!(
+                "[{}] Backed up file: Match-2025-11-23-001/round1.rec",
+                time.format("%H:%M:%S")
+            )
+        );
+        assert_eq!(line.event.detail(), Some(&detail));
+        assert_eq!(line.event.severity(), Severity::Success);
+
+        let plain = StatusLine::new(StatusEvent::Stopped, time);
+        assert!(plain.event.detail().is_none());
+    }
+
+    #[test]
+    fn test_status_event_severity_and_text() {
+        let error = StatusEvent::CopyError {
+            path: PathBuf::from("Match-2025-11-23-001"),
+            source: "disk full".to_string(),
+            detail: None,
+        };
+        assert_eq!(error.severity(), Severity::Error);
+        assert_eq!(
+            error.to_string(),
+            "Error copying Match-2025-11-23-001: disk full"
+        );
+
+        let sync = StatusEvent::InitialSync {
+            backed_up: 5,
+            already_present: 0,
+            skipped: 1200,
+        };
+        assert_eq!(sync.severity(), Severity::Info);
+        assert_eq!(
+            sync.to_string(),
+            "Initial sync: skipped 1,200 older items; synced newest 5"
+        );
+
+        assert_eq!(
+            StatusEvent::Warning("Warning: stale".to_string()).severity(),
+            Severity::Warning
+        );
+        assert_eq!(
+            StatusEvent::Pruned {
+                name: "Match-2025-11-23-001.zip".to_string(),
+                dry_run: true,
+            }
+            .to_string(),
+            "Would delete old backup: Match-2025-11-23-001.zip"
+        );
+        assert_eq!(
+            StatusEvent::WatcherError("queue overflow".to_string()).to_string(),
+            "File watch error: queue overflow"
+        );
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(4210), "4,210");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 + 512 * 1024), "5.5 MB");
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+// Compression level used for tar.zst archives unless configured otherwise
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+// How match folders are stored in the destination
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageFormat {
+    // Plain folder copies; new rounds are copied file by file
+    #[default]
+    Folder,
+    // One `<folder>.zip` per match
+    Zip,
+    // One `<folder>.tar.zst` per match, compressed with Zstandard
+    TarZst,
+}
+
+impl StorageFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            StorageFormat::Folder => "Folder copy",
+            StorageFormat::Zip => "Zip archive",
+            StorageFormat::TarZst => "tar.zst archive",
+        }
+    }
+
+    // Recognises a match archive by its file name, returning its format and the match folder name
+    pub fn from_archive_name(file_name: &str) -> Option<(Self, &str)> {
+        [StorageFormat::Zip, StorageFormat::TarZst]
+            .into_iter()
+            .find_map(|format| {
+                let extension = format.backend(DEFAULT_ZSTD_LEVEL).archive_extension()?;
+                let stem = file_name.strip_suffix(extension)?.strip_suffix('.')?;
+                (!stem.is_empty()).then_some((format, stem))
+            })
+    }
+
+    pub fn backend(self, zstd_level: i32) -> Box<dyn StorageBackend> {
+        match self {
+            StorageFormat::Folder => Box::new(FolderCopy),
+            StorageFormat::Zip => Box::new(ZipStorage),
+            StorageFormat::TarZst => Box::new(TarZstStorage { level: zstd_level }),
+        }
+    }
+}
+
+// A way of storing backed-up match folders. The copy pipeline is the same for every backend;
+// archive backends only decide how the files of one match are packed into a single file.
+pub trait StorageBackend {
+    // Extension of the single file each match folder is packed into, or None when folders
+    // are copied as they are
+    fn archive_extension(&self) -> Option<&'static str>;
+
+    // Starts a new, empty archive. Only called when `archive_extension` is Some.
+    fn new_archive(&self) -> io::Result<Box<dyn ArchiveBuilder>>;
+
+    // Unpacks an archive built by this backend into `destination`, returning how many files
+    // were written
+    fn extract(&self, data: &[u8], destination: &Path) -> io::Result<usize>;
+}
+
+// Collects the files of one match folder into an in-memory archive
+pub trait ArchiveBuilder {
+    // Adds a file last modified at `modified`; `name` is relative to the match folder and uses
+    // forward slashes
+    fn add_file(&mut self, name: &str, data: &[u8], modified: SystemTime) -> io::Result<()>;
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>>;
+}
+
+pub struct FolderCopy;
+
+impl StorageBackend for FolderCopy {
+    fn archive_extension(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn new_archive(&self) -> io::Result<Box<dyn ArchiveBuilder>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "folder copies are not archived",
+        ))
+    }
+
+    fn extract(&self, _data: &[u8], _destination: &Path) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "folder copies are not archived",
+        ))
+    }
+}
+
+pub struct ZipStorage;
+
+impl StorageBackend for ZipStorage {
+    fn archive_extension(&self) -> Option<&'static str> {
+        Some("zip")
+    }
+
+    fn new_archive(&self) -> io::Result<Box<dyn ArchiveBuilder>> {
+        Ok(Box::new(ZipBuilder {
+            zip: zip::ZipWriter::new(io::Cursor::new(Vec::new())),
+        }))
+    }
+
+    fn extract(&self, data: &[u8], destination: &Path) -> io::Result<usize> {
+        let mut zip = zip::ZipArchive::new(io::Cursor::new(data)).map_err(io::Error::other)?;
+        // `extract` refuses entries that would end up outside `destination`
+        zip.extract(destination).map_err(io::Error::other)?;
+        // It leaves every file dated now, so the recorded times are put back
+        let mut extracted = 0;
+        for i in 0..zip.len() {
+            let entry = zip.by_index_raw(i).map_err(io::Error::other)?;
+            if !entry.is_file() {
+                continue;
+            }
+            extracted += 1;
+            let (Some(name), Some(modified)) = (entry.enclosed_name(), entry.last_modified())
+            else {
+                continue;
+            };
+            let Some(modified) = chrono::NaiveDateTime::try_from(modified)
+                .ok()
+                .and_then(|modified| modified.and_local_timezone(chrono::Local).earliest())
+            else {
+                continue;
+            };
+            std::fs::File::options()
+                .write(true)
+                .open(destination.join(name))?
+                .set_modified(modified.into())?;
+        }
+        Ok(extracted)
+    }
+}
+
+struct ZipBuilder {
+    zip: zip::ZipWriter<io::Cursor<Vec<u8>>>,
+}
+
+impl ArchiveBuilder for ZipBuilder {
+    fn add_file(&mut self, name: &str, data: &[u8], modified: SystemTime) -> io::Result<()> {
+        let mut options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        // Zip stores local times from 1980 to 2107, to two seconds
+        let local = chrono::DateTime::<chrono::Local>::from(modified).naive_local();
+        if let Ok(modified) = zip::DateTime::try_from(local) {
+            options = options.last_modified_time(modified);
+        }
+        self.zip
+            .start_file(name, options)
+            .map_err(io::Error::other)?;
+        self.zip.write_all(data)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        Ok(self.zip.finish().map_err(io::Error::other)?.into_inner())
+    }
+}
+
+pub struct TarZstStorage {
+    // Zstandard level, 1 (fastest) to 19 (smallest)
+    pub level: i32,
+}
+
+impl StorageBackend for TarZstStorage {
+    fn archive_extension(&self) -> Option<&'static str> {
+        Some("tar.zst")
+    }
+
+    fn new_archive(&self) -> io::Result<Box<dyn ArchiveBuilder>> {
+        let encoder = zstd::Encoder::new(Vec::new(), self.level)?;
+        Ok(Box::new(TarZstBuilder {
+            tar: tar::Builder::new(encoder),
+        }))
+    }
+
+    fn extract(&self, data: &[u8], destination: &Path) -> io::Result<usize> {
+        std::fs::create_dir_all(destination)?;
+        let mut archive = tar::Archive::new(zstd::Decoder::new(data)?);
+        let mut extracted = 0;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            // `unpack_in` skips entries that would end up outside `destination`
+            if entry.unpack_in(destination)? && entry.header().entry_type().is_file() {
+                extracted += 1;
+            }
+        }
+        Ok(extracted)
+    }
+}
+
+struct TarZstBuilder {
+    tar: tar::Builder<zstd::Encoder<'static, Vec<u8>>>,
+}
+
+impl ArchiveBuilder for TarZstBuilder {
+    fn add_file(&mut self, name: &str, data: &[u8], modified: SystemTime) -> io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        // Put back by `unpack_in`
+        header.set_mtime(
+            modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+        );
+        self.tar.append_data(&mut header, name, data)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        self.tar.into_inner()?.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    // When the packed replays were last modified: an even second, as zip only stores those
+    fn replay_time() -> SystemTime {
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_764_000_000)
+    }
+
+    fn pack(format: StorageFormat) -> Vec<u8> {
+        let mut archive = format.backend(DEFAULT_ZSTD_LEVEL).new_archive().unwrap();
+        archive
+            .add_file("round1.rec", b"round 1", replay_time())
+            .unwrap();
+        archive
+            .add_file("nested/round2.rec", b"round 2", replay_time())
+            .unwrap();
+        archive.finish().unwrap()
+    }
+
+    #[test]
+    fn test_tar_zst_round_trip() {
+        let data = pack(StorageFormat::TarZst);
+        let tar_data = zstd::decode_all(data.as_slice()).unwrap();
+        let mut archive = tar::Archive::new(tar_data.as_slice());
+        let mut entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().display().to_string();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            entries.push((name, contents));
+        }
+        assert_eq!(
+            entries,
+            [
+                ("round1.rec".to_string(), "round 1".to_string()),
+                ("nested/round2.rec".to_string(), "round 2".to_string()),
+            ]
+        );
+
+        // Higher levels trade speed for size but stay readable
+        let mut strong = TarZstStorage { level: 19 }.new_archive().unwrap();
+        strong
+            .add_file("round1.rec", &[7; 4096], SystemTime::now())
+            .unwrap();
+        let strong = strong.finish().unwrap();
+        assert!(strong.len() < 4096);
+        assert!(zstd::decode_all(strong.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_storage_formats() {
+        assert_eq!(StorageFormat::Folder.backend(3).archive_extension(), None);
+        assert_eq!(
+            StorageFormat::Zip.backend(3).archive_extension(),
+            Some("zip")
+        );
+        assert_eq!(
+            StorageFormat::TarZst.backend(3).archive_extension(),
+            Some("tar.zst")
+        );
+        assert!(StorageFormat::Folder.backend(3).new_archive().is_err());
+
+        let zip = zip::ZipArchive::new(io::Cursor::new(pack(StorageFormat::Zip))).unwrap();
+        assert_eq!(zip.len(), 2);
+
+        assert_eq!(
+            StorageFormat::from_archive_name("Match-A.tar.zst"),
+            Some((StorageFormat::TarZst, "Match-A"))
+        );
+        assert_eq!(
+            StorageFormat::from_archive_name("Match-A.zip"),
+            Some((StorageFormat::Zip, "Match-A"))
+        );
+        assert_eq!(StorageFormat::from_archive_name("round1.rec"), None);
+        assert_eq!(StorageFormat::from_archive_name(".zip"), None);
+    }
+
+    #[test]
+    fn test_extract_archives() {
+        let test_dir = std::env::temp_dir().join("siegesaver_extract_test");
+        let _ = std::fs::remove_dir_all(&test_dir);
+
+        for format in [StorageFormat::Zip, StorageFormat::TarZst] {
+            let destination = test_dir.join(format.label());
+            let extracted = format
+                .backend(DEFAULT_ZSTD_LEVEL)
+                .extract(&pack(format), &destination)
+                .unwrap();
+            assert_eq!(extracted, 2);
+            let round2 = destination.join("nested/round2.rec");
+            assert_eq!(std::fs::read_to_string(&round2).unwrap(), "round 2");
+            // Dated like the replays, so they sort the same way as in the source
+            for file in [destination.join("round1.rec"), round2] {
+                assert_eq!(
+                    std::fs::metadata(file).unwrap().modified().unwrap(),
+                    replay_time(),
+                    "{}",
+                    format.label()
+                );
+            }
+        }
+
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+}
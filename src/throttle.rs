@@ -0,0 +1,318 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{FileMetadata, FileSystem};
+use std::cell::Cell;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How much disk I/O backups may use, so copying mid-match does not make the game stutter
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoLimits {
+    pub bytes_per_sec: Option<u64>,
+    // Filesystem operations of any kind, including listing folders and reading metadata
+    pub ops_per_sec: Option<u32>,
+    // Lowers the I/O priority of the copying threads on Windows; ignored elsewhere
+    pub background_priority: bool,
+}
+
+impl IoLimits {
+    // How long an operation moving `bytes` uses up the budget
+    pub fn cost(&self, bytes: u64) -> Duration {
+        let transfer = self
+            .bytes_per_sec
+            .filter(|&limit| limit > 0)
+            .map(|limit| Duration::from_secs_f64(bytes as f64 / limit as f64))
+            .unwrap_or_default();
+        let operation = self
+            .ops_per_sec
+            .filter(|&limit| limit > 0)
+            .map(|limit| Duration::from_secs_f64(1.0 / f64::from(limit)))
+            .unwrap_or_default();
+        transfer.max(operation)
+    }
+}
+
+#[derive(Default)]
+struct ThrottleState {
+    limits: IoLimits,
+    // When the budget used so far is paid off
+    ready_at: Option<Instant>,
+}
+
+// A budget shared by every engine writing to the same disks. Operations are not cut into
+// pieces; after a large copy the next operation waits until the copy is paid for, so the
+// average rate stays below the limits.
+#[derive(Default)]
+pub struct Throttle {
+    state: Mutex<ThrottleState>,
+}
+
+impl Throttle {
+    pub fn new(limits: IoLimits) -> Self {
+        Self {
+            state: Mutex::new(ThrottleState {
+                limits,
+                ready_at: None,
+            }),
+        }
+    }
+
+    pub fn limits(&self) -> IoLimits {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).limits
+    }
+
+    pub fn set_limits(&self, limits: IoLimits) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.limits != limits {
+            state.limits = limits;
+            state.ready_at = None;
+        }
+    }
+
+    // Sleeps until the budget allows another operation
+    fn wait(&self) {
+        let ready_at = self
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .ready_at;
+        if let Some(wait) =
+            ready_at.and_then(|ready_at| ready_at.checked_duration_since(Instant::now()))
+        {
+            std::thread::sleep(wait);
+        }
+    }
+
+    // Charges an operation that started at `started` and moved `bytes`
+    fn record(&self, started: Instant, bytes: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let cost = state.limits.cost(bytes);
+        if cost.is_zero() {
+            return;
+        }
+        let start = state
+            .ready_at
+            .map_or(started, |ready_at| ready_at.max(started));
+        state.ready_at = Some(start + cost);
+    }
+}
+
+thread_local! {
+    // Whether the calling thread is in background mode. Entering it twice fails, as does
+    // leaving it when not in it.
+    static IN_BACKGROUND: Cell<bool> = const { Cell::new(false) };
+}
+
+// Lowers the I/O and CPU priority of the calling thread while `background` is set, and
+// restores it once it is not
+fn set_background_io(background: bool) {
+    IN_BACKGROUND.with(|in_background| {
+        if in_background.replace(background) != background {
+            set_thread_background_mode(background);
+        }
+    });
+}
+
+#[cfg(windows)]
+fn set_thread_background_mode(background: bool) {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+        THREAD_MODE_BACKGROUND_END,
+    };
+
+    let mode = if background {
+        THREAD_MODE_BACKGROUND_BEGIN
+    } else {
+        THREAD_MODE_BACKGROUND_END
+    };
+    // SAFETY: GetCurrentThread returns a pseudo handle that is always valid
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), mode);
+    }
+}
+
+#[cfg(not(windows))]
+fn set_thread_background_mode(_background: bool) {}
+
+// Runs every operation of `inner` through a throttle
+pub struct ThrottledFs {
+    inner: Arc<dyn FileSystem + Send + Sync>,
+    throttle: Arc<Throttle>,
+}
+
+impl ThrottledFs {
+    pub fn new(inner: Arc<dyn FileSystem + Send + Sync>, throttle: Arc<Throttle>) -> Self {
+        Self { inner, throttle }
+    }
+
+    fn run<T>(
+        &self,
+        operation: impl FnOnce(&dyn FileSystem) -> io::Result<T>,
+        bytes: impl FnOnce(&T) -> u64,
+    ) -> io::Result<T> {
+        set_background_io(self.throttle.limits().background_priority);
+        self.throttle.wait();
+        let started = Instant::now();
+        let result = operation(self.inner.as_ref());
+        self.throttle
+            .record(started, result.as_ref().map_or(0, bytes));
+        result
+    }
+}
+
+impl FileSystem for ThrottledFs {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.run(|fs| fs.read_dir(path), |_| 0)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        self.run(|fs| fs.metadata(path), |_| 0)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.run(|fs| fs.create_dir_all(path), |_| 0)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        // Read once and written once
+        self.run(|fs| fs.copy(from, to), |&bytes| bytes * 2)
+    }
+
+    fn copy_with_progress(
+        &self,
+        from: &Path,
+        to: &Path,
+        progress: &dyn Fn(u64, u64) -> io::Result<()>,
+    ) -> io::Result<u64> {
+        // Large files are paid for chunk by chunk, so they are slowed down as well
+        let charged = Cell::new(0);
+        let chunk_done = |copied: u64, total: u64| {
+            let chunk = copied.saturating_sub(charged.replace(copied));
+            self.throttle.record(Instant::now(), chunk * 2);
+            self.throttle.wait();
+            progress(copied, total)
+        };
+        self.run(
+            |fs| fs.copy_with_progress(from, to, &chunk_done),
+            |&bytes| bytes.saturating_sub(charged.get()) * 2,
+        )
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.run(|fs| fs.read(path), |data| data.len() as u64)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.run(|fs| fs.write(path, contents), |_| contents.len() as u64)
+    }
+
+    fn sync(&self, path: &Path) -> io::Result<()> {
+        self.run(|fs| fs.sync(path), |_| 0)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.run(|fs| fs.remove(path), |_| 0)
+    }
+
+    fn open_for_read(&self, path: &Path) -> io::Result<()> {
+        self.run(|fs| fs.open_for_read(path), |_| 0)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run(|fs| fs.rename(from, to), |_| 0)
+    }
+
+    fn recycle(&self, path: &Path) -> io::Result<()> {
+        self.run(|fs| fs.recycle(path), |_| 0)
+    }
+
+    fn hard_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run(|fs| fs.hard_link(from, to), |_| 0)
+    }
+
+    fn reflink(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run(|fs| fs.reflink(from, to), |_| 0)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.run(|fs| fs.canonicalize(path), |_| 0)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.run(|fs| fs.read_link(path), |_| 0)
+    }
+
+    fn create_symlink(&self, target: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+        self.run(|fs| fs.create_symlink(target, link, is_dir), |_| 0)
+    }
+
+    fn copy_times(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run(|fs| fs.copy_times(from, to), |_| 0)
+    }
+
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        self.run(|fs| fs.available_space(path), |_| 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use std::fs;
+
+    #[test]
+    fn test_io_limits_cost() {
+        let unlimited = IoLimits::default();
+        assert_eq!(unlimited.cost(1_000_000), Duration::ZERO);
+
+        let limits = IoLimits {
+            bytes_per_sec: Some(1_000),
+            ops_per_sec: Some(10),
+            background_priority: false,
+        };
+        // Small operations are bound by the operation limit, large ones by the byte limit
+        assert_eq!(limits.cost(0), Duration::from_millis(100));
+        assert_eq!(limits.cost(500), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_throttled_fs_paces_operations() {
+        let test_dir = std::env::temp_dir().join("siegesaver_throttle_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let throttle = Arc::new(Throttle::new(IoLimits {
+            bytes_per_sec: Some(1_000),
+            ..Default::default()
+        }));
+        let fs = ThrottledFs::new(Arc::new(RealFs), Arc::clone(&throttle));
+        let started = Instant::now();
+        for name in ["a", "b", "c"] {
+            fs.write(&test_dir.join(name), &[0; 100]).unwrap();
+        }
+        // The first two writes are paid for before the third one may start
+        assert!(started.elapsed() >= Duration::from_millis(180));
+        assert_eq!(fs.read(&test_dir.join("c")).unwrap().len(), 100);
+
+        throttle.set_limits(IoLimits {
+            background_priority: true,
+            ..Default::default()
+        });
+        fs.write(&test_dir.join("a"), &[0; 100]).unwrap();
+        assert!(IN_BACKGROUND.get());
+
+        // Lifting the limits takes effect right away, on threads that already copied too
+        throttle.set_limits(IoLimits::default());
+        let started = Instant::now();
+        for _ in 0..20 {
+            fs.write(&test_dir.join("a"), &[0; 100]).unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(!IN_BACKGROUND.get());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
@@ -0,0 +1,569 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::i18n::{tr, tr_fmt};
+use crate::status::{format_bytes, format_count};
+use crate::watcher::format_countdown;
+use eframe::egui;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tray_icon::menu::{CheckMenuItem, Menu, MenuId, MenuItem, Submenu};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+pub fn load_icon_for_window() -> std::sync::Arc<egui::IconData> {
+    match load_icon_data_from_file() {
+        Ok(icon_data) => std::sync::Arc::new(icon_data),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load icon.ico for window: {}. Using fallback icon.",
+                e
+            );
+            std::sync::Arc::new(create_fallback_icon_data())
+        }
+    }
+}
+
+fn load_icon_bytes_from_file() -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    // Load the icon.ico file
+    let icon_path = std::env::current_exe()?
+        .parent()
+        .ok_or("Failed to get parent directory")?
+        .join("icon.ico");
+
+    // Try current exe directory first, then try current working directory
+    if icon_path.exists() {
+        Ok(std::fs::read(&icon_path)?)
+    } else {
+        Ok(std::fs::read("icon.ico")?)
+    }
+}
+
+fn load_icon_data_from_file() -> Result<egui::IconData, Box<dyn std::error::Error>> {
+    let icon_data = load_icon_bytes_from_file()?;
+
+    // Load the ICO file using the image crate
+    let img = image::load_from_memory(&icon_data)?;
+
+    // Convert to RGBA8
+    let rgba_image = img.to_rgba8();
+    let (width, height) = rgba_image.dimensions();
+
+    Ok(egui::IconData {
+        rgba: rgba_image.into_raw(),
+        width,
+        height,
+    })
+}
+
+fn generate_fallback_rgba() -> Vec<u8> {
+    // Create a simple 16x16 icon (blue/white pattern)
+    // RGBA format: each pixel is 4 bytes (R, G, B, A)
+    let mut rgba = Vec::with_capacity(16 * 16 * 4);
+
+    for y in 0..16 {
+        for x in 0..16 {
+            // Create a simple pattern - blue circle on transparent background
+            let dx = x as f32 - 7.5;
+            let dy = y as f32 - 7.5;
+            let dist = (dx * dx + dy * dy).sqrt();
+
+            if dist < 6.0 {
+                // Blue color inside the circle
+                rgba.extend_from_slice(&[33, 150, 243, 255]); // Blue (#2196F3)
+            } else {
+                // Transparent outside
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+
+    rgba
+}
+
+fn create_fallback_icon_data() -> egui::IconData {
+    egui::IconData {
+        rgba: generate_fallback_rgba(),
+        width: 16,
+        height: 16,
+    }
+}
+
+// What the tray icon shows: the same circle as the fallback icon, or a badge on icon.ico
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayStatus {
+    Idle,
+    Watching,
+    // A backup is being copied; animated with a spinning gap
+    Copying,
+    // The last backup failed
+    Error,
+}
+
+impl TrayStatus {
+    fn color(self) -> [u8; 4] {
+        match self {
+            TrayStatus::Idle => [158, 158, 158, 255],
+            TrayStatus::Watching => [76, 175, 80, 255],
+            TrayStatus::Copying => [255, 152, 0, 255],
+            TrayStatus::Error => [244, 67, 54, 255],
+        }
+    }
+}
+
+// Frames of the copying animation, and how long each one is shown
+pub const COPYING_FRAMES: usize = 8;
+pub const ANIMATION_INTERVAL: Duration = Duration::from_millis(150);
+
+// Paints a filled circle into an RGBA image. With `gap`, the eighth of the circle starting
+// at that frame's angle is left out, so cycling through the frames makes it spin.
+fn draw_disc(
+    rgba: &mut [u8],
+    width: u32,
+    center: (f32, f32),
+    radius: f32,
+    color: [u8; 4],
+    gap: Option<usize>,
+) {
+    for (index, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+        let x = (index as u32 % width) as f32 + 0.5 - center.0;
+        let y = (index as u32 / width) as f32 + 0.5 - center.1;
+        if (x * x + y * y).sqrt() >= radius {
+            continue;
+        }
+        if let Some(frame) = gap {
+            let turn = (y.atan2(x) / std::f32::consts::TAU).rem_euclid(1.0);
+            if (turn * COPYING_FRAMES as f32) as usize == frame % COPYING_FRAMES {
+                continue;
+            }
+        }
+        pixel.copy_from_slice(&color);
+    }
+}
+
+// The tray icon for a status: a colored circle, or icon.ico with a colored badge in its
+// bottom right corner
+fn status_icon_data(
+    base: Option<&egui::IconData>,
+    status: TrayStatus,
+    frame: usize,
+) -> egui::IconData {
+    let gap = (status == TrayStatus::Copying).then_some(frame);
+    match base {
+        Some(base) => {
+            let mut icon = base.clone();
+            let size = icon.width.min(icon.height) as f32;
+            let radius = size * 0.22;
+            let center = (
+                icon.width as f32 - radius - 1.0,
+                icon.height as f32 - radius - 1.0,
+            );
+            // A white ring keeps the badge visible on dark and colored icons
+            draw_disc(
+                &mut icon.rgba,
+                icon.width,
+                center,
+                radius + size / 16.0,
+                [255; 4],
+                None,
+            );
+            draw_disc(
+                &mut icon.rgba,
+                icon.width,
+                center,
+                radius,
+                status.color(),
+                gap,
+            );
+            icon
+        }
+        None => {
+            let mut rgba = vec![0; 16 * 16 * 4];
+            draw_disc(&mut rgba, 16, (8.0, 8.0), 6.5, status.color(), gap);
+            egui::IconData {
+                rgba,
+                width: 16,
+                height: 16,
+            }
+        }
+    }
+}
+
+// The macOS menu bar icon for a status, as a template image: only its shape counts and the
+// menu bar draws it in its own color, in light and dark mode. Idle is a ring, watching a
+// disc. Errors keep the red badge instead, which is not a template.
+fn menu_bar_icon_data(status: TrayStatus, frame: usize) -> (egui::IconData, bool) {
+    if status == TrayStatus::Error {
+        return (status_icon_data(None, status, frame), false);
+    }
+    let mut rgba = vec![0; 32 * 32 * 4];
+    let gap = (status == TrayStatus::Copying).then_some(frame);
+    draw_disc(&mut rgba, 32, (16.0, 16.0), 13.0, [0, 0, 0, 255], gap);
+    if status == TrayStatus::Idle {
+        draw_disc(&mut rgba, 32, (16.0, 16.0), 9.5, [0; 4], None);
+    }
+    let icon = egui::IconData {
+        rgba,
+        width: 32,
+        height: 32,
+    };
+    (icon, true)
+}
+
+// The tray icon for a status, and whether it is a template image
+fn status_icon(base: Option<&egui::IconData>, status: TrayStatus, frame: usize) -> (Icon, bool) {
+    let (data, template) = if cfg!(target_os = "macos") {
+        menu_bar_icon_data(status, frame)
+    } else {
+        (status_icon_data(base, status, frame), false)
+    };
+    let icon =
+        Icon::from_rgba(data.rgba, data.width, data.height).expect("Failed to create status icon");
+    (icon, template)
+}
+
+// Number of backups being copied right now. The backup engines count themselves in while
+// they copy, and the tray icon animates while the count is above zero.
+#[derive(Clone, Debug, Default)]
+pub struct CopyActivity(Arc<AtomicUsize>);
+
+impl CopyActivity {
+    // Counts a copy as running until the returned guard is dropped
+    pub fn start(&self) -> CopyGuard {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        CopyGuard(Arc::clone(&self.0))
+    }
+
+    pub fn is_copying(&self) -> bool {
+        self.0.load(Ordering::Relaxed) > 0
+    }
+}
+
+pub struct CopyGuard(Arc<AtomicUsize>);
+
+impl Drop for CopyGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// The tray tooltip, e.g. "Watching · last backup 14:32 · 87 matches / 12.4 GB" below the app
+// name. `destination` is the number of matches and bytes in the destination, if known.
+pub fn tray_tooltip(
+    watching: bool,
+    paused: Option<Duration>,
+    last_backup: Option<chrono::DateTime<chrono::Local>>,
+    destination: Option<(usize, u64)>,
+    now: chrono::DateTime<chrono::Local>,
+) -> String {
+    let mut parts = vec![match paused {
+        Some(remaining) => tr_fmt("Paused ({} left)", &[&format_countdown(remaining)]),
+        None if watching => tr("Watching").to_string(),
+        None => tr("Not watching").to_string(),
+    }];
+    if let Some(time) = last_backup {
+        let format = if time.date_naive() == now.date_naive() {
+            "%H:%M"
+        } else {
+            "%Y-%m-%d %H:%M"
+        };
+        parts.push(tr_fmt("last backup {}", &[&time.format(format)]));
+    }
+    if let Some((matches, bytes)) = destination {
+        parts.push(format!(
+            "{} {} / {}",
+            format_count(matches),
+            if matches == 1 {
+                tr("match")
+            } else {
+                tr("matches")
+            },
+            format_bytes(bytes)
+        ));
+    }
+    format!("SiegeSaver\n{}", parts.join(" · "))
+}
+
+// Shows a folder in the system file manager without waiting for it
+pub fn open_folder(folder: &Path) -> std::io::Result<()> {
+    if !folder.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} is not a folder", folder.display()),
+        ));
+    }
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program).arg(folder).spawn().map(|_| ())
+}
+
+// The tray icon together with the ids of its menu items
+pub struct AppTray {
+    icon: TrayIcon,
+    // icon.ico, if it could be loaded; status badges are drawn onto it
+    base_icon: Option<egui::IconData>,
+    // Status and animation frame the icon currently shows
+    shown: (TrayStatus, usize),
+    tooltip: String,
+    // Starts or stops watching; its label shows which one a click does
+    watch_item: MenuItem,
+    watching: bool,
+    // The other items with their English text, to translate them again
+    items: Vec<(MenuItem, &'static str)>,
+    pub(crate) watch_item_id: MenuId,
+    pub(crate) open_source_item_id: MenuId,
+    pub(crate) open_destination_item_id: MenuId,
+    pub(crate) quit_item_id: MenuId,
+    pub(crate) pause_item_id: MenuId,
+    pub(crate) resume_item_id: MenuId,
+    pub(crate) verify_item_id: MenuId,
+    profile_menu: Submenu,
+    // One checkable item per profile, with the profile's name
+    profile_items: Vec<(CheckMenuItem, String)>,
+}
+
+impl AppTray {
+    // Builds the tray icon and its menu. Must be called on the main thread.
+    pub fn create() -> Self {
+        let menu = Menu::new();
+        let watch_item = MenuItem::new(tr("Start Watching"), true, None);
+        let pause_item = MenuItem::new(tr("Pause for 1 hour"), true, None);
+        let resume_item = MenuItem::new(tr("Resume now"), true, None);
+        let verify_item = MenuItem::new(tr("Verify backups"), true, None);
+        let open_source_item = MenuItem::new(tr("Open replay folder"), true, None);
+        let open_destination_item = MenuItem::new(tr("Open backup folder"), true, None);
+        let profile_menu = Submenu::new(tr("Profile"), true);
+        let quit_item = MenuItem::new(tr("Quit"), true, None);
+        menu.append(&watch_item)
+            .expect("Failed to append watch item to menu");
+        menu.append(&pause_item)
+            .expect("Failed to append pause item to menu");
+        menu.append(&resume_item)
+            .expect("Failed to append resume item to menu");
+        menu.append(&verify_item)
+            .expect("Failed to append verify item to menu");
+        menu.append(&open_source_item)
+            .expect("Failed to append open replay folder item to menu");
+        menu.append(&open_destination_item)
+            .expect("Failed to append open backup folder item to menu");
+        menu.append(&profile_menu)
+            .expect("Failed to append profile menu to menu");
+        menu.append(&quit_item)
+            .expect("Failed to append quit item to menu");
+
+        let base_icon = match load_icon_data_from_file() {
+            Ok(icon) => Some(icon),
+            Err(e) => {
+                tracing::warn!("Failed to load icon.ico: {}. Using fallback icon.", e);
+                None
+            }
+        };
+        let (idle_icon, template) = status_icon(base_icon.as_ref(), TrayStatus::Idle, 0);
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("SiegeSaver")
+            .with_icon(idle_icon)
+            .with_icon_as_template(template)
+            .build()
+            .expect("Failed to create tray icon");
+
+        Self {
+            icon,
+            base_icon,
+            shown: (TrayStatus::Idle, 0),
+            tooltip: "SiegeSaver".to_string(),
+            watch_item_id: watch_item.id().clone(),
+            watch_item,
+            watching: false,
+            quit_item_id: quit_item.id().clone(),
+            pause_item_id: pause_item.id().clone(),
+            resume_item_id: resume_item.id().clone(),
+            verify_item_id: verify_item.id().clone(),
+            open_source_item_id: open_source_item.id().clone(),
+            open_destination_item_id: open_destination_item.id().clone(),
+            items: vec![
+                (pause_item, "Pause for 1 hour"),
+                (resume_item, "Resume now"),
+                (verify_item, "Verify backups"),
+                (open_source_item, "Open replay folder"),
+                (open_destination_item, "Open backup folder"),
+                (quit_item, "Quit"),
+            ],
+            profile_menu,
+            profile_items: Vec::new(),
+        }
+    }
+
+    // Rebuilds the profile submenu, checking the active profile
+    pub(crate) fn set_profiles(&mut self, names: &[String], active: &str) {
+        for (item, _) in self.profile_items.drain(..) {
+            let _ = self.profile_menu.remove(&item);
+        }
+        for name in names {
+            let item = CheckMenuItem::new(name, true, name == active, None);
+            if self.profile_menu.append(&item).is_ok() {
+                self.profile_items.push((item, name.clone()));
+            }
+        }
+    }
+
+    // The profile a clicked menu item switches to
+    pub(crate) fn profile_for(&self, id: &MenuId) -> Option<&str> {
+        self.profile_items
+            .iter()
+            .find(|(item, _)| item.id() == id)
+            .map(|(_, name)| name.as_str())
+    }
+
+    pub(crate) fn set_watching(&mut self, watching: bool) {
+        if self.watching != watching {
+            self.watching = watching;
+            self.set_watch_text();
+        }
+    }
+
+    fn set_watch_text(&self) {
+        self.watch_item.set_text(tr(if self.watching {
+            "Stop Watching"
+        } else {
+            "Start Watching"
+        }));
+    }
+
+    // Shows the menu in the language that was just chosen
+    pub(crate) fn retranslate(&self) {
+        self.set_watch_text();
+        for (item, text) in &self.items {
+            item.set_text(tr(text));
+        }
+        self.profile_menu.set_text(tr("Profile"));
+    }
+
+    // Redraws the icon if the status or, while copying, the animation frame changed
+    pub(crate) fn set_status(&mut self, status: TrayStatus, frame: usize) {
+        let frame = if status == TrayStatus::Copying {
+            frame % COPYING_FRAMES
+        } else {
+            0
+        };
+        if self.shown != (status, frame) {
+            let (icon, template) = status_icon(self.base_icon.as_ref(), status, frame);
+            if cfg!(target_os = "macos") {
+                let _ = self.icon.set_icon_with_as_template(Some(icon), template);
+            } else {
+                let _ = self.icon.set_icon(Some(icon));
+            }
+            self.shown = (status, frame);
+        }
+    }
+
+    pub(crate) fn set_tooltip(&mut self, tooltip: String) {
+        if self.tooltip != tooltip {
+            let _ = self.icon.set_tooltip(Some(&tooltip));
+            self.tooltip = tooltip;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(icon: &egui::IconData, x: u32, y: u32) -> &[u8] {
+        let index = ((y * icon.width + x) * 4) as usize;
+        &icon.rgba[index..index + 4]
+    }
+
+    #[test]
+    fn test_status_icons() {
+        let idle = status_icon_data(None, TrayStatus::Idle, 0);
+        assert_eq!(pixel(&idle, 8, 8), TrayStatus::Idle.color());
+        assert_eq!(pixel(&idle, 0, 0), [0; 4]);
+        let error = status_icon_data(None, TrayStatus::Error, 3);
+        assert_eq!(pixel(&error, 8, 8), TrayStatus::Error.color());
+
+        // The gap of the copying animation moves around the circle
+        let frames: Vec<_> = (0..COPYING_FRAMES)
+            .map(|frame| status_icon_data(None, TrayStatus::Copying, frame).rgba)
+            .collect();
+        assert_ne!(frames[0], frames[1]);
+        assert_eq!(
+            frames[0],
+            status_icon_data(None, TrayStatus::Copying, COPYING_FRAMES).rgba
+        );
+
+        // A loaded icon keeps its pixels outside the badge
+        let base = egui::IconData {
+            rgba: vec![10; 32 * 32 * 4],
+            width: 32,
+            height: 32,
+        };
+        let watching = status_icon_data(Some(&base), TrayStatus::Watching, 0);
+        assert_eq!(pixel(&watching, 2, 2), [10; 4]);
+        assert_eq!(
+            pixel(&watching, 31 - 8, 31 - 8),
+            TrayStatus::Watching.color()
+        );
+    }
+
+    #[test]
+    fn test_menu_bar_icons() {
+        let (idle, template) = menu_bar_icon_data(TrayStatus::Idle, 0);
+        assert!(template);
+        assert_eq!(pixel(&idle, 16, 16), [0; 4]);
+        assert_eq!(pixel(&idle, 16, 4), [0, 0, 0, 255]);
+        let (watching, _) = menu_bar_icon_data(TrayStatus::Watching, 0);
+        assert_eq!(pixel(&watching, 16, 16), [0, 0, 0, 255]);
+        // Only the alpha channel is drawn in template images
+        assert!(watching
+            .rgba
+            .chunks_exact(4)
+            .all(|pixel| pixel[..3] == [0; 3]));
+
+        let (error, template) = menu_bar_icon_data(TrayStatus::Error, 0);
+        assert!(!template);
+        assert_eq!(pixel(&error, 8, 8), TrayStatus::Error.color());
+    }
+
+    #[test]
+    fn test_tray_tooltip() {
+        let now = chrono::Local::now();
+        assert_eq!(
+            tray_tooltip(true, None, Some(now), Some((87, 12_400 * 1024 * 1024)), now),
+            format!(
+                "SiegeSaver\nWatching · last backup {} · 87 matches / 12.1 GB",
+                now.format("%H:%M")
+            )
+        );
+        let yesterday = now - chrono::Duration::days(1);
+        assert_eq!(
+            tray_tooltip(false, None, Some(yesterday), Some((1, 10)), now),
+            format!(
+                "SiegeSaver\nNot watching · last backup {} · 1 match / 10 B",
+                yesterday.format("%Y-%m-%d %H:%M")
+            )
+        );
+        assert!(
+            tray_tooltip(true, Some(Duration::from_secs(90)), None, None, now)
+                .starts_with("SiegeSaver\nPaused (")
+        );
+    }
+
+    #[test]
+    fn test_copy_activity() {
+        let activity = CopyActivity::default();
+        assert!(!activity.is_copying());
+        let first = activity.start();
+        let second = activity.clone().start();
+        drop(first);
+        assert!(activity.is_copying());
+        drop(second);
+        assert!(!activity.is_copying());
+    }
+}
@@ -0,0 +1,5580 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::analytics::{library_analytics, DayKd};
+use crate::api::{ApiCommand, ApiServer, ApiStatus, DEFAULT_API_PORT};
+use crate::autostart::{set_autostart, AutostartMode};
+use crate::backup::{
+    parse_exclude_dirs, parse_patterns, source_destinations, source_labels, BackupEngine,
+    BackupOptions, BackupStats, ConflictPolicy, CopyMode, FileFilter, RealFs, SymlinkMode,
+    MAX_COPY_THREADS,
+};
+use crate::build_info;
+use crate::bundle::SettingsBundle;
+use crate::config::{check_folder, normalize_path_input, AppConfig, FolderCheck, Profile};
+use crate::crypto::{decrypt_directory, Decryptor, EncryptionKey};
+use crate::diagnostics::diagnostics_report;
+use crate::discord::DiscordNotifier;
+use crate::dissect::summarize;
+use crate::gdrive::{finish_authorization, start_authorization, GoogleDriveConfig};
+use crate::history::{BackupHistory, HistoryColumn, HistoryEntry, HISTORY_FILE_NAME};
+use crate::i18n::{set_language, tr, tr_fmt, Language};
+use crate::instance::PrimaryInstance;
+use crate::journal::{BackupJournal, JOURNAL_FILE_NAME};
+use crate::library::{
+    backup_date, export_library, filter_library, parse_date, parse_tags, ExportFormat,
+    LibraryFilter,
+};
+use crate::logging::{
+    log_event, log_folder, read_log, set_log_level, LogFilter, LogLevel, LogRecord, LOG_VIEW_LINES,
+};
+use crate::manifest::{Manifest, ManifestEntry, VerifyReport};
+use crate::metrics::{render_prometheus_metrics, write_prometheus_textfile, METRICS_INTERVAL};
+use crate::mirror::{
+    restore_from_trash, trashed_backups, DeletionQueue, TrashedBackup, TRASH_KEEP_DAYS,
+};
+use crate::notifications::DesktopNotifications;
+use crate::presence::RichPresence;
+use crate::queue::BackupQueue;
+use crate::quota::gigabytes_to_bytes;
+use crate::rclone::RcloneConfig;
+use crate::remote::{retry_uploads, Remote, RemoteConfig, UploadQueue, UPLOAD_QUEUE_FILE_NAME};
+use crate::restore::{restore_candidates, restore_match, RestoreCandidate};
+use crate::retention::RetentionPolicy;
+use crate::s3::S3Config;
+use crate::selftest::{run_self_test, SelfTest, SELF_TEST_TIMEOUT, TEST_MATCH_PREFIX};
+use crate::service::{
+    service_state, start_service, stop_service, ServiceState, SERVICE_CHECK_INTERVAL,
+};
+use crate::share::{export_matches, import_matches};
+use crate::snapshot::{latest_snapshot, snapshot_name, snapshots};
+use crate::stats::{backup_stats, STATS_DAYS};
+use crate::status::{format_bytes, remaining_time, EventDetail, Severity, StatusEvent};
+use crate::storage::StorageFormat;
+use crate::throttle::{IoLimits, Throttle};
+use crate::tray::{
+    open_folder, tray_tooltip, AppTray, CopyActivity, TrayStatus, ANIMATION_INTERVAL,
+};
+use crate::watcher::{
+    daily_run_due, format_countdown, handle_mirrored_file_events, in_quiet_hours, is_backup_stale,
+    newest_source_change, parse_time_of_day, until_daily_run, watch_config_file, watch_folder,
+    ConfigWatcher, FolderWatcher, PauseSchedule, RescanSchedule, PAUSE_DURATION,
+    STALE_CHECK_INTERVAL,
+};
+use crate::webdav::{WebDavAuth, WebDavConfig};
+use crate::webhook::WebhookNotifier;
+use chrono::Timelike;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tray_icon::menu::MenuEvent;
+use tray_icon::{MouseButton, TrayIconEvent};
+
+// Colors of the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    // Follows the dark mode setting of the system, also when it changes while running
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::System => "Same as system",
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+
+    fn apply(self, ctx: &egui::Context) {
+        let preference = match self {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        };
+        if ctx.options(|options| options.theme_preference) != preference {
+            ctx.set_theme(preference);
+        }
+    }
+}
+
+// Size and position of the window when it was last closed, so it opens the same way again.
+// In points; the position is left out where the system does not tell it, e.g. on Wayland.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub width: f32,
+    pub height: f32,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub maximized: bool,
+}
+
+// Size of the window the first time, and the smallest size it is restored to
+const DEFAULT_WINDOW_SIZE: egui::Vec2 = egui::vec2(600.0, 400.0);
+const MIN_WINDOW_SIZE: egui::Vec2 = egui::vec2(320.0, 240.0);
+
+impl WindowGeometry {
+    // The window as it is now. While maximized, the size it had before is kept from
+    // `previous`, so it is restored to that when it is no longer maximized.
+    fn current(ctx: &egui::Context, previous: Option<WindowGeometry>) -> Option<Self> {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.minimized == Some(true) {
+                return previous;
+            }
+            let maximized = viewport.maximized == Some(true);
+            if maximized {
+                if let Some(previous) = previous {
+                    return Some(Self {
+                        maximized,
+                        ..previous
+                    });
+                }
+            }
+            let size = viewport.inner_rect?.size();
+            let position = viewport.outer_rect.map(|rect| rect.min);
+            Some(Self {
+                width: size.x,
+                height: size.y,
+                x: position.map(|position| position.x),
+                y: position.map(|position| position.y),
+                maximized,
+            })
+        })
+    }
+}
+
+// The window as it was last closed, or at its default size
+pub fn window_viewport(
+    builder: egui::ViewportBuilder,
+    geometry: Option<WindowGeometry>,
+) -> egui::ViewportBuilder {
+    let Some(geometry) = geometry else {
+        return builder.with_inner_size(DEFAULT_WINDOW_SIZE);
+    };
+    let size = egui::vec2(geometry.width, geometry.height).max(MIN_WINDOW_SIZE);
+    let mut builder = builder
+        .with_inner_size(size)
+        .with_maximized(geometry.maximized);
+    if let (Some(x), Some(y)) = (geometry.x, geometry.y) {
+        builder = builder.with_position([x, y]);
+    }
+    builder
+}
+
+// The time of day of the scheduled full sync, 03:00 if none is set
+fn scheduled_sync_time(time: Option<&str>) -> chrono::NaiveTime {
+    time.and_then(parse_time_of_day)
+        .unwrap_or(chrono::NaiveTime::MIN + chrono::TimeDelta::hours(3))
+}
+
+// The quiet hours of `config`, 18:00 to 23:00 if none are set
+fn quiet_hours(config: &AppConfig) -> (chrono::NaiveTime, chrono::NaiveTime) {
+    config.quiet_hours().unwrap_or((
+        chrono::NaiveTime::MIN + chrono::TimeDelta::hours(18),
+        chrono::NaiveTime::MIN + chrono::TimeDelta::hours(23),
+    ))
+}
+
+// Hour and minute fields for a time of day; returns true if it was changed
+fn time_of_day_edit(ui: &mut egui::Ui, time: &mut chrono::NaiveTime) -> bool {
+    let (mut hour, mut minute) = (time.hour(), time.minute());
+    let mut changed = ui
+        .add(egui::DragValue::new(&mut hour).range(0..=23))
+        .changed();
+    ui.label(":");
+    changed |= ui
+        .add(
+            egui::DragValue::new(&mut minute)
+                .range(0..=59)
+                .custom_formatter(|minute, _| format!("{:02}", minute)),
+        )
+        .changed();
+    if let Some(edited) = chrono::NaiveTime::from_hms_opt(hour, minute, 0) {
+        *time = edited;
+    }
+    changed
+}
+
+// Languages are named in their own language, except for following the system
+fn language_label(language: Language) -> &'static str {
+    match language {
+        Language::System => tr(language.label()),
+        language => language.label(),
+    }
+}
+
+// Operators listed in the Analytics tab
+const TOP_OPERATORS: usize = 10;
+
+// How long the check of a folder field is shown before the folder is looked at again
+const FOLDER_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+// Recent results of `check_folder` for the folder fields, so a writable destination is not
+// probed with a new file every frame
+#[derive(Default)]
+struct FolderChecks {
+    checks: HashMap<(String, bool), (FolderCheck, Instant)>,
+}
+
+impl FolderChecks {
+    fn check(&mut self, path: &str, writable: bool) -> FolderCheck {
+        let now = Instant::now();
+        let key = (path.to_string(), writable);
+        if let Some((check, checked)) = self.checks.get(&key) {
+            if now.duration_since(*checked) < FOLDER_CHECK_INTERVAL {
+                return *check;
+            }
+        }
+        // Drops the paths typed on the way to this one
+        self.checks
+            .retain(|_, (_, checked)| now.duration_since(*checked) < FOLDER_CHECK_INTERVAL);
+        let check = check_folder(path, writable);
+        self.checks.insert(key, (check, now));
+        check
+    }
+}
+
+// Draws a small check mark or cross depending on whether the folder exists, is a folder and,
+// with `writable`, can be written to. Updated while the path is typed.
+fn path_indicator(
+    ui: &mut egui::Ui,
+    checks: &mut FolderChecks,
+    path: &str,
+    writable: bool,
+    missing_hint: &str,
+) {
+    if path.trim().is_empty() {
+        return;
+    }
+    let (ready, hint) = match checks.check(path, writable) {
+        FolderCheck::Ready if writable => (true, tr("Folder exists and can be written to")),
+        FolderCheck::Ready => (true, tr("Folder exists")),
+        FolderCheck::Missing => (false, missing_hint),
+        FolderCheck::NotAFolder => (false, tr("This is a file, not a folder")),
+        FolderCheck::ReadOnly => (false, tr("Folder cannot be written to")),
+    };
+    if ready {
+        ui.colored_label(egui::Color32::GREEN, "✔")
+            .on_hover_text(hint);
+    } else {
+        ui.colored_label(egui::Color32::RED, "✖")
+            .on_hover_text(hint);
+    }
+}
+
+// The folders dropped onto `rect` this frame, taken from `dropped`. While files are dragged
+// over the window, the field under the pointer is outlined as the place they will go.
+fn take_dropped_folders(
+    ui: &egui::Ui,
+    rect: egui::Rect,
+    dropped: &mut Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    if !ui.rect_contains_pointer(rect) {
+        return Vec::new();
+    }
+    let (hovering, dropping) = ui.input(|i| {
+        (
+            !i.raw.hovered_files.is_empty(),
+            !i.raw.dropped_files.is_empty(),
+        )
+    });
+    if hovering {
+        ui.painter()
+            .rect_stroke(rect.expand(2.0), 2.0, ui.visuals().selection.stroke);
+    }
+    if dropping {
+        std::mem::take(dropped)
+    } else {
+        Vec::new()
+    }
+}
+
+// The tallest value on a chart's axis for values up to `most`: the next of 1, 2, 4, 6, 8 or
+// 10 times a power of ten, so the axis labels stay round
+fn axis_ceiling(most: f64) -> f64 {
+    if most <= 0.0 {
+        return 1.0;
+    }
+    let power = 10f64.powf(most.log10().floor());
+    [1.0, 2.0, 4.0, 6.0, 8.0]
+        .into_iter()
+        .map(|step| step * power)
+        .find(|ceiling| *ceiling >= most)
+        .unwrap_or(10.0 * power)
+}
+
+// A chart with one slot per day, oldest on the left, and values from 0 at the bottom to
+// `ceiling` at the top
+struct DayChart {
+    response: egui::Response,
+    painter: egui::Painter,
+    plot: egui::Rect,
+    days: usize,
+    ceiling: f64,
+}
+
+impl DayChart {
+    // Allocates a chart for `days` days and paints its axis: 0, half of `ceiling` and
+    // `ceiling` as labelled grid lines. None without days, leaving the space empty.
+    fn new(ui: &mut egui::Ui, days: usize, ceiling: f64) -> Option<Self> {
+        let size = egui::vec2(ui.available_width().min(600.0), 130.0);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        if days == 0 {
+            return None;
+        }
+        let painter = ui.painter_at(rect);
+        let (axis_width, label_height, margin) = (32.0, 14.0, 6.0);
+        let plot = egui::Rect::from_min_max(
+            egui::pos2(rect.left() + axis_width, rect.top() + margin),
+            egui::pos2(rect.right() - margin, rect.bottom() - label_height),
+        );
+        let chart = Self {
+            response,
+            painter,
+            plot,
+            days,
+            ceiling,
+        };
+        let font = egui::FontId::proportional(11.0);
+        let text_color = ui.visuals().weak_text_color();
+        let stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+        for value in [0.0, ceiling / 2.0, ceiling] {
+            let y = chart.y(value);
+            chart.painter.line_segment(
+                [egui::pos2(plot.left(), y), egui::pos2(plot.right(), y)],
+                stroke,
+            );
+            chart.painter.text(
+                egui::pos2(plot.left() - 4.0, y),
+                egui::Align2::RIGHT_CENTER,
+                format!("{}", (value * 100.0).round() / 100.0),
+                font.clone(),
+                text_color,
+            );
+        }
+        Some(chart)
+    }
+
+    // Paints the first and last day under the chart
+    fn label_days(&self, ui: &egui::Ui, first: chrono::NaiveDate, last: chrono::NaiveDate) {
+        let font = egui::FontId::proportional(11.0);
+        let text_color = ui.visuals().weak_text_color();
+        let bottom = self.response.rect.bottom();
+        self.painter.text(
+            egui::pos2(self.plot.left(), bottom),
+            egui::Align2::LEFT_BOTTOM,
+            first.format("%Y-%m-%d"),
+            font.clone(),
+            text_color,
+        );
+        self.painter.text(
+            egui::pos2(self.plot.right(), bottom),
+            egui::Align2::RIGHT_BOTTOM,
+            last.format("%Y-%m-%d"),
+            font,
+            text_color,
+        );
+    }
+
+    // Where `value` is drawn on screen
+    fn y(&self, value: f64) -> f32 {
+        self.plot.bottom() - self.plot.height() * (value / self.ceiling).clamp(0.0, 1.0) as f32
+    }
+
+    // The left and right edge of the slot of the `index`th day
+    fn slot(&self, index: usize) -> egui::Rangef {
+        let width = self.plot.width() / self.days as f32;
+        let left = self.plot.left() + width * index as f32;
+        egui::Rangef::new(left, left + width)
+    }
+
+    // The day the pointer is over
+    fn hovered(&self) -> Option<usize> {
+        let pointer = self.response.hover_pos()?;
+        (0..self.days).find(|index| self.slot(*index).contains(pointer.x))
+    }
+}
+
+// Bars of the matches backed up each day, with the count of a day shown on hover
+fn backups_per_day_chart(ui: &mut egui::Ui, per_day: &[(chrono::NaiveDate, usize)]) {
+    let most = per_day.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let Some(chart) = DayChart::new(ui, per_day.len(), axis_ceiling(most as f64)) else {
+        return;
+    };
+    let hovered = chart.hovered();
+    for (index, (_, count)) in per_day.iter().enumerate() {
+        let slot = chart.slot(index);
+        let bar = egui::Rect::from_x_y_ranges(
+            slot.min + slot.span() * 0.15..=slot.max - slot.span() * 0.15,
+            chart.y(*count as f64)..=chart.y(0.0),
+        );
+        let color = if hovered == Some(index) {
+            ui.visuals().selection.stroke.color
+        } else {
+            ui.visuals().selection.bg_fill
+        };
+        chart.painter.rect_filled(bar, 2.0, color);
+    }
+    chart.label_days(ui, per_day[0].0, per_day[per_day.len() - 1].0);
+    if let Some((day, count)) = hovered.map(|index| per_day[index]) {
+        chart
+            .response
+            .on_hover_text(format!("{}: {}", day.format("%Y-%m-%d"), count));
+    }
+}
+
+// The K/D of each day with matches as a line, oldest on the left
+fn kd_per_day_chart(ui: &mut egui::Ui, per_day: &[DayKd]) {
+    // A K/D of 1 is always in view, as the line between better and worse than even
+    let most = per_day.iter().map(DayKd::kd).fold(1.0, f64::max);
+    let Some(chart) = DayChart::new(ui, per_day.len(), axis_ceiling(most)) else {
+        return;
+    };
+    let even = chart.y(1.0);
+    chart.painter.line_segment(
+        [
+            egui::pos2(chart.plot.left(), even),
+            egui::pos2(chart.plot.right(), even),
+        ],
+        egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+    let points: Vec<egui::Pos2> = per_day
+        .iter()
+        .enumerate()
+        .map(|(index, day)| egui::pos2(chart.slot(index).center(), chart.y(day.kd())))
+        .collect();
+    let color = ui.visuals().selection.stroke.color;
+    chart.painter.add(egui::Shape::line(
+        points.clone(),
+        egui::Stroke::new(2.0, color),
+    ));
+    let hovered = chart.hovered();
+    for (index, point) in points.iter().enumerate() {
+        let radius = if hovered == Some(index) { 4.0 } else { 2.5 };
+        chart.painter.circle_filled(*point, radius, color);
+    }
+    chart.label_days(ui, per_day[0].day, per_day[per_day.len() - 1].day);
+    if let Some(day) = hovered.map(|index| &per_day[index]) {
+        chart.response.on_hover_text(format!(
+            "{}: {:.2} ({}/{})",
+            day.day.format("%Y-%m-%d"),
+            day.kd(),
+            day.kills,
+            day.deaths
+        ));
+    }
+}
+
+// Edits the settings of an S3 remote; returns true if any of them changed
+fn s3_settings(ui: &mut egui::Ui, id: usize, config: &mut S3Config) -> bool {
+    let mut changed = false;
+    egui::Grid::new(("s3_settings", id))
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (label, value, hint) in [
+                (
+                    tr("Endpoint:"),
+                    &mut config.endpoint,
+                    "e.g. https://s3.eu-central-1.amazonaws.com or http://nas:9000",
+                ),
+                (tr("Region:"), &mut config.region, tr("Empty for us-east-1")),
+                (tr("Bucket:"), &mut config.bucket, ""),
+                (
+                    tr("Prefix:"),
+                    &mut config.prefix,
+                    tr("Folder in the bucket, empty for the top level"),
+                ),
+                (
+                    tr("Access key ID:"),
+                    &mut config.access_key_id,
+                    tr("Empty to use AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY"),
+                ),
+            ] {
+                ui.label(label);
+                changed |= ui
+                    .add(egui::TextEdit::singleline(value).hint_text(hint))
+                    .changed();
+                ui.end_row();
+            }
+            ui.label(tr("Secret access key:"));
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut config.secret_access_key).password(true))
+                .changed();
+            ui.end_row();
+        });
+    changed
+}
+
+// Edits the settings of a WebDAV remote; returns true if any of them changed
+fn webdav_settings(ui: &mut egui::Ui, id: usize, config: &mut WebDavConfig) -> bool {
+    let mut changed = false;
+    egui::Grid::new(("webdav_settings", id))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr("URL:"));
+            changed |=
+                ui.add(egui::TextEdit::singleline(&mut config.url).hint_text(
+                    "e.g. https://cloud.example.com/remote.php/dav/files/alice/Replays",
+                ))
+                .changed();
+            ui.end_row();
+            ui.label(tr("Username:"));
+            changed |= ui.text_edit_singleline(&mut config.username).changed();
+            ui.end_row();
+            ui.label(tr("Password:"));
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut config.password).password(true))
+                .changed();
+            ui.end_row();
+            ui.label(tr("Authentication:"));
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .radio_value(&mut config.auth, WebDavAuth::Basic, "Basic")
+                    .changed();
+                changed |= ui
+                    .radio_value(&mut config.auth, WebDavAuth::Digest, "Digest")
+                    .changed();
+            });
+            ui.end_row();
+        });
+    changed
+}
+
+// Edits the settings of a Google Drive remote; returns whether any of them changed and
+// whether "Connect" was clicked
+fn google_drive_settings(
+    ui: &mut egui::Ui,
+    id: usize,
+    config: &mut GoogleDriveConfig,
+    connecting: bool,
+) -> (bool, bool) {
+    let mut changed = false;
+    egui::Grid::new(("google_drive_settings", id))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr("Folder:"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut config.folder)
+                        .hint_text(tr("Created if missing")),
+                )
+                .changed();
+            ui.end_row();
+            ui.label(tr("OAuth client ID:"));
+            changed |= ui.text_edit_singleline(&mut config.client_id).changed();
+            ui.end_row();
+            ui.label(tr("OAuth client secret:"));
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut config.client_secret).password(true))
+                .changed();
+            ui.end_row();
+        });
+    let connect = ui
+        .horizontal(|ui| {
+            if config.refresh_token.is_empty() {
+                ui.label(tr("Not connected"));
+            } else {
+                ui.colored_label(egui::Color32::GREEN, tr("✔ Connected"));
+            }
+            ui.add_enabled(!connecting, egui::Button::new(tr("Connect")))
+                .on_hover_text(tr(
+                    "Shows a code in the status log to enter on Google's sign-in page. The \
+                     client must be of type \"TVs and Limited Input devices\".",
+                ))
+                .clicked()
+        })
+        .inner;
+    (changed, connect)
+}
+
+// Edits the settings of an rclone remote; returns true if any of them changed
+fn rclone_settings(ui: &mut egui::Ui, id: usize, config: &mut RcloneConfig) -> bool {
+    let mut changed = false;
+    egui::Grid::new(("rclone_settings", id))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr("Destination:"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut config.destination)
+                        .hint_text(tr("e.g. onedrive:Replays")),
+                )
+                .changed();
+            ui.end_row();
+            ui.label(tr("rclone program:"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut config.program)
+                        .hint_text(tr("rclone, if it is on the PATH")),
+                )
+                .changed();
+            ui.end_row();
+            ui.label(tr("Extra arguments:"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut config.extra_args)
+                        .hint_text(tr("e.g. --config D:\\rclone.conf")),
+                )
+                .changed();
+            ui.end_row();
+        });
+    changed
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Backup,
+    Restore,
+    Library,
+    History,
+    Stats,
+    Analytics,
+}
+
+pub struct SiegeSaverApp {
+    active_profile: String,
+    // The inactive profiles
+    profiles: Vec<Profile>,
+    // Name typed for a new profile
+    new_profile_name: String,
+    // As typed, one entry per row in the Backup tab
+    source_folders: Vec<String>,
+    polling_sources: Vec<String>,
+    poll_interval_secs: u64,
+    destination_folder: String,
+    // As typed, one entry per row in the Backup tab
+    mirror_folders: Vec<String>,
+    remote_destinations: Vec<RemoteConfig>,
+    // Unfinished uploads to the remote destinations, saved next to the config file
+    upload_queue: Arc<UploadQueue>,
+    journal: Arc<BackupJournal>,
+    // Set while a Google account is being connected
+    drive_connecting: Arc<AtomicBool>,
+    // Index of the Google Drive remote that was connected, with its refresh token
+    drive_token: Arc<Mutex<Option<(usize, String)>>>,
+    // One per source folder while watching
+    watchers: Vec<FolderWatcher>,
+    // The end of the log: what earlier sessions logged, then the events of this one
+    log_records: VecDeque<LogRecord>,
+    log_filter: LogFilter,
+    // Errors logged before are not counted in the log view
+    session_start: chrono::NaiveDateTime,
+    // Status line whose details are shown in the popup, if any
+    open_detail: Option<(String, EventDetail)>,
+    is_watching: bool,
+    status_sender: Sender<StatusEvent>,
+    status_receiver: Receiver<StatusEvent>,
+    start_on_boot: bool,
+    autostart_mode: AutostartMode,
+    // Whether exported settings include the destination manifests
+    bundle_manifests: bool,
+    // Whether copied and exported logs show placeholders instead of folder paths
+    anonymize_log: bool,
+    // Set when the config file was edited outside the app
+    config_watcher: Option<ConfigWatcher>,
+    config_changed: Arc<AtomicBool>,
+    tray: AppTray,
+    instance: PrimaryInstance,
+    // Backups being copied right now, by any engine
+    copy_activity: CopyActivity,
+    // Set by an error and cleared by the next successful backup; shown in the tray icon
+    tray_error: bool,
+    started: Instant,
+    // Matches and bytes in the destination folder it was counted for, for the tray tooltip
+    destination_summary: Option<(String, usize, u64)>,
+    should_exit: bool,
+    // Shared with the event handler thread, which drops events while set
+    paused: Arc<AtomicBool>,
+    pause: PauseSchedule,
+    stats: Arc<Mutex<BackupStats>>,
+    stale_warning_enabled: bool,
+    stale_after_minutes: u64,
+    watching_since: Option<SystemTime>,
+    last_stale_check: Option<Instant>,
+    stale_warned: bool,
+    fsync_writes: bool,
+    verify_copies: bool,
+    encrypt_backups: bool,
+    incremental_backups: bool,
+    storage_format: StorageFormat,
+    zstd_level: i32,
+    // Match folder currently being archived, with files done and total
+    archive_progress: Option<(String, usize, usize)>,
+    // Large files being copied right now: (bytes copied, total bytes, started)
+    copy_progress: BTreeMap<PathBuf, (u64, u64, Instant)>,
+    // Backups the watcher threads are about to run; replaced whenever watching starts or stops
+    backup_queue: Arc<BackupQueue>,
+    // Never persisted; has to be re-entered after every restart
+    encryption_passphrase: String,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    // Empty when the Prometheus export is disabled
+    prometheus_textfile: String,
+    api_enabled: bool,
+    api_port: u16,
+    api: Option<ApiServer>,
+    // Woken by background threads, e.g. for commands of the HTTP API while the window is hidden
+    ctx: egui::Context,
+    // Empty when Discord notifications are off
+    discord_webhook_url: String,
+    discord: Option<DiscordNotifier>,
+    // Empty when the HTTP callback is off
+    webhook_url: String,
+    webhook: Option<WebhookNotifier>,
+    desktop_notifications: DesktopNotifications,
+    // Empty when no command runs after backups
+    post_backup_command: String,
+    r6_dissect_path: String,
+    discord_presence: bool,
+    discord_application_id: String,
+    presence: Option<RichPresence>,
+    // Whether the presence last showed the watcher as running; None until it was shown
+    presence_watching: Option<bool>,
+    initial_sync_enabled: bool,
+    rescan_enabled: bool,
+    rescan_interval_minutes: u64,
+    scheduled_sync_enabled: bool,
+    scheduled_sync_time: chrono::NaiveTime,
+    // Seconds since the Unix epoch
+    last_scheduled_sync: Option<i64>,
+    // Scheduled syncs due before this are not started, see `daily_run_due`
+    started_at: chrono::NaiveDateTime,
+    quiet_hours_enabled: bool,
+    quiet_hours_start: chrono::NaiveTime,
+    quiet_hours_end: chrono::NaiveTime,
+    // Whether the backup queue is held for the quiet hours right now
+    in_quiet_hours: bool,
+    // Shared with the event handlers for "Rescan now"
+    rescan: RescanSchedule,
+    initial_sync_limit: usize,
+    debounce_ms: u64,
+    quiet_period_secs: u64,
+    copy_threads: usize,
+    io_limit_enabled: bool,
+    io_limit_mb_per_sec: u64,
+    iops_limit_enabled: bool,
+    iops_limit: u32,
+    background_io_priority: bool,
+    copy_mode: CopyMode,
+    dedupe_files: bool,
+    symlinks: SymlinkMode,
+    conflict_policy: ConflictPolicy,
+    mirror_deletions: bool,
+    mirror_deletion_grace_minutes: u64,
+    // Set while the warning shown before mirror mode is turned on is open
+    confirm_mirror_deletions: bool,
+    // Backups mirror mode is about to delete, shared with every engine
+    deletions: Arc<DeletionQueue>,
+    // Backups in the trash of every destination, read when "Deleted backups" is opened
+    trashed_backups: Option<Vec<TrashedBackup>>,
+    move_after_verify: bool,
+    snapshot_runs: bool,
+    keep_versions_enabled: bool,
+    keep_file_versions: usize,
+    prune_to_recycle_bin: bool,
+    // Set while the warning shown before moving matches out of the source is open
+    confirm_move_after_verify: bool,
+    log_level: LogLevel,
+    theme: Theme,
+    language: Language,
+    window: Option<WindowGeometry>,
+    throttle: Arc<Throttle>,
+    keep_last_enabled: bool,
+    keep_last_matches: usize,
+    max_age_enabled: bool,
+    max_backup_age_days: u64,
+    quota_enabled: bool,
+    quota_gb: u64,
+    min_free_space_enabled: bool,
+    min_free_space_gb: u64,
+    // Set while a "Backup Now" scan is running in the background
+    manual_backup_running: Arc<AtomicBool>,
+    // Set while backups are verified or re-copied in the background
+    verify_running: Arc<AtomicBool>,
+    // Set while "Run test backup" waits for its test match
+    self_test_running: Arc<AtomicBool>,
+    // Problems found by the last "Verify Backups", one report per damaged destination,
+    // offered for re-copying
+    damaged_backups: Vec<VerifyReport>,
+    // Folders dropped onto the window that did not land on a folder field, offered as
+    // sources or destination
+    dropped_folders: Vec<PathBuf>,
+    folder_checks: FolderChecks,
+    // Destinations that cannot be reached while watching, e.g. an unplugged drive
+    offline_destinations: BTreeSet<PathBuf>,
+    tab: Tab,
+    // Matches listed in the Restore tab with the source folder they are restored into and
+    // whether each one is selected
+    restore_candidates: Vec<(RestoreCandidate, PathBuf, bool)>,
+    // Snapshots of the main destination, oldest first, and the one the Restore tab lists;
+    // the latest if None
+    restore_snapshots: Vec<String>,
+    restore_snapshot: Option<String>,
+    restore_running: Arc<AtomicBool>,
+    // Matches restored and total, while a restore is running
+    restore_progress: Option<(usize, usize)>,
+    // Selected matches that already exist in the source, waiting for confirmation
+    restore_conflicts: Option<Vec<String>>,
+    // Manifest of every destination, loaded when the Library tab is opened
+    library: Vec<(PathBuf, Manifest)>,
+    library_search: String,
+    // YYYY-MM-DD, empty for no bound
+    library_from: String,
+    library_to: String,
+    library_tag: Option<String>,
+    // Destination and name of the item whose tags and notes are being edited, with the tags
+    // as typed and the notes
+    library_editing: Option<(PathBuf, String, String, String)>,
+    // Destination and name of the items ticked for sharing
+    library_selected: BTreeSet<(PathBuf, String)>,
+    // Every finished backup, saved next to the config file
+    history: BackupHistory,
+    history_sort: HistoryColumn,
+    history_ascending: bool,
+    // Manifest of the destination read when the Stats tab was opened
+    stats_manifest: Option<Manifest>,
+    // One source-relative folder per line
+    watch_exclude_text: String,
+    // Comma-separated glob patterns for file names
+    include_patterns_text: String,
+    exclude_patterns_text: String,
+    config_path: PathBuf,
+    last_metrics_write: Option<Instant>,
+    // The Windows service watching instead of the GUI, if installed
+    service_state: ServiceState,
+    last_service_check: Option<Instant>,
+}
+
+impl SiegeSaverApp {
+    pub fn new(cc: &eframe::CreationContext<'_>, tray: AppTray, instance: PrimaryInstance) -> Self {
+        instance.set_context(cc.egui_ctx.clone());
+        let config_path = AppConfig::resolve_path();
+        let config = AppConfig::load(&config_path);
+        let quiet_hours_enabled = config.quiet_hours().is_some();
+        let (quiet_hours_start, quiet_hours_end) = quiet_hours(&config);
+        let history = BackupHistory::load(&config_path.with_file_name(HISTORY_FILE_NAME));
+        let (status_sender, status_receiver) = channel();
+        let discord = config
+            .discord_webhook_url
+            .clone()
+            .map(|url| DiscordNotifier::spawn(url, status_sender.clone()));
+        let presence = (config.discord_presence && !config.discord_application_id.is_empty())
+            .then(|| RichPresence::spawn(config.discord_application_id.clone()));
+        let webhook = config
+            .webhook_url
+            .clone()
+            .map(|url| WebhookNotifier::spawn(url, status_sender.clone()));
+        let mut source_folders = config.source_folders;
+        if source_folders.is_empty() {
+            source_folders.push(String::new());
+        }
+        let mut app = Self {
+            active_profile: config.active_profile,
+            profiles: config.profiles,
+            new_profile_name: String::new(),
+            source_folders,
+            polling_sources: config.polling_sources,
+            poll_interval_secs: config.poll_interval_secs,
+            destination_folder: config.destination_folder,
+            mirror_folders: config.mirror_folders,
+            remote_destinations: config.remote_destinations,
+            upload_queue: Arc::new(UploadQueue::load(
+                &config_path.with_file_name(UPLOAD_QUEUE_FILE_NAME),
+            )),
+            journal: Arc::new(BackupJournal::load(
+                &config_path.with_file_name(JOURNAL_FILE_NAME),
+            )),
+            drive_connecting: Arc::new(AtomicBool::new(false)),
+            drive_token: Arc::new(Mutex::new(None)),
+            watchers: Vec::new(),
+            log_records: read_log(&config_path, LOG_VIEW_LINES)
+                .unwrap_or_default()
+                .into(),
+            log_filter: LogFilter::default(),
+            session_start: chrono::Local::now().naive_local(),
+            open_detail: None,
+            is_watching: false,
+            status_sender,
+            status_receiver,
+            start_on_boot: config.start_on_boot,
+            autostart_mode: config.autostart_mode,
+            bundle_manifests: false,
+            anonymize_log: false,
+            config_watcher: None,
+            config_changed: Arc::new(AtomicBool::new(false)),
+            tray,
+            instance,
+            copy_activity: CopyActivity::default(),
+            tray_error: false,
+            started: Instant::now(),
+            destination_summary: None,
+            should_exit: false,
+            paused: Arc::new(AtomicBool::new(false)),
+            pause: PauseSchedule::default(),
+            stats: Arc::new(Mutex::new(BackupStats::default())),
+            stale_warning_enabled: config.stale_warning_enabled,
+            stale_after_minutes: config.stale_after_minutes,
+            watching_since: None,
+            last_stale_check: None,
+            stale_warned: false,
+            fsync_writes: config.fsync_writes,
+            verify_copies: config.verify_copies,
+            encrypt_backups: config.encrypt_backups,
+            incremental_backups: config.incremental_backups,
+            storage_format: config.storage_format,
+            zstd_level: config.zstd_level,
+            archive_progress: None,
+            copy_progress: BTreeMap::new(),
+            backup_queue: Arc::new(BackupQueue::default()),
+            encryption_passphrase: String::new(),
+            encryption_key: None,
+            prometheus_textfile: config.prometheus_textfile.unwrap_or_default(),
+            api_enabled: config.api_port.is_some(),
+            api_port: config.api_port.unwrap_or(DEFAULT_API_PORT),
+            api: None,
+            ctx: cc.egui_ctx.clone(),
+            discord,
+            discord_webhook_url: config.discord_webhook_url.unwrap_or_default(),
+            webhook,
+            webhook_url: config.webhook_url.unwrap_or_default(),
+            desktop_notifications: config.desktop_notifications,
+            post_backup_command: config.post_backup_command.unwrap_or_default(),
+            r6_dissect_path: config.r6_dissect_path.unwrap_or_default(),
+            discord_presence: config.discord_presence,
+            discord_application_id: config.discord_application_id,
+            presence,
+            presence_watching: None,
+            initial_sync_enabled: config.initial_sync_limit.is_some(),
+            rescan_enabled: config.rescan_interval_minutes.is_some(),
+            rescan_interval_minutes: config.rescan_interval_minutes.unwrap_or(15),
+            scheduled_sync_enabled: config.scheduled_sync_time.is_some(),
+            scheduled_sync_time: scheduled_sync_time(config.scheduled_sync_time.as_deref()),
+            last_scheduled_sync: config.last_scheduled_sync,
+            started_at: chrono::Local::now().naive_local(),
+            quiet_hours_enabled,
+            quiet_hours_start,
+            quiet_hours_end,
+            in_quiet_hours: false,
+            rescan: RescanSchedule::default(),
+            initial_sync_limit: config.initial_sync_limit.unwrap_or(50),
+            debounce_ms: config.debounce_ms,
+            quiet_period_secs: config.quiet_period_secs,
+            copy_threads: config.copy_threads,
+            io_limit_enabled: config.io_limit_mb_per_sec.is_some(),
+            io_limit_mb_per_sec: config.io_limit_mb_per_sec.unwrap_or(20),
+            iops_limit_enabled: config.io_limit_ops_per_sec.is_some(),
+            iops_limit: config.io_limit_ops_per_sec.unwrap_or(200),
+            background_io_priority: config.background_io_priority,
+            copy_mode: config.copy_mode,
+            dedupe_files: config.dedupe_files,
+            symlinks: config.symlinks,
+            conflict_policy: config.conflict_policy,
+            mirror_deletions: config.mirror_deletions,
+            mirror_deletion_grace_minutes: config.mirror_deletion_grace_minutes,
+            confirm_mirror_deletions: false,
+            deletions: Arc::new(DeletionQueue::default()),
+            trashed_backups: None,
+            move_after_verify: config.move_after_verify,
+            snapshot_runs: config.snapshot_runs,
+            keep_versions_enabled: config.keep_file_versions.is_some(),
+            keep_file_versions: config.keep_file_versions.unwrap_or(5),
+            prune_to_recycle_bin: config.prune_to_recycle_bin,
+            confirm_move_after_verify: false,
+            log_level: config.log_level,
+            theme: config.theme,
+            language: config.language,
+            window: config.window,
+            throttle: Arc::new(Throttle::default()),
+            keep_last_enabled: config.keep_last_matches.is_some(),
+            keep_last_matches: config.keep_last_matches.unwrap_or(100),
+            max_age_enabled: config.max_backup_age_days.is_some(),
+            max_backup_age_days: config.max_backup_age_days.unwrap_or(30),
+            quota_enabled: config.destination_quota_gb.is_some(),
+            quota_gb: config.destination_quota_gb.unwrap_or(50),
+            min_free_space_enabled: config.min_free_space_gb.is_some(),
+            min_free_space_gb: config.min_free_space_gb.unwrap_or(1),
+            last_metrics_write: None,
+            service_state: service_state(),
+            last_service_check: Some(Instant::now()),
+            manual_backup_running: Arc::new(AtomicBool::new(false)),
+            verify_running: Arc::new(AtomicBool::new(false)),
+            self_test_running: Arc::new(AtomicBool::new(false)),
+            damaged_backups: Vec::new(),
+            dropped_folders: Vec::new(),
+            folder_checks: FolderChecks::default(),
+            offline_destinations: BTreeSet::new(),
+            tab: Tab::Backup,
+            restore_candidates: Vec::new(),
+            restore_snapshots: Vec::new(),
+            restore_snapshot: None,
+            restore_running: Arc::new(AtomicBool::new(false)),
+            restore_progress: None,
+            restore_conflicts: None,
+            library: Vec::new(),
+            library_search: String::new(),
+            library_from: String::new(),
+            library_to: String::new(),
+            library_tag: None,
+            library_editing: None,
+            library_selected: BTreeSet::new(),
+            history,
+            history_sort: HistoryColumn::Time,
+            history_ascending: false,
+            stats_manifest: None,
+            watch_exclude_text: config.watch_exclude_dirs.join("\n"),
+            include_patterns_text: config.include_patterns.join(", "),
+            exclude_patterns_text: config.exclude_patterns.join(", "),
+            config_path,
+        };
+        let names = app.config().profile_names();
+        app.tray.set_profiles(&names, &app.active_profile);
+        app.watch_config_file(&cc.egui_ctx);
+        app.restart_api();
+        app.add_status(StatusEvent::Info(build_info()));
+        app.add_status(StatusEvent::Info(format!(
+            "Using config file: {}",
+            app.config_path.display()
+        )));
+        app
+    }
+
+    fn config(&self) -> AppConfig {
+        AppConfig {
+            start_on_boot: self.start_on_boot,
+            autostart_mode: self.autostart_mode,
+            active_profile: self.active_profile.clone(),
+            profiles: self.profiles.clone(),
+            source_folders: self
+                .source_folders
+                .iter()
+                .map(|folder| normalize_path_input(folder))
+                .filter(|folder| !folder.is_empty())
+                .collect(),
+            // Only those still listed as sources
+            polling_sources: self
+                .source_folders
+                .iter()
+                .map(|folder| normalize_path_input(folder))
+                .filter(|folder| self.polling_sources.contains(folder))
+                .collect(),
+            poll_interval_secs: self.poll_interval_secs,
+            destination_folder: normalize_path_input(&self.destination_folder),
+            mirror_folders: self
+                .mirror_paths()
+                .iter()
+                .map(|mirror| mirror.display().to_string())
+                .collect(),
+            remote_destinations: self.remote_destinations.clone(),
+            stale_warning_enabled: self.stale_warning_enabled,
+            stale_after_minutes: self.stale_after_minutes,
+            fsync_writes: self.fsync_writes,
+            verify_copies: self.verify_copies,
+            encrypt_backups: self.encrypt_backups,
+            prometheus_textfile: Some(normalize_path_input(&self.prometheus_textfile))
+                .filter(|path| !path.is_empty()),
+            api_port: self.api_enabled.then_some(self.api_port),
+            discord_webhook_url: Some(self.discord_webhook_url.trim().to_string())
+                .filter(|url| !url.is_empty()),
+            webhook_url: Some(self.webhook_url.trim().to_string()).filter(|url| !url.is_empty()),
+            desktop_notifications: self.desktop_notifications,
+            post_backup_command: Some(self.post_backup_command.trim().to_string())
+                .filter(|command| !command.is_empty()),
+            r6_dissect_path: Some(self.r6_dissect_path.trim().to_string())
+                .filter(|path| !path.is_empty()),
+            discord_presence: self.discord_presence,
+            discord_application_id: self.discord_application_id.trim().to_string(),
+            initial_sync_limit: self.initial_sync_enabled.then_some(self.initial_sync_limit),
+            rescan_interval_minutes: self.rescan_enabled.then_some(self.rescan_interval_minutes),
+            scheduled_sync_time: self
+                .scheduled_sync_enabled
+                .then(|| self.scheduled_sync_time.format("%H:%M").to_string()),
+            last_scheduled_sync: self.last_scheduled_sync,
+            quiet_hours_start: self
+                .quiet_hours_enabled
+                .then(|| self.quiet_hours_start.format("%H:%M").to_string()),
+            quiet_hours_end: self
+                .quiet_hours_enabled
+                .then(|| self.quiet_hours_end.format("%H:%M").to_string()),
+            debounce_ms: self.debounce_ms,
+            quiet_period_secs: self.quiet_period_secs,
+            copy_threads: self.copy_threads,
+            io_limit_mb_per_sec: self.io_limit_enabled.then_some(self.io_limit_mb_per_sec),
+            io_limit_ops_per_sec: self.iops_limit_enabled.then_some(self.iops_limit),
+            background_io_priority: self.background_io_priority,
+            copy_mode: self.copy_mode,
+            dedupe_files: self.dedupe_files,
+            symlinks: self.symlinks,
+            conflict_policy: self.conflict_policy,
+            mirror_deletions: self.mirror_deletions,
+            mirror_deletion_grace_minutes: self.mirror_deletion_grace_minutes,
+            move_after_verify: self.move_after_verify,
+            snapshot_runs: self.snapshot_runs,
+            keep_file_versions: self
+                .keep_versions_enabled
+                .then_some(self.keep_file_versions),
+            prune_to_recycle_bin: self.prune_to_recycle_bin,
+            log_level: self.log_level,
+            theme: self.theme,
+            language: self.language,
+            window: self.window,
+            watch_exclude_dirs: parse_exclude_dirs(&self.watch_exclude_text),
+            incremental_backups: self.incremental_backups,
+            storage_format: self.storage_format,
+            zstd_level: self.zstd_level,
+            include_patterns: parse_patterns(&self.include_patterns_text),
+            exclude_patterns: parse_patterns(&self.exclude_patterns_text),
+            keep_last_matches: self.keep_last_enabled.then_some(self.keep_last_matches),
+            max_backup_age_days: self.max_age_enabled.then_some(self.max_backup_age_days),
+            destination_quota_gb: self.quota_enabled.then_some(self.quota_gb),
+            min_free_space_gb: self
+                .min_free_space_enabled
+                .then_some(self.min_free_space_gb),
+        }
+    }
+
+    fn save_config(&self) {
+        self.config().save(&self.config_path);
+    }
+
+    // Saves the size and position of the window as it is closed
+    fn remember_window(&mut self, ctx: &egui::Context) {
+        let window = WindowGeometry::current(ctx, self.window);
+        if window != self.window {
+            self.window = window;
+            self.save_config();
+        }
+    }
+
+    // Shows the active profile's settings from `config`
+    fn load_profile(&mut self, config: AppConfig) {
+        self.active_profile = config.active_profile;
+        self.profiles = config.profiles;
+        self.source_folders = config.source_folders;
+        if self.source_folders.is_empty() {
+            self.source_folders.push(String::new());
+        }
+        self.polling_sources = config.polling_sources;
+        self.destination_folder = config.destination_folder;
+        self.mirror_folders = config.mirror_folders;
+        self.remote_destinations = config.remote_destinations;
+        self.watch_exclude_text = config.watch_exclude_dirs.join("\n");
+        self.include_patterns_text = config.include_patterns.join(", ");
+        self.exclude_patterns_text = config.exclude_patterns.join(", ");
+        self.storage_format = config.storage_format;
+        self.library.clear();
+        self.library_editing = None;
+        self.library_selected.clear();
+        self.restore_candidates.clear();
+        self.damaged_backups.clear();
+    }
+
+    // Shows every setting from `config`, restarting the notifiers whose settings changed.
+    // Watching restarts so new folders and filters apply.
+    fn apply_config(&mut self, config: AppConfig) {
+        let was_watching = self.is_watching;
+        if was_watching {
+            self.stop_watching();
+        }
+        let previous = self.config();
+        if config.discord_webhook_url != previous.discord_webhook_url {
+            self.discord = config
+                .discord_webhook_url
+                .clone()
+                .map(|url| DiscordNotifier::spawn(url, self.status_sender.clone()));
+        }
+        if config.webhook_url != previous.webhook_url {
+            self.webhook = config
+                .webhook_url
+                .clone()
+                .map(|url| WebhookNotifier::spawn(url, self.status_sender.clone()));
+        }
+        if (config.discord_presence, &config.discord_application_id)
+            != (previous.discord_presence, &previous.discord_application_id)
+        {
+            self.presence = (config.discord_presence && !config.discord_application_id.is_empty())
+                .then(|| RichPresence::spawn(config.discord_application_id.clone()));
+            self.presence_watching = None;
+        }
+
+        self.start_on_boot = config.start_on_boot;
+        self.autostart_mode = config.autostart_mode;
+        self.stale_warning_enabled = config.stale_warning_enabled;
+        self.stale_after_minutes = config.stale_after_minutes;
+        self.fsync_writes = config.fsync_writes;
+        self.verify_copies = config.verify_copies;
+        self.encrypt_backups = config.encrypt_backups;
+        self.incremental_backups = config.incremental_backups;
+        self.zstd_level = config.zstd_level;
+        self.prometheus_textfile = config.prometheus_textfile.clone().unwrap_or_default();
+        self.api_enabled = config.api_port.is_some();
+        self.api_port = config.api_port.unwrap_or(DEFAULT_API_PORT);
+        if config.api_port != previous.api_port {
+            self.restart_api();
+        }
+        self.discord_webhook_url = config.discord_webhook_url.clone().unwrap_or_default();
+        self.webhook_url = config.webhook_url.clone().unwrap_or_default();
+        self.desktop_notifications = config.desktop_notifications;
+        self.post_backup_command = config.post_backup_command.clone().unwrap_or_default();
+        self.r6_dissect_path = config.r6_dissect_path.clone().unwrap_or_default();
+        self.discord_presence = config.discord_presence;
+        self.discord_application_id = config.discord_application_id.clone();
+        self.initial_sync_enabled = config.initial_sync_limit.is_some();
+        self.rescan_enabled = config.rescan_interval_minutes.is_some();
+        self.rescan_interval_minutes = config.rescan_interval_minutes.unwrap_or(15);
+        self.scheduled_sync_enabled = config.scheduled_sync_time.is_some();
+        self.scheduled_sync_time = scheduled_sync_time(config.scheduled_sync_time.as_deref());
+        self.last_scheduled_sync = config.last_scheduled_sync;
+        self.quiet_hours_enabled = config.quiet_hours().is_some();
+        (self.quiet_hours_start, self.quiet_hours_end) = quiet_hours(&config);
+        self.initial_sync_limit = config.initial_sync_limit.unwrap_or(50);
+        self.debounce_ms = config.debounce_ms;
+        self.quiet_period_secs = config.quiet_period_secs;
+        self.copy_threads = config.copy_threads;
+        self.io_limit_enabled = config.io_limit_mb_per_sec.is_some();
+        self.io_limit_mb_per_sec = config.io_limit_mb_per_sec.unwrap_or(20);
+        self.iops_limit_enabled = config.io_limit_ops_per_sec.is_some();
+        self.iops_limit = config.io_limit_ops_per_sec.unwrap_or(200);
+        self.background_io_priority = config.background_io_priority;
+        self.copy_mode = config.copy_mode;
+        self.dedupe_files = config.dedupe_files;
+        self.symlinks = config.symlinks;
+        self.conflict_policy = config.conflict_policy;
+        self.mirror_deletions = config.mirror_deletions;
+        self.mirror_deletion_grace_minutes = config.mirror_deletion_grace_minutes;
+        self.move_after_verify = config.move_after_verify;
+        self.snapshot_runs = config.snapshot_runs;
+        self.keep_versions_enabled = config.keep_file_versions.is_some();
+        self.keep_file_versions = config.keep_file_versions.unwrap_or(5);
+        self.prune_to_recycle_bin = config.prune_to_recycle_bin;
+        self.log_level = config.log_level;
+        set_log_level(self.log_level);
+        self.theme = config.theme;
+        self.window = config.window;
+        if self.language != config.language {
+            self.language = config.language;
+            set_language(self.language);
+            self.tray.retranslate();
+        }
+        self.poll_interval_secs = config.poll_interval_secs;
+        self.keep_last_enabled = config.keep_last_matches.is_some();
+        self.keep_last_matches = config.keep_last_matches.unwrap_or(100);
+        self.max_age_enabled = config.max_backup_age_days.is_some();
+        self.max_backup_age_days = config.max_backup_age_days.unwrap_or(30);
+        self.quota_enabled = config.destination_quota_gb.is_some();
+        self.quota_gb = config.destination_quota_gb.unwrap_or(50);
+        self.min_free_space_enabled = config.min_free_space_gb.is_some();
+        self.min_free_space_gb = config.min_free_space_gb.unwrap_or(1);
+        self.last_metrics_write = None;
+        self.tray
+            .set_profiles(&config.profile_names(), &config.active_profile);
+        self.load_profile(config);
+        if was_watching {
+            self.start_watching();
+        }
+    }
+
+    fn watch_config_file(&mut self, ctx: &egui::Context) {
+        if let Some(folder) = self.config_path.parent() {
+            let _ = fs::create_dir_all(folder);
+        }
+        let changed = Arc::clone(&self.config_changed);
+        let ctx = ctx.clone();
+        let watcher = watch_config_file(&self.config_path, move || {
+            changed.store(true, Ordering::Relaxed);
+            ctx.request_repaint();
+        });
+        match watcher {
+            Ok(watcher) => self.config_watcher = Some(watcher),
+            Err(e) => self.add_status(StatusEvent::Warning(format!(
+                "{}; changes to it apply after a restart",
+                e
+            ))),
+        }
+    }
+
+    // Applies the config file after it was edited outside the app. Our own saves and
+    // half-written files are left alone.
+    fn reload_config(&mut self) {
+        let config = match AppConfig::read(&self.config_path) {
+            Ok(config) => config,
+            Err(e) => {
+                self.add_status(StatusEvent::Warning(format!(
+                    "Not reloading settings: {}",
+                    e
+                )));
+                return;
+            }
+        };
+        if serde_json::to_value(&config).ok() == serde_json::to_value(self.config()).ok() {
+            return;
+        }
+        self.apply_config(config);
+        self.add_status(StatusEvent::Info(format!(
+            "Reloaded settings from {}",
+            self.config_path.display()
+        )));
+    }
+
+    // Asks where to save the settings and writes them there as one file
+    fn export_settings(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("json", &["json"])
+            .set_file_name("siegesaver-settings.json")
+            .save_file()
+        else {
+            return;
+        };
+        let result = SettingsBundle::new(&RealFs, self.config(), self.bundle_manifests)
+            .and_then(|bundle| bundle.save(&path));
+        match result {
+            Ok(()) => self.add_status(StatusEvent::Info(format!(
+                "Exported settings to {}",
+                path.display()
+            ))),
+            Err(e) => self.add_status(StatusEvent::Error(format!(
+                "Error exporting settings: {}",
+                e
+            ))),
+        }
+    }
+
+    // The lines the log view shows, with the version and a summary of the settings, for
+    // bug reports
+    fn log_report(&self) -> String {
+        let records: Vec<&LogRecord> = self
+            .log_records
+            .iter()
+            .filter(|record| self.log_filter.matches(record))
+            .collect();
+        diagnostics_report(&self.config(), &records, self.anonymize_log)
+    }
+
+    fn export_log(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("txt", &["txt"])
+            .set_file_name("siegesaver-log.txt")
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, self.log_report()) {
+            Ok(()) => self.add_status(StatusEvent::Info(format!(
+                "Exported the log to {}",
+                path.display()
+            ))),
+            Err(e) => self.add_status(StatusEvent::Error(format!(
+                "Error exporting the log: {}",
+                e
+            ))),
+        }
+    }
+
+    // Replaces the settings with exported ones. Starting on boot stays as set up on this
+    // machine.
+    fn import_settings(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("json", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let mut bundle = match SettingsBundle::load(&path) {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error importing settings from {}: {}",
+                    path.display(),
+                    e
+                )));
+                return;
+            }
+        };
+        bundle.config.start_on_boot = self.start_on_boot;
+        bundle.config.autostart_mode = self.autostart_mode;
+        match bundle.restore_manifests(&RealFs) {
+            Ok(0) => {}
+            Ok(restored) => self.add_status(StatusEvent::Info(format!(
+                "Restored {} backup manifest(s)",
+                restored
+            ))),
+            Err(e) => self.add_status(StatusEvent::Warning(format!(
+                "Error restoring the backup manifests: {}",
+                e
+            ))),
+        }
+        bundle.config.save(&self.config_path);
+        self.apply_config(bundle.config);
+        self.add_status(StatusEvent::Info(format!(
+            "Imported settings from {}",
+            path.display()
+        )));
+    }
+
+    // Switches, adds or deletes profiles. Watching restarts with the new profile's folders.
+    fn change_profile(&mut self, change: impl FnOnce(&mut AppConfig) -> Result<(), String>) {
+        let mut config = self.config();
+        if let Err(e) = change(&mut config) {
+            self.add_status(StatusEvent::Error(format!("Error: {}", e)));
+            return;
+        }
+        let was_watching = self.is_watching;
+        if was_watching {
+            self.stop_watching();
+        }
+        config.save(&self.config_path);
+        self.tray
+            .set_profiles(&config.profile_names(), &config.active_profile);
+        self.add_status(StatusEvent::Info(format!(
+            "Using profile: {}",
+            config.active_profile
+        )));
+        self.load_profile(config);
+        if was_watching {
+            self.start_watching();
+        }
+    }
+
+    fn switch_profile(&mut self, name: &str) {
+        if name != self.active_profile {
+            self.change_profile(|config| config.switch_profile(name));
+        }
+    }
+
+    fn source_paths(&self) -> Vec<PathBuf> {
+        self.source_folders
+            .iter()
+            .map(|folder| normalize_path_input(folder))
+            .filter(|folder| !folder.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    // Adds `folders` to the source folders, filling in the empty field of a new profile first
+    fn add_sources(&mut self, folders: Vec<PathBuf>) {
+        for folder in folders {
+            let folder = folder.display().to_string();
+            if self.source_folders.contains(&folder) {
+                continue;
+            }
+            match self.source_folders.iter_mut().find(|f| f.trim().is_empty()) {
+                Some(empty) => *empty = folder,
+                None => self.source_folders.push(folder),
+            }
+        }
+    }
+
+    // Takes the folders dropped onto the window this frame. The source and destination fields
+    // pick them up if they were dropped onto them, anything else is offered on the Backup tab.
+    fn receive_dropped_files(&mut self, ctx: &egui::Context) {
+        let paths: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        if paths.is_empty() {
+            return;
+        }
+        let (folders, files): (Vec<PathBuf>, Vec<PathBuf>) =
+            paths.into_iter().partition(|path| path.is_dir());
+        for file in files {
+            self.add_status(StatusEvent::Warning(format!(
+                "Only folders can be dropped, {} is not a folder",
+                file.display()
+            )));
+        }
+        if !folders.is_empty() {
+            self.dropped_folders = folders;
+            self.tab = Tab::Backup;
+        }
+    }
+
+    fn mirror_paths(&self) -> Vec<PathBuf> {
+        self.mirror_folders
+            .iter()
+            .map(|folder| normalize_path_input(folder))
+            .filter(|folder| !folder.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    // Each source folder with the folder it is backed up to in `destination`. Without any
+    // source the destination is still listed so it can be verified and browsed.
+    fn targets_in(&self, destination: PathBuf) -> Vec<(PathBuf, PathBuf)> {
+        let sources = self.source_paths();
+        if sources.is_empty() {
+            return vec![(PathBuf::new(), destination)];
+        }
+        let destinations = source_destinations(&sources, &destination);
+        sources.into_iter().zip(destinations).collect()
+    }
+
+    // Targets in the main destination, which the Restore and Library tabs read from
+    fn backup_targets(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.targets_in(PathBuf::from(normalize_path_input(
+            &self.destination_folder,
+        )))
+    }
+
+    // Targets in the main destination followed by those in every mirror
+    fn all_targets(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut targets = self.backup_targets();
+        for mirror in self.mirror_paths() {
+            targets.extend(self.targets_in(mirror));
+        }
+        targets
+    }
+
+    fn backup_engine(&self, source: PathBuf, destination: PathBuf) -> BackupEngine {
+        let options = self.backup_options(&source);
+        // Every engine shares one budget, so the limits hold for all of them together
+        self.throttle.set_limits(self.io_limits());
+        let source_path = source.clone();
+        // Only copies to the main destination count towards the statistics
+        let is_mirror = self
+            .mirror_paths()
+            .iter()
+            .any(|mirror| destination.starts_with(mirror));
+        let mut engine = BackupEngine::new(source, destination, options)
+            .with_retention(self.retention_policy())
+            .with_quota(self.quota_bytes())
+            .with_min_free_space(self.min_free_space())
+            .with_quiet_period(Duration::from_secs(self.quiet_period_secs))
+            .with_journal(Arc::clone(&self.journal))
+            .with_throttle(Arc::clone(&self.throttle))
+            .with_dedupe(self.dedupe_files)
+            .with_versions(
+                self.keep_versions_enabled
+                    .then_some(self.keep_file_versions),
+            )
+            .with_recycle_bin(self.prune_to_recycle_bin)
+            .with_volume_tracking()
+            .with_activity(self.copy_activity.clone());
+        if self.snapshot_runs {
+            engine = engine.with_snapshot(snapshot_name(chrono::Local::now().naive_local()));
+        }
+        if self.mirror_deletions && !self.move_after_verify {
+            engine = engine.with_mirror_deletions(
+                Arc::clone(&self.deletions),
+                Duration::from_secs(self.mirror_deletion_grace_minutes * 60),
+            );
+        }
+        if is_mirror {
+            engine
+        } else {
+            engine
+                .with_stats(Arc::clone(&self.stats))
+                .with_remotes(self.remotes(&source_path))
+                .with_upload_queue(Arc::clone(&self.upload_queue))
+                .with_move_after_verify(self.move_after_verify)
+                .with_post_backup_command(
+                    Some(self.post_backup_command.trim().to_string())
+                        .filter(|command| !command.is_empty()),
+                )
+                .with_match_stats(
+                    Some(self.r6_dissect_path.trim())
+                        .filter(|path| !path.is_empty())
+                        .map(PathBuf::from),
+                )
+        }
+    }
+
+    // The remote destinations backups of `source` are uploaded to. Incomplete settings are
+    // rejected in prepare_backup.
+    fn remotes(&self, source: &Path) -> Vec<Remote> {
+        let sources = self.source_paths();
+        let folder = if sources.len() < 2 {
+            String::new()
+        } else {
+            sources
+                .iter()
+                .position(|known| known == source)
+                .and_then(|index| source_labels(&sources).into_iter().nth(index))
+                .unwrap_or_default()
+        };
+        self.remote_destinations
+            .iter()
+            .filter_map(|config| {
+                Some(Remote {
+                    label: config.label(),
+                    store: config.store(&self.status_sender).ok()?,
+                    folder: folder.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn backup_options(&self, source: &Path) -> BackupOptions {
+        BackupOptions {
+            fsync_writes: self.fsync_writes,
+            verify_copies: self.verify_copies,
+            encryption: self.encryption_key.clone(),
+            excluded_dirs: parse_exclude_dirs(&self.watch_exclude_text)
+                .iter()
+                .map(|dir| source.join(dir))
+                .collect(),
+            incremental: self.incremental_backups,
+            // Invalid patterns are rejected in prepare_backup before anything is copied
+            file_filter: self.file_filter().unwrap_or_default(),
+            storage: self.storage_format,
+            zstd_level: self.zstd_level,
+            copy_threads: self.copy_threads,
+            cancel: None,
+            copy_mode: self.copy_mode,
+            symlinks: self.symlinks,
+            conflicts: self.conflict_policy,
+            dedupe_store: None,
+            versions: None,
+        }
+    }
+
+    fn io_limits(&self) -> IoLimits {
+        IoLimits {
+            bytes_per_sec: self
+                .io_limit_enabled
+                .then_some(self.io_limit_mb_per_sec.max(1) * 1024 * 1024),
+            ops_per_sec: self.iops_limit_enabled.then_some(self.iops_limit.max(1)),
+            background_priority: self.background_io_priority,
+        }
+    }
+
+    fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            keep_last: self.keep_last_enabled.then_some(self.keep_last_matches),
+            max_age_days: self.max_age_enabled.then_some(self.max_backup_age_days),
+        }
+    }
+
+    fn quota_bytes(&self) -> Option<u64> {
+        self.quota_enabled
+            .then(|| gigabytes_to_bytes(self.quota_gb))
+    }
+
+    fn min_free_space(&self) -> Option<u64> {
+        self.min_free_space_enabled
+            .then(|| gigabytes_to_bytes(self.min_free_space_gb))
+    }
+
+    fn file_filter(&self) -> Result<FileFilter, String> {
+        FileFilter::new(
+            &parse_patterns(&self.include_patterns_text),
+            &parse_patterns(&self.exclude_patterns_text),
+        )
+    }
+
+    fn add_status(&mut self, event: StatusEvent) {
+        log_event(&event);
+        let text = match self.source_label(&event) {
+            Some(label) => format!("[{}] {}", label, event),
+            None => event.to_string(),
+        };
+        self.log_records.push_back(LogRecord {
+            time: chrono::Local::now().naive_local(),
+            severity: event.severity(),
+            text,
+            detail: event.detail().cloned(),
+        });
+        if self.log_records.len() > LOG_VIEW_LINES {
+            self.log_records.pop_front();
+        }
+    }
+
+    // With several source folders, the label of the one an event came from. Events about a
+    // mirror also name the mirror.
+    fn source_label(&self, event: &StatusEvent) -> Option<String> {
+        let detail = event.detail()?;
+        let sources = self.source_paths();
+        let source = if sources.len() < 2 {
+            None
+        } else {
+            let index = sources
+                .iter()
+                .position(|source| detail.source.starts_with(source))?;
+            source_labels(&sources).into_iter().nth(index)
+        };
+        let mirror = self
+            .mirror_paths()
+            .into_iter()
+            .find(|mirror| detail.destination.starts_with(mirror))
+            .map(|mirror| format!("mirror {}", mirror.display()));
+        match (source, mirror) {
+            (Some(source), Some(mirror)) => Some(format!("{} → {}", source, mirror)),
+            (source, mirror) => source.or(mirror),
+        }
+    }
+
+    // Validates the folders, creates the destinations and derives the encryption key.
+    // Returns each source with its destinations, the main one first, when a backup can run.
+    fn prepare_backup(&mut self) -> Option<Vec<(PathBuf, Vec<PathBuf>)>> {
+        for folder in &mut self.source_folders {
+            *folder = normalize_path_input(folder);
+        }
+        self.destination_folder = normalize_path_input(&self.destination_folder);
+
+        let sources = self.source_paths();
+        if sources.is_empty() || self.destination_folder.is_empty() {
+            self.add_status(StatusEvent::Error(
+                "Error: Please select both source and destination folders".to_string(),
+            ));
+            return None;
+        }
+
+        if let Err(e) = self.file_filter() {
+            self.add_status(StatusEvent::Error(format!("Error: {}", e)));
+            return None;
+        }
+
+        for (index, source) in sources.iter().enumerate() {
+            if !source.exists() {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error: Source folder does not exist: {}",
+                    source.display()
+                )));
+                return None;
+            }
+            if sources[..index].contains(source) {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error: Source folder is listed twice: {}",
+                    source.display()
+                )));
+                return None;
+            }
+        }
+
+        for remote in &self.remote_destinations {
+            if let Err(e) = remote.store(&self.status_sender) {
+                self.add_status(StatusEvent::Error(format!("Error: {}", e)));
+                return None;
+            }
+        }
+
+        match check_folder(&self.destination_folder, true) {
+            FolderCheck::NotAFolder => {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error: Destination is not a folder: {}",
+                    self.destination_folder
+                )));
+                return None;
+            }
+            FolderCheck::ReadOnly => {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error: Destination folder cannot be written to: {}",
+                    self.destination_folder
+                )));
+                return None;
+            }
+            FolderCheck::Ready | FolderCheck::Missing => {}
+        }
+
+        let destination_path = PathBuf::from(&self.destination_folder);
+
+        if !destination_path.exists() {
+            if let Err(e) = fs::create_dir_all(&destination_path) {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error creating destination folder: {}",
+                    e
+                )));
+                return None;
+            }
+            self.add_status(StatusEvent::Info(format!(
+                "Created destination folder: {}",
+                self.destination_folder
+            )));
+        }
+
+        self.encryption_key = None;
+        if self.encrypt_backups {
+            if self.encryption_passphrase.is_empty() {
+                self.add_status(StatusEvent::Error(
+                    "Error: Enter an encryption passphrase or disable backup encryption"
+                        .to_string(),
+                ));
+                return None;
+            }
+            match EncryptionKey::generate(&self.encryption_passphrase) {
+                Ok(key) => self.encryption_key = Some(Arc::new(key)),
+                Err(e) => {
+                    self.add_status(StatusEvent::Error(format!(
+                        "Error preparing encryption: {}",
+                        e
+                    )));
+                    return None;
+                }
+            }
+        }
+
+        let mut targets: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+        for (source, destination) in self.all_targets() {
+            if let Err(e) = fs::create_dir_all(&destination) {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error creating destination folder {}: {}",
+                    destination.display(),
+                    e
+                )));
+                return None;
+            }
+            match targets.iter_mut().find(|(known, _)| *known == source) {
+                Some((_, destinations)) => destinations.push(destination),
+                None => targets.push((source, vec![destination])),
+            }
+        }
+        Some(targets)
+    }
+
+    fn start_watching(&mut self) {
+        if self.service_state == ServiceState::Running {
+            self.add_status(StatusEvent::Warning(
+                "The SiegeSaver service is already watching; stop it to watch from here"
+                    .to_string(),
+            ));
+            return;
+        }
+        let Some(targets) = self.prepare_backup() else {
+            return;
+        };
+
+        let mut watchers = Vec::new();
+        let mut receivers = Vec::new();
+        let config = self.config();
+        for (source_path, _) in &targets {
+            let (tx, rx) = channel();
+            match watch_folder(
+                source_path,
+                &config.watch_options(source_path),
+                tx,
+                self.status_sender.clone(),
+            ) {
+                Ok(watcher) => watchers.push(watcher),
+                Err(e) => {
+                    self.add_status(StatusEvent::Error(format!(
+                        "{} ({})",
+                        e,
+                        source_path.display()
+                    )));
+                    return;
+                }
+            }
+            receivers.push(rx);
+        }
+
+        self.watchers = watchers;
+        self.is_watching = true;
+        self.watching_since = Some(SystemTime::now());
+        self.last_stale_check = Some(Instant::now());
+        self.stale_warned = false;
+
+        self.retry_uploads(&targets[0].0);
+
+        let initial_sync_limit = self.initial_sync_enabled.then_some(self.initial_sync_limit);
+        self.rescan.interval = config.rescan_schedule().interval;
+        self.backup_queue = Arc::new(BackupQueue::default());
+        for ((source_path, destinations), rx) in targets.into_iter().zip(receivers) {
+            self.add_status(StatusEvent::Started {
+                source: source_path.clone(),
+            });
+
+            // Spawn a thread per source to handle its file events, copying into every
+            // destination
+            let engines: Vec<BackupEngine> = destinations
+                .into_iter()
+                .map(|destination| self.backup_engine(source_path.clone(), destination))
+                .collect();
+            let status_tx = self.status_sender.clone();
+            let paused = Arc::clone(&self.paused);
+            let rescan = self.rescan.clone();
+            let queue = Arc::clone(&self.backup_queue);
+            std::thread::spawn(move || {
+                // Events arriving during the initial sync queue up in the channel meanwhile
+                for engine in &engines {
+                    engine.resume_interrupted(&status_tx);
+                    engine.initial_sync(initial_sync_limit, &status_tx);
+                }
+                handle_mirrored_file_events(rx, &engines, &status_tx, &paused, &rescan, &queue);
+            });
+        }
+    }
+
+    // Uploads what is left in the upload queue from earlier runs, in the background. Queued
+    // keys already include the source's folder, so the remotes of any source will do.
+    fn retry_uploads(&self, source: &Path) {
+        if self.upload_queue.pending().is_empty() {
+            return;
+        }
+        let remotes = self.remotes(source);
+        let queue = Arc::clone(&self.upload_queue);
+        let status_tx = self.status_sender.clone();
+        std::thread::spawn(move || {
+            let (uploaded, error) = retry_uploads(&RealFs, &remotes, &queue);
+            if uploaded > 0 {
+                let _ = status_tx.send(StatusEvent::Info(format!(
+                    "Uploaded {} files left over from earlier",
+                    uploaded
+                )));
+            }
+            if let Some(e) = error {
+                let _ = status_tx.send(StatusEvent::Warning(format!(
+                    "{} uploads are still queued: {}",
+                    queue.pending().len(),
+                    e
+                )));
+            }
+        });
+    }
+
+    // Connects a Google Drive remote to a Google account in the background. The user is asked
+    // in the status log to enter a code on Google's sign-in page.
+    fn connect_google_drive(&self, index: usize) {
+        let Some(RemoteConfig::GoogleDrive(config)) = self.remote_destinations.get(index) else {
+            return;
+        };
+        let config = config.clone();
+        let connecting = Arc::clone(&self.drive_connecting);
+        let token = Arc::clone(&self.drive_token);
+        let status_tx = self.status_sender.clone();
+        connecting.store(true, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            let result = start_authorization(&config).and_then(|authorization| {
+                let _ = status_tx.send(StatusEvent::Info(format!(
+                    "To connect Google Drive, open {} and enter the code {}",
+                    authorization.verification_url, authorization.user_code
+                )));
+                finish_authorization(&config, &authorization)
+            });
+            match result {
+                Ok(refresh_token) => {
+                    *token.lock().unwrap_or_else(|e| e.into_inner()) = Some((index, refresh_token));
+                    let _ =
+                        status_tx.send(StatusEvent::Info(format!("Connected {}", config.label())));
+                }
+                Err(e) => {
+                    let _ = status_tx.send(StatusEvent::Error(format!("Error: {}", e)));
+                }
+            }
+            connecting.store(false, Ordering::Relaxed);
+        });
+    }
+
+    // Holds the backup queue during the quiet hours. Changes are still queued, and the handlers
+    // run them on their own once the queue is released.
+    fn update_quiet_hours(&mut self, ctx: &egui::Context) {
+        let now = chrono::Local::now().naive_local();
+        let quiet = self.quiet_hours_enabled
+            && in_quiet_hours(self.quiet_hours_start, self.quiet_hours_end, now.time());
+        if self.quiet_hours_enabled {
+            let next = if quiet {
+                self.quiet_hours_end
+            } else {
+                self.quiet_hours_start
+            };
+            ctx.request_repaint_after(until_daily_run(next, now).min(Duration::from_secs(60)));
+        }
+        // Watching starts with a new queue, so it is held again every frame
+        self.backup_queue.hold(quiet);
+        if quiet == self.in_quiet_hours {
+            return;
+        }
+        self.in_quiet_hours = quiet;
+        let message = if quiet {
+            format!(
+                "Quiet hours started, changes are queued until {}",
+                self.quiet_hours_end.format("%H:%M")
+            )
+        } else {
+            let queued = self.backup_queue.jobs().len();
+            format!("Quiet hours ended, running {} queued backups", queued)
+        };
+        self.add_status(StatusEvent::Info(message));
+    }
+
+    // Starts the scheduled full sync once its time of day has come
+    fn run_scheduled_sync(&mut self, ctx: &egui::Context) {
+        if !self.scheduled_sync_enabled {
+            return;
+        }
+        let now = chrono::Local::now();
+        let time = self.scheduled_sync_time;
+        // Checked at least every minute, in case the clock jumps, e.g. after sleep
+        ctx.request_repaint_after(
+            until_daily_run(time, now.naive_local()).min(Duration::from_secs(60)),
+        );
+        let last_run = self
+            .last_scheduled_sync
+            .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+            .map(|time| time.with_timezone(&chrono::Local).naive_local());
+        if !daily_run_due(time, now.naive_local(), last_run, self.started_at) {
+            return;
+        }
+        self.last_scheduled_sync = Some(now.timestamp());
+        self.save_config();
+        if self.manual_backup_running.load(Ordering::Relaxed) {
+            self.add_status(StatusEvent::Info(
+                "Skipped the scheduled full sync, a backup of all matches is already running"
+                    .to_string(),
+            ));
+            return;
+        }
+        self.add_status(StatusEvent::Info(
+            "Starting the scheduled full sync".to_string(),
+        ));
+        self.backup_now();
+    }
+
+    // Copies every existing match that is missing from the destination, in the background
+    fn backup_now(&mut self) {
+        if self.manual_backup_running.load(Ordering::Relaxed) {
+            return;
+        }
+        // While watching, reuse the folders and key the watchers were started with
+        let targets = if self.is_watching {
+            self.all_targets()
+        } else {
+            match self.prepare_backup() {
+                Some(targets) => targets
+                    .into_iter()
+                    .flat_map(|(source, destinations)| {
+                        destinations
+                            .into_iter()
+                            .map(move |destination| (source.clone(), destination))
+                    })
+                    .collect(),
+                None => return,
+            }
+        };
+
+        let mut engines = Vec::new();
+        for (source_path, destination_path) in targets {
+            self.add_status(StatusEvent::Info(format!(
+                "Backing up existing matches from {} to {}",
+                source_path.display(),
+                destination_path.display()
+            )));
+            engines.push(self.backup_engine(source_path, destination_path));
+        }
+        let status_tx = self.status_sender.clone();
+        let running = Arc::clone(&self.manual_backup_running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            for engine in engines {
+                engine.initial_sync(None, &status_tx);
+            }
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    // Lists what the retention policy would delete from the destination right now
+    fn preview_pruning(&mut self) {
+        let destination = PathBuf::from(normalize_path_input(&self.destination_folder));
+        if !destination.is_dir() {
+            self.add_status(StatusEvent::Error(
+                "Error: Destination folder does not exist".to_string(),
+            ));
+            return;
+        }
+        let policy = self.retention_policy();
+        if !policy.is_enabled() {
+            self.add_status(StatusEvent::Info(
+                "No retention policy set, old backups are kept".to_string(),
+            ));
+            return;
+        }
+        let mut pruned = 0;
+        for (source, destination) in self.all_targets() {
+            if !destination.is_dir() {
+                continue;
+            }
+            let options = self.backup_options(&source);
+            let destination = self.current_backups(destination);
+            let engine = BackupEngine::new(source, destination, options).with_retention(policy);
+            pruned += engine.prune(true, &self.status_sender);
+        }
+        if pruned == 0 {
+            self.add_status(StatusEvent::Info(
+                "Pruning preview: no old backups would be deleted".to_string(),
+            ));
+        }
+    }
+
+    // Engines for verifying or repairing the destinations as currently configured
+    fn verify_engines(&mut self) -> Option<Vec<BackupEngine>> {
+        if self.verify_running.load(Ordering::Relaxed) {
+            return None;
+        }
+        let destination = PathBuf::from(normalize_path_input(&self.destination_folder));
+        if !destination.is_dir() {
+            self.add_status(StatusEvent::Error(
+                "Error: Destination folder does not exist".to_string(),
+            ));
+            return None;
+        }
+        Some(
+            self.all_targets()
+                .into_iter()
+                .filter(|(_, destination)| destination.is_dir())
+                .map(|(source, destination)| {
+                    let options = self.backup_options(&source);
+                    BackupEngine::new(source, self.current_backups(destination), options)
+                })
+                .collect(),
+        )
+    }
+
+    // Re-hashes every backed-up file against the manifests, in the background
+    fn verify_backups(&mut self) {
+        let Some(engines) = self.verify_engines() else {
+            return;
+        };
+        self.damaged_backups.clear();
+        self.add_status(StatusEvent::Info(format!(
+            "Verifying backups in {}",
+            self.destination_folder
+        )));
+        let status_tx = self.status_sender.clone();
+        let running = Arc::clone(&self.verify_running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            for engine in engines {
+                engine.verify(&status_tx);
+            }
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    // Puts a test match into the first source folder and checks in the background that the
+    // watcher backs it up into the destination, verified and recorded, then deletes it again
+    fn run_test_backup(&mut self) {
+        if self.self_test_running.load(Ordering::Relaxed) {
+            return;
+        }
+        if self.in_quiet_hours {
+            self.add_status(StatusEvent::Warning(
+                "Backups are held for the quiet hours, so a test backup would not finish until \
+                 they end"
+                    .to_string(),
+            ));
+            return;
+        }
+        let Some((source, destination)) = self.backup_targets().into_iter().next() else {
+            return;
+        };
+        let test = SelfTest {
+            source_folder: source,
+            destination_folder: destination,
+            archived: self.storage_format != StorageFormat::Folder,
+            snapshots: self.snapshot_runs,
+            // Long enough for the watcher to settle and wait for the replays to be finished
+            timeout: SELF_TEST_TIMEOUT
+                + Duration::from_millis(self.debounce_ms)
+                + Duration::from_secs(self.quiet_period_secs * 2),
+        };
+        let name = format!(
+            "{}{}",
+            TEST_MATCH_PREFIX,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let status_tx = self.status_sender.clone();
+        let running = Arc::clone(&self.self_test_running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            run_self_test(&test, &name, &status_tx);
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    // Copies the files the last verification found missing or damaged again, in the background
+    fn repair_backups(&mut self) {
+        if self.damaged_backups.is_empty() {
+            return;
+        }
+        if self.encrypt_backups && self.encryption_key.is_none() {
+            // Re-copied files have to be encrypted like the rest of the destination
+            if self.prepare_backup().is_none() {
+                return;
+            }
+        }
+        let Some(engines) = self.verify_engines() else {
+            return;
+        };
+        let reports = std::mem::take(&mut self.damaged_backups);
+        let status_tx = self.status_sender.clone();
+        let running = Arc::clone(&self.verify_running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            for engine in engines {
+                let report = reports
+                    .iter()
+                    .find(|report| report.destination == engine.destination_folder());
+                if let Some(report) = report {
+                    engine.repair(report, &status_tx);
+                    engine.verify(&status_tx);
+                }
+            }
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    // Destinations of sources that have not been backed up yet are skipped when there are
+    // several sources
+    // The folder with the current backups of `destination`: the latest snapshot in it when
+    // every run gets its own
+    fn current_backups(&self, destination: PathBuf) -> PathBuf {
+        if !self.snapshot_runs {
+            return destination;
+        }
+        latest_snapshot(&RealFs, &destination).unwrap_or(destination)
+    }
+
+    fn existing_targets(&self) -> Vec<(PathBuf, PathBuf)> {
+        let targets = self.backup_targets();
+        if targets.len() < 2 {
+            return targets;
+        }
+        targets
+            .into_iter()
+            .filter(|(_, destination)| destination.is_dir())
+            .collect()
+    }
+
+    fn refresh_restore_list(&mut self) {
+        self.restore_candidates.clear();
+        self.restore_snapshots = match self.backup_targets().first() {
+            Some((_, destination)) if self.snapshot_runs => snapshots(&RealFs, destination)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|snapshot| Some(snapshot.file_name()?.to_string_lossy().into_owned()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if self
+            .restore_snapshot
+            .as_ref()
+            .is_some_and(|name| !self.restore_snapshots.contains(name))
+        {
+            self.restore_snapshot = None;
+        }
+        for (source, destination) in self.existing_targets() {
+            // Point-in-time recovery from an earlier run
+            let destination = match &self.restore_snapshot {
+                Some(name) => destination.join(name),
+                None => self.current_backups(destination),
+            };
+            match restore_candidates(&destination) {
+                Ok(candidates) => self.restore_candidates.extend(
+                    candidates
+                        .into_iter()
+                        .map(|candidate| (candidate, source.clone(), false)),
+                ),
+                Err(e) => {
+                    self.add_status(StatusEvent::Error(format!(
+                        "Error listing backed-up matches in {}: {}",
+                        destination.display(),
+                        e
+                    )));
+                }
+            }
+        }
+    }
+
+    fn refresh_library(&mut self) {
+        self.library.clear();
+        for (_, destination) in self.existing_targets() {
+            let destination = self.current_backups(destination);
+            match Manifest::load(&RealFs, &destination) {
+                Ok(manifest) => self.library.push((destination, manifest)),
+                Err(e) => {
+                    self.add_status(StatusEvent::Error(format!(
+                        "Error reading the backup library in {}: {}",
+                        destination.display(),
+                        e
+                    )));
+                }
+            }
+        }
+    }
+
+    // Library items of every destination passing the filter, most recently backed up first
+    fn library_items(&self, filter: &LibraryFilter) -> Vec<(&Path, &str, &ManifestEntry)> {
+        let mut items: Vec<_> = self
+            .library
+            .iter()
+            .flat_map(|(destination, manifest)| {
+                filter_library(manifest, filter)
+                    .into_iter()
+                    .map(move |(name, entry)| (destination.as_path(), name, entry))
+            })
+            .collect();
+        items.sort_by_key(|(_, _, entry)| std::cmp::Reverse(entry.last_backup));
+        items
+    }
+
+    // Stores the tags and notes being edited. The manifest is read again first so entries
+    // written by backups since the library was loaded are kept.
+    fn save_library_annotations(&mut self) {
+        let Some((destination, name, tags, notes)) = self.library_editing.take() else {
+            return;
+        };
+        let result = Manifest::load(&RealFs, &destination).and_then(|mut manifest| {
+            let entry = manifest.entries.get_mut(&name).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("{} is no longer in the destination", name),
+                )
+            })?;
+            entry.tags = parse_tags(&tags);
+            entry.notes = notes.trim().to_string();
+            manifest.save(&RealFs, &destination)?;
+            Ok(manifest)
+        });
+        match result {
+            Ok(manifest) => {
+                if let Some((_, loaded)) = self
+                    .library
+                    .iter_mut()
+                    .find(|(loaded_from, _)| *loaded_from == destination)
+                {
+                    *loaded = manifest;
+                }
+            }
+            Err(e) => self.add_status(StatusEvent::Error(format!(
+                "Error saving tags and notes for {}: {}",
+                name, e
+            ))),
+        }
+    }
+
+    // Asks where to save the listed library items and writes them there
+    fn export_library_items(&mut self, filter: &LibraryFilter, format: ExportFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.extension(), &[format.extension()])
+            .set_file_name(format!("siegesaver-library.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        let items: Vec<_> = self
+            .library_items(filter)
+            .into_iter()
+            .map(|(_, name, entry)| (name, entry))
+            .collect();
+        let result = export_library(&items, format)
+            .and_then(|contents| fs::write(&path, contents).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => self.add_status(StatusEvent::Info(format!(
+                "Exported {} items to {}",
+                items.len(),
+                path.display()
+            ))),
+            Err(e) => self.add_status(StatusEvent::Error(format!(
+                "Error exporting the library to {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    // Packs the ticked library items into a zip file for sharing, in the background
+    fn export_selected_matches(&mut self) {
+        let items: Vec<(PathBuf, String, ManifestEntry)> = self
+            .library
+            .iter()
+            .flat_map(|(destination, manifest)| {
+                manifest
+                    .entries
+                    .iter()
+                    .map(move |(name, entry)| (destination, name, entry))
+            })
+            .filter(|(destination, name, _)| {
+                self.library_selected
+                    .contains(&(destination.to_path_buf(), name.to_string()))
+            })
+            .map(|(destination, name, entry)| (destination.clone(), name.clone(), entry.clone()))
+            .collect();
+        if items.is_empty() {
+            return;
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("zip", &["zip"])
+            .set_file_name("siegesaver-matches.zip")
+            .save_file()
+        else {
+            return;
+        };
+        let status_tx = self.status_sender.clone();
+        std::thread::spawn(move || {
+            let event = match export_matches(&RealFs, &items, &path) {
+                Ok(exported) => StatusEvent::Info(format!(
+                    "Exported {} matches to {}",
+                    exported,
+                    path.display()
+                )),
+                Err(e) => StatusEvent::Error(format!(
+                    "Error exporting the matches to {}: {}",
+                    path.display(),
+                    e
+                )),
+            };
+            let _ = status_tx.send(event);
+        });
+    }
+
+    // Adds the matches of a zip file exported by SiegeSaver to the destination of the first
+    // source, in the background
+    fn import_shared_matches(&mut self) {
+        let Some((_, destination)) = self.backup_targets().into_iter().next() else {
+            self.add_status(StatusEvent::Error(
+                "Error: Set the source and destination folders in the Backup tab before \
+                 importing matches"
+                    .to_string(),
+            ));
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("zip", &["zip"])
+            .pick_file()
+        else {
+            return;
+        };
+        let destination = self.current_backups(destination);
+        let status_tx = self.status_sender.clone();
+        std::thread::spawn(move || {
+            let event = match import_matches(&RealFs, &path, &destination) {
+                Ok(report) => {
+                    if !report.skipped.is_empty() {
+                        let _ = status_tx.send(StatusEvent::Warning(format!(
+                            "Skipped {} matches that are already in {}: {}",
+                            report.skipped.len(),
+                            destination.display(),
+                            report.skipped.join(", ")
+                        )));
+                    }
+                    StatusEvent::Info(format!(
+                        "Imported {} matches from {} into {}; refresh the library to see them",
+                        report.imported.len(),
+                        path.display(),
+                        destination.display()
+                    ))
+                }
+                Err(e) => StatusEvent::Error(format!(
+                    "Error importing the matches from {}: {}",
+                    path.display(),
+                    e
+                )),
+            };
+            let _ = status_tx.send(event);
+        });
+    }
+
+    // Copies the selected matches back into the source folder in the background. Unless
+    // `overwrite` is set, matches that are still in the source are confirmed first.
+    fn restore_selected(&mut self, overwrite: bool) {
+        if self.restore_running.load(Ordering::Relaxed) {
+            return;
+        }
+        let selected: Vec<(RestoreCandidate, PathBuf)> = self
+            .restore_candidates
+            .iter()
+            .filter(|(_, _, selected)| *selected)
+            .map(|(candidate, target, _)| (candidate.clone(), target.clone()))
+            .collect();
+        if let Some((_, target)) = selected.iter().find(|(_, target)| !target.is_dir()) {
+            self.add_status(StatusEvent::Error(format!(
+                "Error: Source folder does not exist, set it in the Backup tab: {}",
+                target.display()
+            )));
+            return;
+        }
+        let conflicts: Vec<String> = selected
+            .iter()
+            .filter(|(candidate, target)| target.join(&candidate.name).exists())
+            .map(|(candidate, _)| candidate.name.clone())
+            .collect();
+        if !overwrite && !conflicts.is_empty() {
+            self.restore_conflicts = Some(conflicts);
+            return;
+        }
+
+        let mut decryptor = (!self.encryption_passphrase.is_empty())
+            .then(|| Decryptor::new(&self.encryption_passphrase));
+        let status_tx = self.status_sender.clone();
+        let running = Arc::clone(&self.restore_running);
+        running.store(true, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            let total = selected.len();
+            for (done, (candidate, target)) in selected.iter().enumerate() {
+                let _ = status_tx.send(StatusEvent::RestoreProgress { done, total });
+                let event = match restore_match(&candidate.backup, target, &mut decryptor) {
+                    Ok(files) => StatusEvent::Restored {
+                        name: candidate.name.clone(),
+                        files,
+                    },
+                    Err(e) => {
+                        StatusEvent::Error(format!("Error restoring {}: {}", candidate.name, e))
+                    }
+                };
+                let _ = status_tx.send(event);
+            }
+            let _ = status_tx.send(StatusEvent::RestoreProgress { done: total, total });
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    fn stop_watching(&mut self) {
+        self.watchers.clear();
+        self.is_watching = false;
+        self.watching_since = None;
+        self.pause.resume();
+        self.paused.store(false, Ordering::Relaxed);
+        self.backup_queue = Arc::new(BackupQueue::default());
+        self.offline_destinations.clear();
+        self.add_status(StatusEvent::Stopped);
+    }
+
+    // Points the destination or mirror setting that `from` is in to where its drive is now
+    fn follow_moved_destination(&mut self, from: &Path, to: &Path) {
+        let folders = std::iter::once(&mut self.destination_folder).chain(&mut self.mirror_folders);
+        for folder in folders {
+            let path = PathBuf::from(normalize_path_input(folder));
+            let Ok(relative) = from.strip_prefix(&path) else {
+                continue;
+            };
+            if path.as_os_str().is_empty() {
+                continue;
+            }
+            if let Some(moved) = to.ancestors().nth(relative.components().count()) {
+                *folder = moved.display().to_string();
+            }
+            break;
+        }
+        self.save_config();
+    }
+
+    fn open_folder(&mut self, folder: &Path) {
+        if let Err(e) = open_folder(folder) {
+            self.add_status(StatusEvent::Error(format!(
+                "Error opening {}: {}",
+                folder.display(),
+                e
+            )));
+        }
+    }
+
+    fn pause_watching(&mut self, duration: Duration) {
+        if !self.is_watching {
+            self.add_status(StatusEvent::Error(
+                "Error: Not watching, nothing to pause".to_string(),
+            ));
+            return;
+        }
+        self.pause.pause_for(Instant::now(), duration);
+        self.paused.store(true, Ordering::Relaxed);
+        self.add_status(StatusEvent::Info(format!(
+            "Backups paused for {}",
+            format_countdown(duration)
+        )));
+    }
+
+    fn resume_watching(&mut self) {
+        if !self.pause.is_paused() {
+            return;
+        }
+        self.pause.resume();
+        self.paused.store(false, Ordering::Relaxed);
+        self.add_status(StatusEvent::Info("Backups resumed".to_string()));
+    }
+
+    fn update_pause(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        if self.pause.poll_expired(now) {
+            self.paused.store(false, Ordering::Relaxed);
+            self.add_status(StatusEvent::Info(
+                "Pause ended, backups resumed".to_string(),
+            ));
+        }
+
+        let paused = self.pause.remaining(now);
+        if paused.is_some() {
+            // Keep the countdown ticking even when nothing else happens
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        let last_backup = self
+            .history
+            .entries
+            .iter()
+            .filter(|entry| entry.error.is_none())
+            .map(|entry| entry.time)
+            .max()
+            .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+            .map(|time| time.with_timezone(&chrono::Local));
+        let destination = self
+            .destination_summary
+            .as_ref()
+            .map(|(_, matches, bytes)| (*matches, *bytes));
+        self.tray.set_tooltip(tray_tooltip(
+            self.is_watching,
+            paused,
+            last_backup,
+            destination,
+            chrono::Local::now(),
+        ));
+        self.tray.set_watching(self.is_watching);
+    }
+
+    // Counts the matches in the destination's manifest, without building one if it is missing
+    fn refresh_destination_summary(&mut self) {
+        let destination = Path::new(&self.destination_folder);
+        let summary =
+            if !self.destination_folder.is_empty() && Manifest::exists(&RealFs, destination) {
+                Manifest::load(&RealFs, destination)
+                    .map(|manifest| (manifest.entries.len(), manifest.total_bytes()))
+                    .unwrap_or_default()
+            } else {
+                (0, 0)
+            };
+        self.destination_summary = Some((self.destination_folder.clone(), summary.0, summary.1));
+    }
+
+    fn update_tray_icon(&mut self, ctx: &egui::Context) {
+        let status = if self.copy_activity.is_copying() {
+            TrayStatus::Copying
+        } else if self.tray_error {
+            TrayStatus::Error
+        } else if self.is_watching {
+            TrayStatus::Watching
+        } else {
+            TrayStatus::Idle
+        };
+        let frame = (self.started.elapsed().as_millis() / ANIMATION_INTERVAL.as_millis()) as usize;
+        self.tray.set_status(status, frame);
+        if status == TrayStatus::Copying {
+            ctx.request_repaint_after(ANIMATION_INTERVAL);
+        } else if self.is_watching {
+            // Copies start on the watcher's thread; look for them even while the window is idle
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+
+    fn check_backup_staleness(&mut self) {
+        if !self.is_watching || !self.stale_warning_enabled {
+            return;
+        }
+        let Some(watching_since) = self.watching_since else {
+            return;
+        };
+        if self
+            .last_stale_check
+            .is_some_and(|checked| checked.elapsed() < STALE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_stale_check = Some(Instant::now());
+
+        let last_backup = self.stats.lock().map(|s| s.last_backup).unwrap_or(None);
+        let stale_after = Duration::from_secs(self.stale_after_minutes * 60);
+        let newest_change = self
+            .source_paths()
+            .iter()
+            .filter_map(|source| newest_source_change(source))
+            .max();
+        let stale = is_backup_stale(
+            last_backup,
+            watching_since,
+            newest_change,
+            SystemTime::now(),
+            stale_after,
+        );
+
+        if stale && !self.stale_warned {
+            self.stale_warned = true;
+            let warning = StatusEvent::Warning(format!(
+                "Warning: No backup in the last {} minutes although the source folder changed. \
+                 The watcher may have stopped or the game may be writing elsewhere.",
+                self.stale_after_minutes
+            ));
+            self.desktop_notifications.notify(&warning);
+            self.add_status(warning);
+        } else if !stale {
+            self.stale_warned = false;
+        }
+    }
+
+    fn decrypt_backups(&mut self) {
+        if self.encryption_passphrase.is_empty() {
+            self.add_status(StatusEvent::Error(
+                "Error: Enter the passphrase the backups were encrypted with".to_string(),
+            ));
+            return;
+        }
+        let Some(source) = rfd::FileDialog::new()
+            .set_title(tr("Select encrypted backup folder"))
+            .pick_folder()
+        else {
+            return;
+        };
+        let Some(destination) = rfd::FileDialog::new()
+            .set_title(tr("Select where to save decrypted files"))
+            .pick_folder()
+        else {
+            return;
+        };
+
+        self.add_status(StatusEvent::Info(format!(
+            "Decrypting backups in {}",
+            source.display()
+        )));
+        let status_tx = self.status_sender.clone();
+        let mut decryptor = Decryptor::new(&self.encryption_passphrase);
+        std::thread::spawn(move || {
+            let event = match decrypt_directory(&source, &destination, &mut decryptor) {
+                Ok(count) => StatusEvent::Info(format!(
+                    "Decrypted {} file(s) into {}",
+                    count,
+                    destination.display()
+                )),
+                Err(e) => StatusEvent::Error(format!("Error decrypting backups: {}", e)),
+            };
+            let _ = status_tx.send(event);
+        });
+    }
+
+    // Follows the state of the Windows service. When it starts, it takes over watching.
+    fn check_service(&mut self, ctx: &egui::Context) {
+        if self.service_state != ServiceState::NotInstalled {
+            ctx.request_repaint_after(SERVICE_CHECK_INTERVAL);
+        }
+        if self
+            .last_service_check
+            .is_some_and(|checked| checked.elapsed() < SERVICE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_service_check = Some(Instant::now());
+        self.service_state = service_state();
+        if self.service_state == ServiceState::Running && self.is_watching {
+            self.stop_watching();
+            self.add_status(StatusEvent::Info(
+                "The SiegeSaver service took over watching".to_string(),
+            ));
+        }
+    }
+
+    fn control_service(&mut self, start: bool) {
+        let result = if start {
+            start_service()
+        } else {
+            stop_service()
+        };
+        match result {
+            Ok(()) => self.add_status(StatusEvent::Info(format!(
+                "{} the SiegeSaver service",
+                if start { "Starting" } else { "Stopping" }
+            ))),
+            Err(e) => self.add_status(StatusEvent::Error(e)),
+        }
+        self.last_service_check = None;
+    }
+
+    // Runs the HTTP API on the configured port, or stops it
+    fn restart_api(&mut self) {
+        let port = self.api.as_ref().map(ApiServer::port);
+        if self.api_enabled && port == Some(self.api_port) {
+            return;
+        }
+        // Closes the port first, in case the new server listens on the same one
+        self.api = None;
+        if !self.api_enabled {
+            return;
+        }
+        let history_path = self.config_path.with_file_name(HISTORY_FILE_NAME);
+        let ctx = self.ctx.clone();
+        match ApiServer::spawn(self.api_port, history_path, move || ctx.request_repaint()) {
+            Ok(api) => {
+                self.add_status(StatusEvent::Info(format!(
+                    "HTTP API listening on http://127.0.0.1:{}",
+                    api.port()
+                )));
+                self.api = Some(api);
+            }
+            Err(e) => self.add_status(StatusEvent::Error(format!(
+                "Error starting the HTTP API on port {}: {}",
+                self.api_port, e
+            ))),
+        }
+    }
+
+    // Shares the current status with the HTTP API and carries out the commands it received
+    fn update_api(&mut self) {
+        let Some(api) = &self.api else {
+            return;
+        };
+        let commands = api.take_commands();
+        let (files_backed_up, bytes_backed_up, errors, last_backup) = match self.stats.lock() {
+            Ok(stats) => (
+                stats.files_backed_up,
+                stats.bytes_backed_up,
+                stats.errors,
+                stats
+                    .last_backup
+                    .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|since| since.as_secs()),
+            ),
+            Err(_) => (0, 0, 0, None),
+        };
+        api.update(ApiStatus {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            watching: self.is_watching,
+            paused_seconds: self
+                .pause
+                .remaining(Instant::now())
+                .map(|remaining| remaining.as_secs()),
+            sources: self
+                .source_paths()
+                .iter()
+                .map(|source| source.display().to_string())
+                .collect(),
+            destination: self.destination_folder.clone(),
+            queued: self.backup_queue.jobs().len(),
+            files_backed_up,
+            bytes_backed_up,
+            errors,
+            last_backup,
+        });
+        for command in commands {
+            match command {
+                ApiCommand::StartWatching if !self.is_watching => self.start_watching(),
+                ApiCommand::StopWatching if self.is_watching => self.stop_watching(),
+                _ => {}
+            }
+        }
+    }
+
+    fn write_metrics(&mut self, ctx: &egui::Context) {
+        if self.prometheus_textfile.is_empty() {
+            return;
+        }
+        ctx.request_repaint_after(METRICS_INTERVAL);
+        if self
+            .last_metrics_write
+            .is_some_and(|written| written.elapsed() < METRICS_INTERVAL)
+        {
+            return;
+        }
+        self.last_metrics_write = Some(Instant::now());
+
+        let metrics = match self.stats.lock() {
+            Ok(stats) => {
+                let sources = self
+                    .source_paths()
+                    .iter()
+                    .map(|source| source.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(";");
+                render_prometheus_metrics(&stats, &sources, self.is_watching)
+            }
+            Err(_) => return,
+        };
+        let path = PathBuf::from(normalize_path_input(&self.prometheus_textfile));
+        if let Err(e) = write_prometheus_textfile(&path, &metrics) {
+            self.add_status(StatusEvent::Error(format!(
+                "Error writing Prometheus textfile {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    }
+
+    fn set_start_on_boot(&mut self, enabled: bool) {
+        match set_autostart(enabled, self.autostart_mode, &self.config_path) {
+            Ok(()) => {
+                self.start_on_boot = enabled;
+                self.save_config();
+                let status = if enabled { "enabled" } else { "disabled" };
+                self.add_status(StatusEvent::Info(format!(
+                    "Start on system boot {}",
+                    status
+                )));
+            }
+            Err(e) => {
+                self.add_status(StatusEvent::Error(format!(
+                    "Error setting start on boot: {}",
+                    e
+                )));
+            }
+        }
+    }
+}
+
+impl SiegeSaverApp {
+    // The end of the log with the lines of this session, filtered by level and text
+    fn show_log(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Log:"));
+            let since = self.session_start;
+            let errors = self
+                .log_records
+                .iter()
+                .filter(|record| record.severity == Severity::Error && record.time >= since)
+                .count();
+            if errors > 0 {
+                ui.colored_label(egui::Color32::RED, tr_fmt("{} error(s)", &[&errors]))
+                    .on_hover_text(tr("Since SiegeSaver started"));
+            }
+            ui.checkbox(&mut self.log_filter.info, tr("Info"));
+            ui.checkbox(&mut self.log_filter.warnings, tr("Warnings"));
+            ui.checkbox(&mut self.log_filter.errors, tr("Errors"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.log_filter.search)
+                    .hint_text(tr("Search"))
+                    .desired_width(150.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button(tr("Copy"))
+                .on_hover_text(tr("Copies the shown lines with the version and settings"))
+                .clicked()
+            {
+                ui.ctx().copy_text(self.log_report());
+            }
+            if ui.button(tr("Export log...")).clicked() {
+                self.export_log();
+            }
+            ui.checkbox(&mut self.anonymize_log, tr("Hide folder paths"));
+        });
+
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for record in &self.log_records {
+                    if !self.log_filter.matches(record) {
+                        continue;
+                    }
+                    let line = format!(
+                        "{}  {}",
+                        record.time.format("%Y-%m-%d %H:%M:%S"),
+                        record.text
+                    );
+                    let text = match record.severity {
+                        Severity::Info => egui::RichText::new(&line),
+                        Severity::Success => {
+                            egui::RichText::new(&line).color(egui::Color32::LIGHT_GREEN)
+                        }
+                        Severity::Warning => {
+                            egui::RichText::new(&line).color(ui.visuals().warn_fg_color)
+                        }
+                        Severity::Error => egui::RichText::new(&line).color(egui::Color32::RED),
+                    };
+                    match &record.detail {
+                        Some(detail) => {
+                            let response = ui
+                                .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                .on_hover_text(tr("Click for details"));
+                            if response.clicked() {
+                                self.open_detail = Some((line, detail.clone()));
+                            }
+                        }
+                        None => {
+                            ui.label(text);
+                        }
+                    }
+                }
+            });
+    }
+
+    fn show_event_detail(&mut self, ctx: &egui::Context) {
+        let Some((text, detail)) = &self.open_detail else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new(tr("Event details"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(text);
+                ui.separator();
+                egui::Grid::new("event_detail_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr("Source:"));
+                        ui.label(detail.source.display().to_string());
+                        ui.end_row();
+                        ui.label(tr("Destination:"));
+                        ui.label(detail.destination.display().to_string());
+                        ui.end_row();
+                        if let Some(files) = detail.files {
+                            ui.label(tr("Files:"));
+                            ui.label(files.to_string());
+                            ui.end_row();
+                        }
+                        if let Some(bytes) = detail.bytes {
+                            ui.label(tr("Size:"));
+                            ui.label(format_bytes(bytes));
+                            ui.end_row();
+                        }
+                        if let Some(duration) = detail.duration {
+                            ui.label(tr("Duration:"));
+                            ui.label(format!("{:.2} s", duration.as_secs_f64()));
+                            ui.end_row();
+                        }
+                    });
+                if let Some(error) = &detail.error {
+                    ui.separator();
+                    ui.colored_label(egui::Color32::RED, error);
+                    if ui.button(tr("Copy error")).clicked() {
+                        ctx.copy_text(format!("{}\n{}", text, error));
+                    }
+                }
+            });
+
+        if !open {
+            self.open_detail = None;
+        }
+    }
+}
+
+impl SiegeSaverApp {
+    // Waiting and running backups, with buttons to reorder and cancel them
+    fn show_backup_queue(&mut self, ui: &mut egui::Ui) {
+        let jobs = self.backup_queue.jobs();
+        if jobs.is_empty() {
+            return;
+        }
+        // The queue changes without status events, e.g. when a job starts
+        ui.ctx().request_repaint_after(Duration::from_millis(250));
+        egui::CollapsingHeader::new(tr_fmt("Backup queue ({})", &[&jobs.len()]))
+            .default_open(true)
+            .show(ui, |ui| {
+                for job in &jobs {
+                    let relative = job
+                        .path
+                        .strip_prefix(&job.source_folder)
+                        .unwrap_or(&job.path);
+                    ui.horizontal(|ui| {
+                        if job.running {
+                            ui.spinner();
+                        } else {
+                            if ui
+                                .small_button("⏶")
+                                .on_hover_text(tr("Back up earlier"))
+                                .clicked()
+                            {
+                                self.backup_queue.move_job(job.id, -1);
+                            }
+                            if ui
+                                .small_button("⏷")
+                                .on_hover_text(tr("Back up later"))
+                                .clicked()
+                            {
+                                self.backup_queue.move_job(job.id, 1);
+                            }
+                        }
+                        if ui
+                            .small_button("✖")
+                            .on_hover_text(tr("Cancel this backup"))
+                            .clicked()
+                        {
+                            self.backup_queue.cancel(job.id);
+                        }
+                        ui.label(relative.display().to_string())
+                            .on_hover_text(format!(
+                                "{} → {}",
+                                job.path.display(),
+                                job.destination_folder.display()
+                            ));
+                        // Large files of the job that are being copied
+                        let (copied, total) = self
+                            .copy_progress
+                            .iter()
+                            .filter(|(path, _)| path.starts_with(relative))
+                            .fold((0, 0), |(copied, total), (_, progress)| {
+                                (copied + progress.0, total + progress.1)
+                            });
+                        if job.running && total > 0 {
+                            ui.add(
+                                egui::ProgressBar::new(copied as f32 / total as f32)
+                                    .desired_width(150.0)
+                                    .text(format!(
+                                        "{}/{}",
+                                        format_bytes(copied),
+                                        format_bytes(total)
+                                    )),
+                            );
+                        }
+                    });
+                }
+            });
+    }
+
+    // Backups mirror mode is about to delete, each with a button to keep it, and the backups
+    // it already moved to the trash, each with a button to bring it back
+    fn show_pending_deletions(&mut self, ui: &mut egui::Ui) {
+        if !self.mirror_deletions {
+            return;
+        }
+        let pending = self.deletions.pending();
+        if !pending.is_empty() {
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+            egui::CollapsingHeader::new(tr_fmt("Backups to delete ({})", &[&pending.len()]))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for deletion in &pending {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .small_button(tr("Keep backup"))
+                                .on_hover_text(tr(
+                                    "Keep this backup although its match was deleted",
+                                ))
+                                .clicked()
+                            {
+                                self.deletions
+                                    .keep(&deletion.destination_folder, &deletion.name);
+                            }
+                            let remaining = deletion
+                                .due
+                                .duration_since(SystemTime::now())
+                                .unwrap_or_default();
+                            ui.label(tr_fmt(
+                                "{} in {} min",
+                                &[&deletion.name, &remaining.as_secs().div_ceil(60)],
+                            ))
+                            .on_hover_text(deletion.destination_folder.display().to_string());
+                        });
+                    }
+                });
+        }
+
+        let response = egui::CollapsingHeader::new(tr("Deleted backups"))
+            .id_salt("trashed_backups")
+            .show(ui, |ui| {
+                let destinations: Vec<PathBuf> = self
+                    .all_targets()
+                    .into_iter()
+                    .map(|(_, destination)| self.current_backups(destination))
+                    .collect();
+                let trashed = self.trashed_backups.get_or_insert_with(|| {
+                    destinations
+                        .iter()
+                        .flat_map(|destination| {
+                            trashed_backups(&RealFs, destination).unwrap_or_default()
+                        })
+                        .collect()
+                });
+                if trashed.is_empty() {
+                    ui.label(tr("The trash is empty."));
+                }
+                let mut restored = None;
+                for backup in trashed.iter() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .small_button(tr("Undo"))
+                            .on_hover_text(tr("Move this backup back out of the trash"))
+                            .clicked()
+                        {
+                            restored = Some(backup.clone());
+                        }
+                        let deleted = chrono::DateTime::<chrono::Local>::from(backup.deleted);
+                        ui.label(format!(
+                            "{} ({})",
+                            backup.name,
+                            deleted.format("%Y-%m-%d %H:%M")
+                        ))
+                        .on_hover_text(backup.destination_folder.display().to_string());
+                    });
+                }
+                ui.label(tr_fmt(
+                    "Deleted backups are kept for {} days.",
+                    &[&TRASH_KEEP_DAYS],
+                ));
+                restored
+            });
+        if response.fully_closed() {
+            // Read again the next time it is opened
+            self.trashed_backups = None;
+        }
+        if let Some(backup) = response.body_returned.flatten() {
+            self.trashed_backups = None;
+            match restore_from_trash(&RealFs, &backup) {
+                Ok(()) => self.add_status(StatusEvent::Info(format!(
+                    "Restored the backup {} from the trash",
+                    backup.name
+                ))),
+                Err(e) => self.add_status(StatusEvent::Error(format!(
+                    "Error restoring the backup {} from the trash: {}",
+                    backup.name, e
+                ))),
+            }
+        }
+    }
+
+    fn show_backup_tab(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Profile:"));
+            let mut selected = self.active_profile.clone();
+            egui::ComboBox::from_id_salt("profile")
+                .selected_text(&selected)
+                .show_ui(ui, |ui| {
+                    for name in self.config().profile_names() {
+                        ui.selectable_value(&mut selected, name.clone(), name);
+                    }
+                });
+            if selected != self.active_profile {
+                self.switch_profile(&selected);
+            }
+            if ui
+                .add_enabled(!self.profiles.is_empty(), egui::Button::new(tr("Delete")))
+                .on_hover_text(tr("Delete this profile; its backups are kept"))
+                .clicked()
+            {
+                self.change_profile(AppConfig::remove_active_profile);
+            }
+            ui.separator();
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_profile_name)
+                    .hint_text(tr("New profile name"))
+                    .desired_width(140.0),
+            );
+            if ui
+                .button(tr("Add profile"))
+                .on_hover_text(tr(
+                    "Profiles keep their own folders and filters, e.g. for other games or accounts",
+                ))
+                .clicked()
+            {
+                let name = std::mem::take(&mut self.new_profile_name);
+                self.change_profile(|config| config.add_profile(&name));
+            }
+        });
+
+        // On the frame of the drop, the fields take the folders dropped onto them first
+        let dropping = ui.input(|i| !i.raw.dropped_files.is_empty());
+        if !self.dropped_folders.is_empty() && !dropping {
+            ui.horizontal_wrapped(|ui| {
+                let names: Vec<String> = self
+                    .dropped_folders
+                    .iter()
+                    .map(|folder| folder.display().to_string())
+                    .collect();
+                ui.label(tr_fmt("Dropped {}:", &[&names.join(", ")]));
+                if ui
+                    .add_enabled(!self.is_watching, egui::Button::new(tr("Add as source")))
+                    .clicked()
+                {
+                    let folders = std::mem::take(&mut self.dropped_folders);
+                    self.add_sources(folders);
+                    self.save_config();
+                }
+                if ui.button(tr("Use as destination")).clicked() {
+                    let folders = std::mem::take(&mut self.dropped_folders);
+                    self.destination_folder = folders[0].display().to_string();
+                    self.save_config();
+                }
+                if ui.button(tr("Dismiss")).clicked() {
+                    self.dropped_folders.clear();
+                }
+            });
+        }
+
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.label(tr("Source Folders (to watch for new match folders):"));
+            let mut changed = false;
+            let mut remove = None;
+            // Further folders dropped onto a source field
+            let mut added = Vec::new();
+            let removable = self.source_folders.len() > 1;
+            for (index, folder) in self.source_folders.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let response = ui.text_edit_singleline(folder);
+                    if response.lost_focus() {
+                        *folder = normalize_path_input(folder);
+                    }
+                    changed |= response.changed() || response.lost_focus();
+                    let mut dropped =
+                        take_dropped_folders(ui, response.rect, &mut self.dropped_folders);
+                    if !dropped.is_empty() {
+                        *folder = dropped.remove(0).display().to_string();
+                        added.append(&mut dropped);
+                        changed = true;
+                    }
+                    path_indicator(
+                        ui,
+                        &mut self.folder_checks,
+                        folder,
+                        false,
+                        tr("Folder does not exist"),
+                    );
+                    if ui
+                        .button(tr("Browse"))
+                        .on_hover_text(tr("Or drop a folder onto the field"))
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            *folder = path.display().to_string();
+                            changed = true;
+                        }
+                    }
+                    let path = normalize_path_input(folder);
+                    let mut polling = self.polling_sources.contains(&path);
+                    if ui
+                        .checkbox(&mut polling, tr("Poll"))
+                        .on_hover_text(tr(
+                            "Check the folder for changes on an interval instead of being \
+                             notified, for network shares and exFAT drives that miss changes. \
+                             Applies the next time watching starts.",
+                        ))
+                        .changed()
+                    {
+                        self.polling_sources.retain(|source| *source != path);
+                        if polling {
+                            self.polling_sources.push(path);
+                        }
+                        changed = true;
+                    }
+                    if removable && ui.button(tr("Remove")).clicked() {
+                        remove = Some(index);
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!self.is_watching, egui::Button::new(tr("Add source")))
+                    .on_hover_text(tr(
+                        "Each source is backed up into its own subfolder of the destination",
+                    ))
+                    .clicked()
+                {
+                    self.source_folders.push(String::new());
+                }
+            });
+            if !self.polling_sources.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Check polled folders every"));
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.poll_interval_secs)
+                                .range(1..=3600)
+                                .suffix(" s"),
+                        )
+                        .changed();
+                });
+            }
+            if let Some(index) = remove {
+                self.source_folders.remove(index);
+                changed = true;
+            }
+            if !added.is_empty() {
+                if self.is_watching {
+                    self.add_status(StatusEvent::Warning(
+                        "Stop watching to add more source folders".to_string(),
+                    ));
+                } else {
+                    self.add_sources(added);
+                }
+            }
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.label(tr("Destination Folder (where backups will be saved):"));
+            ui.horizontal(|ui| {
+                let response = ui.text_edit_singleline(&mut self.destination_folder);
+                if response.lost_focus() {
+                    self.destination_folder = normalize_path_input(&self.destination_folder);
+                }
+                if response.changed() || response.lost_focus() {
+                    self.save_config();
+                }
+                let dropped = take_dropped_folders(ui, response.rect, &mut self.dropped_folders);
+                if let Some(folder) = dropped.first() {
+                    self.destination_folder = folder.display().to_string();
+                    self.save_config();
+                }
+                path_indicator(
+                    ui,
+                    &mut self.folder_checks,
+                    &self.destination_folder,
+                    true,
+                    tr("Folder does not exist yet, it will be created when watching starts"),
+                );
+                if ui
+                    .button(tr("Browse"))
+                    .on_hover_text(tr("Or drop a folder onto the field"))
+                    .clicked()
+                {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.destination_folder = path.display().to_string();
+                        self.save_config();
+                    }
+                }
+            });
+
+            ui.label(tr("Mirrors (optional, every backup is also copied here):"));
+            let mut changed = false;
+            let mut remove = None;
+            for (index, folder) in self.mirror_folders.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let response = ui.text_edit_singleline(folder);
+                    if response.lost_focus() {
+                        *folder = normalize_path_input(folder);
+                    }
+                    changed |= response.changed() || response.lost_focus();
+                    path_indicator(
+                        ui,
+                        &mut self.folder_checks,
+                        folder,
+                        true,
+                        tr("Folder does not exist yet, it will be created when watching starts"),
+                    );
+                    if ui.button(tr("Browse")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            *folder = path.display().to_string();
+                            changed = true;
+                        }
+                    }
+                    if ui.button(tr("Remove")).clicked() {
+                        remove = Some(index);
+                    }
+                });
+            }
+            if ui
+                .add_enabled(!self.is_watching, egui::Button::new(tr("Add mirror")))
+                .on_hover_text(tr(
+                    "Failed copies are retried for the mirror they failed on, without copying \
+                     to the other destinations again",
+                ))
+                .clicked()
+            {
+                self.mirror_folders.push(String::new());
+            }
+            if let Some(index) = remove {
+                self.mirror_folders.remove(index);
+                changed = true;
+            }
+
+            ui.label(tr(
+                "Remote destinations (optional, every backup is also uploaded here):",
+            ));
+            let mut remove = None;
+            let mut connect = None;
+            let connecting = self.drive_connecting.load(Ordering::Relaxed);
+            for (index, remote) in self.remote_destinations.iter_mut().enumerate() {
+                egui::CollapsingHeader::new(remote.label())
+                    .id_salt(("remote", index))
+                    .show(ui, |ui| {
+                        match remote {
+                            RemoteConfig::S3(config) => {
+                                changed |= s3_settings(ui, index, config);
+                            }
+                            RemoteConfig::WebDav(config) => {
+                                changed |= webdav_settings(ui, index, config);
+                            }
+                            RemoteConfig::GoogleDrive(config) => {
+                                let (edited, clicked) =
+                                    google_drive_settings(ui, index, config, connecting);
+                                changed |= edited;
+                                if clicked {
+                                    connect = Some(index);
+                                }
+                            }
+                            RemoteConfig::Rclone(config) => {
+                                changed |= rclone_settings(ui, index, config);
+                            }
+                        }
+                        if ui
+                            .add_enabled(!self.is_watching, egui::Button::new(tr("Remove")))
+                            .clicked()
+                        {
+                            remove = Some(index);
+                        }
+                    });
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!self.is_watching, egui::Button::new(tr("Add S3 bucket")))
+                    .on_hover_text(tr(
+                        "AWS, Backblaze B2, MinIO or any other S3-compatible storage. Large \
+                         archives are sent as multipart uploads.",
+                    ))
+                    .clicked()
+                {
+                    self.remote_destinations
+                        .push(RemoteConfig::S3(S3Config::default()));
+                    changed = true;
+                }
+                if ui
+                    .add_enabled(
+                        !self.is_watching,
+                        egui::Button::new(tr("Add WebDAV folder")),
+                    )
+                    .on_hover_text(tr(
+                        "Nextcloud, ownCloud or any other WebDAV server. Large archives are \
+                         uploaded in chunks on Nextcloud.",
+                    ))
+                    .clicked()
+                {
+                    self.remote_destinations
+                        .push(RemoteConfig::WebDav(WebDavConfig::default()));
+                    changed = true;
+                }
+                if ui
+                    .add_enabled(!self.is_watching, egui::Button::new(tr("Add Google Drive")))
+                    .on_hover_text(tr(
+                        "Uploads into a folder of your Google Drive. Uploads that fail are \
+                         retried when watching starts again, also after a restart.",
+                    ))
+                    .clicked()
+                {
+                    self.remote_destinations
+                        .push(RemoteConfig::GoogleDrive(GoogleDriveConfig::default()));
+                    changed = true;
+                }
+                if ui
+                    .add_enabled(
+                        !self.is_watching,
+                        egui::Button::new(tr("Add rclone remote")),
+                    )
+                    .on_hover_text(tr(
+                        "Any cloud rclone supports, through a remote set up with `rclone \
+                         config`. rclone's transfer progress is shown in the status log.",
+                    ))
+                    .clicked()
+                {
+                    self.remote_destinations
+                        .push(RemoteConfig::Rclone(RcloneConfig::default()));
+                    changed = true;
+                }
+            });
+            if let Some(index) = connect {
+                self.connect_google_drive(index);
+            }
+            if let Some(index) = remove {
+                self.remote_destinations.remove(index);
+                changed = true;
+            }
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.add_space(20.0);
+
+        if self.service_state != ServiceState::NotInstalled {
+            ui.horizontal(|ui| {
+                ui.label(tr_fmt(
+                    "Windows service: {}",
+                    &[&tr(self.service_state.label())],
+                ))
+                .on_hover_text(tr(
+                    "Backs up without anyone logged in. Restart it to apply changed \
+                         settings.",
+                ));
+                let running = self.service_state == ServiceState::Running;
+                let label = if running {
+                    tr("Stop service")
+                } else {
+                    tr("Start service")
+                };
+                if matches!(
+                    self.service_state,
+                    ServiceState::Running | ServiceState::Stopped
+                ) && ui.button(label).clicked()
+                {
+                    self.control_service(!running);
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            if !self.is_watching {
+                let service_running = self.service_state == ServiceState::Running;
+                if ui
+                    .add_enabled(!service_running, egui::Button::new(tr("Start Watching")))
+                    .on_disabled_hover_text(tr("The SiegeSaver service is already watching"))
+                    .clicked()
+                {
+                    self.start_watching();
+                }
+            } else if ui.button(tr("Stop Watching")).clicked() {
+                self.stop_watching();
+            }
+
+            let backup_running = self.manual_backup_running.load(Ordering::Relaxed);
+            if ui
+                .add_enabled(!backup_running, egui::Button::new(tr("Backup Now")))
+                .on_hover_text(tr(
+                    "Copy every existing match that is not in the destination yet",
+                ))
+                .clicked()
+            {
+                self.backup_now();
+            }
+
+            let verify_running = self.verify_running.load(Ordering::Relaxed);
+            if ui
+                .add_enabled(!verify_running, egui::Button::new(tr("Verify Backups")))
+                .on_hover_text(tr(
+                    "Re-read every backed-up file and check it against the checksums \
+                     recorded when it was copied",
+                ))
+                .clicked()
+            {
+                self.verify_backups();
+            }
+
+            let test_running = self.self_test_running.load(Ordering::Relaxed);
+            if ui
+                .add_enabled(
+                    self.is_watching && !self.pause.is_paused() && !test_running,
+                    egui::Button::new(tr("Run test backup")),
+                )
+                .on_hover_text(tr(
+                    "Puts a test match with dummy replays into the first source folder, checks \
+                     that it is backed up, verified and recorded in the destination, and deletes \
+                     it again. Shows in the log where the backup got stuck.",
+                ))
+                .on_disabled_hover_text(tr("Start watching first"))
+                .clicked()
+            {
+                self.run_test_backup();
+            }
+            if !self.damaged_backups.is_empty() {
+                let problems: usize = self
+                    .damaged_backups
+                    .iter()
+                    .map(|report| report.problems.len())
+                    .sum();
+                let label = tr_fmt("Re-copy {} from source", &[&problems]);
+                if ui
+                    .add_enabled(!verify_running, egui::Button::new(label))
+                    .on_hover_text(tr(
+                        "Copy missing and damaged files again if the source still has them",
+                    ))
+                    .clicked()
+                {
+                    self.repair_backups();
+                }
+            }
+
+            if let Some(remaining) = self.pause.remaining(Instant::now()) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    tr_fmt("⏸ Paused ({} left)", &[&format_countdown(remaining)]),
+                );
+                if ui.button(tr("Resume now")).clicked() {
+                    self.resume_watching();
+                }
+            } else if self.is_watching {
+                ui.colored_label(egui::Color32::GREEN, tr("● Watching"));
+                if ui.button(tr("Pause for 1 hour")).clicked() {
+                    self.pause_watching(PAUSE_DURATION);
+                }
+                if self.in_quiet_hours {
+                    let end = self.quiet_hours_end.format("%H:%M").to_string();
+                    ui.weak(tr_fmt("Quiet hours, backups are queued until {}", &[&end]));
+                }
+            } else {
+                ui.colored_label(egui::Color32::GRAY, tr("○ Not Watching"));
+            }
+        });
+        for destination in &self.offline_destinations {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr_fmt(
+                    "⚠ {} is not reachable. Backups are held and resume when it is back.",
+                    &[&destination.display()],
+                ),
+            );
+        }
+
+        self.show_backup_queue(ui);
+        self.show_pending_deletions(ui);
+
+        ui.add_space(20.0);
+
+        ui.horizontal(|ui| {
+            let mut start_on_boot = self.start_on_boot;
+            if ui
+                .checkbox(&mut start_on_boot, tr("Start on system boot"))
+                .changed()
+            {
+                self.set_start_on_boot(start_on_boot);
+            }
+            if cfg!(target_os = "linux") {
+                let previous = self.autostart_mode;
+                egui::ComboBox::from_id_salt("autostart_mode")
+                    .selected_text(tr(self.autostart_mode.label()))
+                    .show_ui(ui, |ui| {
+                        for mode in [AutostartMode::Desktop, AutostartMode::Systemd] {
+                            ui.selectable_value(&mut self.autostart_mode, mode, tr(mode.label()));
+                        }
+                    })
+                    .response
+                    .on_hover_text(tr(
+                        "In the background, SiegeSaver runs without its window from login, \
+                         even without a desktop session. Restart it with systemctl --user \
+                         restart siegesaver to apply changed settings.",
+                    ));
+                if self.autostart_mode != previous {
+                    if self.start_on_boot {
+                        self.set_start_on_boot(true);
+                    } else {
+                        self.save_config();
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Settings:"));
+            if ui
+                .button(tr("Export..."))
+                .on_hover_text(tr(
+                    "Save every setting to one file to set up another PC. It includes \
+                     passwords and keys of remote destinations.",
+                ))
+                .clicked()
+            {
+                self.export_settings();
+            }
+            ui.checkbox(&mut self.bundle_manifests, tr("with backup manifests"))
+                .on_hover_text(tr(
+                    "Also save the index of every backed-up match, for moving the backup \
+                     drive along",
+                ));
+            if ui
+                .button(tr("Import..."))
+                .on_hover_text(tr("Replace the settings with exported ones"))
+                .clicked()
+            {
+                self.import_settings();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Theme:"));
+            let previous = self.theme;
+            egui::ComboBox::from_id_salt("theme")
+                .selected_text(tr(self.theme.label()))
+                .show_ui(ui, |ui| {
+                    for theme in Theme::ALL {
+                        ui.selectable_value(&mut self.theme, theme, tr(theme.label()));
+                    }
+                });
+            if self.theme != previous {
+                self.save_config();
+            }
+            ui.label(tr("Language:"));
+            let previous = self.language;
+            egui::ComboBox::from_id_salt("language")
+                .selected_text(language_label(self.language))
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        let label = language_label(language);
+                        ui.selectable_value(&mut self.language, language, label);
+                    }
+                });
+            if self.language != previous {
+                set_language(self.language);
+                self.tray.retranslate();
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Log file:"));
+            let previous = self.log_level;
+            egui::ComboBox::from_id_salt("log_level")
+                .selected_text(tr(self.log_level.label()))
+                .show_ui(ui, |ui| {
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut self.log_level, level, tr(level.label()));
+                    }
+                })
+                .response
+                .on_hover_text(tr(
+                    "How much is written to the daily log files. Choose Debug and attach \
+                     the log when reporting a backup that was missed.",
+                ));
+            if self.log_level != previous {
+                set_log_level(self.log_level);
+                self.save_config();
+            }
+            if ui.button(tr("Open log folder")).clicked() {
+                let folder = log_folder(&self.config_path);
+                self.open_folder(&folder);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(
+                    &mut self.stale_warning_enabled,
+                    tr("Warn if no backup happens for"),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.stale_after_minutes)
+                        .range(1..=24 * 60)
+                        .suffix(" min"),
+                )
+                .changed();
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(
+                    &mut self.incremental_backups,
+                    tr("Add new rounds to matches that are already backed up"),
+                )
+                .on_hover_text(tr(
+                    "Only files that are new or changed since the last backup are copied. \
+                     When off, matches already in the destination are left untouched \
+                     when watching starts. Applies the next time watching starts.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .add_enabled(
+                    self.conflict_policy == ConflictPolicy::Merge,
+                    egui::Checkbox::new(&mut self.keep_versions_enabled, tr("Keep the last")),
+                )
+                .on_disabled_hover_text(tr(
+                    "Only available when files already in the destination are set to \"Update if changed\"",
+                ))
+                .on_hover_text(tr(
+                    "Before a backed-up file that changed is replaced, the earlier version is \
+                     moved into a .versions folder in the destination, named with the time it \
+                     was replaced. Only the newest versions of each file are kept. Applies the \
+                     next time watching starts.",
+                ))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.keep_file_versions).range(1..=1000))
+                .changed();
+            ui.label(tr("earlier versions of changed files"));
+            if changed {
+                self.save_config();
+            }
+        });
+
+        if ui
+            .checkbox(
+                &mut self.snapshot_runs,
+                tr("Keep a dated snapshot of every backup run"),
+            )
+            .on_hover_text(tr(
+                "Each time watching starts or \"Backup Now\" runs, backups go into a new folder \
+                 such as 2025-11-24T14-32 in the destination. Files that did not change are \
+                 hard-linked from the previous snapshot, so they take no extra space, and the \
+                 Restore tab can restore any earlier snapshot. Applies the next time watching \
+                 starts.",
+            ))
+            .changed()
+        {
+            self.save_config();
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Store matches as:"));
+            let previous = (self.storage_format, self.zstd_level);
+            egui::ComboBox::from_id_salt("storage_format")
+                .selected_text(tr(self.storage_format.label()))
+                .show_ui(ui, |ui| {
+                    for format in [
+                        StorageFormat::Folder,
+                        StorageFormat::Zip,
+                        StorageFormat::TarZst,
+                    ] {
+                        ui.selectable_value(&mut self.storage_format, format, tr(format.label()));
+                    }
+                })
+                .response
+                .on_hover_text(tr(
+                    "Archives pack every match folder into a single file in the \
+                     destination, rebuilt whenever a new round is added. Applies the next \
+                     time watching starts.",
+                ));
+            if self.storage_format == StorageFormat::TarZst {
+                ui.add(egui::Slider::new(&mut self.zstd_level, 1..=19).text(tr("level")))
+                    .on_hover_text(tr("Higher levels make smaller archives but take longer"));
+            }
+            if (self.storage_format, self.zstd_level) != previous {
+                self.save_config();
+            }
+            if let Some((name, done, total)) = &self.archive_progress {
+                ui.add(
+                    egui::ProgressBar::new(*done as f32 / *total as f32)
+                        .desired_width(150.0)
+                        .text(format!("{} {}/{}", name, done, total)),
+                );
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(
+                    &mut self.fsync_writes,
+                    tr("Flush backups to disk immediately"),
+                )
+                .on_hover_text(tr(
+                    "Guarantees backed-up replays survive a crash or power loss, \
+                     but makes copying noticeably slower. Applies the next time \
+                     watching starts.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.verify_copies, tr("Verify every copy (SHA-256)"))
+                .on_hover_text(tr(
+                    "Reads each backed-up file back and compares it with the source. \
+                     Damaged copies are retried and reported if they keep failing. \
+                     Applies the next time watching starts.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(
+                    &mut self.encrypt_backups,
+                    tr("Encrypt backups (AES-256-GCM)"),
+                )
+                .on_hover_text(tr(
+                    "Backed-up files and match archives are stored as <name>.enc. The \
+                     passphrase is kept in memory only and must be re-entered after every \
+                     restart.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(
+                    &mut self.initial_sync_enabled,
+                    tr("When watching starts, only back up the newest"),
+                )
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.initial_sync_limit).range(1..=10_000))
+                .changed();
+            ui.label(tr("existing matches"));
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(
+                    &mut self.rescan_enabled,
+                    tr("Rescan the source folders every"),
+                )
+                .on_hover_text(tr(
+                    "Backs up changes the watcher missed. Applies the next time watching \
+                     starts.",
+                ))
+                .changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.rescan_interval_minutes)
+                        .range(1..=24 * 60)
+                        .suffix(" min"),
+                )
+                .changed();
+            if changed {
+                self.save_config();
+            }
+            if ui
+                .add_enabled(self.is_watching, egui::Button::new(tr("Rescan now")))
+                .clicked()
+            {
+                self.rescan.request();
+                self.add_status(StatusEvent::Info(
+                    "Rescanning the source folders".to_string(),
+                ));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(
+                    &mut self.scheduled_sync_enabled,
+                    tr("Back up everything every day at"),
+                )
+                .on_hover_text(tr(
+                    "Backs up every match in the source folders that is missing or changed in \
+                     the destination, also while not watching. A run missed while SiegeSaver \
+                     was closed is made up for when it starts again.",
+                ))
+                .changed();
+            changed |= time_of_day_edit(ui, &mut self.scheduled_sync_time);
+            if changed {
+                self.save_config();
+            }
+            let last_run = self
+                .last_scheduled_sync
+                .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+                .map(|time| {
+                    time.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                });
+            ui.weak(match last_run {
+                Some(time) => tr_fmt("Last scheduled run: {}", &[&time]),
+                None => tr("Not run yet").to_string(),
+            });
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(&mut self.quiet_hours_enabled, tr("Quiet hours from"))
+                .on_hover_text(tr(
+                    "Changes are queued but not backed up between these times, e.g. while \
+                     streaming. The queued backups run on their own once the quiet hours end.",
+                ))
+                .changed();
+            changed |= time_of_day_edit(ui, &mut self.quiet_hours_start);
+            ui.label(tr("to"));
+            changed |= time_of_day_edit(ui, &mut self.quiet_hours_end);
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Back up changes once they settled for"));
+            if ui
+                .add(
+                    egui::DragValue::new(&mut self.debounce_ms)
+                        .range(100..=60_000)
+                        .speed(10)
+                        .suffix(" ms"),
+                )
+                .on_hover_text(tr(
+                    "Changes arriving within this time are backed up together. Takes effect \
+                     the next time watching starts.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Wait until replays were not written to for"));
+            if ui
+                .add(
+                    egui::DragValue::new(&mut self.quiet_period_secs)
+                        .range(0..=120)
+                        .suffix(" s"),
+                )
+                .on_hover_text(tr(
+                    "So a replay the game is still writing is not backed up cut short. 0 \
+                     backs up right away. Takes effect the next time watching starts.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Copy up to"));
+            if ui
+                .add(egui::DragValue::new(&mut self.copy_threads).range(1..=MAX_COPY_THREADS))
+                .on_hover_text(tr(
+                    "Files of a match copied at the same time. More is faster on SSDs; use \
+                     1 for hard drives. Takes effect the next time watching starts.",
+                ))
+                .changed()
+            {
+                self.save_config();
+            }
+            ui.label(tr("files at once"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Put files into the destination as:"));
+            let previous = self.copy_mode;
+            egui::ComboBox::from_id_salt("copy_mode")
+                .selected_text(tr(self.copy_mode.label()))
+                .show_ui(ui, |ui| {
+                    for mode in [CopyMode::Copy, CopyMode::Reflink, CopyMode::Hardlink]
+                        .into_iter()
+                        .filter(|mode| mode.is_supported())
+                    {
+                        ui.selectable_value(&mut self.copy_mode, mode, tr(mode.label()));
+                    }
+                })
+                .response
+                .on_hover_text(tr(
+                    "If the destination is on the same volume as the replays, clones \
+                     (Btrfs, XFS, APFS, ReFS) and hard links (NTFS and most others) are instant \
+                     and take no extra space. A hard-linked backup changes along with its \
+                     replay if the game ever rewrites it. Anything that cannot be linked is \
+                     copied. Applies the next time watching starts.",
+                ));
+            if self.copy_mode != previous {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Symbolic links in the source:"));
+            let previous = self.symlinks;
+            egui::ComboBox::from_id_salt("symlinks")
+                .selected_text(tr(self.symlinks.label()))
+                .show_ui(ui, |ui| {
+                    for mode in [SymlinkMode::Skip, SymlinkMode::Copy, SymlinkMode::Follow] {
+                        ui.selectable_value(&mut self.symlinks, mode, tr(mode.label()));
+                    }
+                })
+                .response
+                .on_hover_text(tr(
+                    "Skipped by default. \"Copy as links\" recreates them in the destination, \
+                     pointing to the same place. \"Follow\" backs up what they point to, \
+                     except broken links and links back into a folder being copied, which \
+                     would never end. Applies the next time watching starts.",
+                ));
+            if self.symlinks != previous {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Files already in the destination:"));
+            let previous = self.conflict_policy;
+            egui::ComboBox::from_id_salt("conflict_policy")
+                .selected_text(tr(self.conflict_policy.label()))
+                .show_ui(ui, |ui| {
+                    for policy in ConflictPolicy::ALL {
+                        ui.selectable_value(&mut self.conflict_policy, policy, tr(policy.label()));
+                    }
+                })
+                .response
+                .on_hover_text(tr(
+                    "What happens when a file or match archive is backed up again. \
+                     \"Update if changed\" copies it only if the replay changed since. \
+                     \"Keep the existing backup\" never replaces it, so new rounds are not added \
+                     to archives. \"Always copy again\" replaces it even if it is unchanged. \
+                     \"Keep both, numbered\" renames the earlier backup, e.g. to \
+                     round-01 (1).rec. Applies the next time watching starts.",
+                ));
+            if self.conflict_policy != previous {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut enabled = self.mirror_deletions;
+            let response = ui
+                .add_enabled(
+                    !self.move_after_verify,
+                    egui::Checkbox::new(
+                        &mut enabled,
+                        tr("Delete backups of matches deleted from the source after"),
+                    ),
+                )
+                .on_disabled_hover_text(tr(
+                    "Not available while matches are deleted from the game folder after \
+                     their backup",
+                ))
+                .on_hover_text(tr(
+                    "Mirror mode. Matches the game deletes to make room for new ones always \
+                     keep their backups. Deleted backups go to a trash folder in the \
+                     destination first. Applies the next time watching starts.",
+                ));
+            if response.changed() {
+                if enabled {
+                    // Turned on only once the warning is confirmed
+                    self.confirm_mirror_deletions = true;
+                } else {
+                    self.mirror_deletions = false;
+                    self.save_config();
+                }
+            }
+            let response = ui.add(
+                egui::DragValue::new(&mut self.mirror_deletion_grace_minutes)
+                    .range(1..=10080)
+                    .suffix(" min"),
+            );
+            if response.changed() {
+                self.save_config();
+            }
+        });
+
+        let mut enabled = self.move_after_verify;
+        let response = ui
+            .add_enabled(
+                !self.mirror_deletions,
+                egui::Checkbox::new(
+                    &mut enabled,
+                    tr("Delete matches from the game folder once their backup is verified"),
+                ),
+            )
+            .on_disabled_hover_text(tr("Not available in mirror mode"))
+            .on_hover_text(tr(
+                "Frees space on the game's drive. A match is deleted from the source once \
+                 every file of it is backed up and reads back with its checksum; the newest \
+                 match is never touched. Applies the next time watching starts.",
+            ));
+        if response.changed() {
+            if enabled {
+                // Turned on only once the warning is confirmed
+                self.confirm_move_after_verify = true;
+            } else {
+                self.move_after_verify = false;
+                self.save_config();
+            }
+        }
+
+        if ui
+            .checkbox(
+                &mut self.dedupe_files,
+                tr("Store identical files only once (dedupe)"),
+            )
+            .on_hover_text(tr(
+                "Files with the same contents, such as replays shared between patches, are \
+                 kept once in a siegesaver-store folder in the destination and hard-linked \
+                 into each match. Files nothing links to anymore are deleted when old \
+                 matches are pruned. Needs a destination that supports hard links (not FAT) \
+                 and does not apply to encrypted backups. Applies the next time watching \
+                 starts.",
+            ))
+            .changed()
+        {
+            self.save_config();
+        }
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(&mut self.io_limit_enabled, tr("Limit backups to"))
+                .on_hover_text(tr(
+                    "Keeps copying from competing with the game for the disk, which can \
+                     cause stutter mid-match. Takes effect the next time watching starts.",
+                ))
+                .changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.io_limit_mb_per_sec)
+                        .range(1..=1000)
+                        .suffix(" MB/s"),
+                )
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.iops_limit_enabled, tr("and"))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.iops_limit).range(1..=10_000))
+                .changed();
+            ui.label(tr("operations/s"));
+            if changed {
+                self.save_config();
+            }
+        });
+
+        if cfg!(windows)
+            && ui
+                .checkbox(
+                    &mut self.background_io_priority,
+                    tr("Copy with background I/O priority"),
+                )
+                .on_hover_text(tr(
+                    "Lets Windows put the game's disk access first. Takes effect the next \
+                     time watching starts.",
+                ))
+                .changed()
+        {
+            self.save_config();
+        }
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(&mut self.keep_last_enabled, tr("Keep only the newest"))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.keep_last_matches).range(1..=100_000))
+                .changed();
+            ui.label(tr("matches"));
+            changed |= ui
+                .checkbox(
+                    &mut self.max_age_enabled,
+                    tr("and delete matches older than"),
+                )
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.max_backup_age_days).range(1..=36_500))
+                .changed();
+            ui.label(tr("days"));
+            if changed {
+                self.save_config();
+            }
+            if ui
+                .button(tr("Preview"))
+                .on_hover_text(tr(
+                    "Lists the backups that would be deleted from the destination without \
+                     deleting anything. Pruning runs after every backup.",
+                ))
+                .clicked()
+            {
+                self.preview_pruning();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(&mut self.quota_enabled, tr("Limit the destination to"))
+                .on_hover_text(tr(
+                    "When a backup pushes the destination over the limit, the matches \
+                     backed up longest ago are deleted until it fits again. Applies the \
+                     next time watching starts.",
+                ))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.quota_gb).range(1..=100_000))
+                .changed();
+            ui.label(tr("GB"));
+            if changed {
+                self.save_config();
+            }
+        });
+
+        if ui
+            .checkbox(
+                &mut self.prune_to_recycle_bin,
+                tr("Move deleted matches to the recycle bin"),
+            )
+            .on_hover_text(tr(
+                "Matches deleted to keep only the newest or to stay under the size limit go \
+                 to the system's recycle bin, where they can be restored from. The recycle \
+                 bin still takes space on the destination's drive, so turn this off for \
+                 drives that are short on space. Drives without a recycle bin, such as \
+                 network shares, delete them for good either way. Applies the next time \
+                 watching starts.",
+            ))
+            .changed()
+        {
+            self.save_config();
+        }
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(&mut self.min_free_space_enabled, tr("Keep at least"))
+                .on_hover_text(tr(
+                    "Backups that would leave less free space on the destination's drive \
+                     are held with a warning until there is room, instead of failing \
+                     halfway. Backups that do not fit at all are always held. Applies the \
+                     next time watching starts.",
+                ))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.min_free_space_gb).range(1..=100_000))
+                .changed();
+            ui.label(tr("GB free on the destination drive"));
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Excluded subfolders:")).on_hover_text(tr(
+                "Folders inside the source (one per line, relative to it) that are \
+                 never backed up, e.g. cache or temp. Applies the next time watching starts.",
+            ));
+            if ui
+                .add(
+                    egui::TextEdit::multiline(&mut self.watch_exclude_text)
+                        .desired_rows(2)
+                        .hint_text("cache\ntemp"),
+                )
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Back up files:"))
+                .on_hover_text(tr("Comma-separated file name patterns, e.g. *.rec. \
+                 Applies the next time watching starts."));
+            let mut changed = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.include_patterns_text)
+                        .desired_width(120.0)
+                        .hint_text("*.rec"),
+                )
+                .changed();
+            ui.label(tr("except:"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.exclude_patterns_text)
+                        .desired_width(120.0)
+                        .hint_text("*.tmp, *.partial"),
+                )
+                .changed();
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Prometheus textfile:"))
+                .on_hover_text(tr("Leave empty to disable. Rewritten every 15 seconds."));
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.prometheus_textfile)
+                        .hint_text("/var/lib/node_exporter/siegesaver.prom"),
+                )
+                .changed()
+            {
+                self.last_metrics_write = None;
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let toggled = ui
+                .checkbox(&mut self.api_enabled, tr("HTTP API on port"))
+                .on_hover_text(tr(
+                    "Lets scripts, dashboards and Stream Deck plugins on this PC read the \
+                     status and history and start or stop watching, through GET /status, \
+                     GET /history?limit=50, POST /start and POST /stop on 127.0.0.1.",
+                ))
+                .changed();
+            let port = ui.add_enabled(
+                self.api_enabled,
+                egui::DragValue::new(&mut self.api_port).range(1024..=65535),
+            );
+            if toggled || port.changed() {
+                self.save_config();
+            }
+            // Not restarted for every step while the port is dragged
+            if toggled || port.drag_stopped() || port.lost_focus() {
+                self.restart_api();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("After each backup run:")).on_hover_text(tr(
+                "Leave empty to disable. Runs in the system shell with SIEGESAVER_MATCH_NAME, \
+                 SIEGESAVER_MATCH_DIR, SIEGESAVER_SOURCE_DIR, SIEGESAVER_DEST_DIR, \
+                 SIEGESAVER_FILES and SIEGESAVER_BYTES set. Applies the next time watching \
+                 starts.",
+            ));
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.post_backup_command)
+                        .hint_text(tr("e.g. python upload.py \"%SIEGESAVER_MATCH_DIR%\"")),
+                )
+                .changed()
+            {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Match statistics with r6-dissect:"))
+                .on_hover_text(tr(
+                    "Leave empty to disable. After each backup, the r6-dissect program reads \
+                     the match's replays, and its rounds, kills and scoreboard are stored in \
+                     the backup manifest. The Library tab then shows whether the match was \
+                     won and your kills and deaths. Applies the next time watching starts.",
+                ));
+            let mut changed = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.r6_dissect_path)
+                        .hint_text(tr("Path to r6-dissect")),
+                )
+                .changed();
+            if ui.button(tr("Browse")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().pick_file() {
+                    self.r6_dissect_path = path.display().to_string();
+                    changed = true;
+                }
+            }
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Desktop notifications:"));
+            let settings = &mut self.desktop_notifications;
+            let mut changed = ui.checkbox(&mut settings.backups, tr("Backups")).changed();
+            changed |= ui.checkbox(&mut settings.uploads, tr("Uploads")).changed();
+            changed |= ui
+                .checkbox(&mut settings.warnings, tr("Warnings"))
+                .changed();
+            changed |= ui.checkbox(&mut settings.errors, tr("Errors")).changed();
+            if changed {
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Discord webhook:")).on_hover_text(tr(
+                "Leave empty to disable. Finished matches and errors are posted to the \
+                 channel, at most one message every 10 seconds.",
+            ));
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.discord_webhook_url)
+                        .password(true)
+                        .hint_text("https://discord.com/api/webhooks/…"),
+                )
+                .changed()
+            {
+                let url = self.discord_webhook_url.trim();
+                self.discord = (!url.is_empty())
+                    .then(|| DiscordNotifier::spawn(url.to_string(), self.status_sender.clone()));
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut changed = ui
+                .checkbox(&mut self.discord_presence, tr("Discord Rich Presence"))
+                .on_hover_text(tr(
+                    "Shows whether replays are being watched and how many matches are \
+                     archived on your Discord profile",
+                ))
+                .changed();
+            ui.label(tr("Application ID:"))
+                .on_hover_text(tr("Create an application named SiegeSaver at \
+                 https://discord.com/developers/applications and copy its ID here"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.discord_application_id)
+                        .desired_width(160.0),
+                )
+                .changed();
+            if changed {
+                let id = self.discord_application_id.trim();
+                self.presence = (self.discord_presence && !id.is_empty())
+                    .then(|| RichPresence::spawn(id.to_string()));
+                self.presence_watching = None;
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("HTTP callback:")).on_hover_text(tr(
+                "Leave empty to disable. Every finished or failed backup is POSTed here as \
+                 JSON with the event type, match name, paths, size and SHA-256 checksums.",
+            ));
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.webhook_url)
+                        .hint_text("http://homeassistant.local:8123/api/webhook/siegesaver"),
+                )
+                .changed()
+            {
+                let url = self.webhook_url.trim();
+                self.webhook = (!url.is_empty())
+                    .then(|| WebhookNotifier::spawn(url.to_string(), self.status_sender.clone()));
+                self.save_config();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Passphrase:"));
+            ui.add(egui::TextEdit::singleline(&mut self.encryption_passphrase).password(true));
+            if ui.button(tr("Decrypt…")).clicked() {
+                self.decrypt_backups();
+            }
+        });
+    }
+
+    fn show_restore_tab(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr_fmt(
+                "Backed-up matches in {}",
+                &[&self.destination_folder],
+            ));
+            if ui.button(tr("Refresh")).clicked() {
+                self.refresh_restore_list();
+            }
+        });
+        if !self.restore_snapshots.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label(tr("Snapshot:"));
+                let previous = self.restore_snapshot.clone();
+                egui::ComboBox::from_id_salt("restore_snapshot")
+                    .selected_text(match &self.restore_snapshot {
+                        Some(name) => name.clone(),
+                        None => tr("Latest").to_string(),
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.restore_snapshot, None, tr("Latest"));
+                        for name in self.restore_snapshots.iter().rev() {
+                            ui.selectable_value(
+                                &mut self.restore_snapshot,
+                                Some(name.clone()),
+                                name,
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text(tr("Restore the matches as they were after an earlier run"));
+                if self.restore_snapshot != previous {
+                    self.refresh_restore_list();
+                }
+            });
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("restore_list")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                if self.restore_candidates.is_empty() {
+                    ui.label(tr("No backed-up matches found"));
+                }
+                let several_sources = self.source_paths().len() > 1;
+                for (candidate, target, selected) in &mut self.restore_candidates {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(selected, &candidate.name);
+                        let modified = chrono::DateTime::<chrono::Local>::from(candidate.modified);
+                        ui.weak(modified.format("%Y-%m-%d %H:%M").to_string());
+                        if several_sources {
+                            ui.weak(format!("→ {}", target.display()));
+                        }
+                    });
+                }
+            });
+
+        ui.horizontal(|ui| {
+            let selected = self
+                .restore_candidates
+                .iter()
+                .filter(|(_, _, selected)| *selected)
+                .count();
+            let running = self.restore_running.load(Ordering::Relaxed);
+            if ui
+                .add_enabled(
+                    selected > 0 && !running,
+                    egui::Button::new(tr_fmt("Restore selected ({})", &[&selected])),
+                )
+                .on_hover_text(tr(
+                    "Copy the selected matches back into the source folder they came from",
+                ))
+                .clicked()
+            {
+                self.restore_selected(false);
+            }
+            if let Some((done, total)) = self.restore_progress {
+                ui.add(
+                    egui::ProgressBar::new(done as f32 / total as f32)
+                        .desired_width(150.0)
+                        .text(format!("{}/{}", done, total)),
+                );
+            }
+        });
+        ui.small(tr(
+            "Encrypted backups are decrypted with the passphrase from the Backup tab.",
+        ));
+    }
+
+    fn show_library_tab(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Search:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.library_search)
+                    .hint_text(tr("Match, source or file name"))
+                    .desired_width(200.0),
+            );
+            if ui.button(tr("Refresh")).clicked() {
+                self.refresh_library();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Backed up from:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.library_from)
+                    .hint_text("YYYY-MM-DD")
+                    .desired_width(90.0),
+            );
+            ui.label(tr("to:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.library_to)
+                    .hint_text("YYYY-MM-DD")
+                    .desired_width(90.0),
+            );
+            ui.label(tr("Tag:"));
+            let tags: BTreeSet<String> = self
+                .library
+                .iter()
+                .flat_map(|(_, manifest)| manifest.tags())
+                .map(String::from)
+                .collect();
+            egui::ComboBox::from_id_salt("library_tag")
+                .selected_text(self.library_tag.as_deref().unwrap_or(tr("All")))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.library_tag, None, tr("All"));
+                    for tag in tags {
+                        ui.selectable_value(&mut self.library_tag, Some(tag.clone()), tag);
+                    }
+                });
+        });
+
+        let filter = match (parse_date(&self.library_from), parse_date(&self.library_to)) {
+            (Ok(from), Ok(to)) => LibraryFilter {
+                text: self.library_search.clone(),
+                tag: self.library_tag.clone(),
+                from,
+                to,
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+                return;
+            }
+        };
+        if self.library.is_empty() {
+            ui.label(tr("No backup library loaded"));
+            return;
+        }
+        let items = self.library_items(&filter);
+        let total: usize = self
+            .library
+            .iter()
+            .map(|(_, manifest)| manifest.entries.len())
+            .sum();
+        let mut export = None;
+        let (mut share, mut import) = (false, false);
+        ui.horizontal(|ui| {
+            ui.label(tr_fmt("{} of {} backed-up items", &[&items.len(), &total]));
+            if ui
+                .button(tr("Export CSV"))
+                .on_hover_text(tr("Save the listed items with their files and checksums"))
+                .clicked()
+            {
+                export = Some(ExportFormat::Csv);
+            }
+            if ui
+                .button(tr("Export JSON"))
+                .on_hover_text(tr("Save the listed items with their files and checksums"))
+                .clicked()
+            {
+                export = Some(ExportFormat::Json);
+            }
+        });
+        ui.horizontal(|ui| {
+            let selected = self.library_selected.len();
+            share = ui
+                .add_enabled(
+                    selected > 0,
+                    egui::Button::new(tr_fmt("Export selected... ({})", &[&selected])),
+                )
+                .on_hover_text(tr(
+                    "Save the ticked matches with their tags, notes and statistics as one zip \
+                     file to share with teammates",
+                ))
+                .clicked();
+            import = ui
+                .button(tr("Import..."))
+                .on_hover_text(tr(
+                    "Add the matches of a zip file exported by SiegeSaver to the destination",
+                ))
+                .clicked();
+        });
+
+        let mut edit = None;
+        let mut toggled = Vec::new();
+        egui::ScrollArea::vertical()
+            .id_salt("library_list")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("library_grid")
+                    .num_columns(8)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in [
+                            tr("Match"),
+                            tr("Backed up"),
+                            tr("Files"),
+                            tr("Size"),
+                            tr("Result"),
+                            tr("K/D"),
+                            tr("Tags"),
+                            tr("Source"),
+                        ] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        for (destination, name, entry) in items {
+                            let key = (destination.to_path_buf(), name.to_string());
+                            let mut ticked = self.library_selected.contains(&key);
+                            if ui.checkbox(&mut ticked, name).changed() {
+                                toggled.push((key, ticked));
+                            }
+                            ui.label(
+                                backup_date(entry.first_backup)
+                                    .map(|date| date.to_string())
+                                    .unwrap_or_default(),
+                            );
+                            ui.label(entry.files.len().to_string());
+                            ui.label(format_bytes(entry.bytes));
+                            // Only known for matches read with r6-dissect
+                            match entry.match_stats.as_ref().and_then(summarize) {
+                                Some(summary) => {
+                                    let result = match summary.won() {
+                                        Some(true) => tr("Win"),
+                                        Some(false) => tr("Loss"),
+                                        None => tr("Draw"),
+                                    };
+                                    ui.label(format!(
+                                        "{} {}-{}",
+                                        result, summary.rounds_won, summary.rounds_lost
+                                    ));
+                                    ui.label(format!(
+                                        "{}/{} ({:.2})",
+                                        summary.kills,
+                                        summary.deaths,
+                                        summary.kd()
+                                    ))
+                                    .on_hover_text(&summary.username);
+                                }
+                                None => {
+                                    ui.label("");
+                                    ui.label("");
+                                }
+                            }
+                            let tags = entry
+                                .tags
+                                .iter()
+                                .map(String::as_str)
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let response = ui.label(tags);
+                            if !entry.notes.is_empty() {
+                                response.on_hover_text(&entry.notes);
+                            }
+                            ui.horizontal(|ui| {
+                                ui.weak(
+                                    entry
+                                        .source
+                                        .as_ref()
+                                        .map(|source| source.display().to_string())
+                                        .unwrap_or_else(|| tr("unknown").to_string()),
+                                );
+                                if ui.small_button(tr("Edit")).clicked() {
+                                    edit = Some((destination, name, entry));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+        let edit = edit.map(|(destination, name, entry)| {
+            let tags = entry
+                .tags
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            (
+                destination.to_path_buf(),
+                name.to_string(),
+                tags,
+                entry.notes.clone(),
+            )
+        });
+        if edit.is_some() {
+            self.library_editing = edit;
+        }
+        for (key, ticked) in toggled {
+            if ticked {
+                self.library_selected.insert(key);
+            } else {
+                self.library_selected.remove(&key);
+            }
+        }
+
+        let mut save = false;
+        if let Some((_, name, tags, notes)) = &mut self.library_editing {
+            ui.separator();
+            ui.label(tr_fmt("Tags and notes for {}", &[&name]));
+            ui.horizontal(|ui| {
+                ui.label(tr("Tags:"));
+                ui.add(
+                    egui::TextEdit::singleline(tags)
+                        .hint_text(tr("clutch, ranked, review later"))
+                        .desired_width(300.0),
+                );
+            });
+            ui.add(
+                egui::TextEdit::multiline(notes)
+                    .hint_text(tr("Notes"))
+                    .desired_rows(3),
+            );
+            ui.horizontal(|ui| {
+                save = ui.button(tr("Save")).clicked();
+                if ui.button(tr("Cancel")).clicked() {
+                    self.library_editing = None;
+                }
+            });
+        }
+        if save {
+            self.save_library_annotations();
+        }
+        if let Some(format) = export {
+            self.export_library_items(&filter, format);
+        }
+        if share {
+            self.export_selected_matches();
+        }
+        if import {
+            self.import_shared_matches();
+        }
+    }
+
+    fn show_history_tab(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr_fmt(
+            "{} backups recorded. Click a column to sort by it.",
+            &[&self.history.entries.len()],
+        ));
+
+        egui::ScrollArea::vertical()
+            .id_salt("history_table")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("history_grid")
+                    .num_columns(HistoryColumn::ALL.len())
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for column in HistoryColumn::ALL {
+                            let mut label = tr(column.label()).to_string();
+                            if column == self.history_sort {
+                                label.push_str(if self.history_ascending {
+                                    " ⏶"
+                                } else {
+                                    " ⏷"
+                                });
+                            }
+                            if ui.button(label).clicked() {
+                                if column == self.history_sort {
+                                    self.history_ascending = !self.history_ascending;
+                                } else {
+                                    self.history_sort = column;
+                                    self.history_ascending = true;
+                                }
+                            }
+                        }
+                        ui.end_row();
+
+                        for index in self
+                            .history
+                            .sorted(self.history_sort, self.history_ascending)
+                        {
+                            let entry = &self.history.entries[index];
+                            let time = chrono::DateTime::from_timestamp(entry.time, 0)
+                                .map(|time| {
+                                    time.with_timezone(&chrono::Local)
+                                        .format("%Y-%m-%d %H:%M:%S")
+                                        .to_string()
+                                })
+                                .unwrap_or_default();
+                            ui.label(time);
+                            ui.label(&entry.name);
+                            ui.label(entry.files.to_string());
+                            ui.label(format_bytes(entry.bytes));
+                            ui.label(format!("{:.2} s", entry.duration_ms as f64 / 1000.0));
+                            match &entry.error {
+                                Some(error) => {
+                                    ui.colored_label(egui::Color32::RED, tr("Failed"))
+                                        .on_hover_text(error);
+                                }
+                                None => {
+                                    ui.colored_label(egui::Color32::LIGHT_GREEN, tr("OK"));
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn refresh_stats(&mut self) {
+        let destination = Path::new(&self.destination_folder);
+        self.stats_manifest = None;
+        if self.destination_folder.is_empty() || !Manifest::exists(&RealFs, destination) {
+            return;
+        }
+        match Manifest::load(&RealFs, destination) {
+            Ok(manifest) => self.stats_manifest = Some(manifest),
+            Err(e) => self.add_status(StatusEvent::Error(format!(
+                "Error reading the backup manifest in {}: {}",
+                destination.display(),
+                e
+            ))),
+        }
+    }
+
+    fn show_stats_tab(&mut self, ui: &mut egui::Ui) {
+        let stats = backup_stats(
+            self.stats_manifest.as_ref(),
+            &self.history,
+            chrono::Local::now().date_naive(),
+            STATS_DAYS,
+        );
+        egui::Grid::new("stats_grid").num_columns(2).show(ui, |ui| {
+            ui.label(tr("Matches in the destination:"));
+            ui.label(stats.matches.to_string());
+            ui.end_row();
+            ui.label(tr("Total size:"));
+            ui.label(format_bytes(stats.total_bytes));
+            ui.end_row();
+            ui.label(tr("Average match size:"));
+            ui.label(format_bytes(stats.average_match_bytes));
+            ui.end_row();
+            ui.label(tr("Last error:"));
+            match &stats.last_error {
+                Some((time, name, error)) => {
+                    let time = chrono::DateTime::from_timestamp(*time, 0)
+                        .map(|time| {
+                            time.with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M:%S")
+                                .to_string()
+                        })
+                        .unwrap_or_default();
+                    ui.colored_label(egui::Color32::RED, format!("{} {}", time, name))
+                        .on_hover_text(error);
+                }
+                None => {
+                    ui.label(tr("None"));
+                }
+            }
+            ui.end_row();
+        });
+        if ui.button(tr("Refresh")).clicked() {
+            self.refresh_stats();
+        }
+
+        ui.add_space(10.0);
+        ui.label(tr_fmt(
+            "Matches backed up per day (last {} days):",
+            &[&STATS_DAYS],
+        ));
+        backups_per_day_chart(ui, &stats.per_day);
+    }
+
+    fn show_analytics_tab(&mut self, ui: &mut egui::Ui) {
+        let analytics = library_analytics(
+            self.library
+                .iter()
+                .flat_map(|(_, manifest)| manifest.entries.iter())
+                .map(|(name, entry)| (name.as_str(), entry)),
+        );
+        if ui.button(tr("Refresh")).clicked() {
+            self.refresh_library();
+        }
+        if analytics.matches == 0 {
+            ui.label(tr(
+                "No match statistics yet. Set the path to r6-dissect in the settings, and \
+                 matches backed up from then on are read for them.",
+            ));
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("analytics_scroll")
+            .show(ui, |ui| {
+                egui::Grid::new("analytics_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr("Matches with statistics:"));
+                        ui.label(analytics.matches.to_string());
+                        ui.end_row();
+                        ui.label(tr("Won / lost:"));
+                        ui.label(format!(
+                            "{} / {} ({:.0}%)",
+                            analytics.won,
+                            analytics.lost,
+                            analytics.winrate() * 100.0
+                        ));
+                        ui.end_row();
+                        ui.label(tr("Kills / deaths:"));
+                        ui.label(format!(
+                            "{} / {} ({:.2})",
+                            analytics.kills,
+                            analytics.deaths,
+                            analytics.kd()
+                        ));
+                        ui.end_row();
+                    });
+
+                ui.add_space(10.0);
+                ui.strong(tr("Winrate per map"));
+                egui::Grid::new("analytics_maps_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Map"));
+                        ui.strong(tr("Played"));
+                        ui.strong(tr("Won"));
+                        ui.strong(tr("Lost"));
+                        ui.strong(tr("Winrate"));
+                        ui.end_row();
+                        for record in &analytics.maps {
+                            ui.label(&record.map);
+                            ui.label(record.played().to_string());
+                            ui.label(record.won.to_string());
+                            ui.label(record.lost.to_string());
+                            ui.label(format!("{:.0}%", record.winrate() * 100.0));
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(10.0);
+                ui.strong(tr("Most played operators"));
+                egui::Grid::new("analytics_operators_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Operator"));
+                        ui.strong(tr("Rounds"));
+                        ui.end_row();
+                        for (operator, rounds) in analytics.operators.iter().take(TOP_OPERATORS) {
+                            ui.label(operator);
+                            ui.label(rounds.to_string());
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(10.0);
+                ui.strong(tr("K/D over time"));
+                kd_per_day_chart(ui, &analytics.kd_per_day);
+            });
+    }
+
+    // Asks before mirror mode is turned on, as it deletes backups of matches deleted from the
+    // replay folder
+    fn show_mirror_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_mirror_deletions {
+            return;
+        }
+
+        let mut decision = None;
+        egui::Window::new(tr("Turn on mirror mode?"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr_fmt(
+                    "When you delete a match from the replay folder, its backup is deleted too, \
+                     {} minutes later. Matches the game deletes to make room for new ones keep \
+                     their backups. Deleted backups stay in a trash folder in the destination \
+                     for {} days, from where they can be brought back.",
+                    &[&self.mirror_deletion_grace_minutes, &TRASH_KEEP_DAYS],
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Turn on")).clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button(tr("Cancel")).clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+
+        if let Some(enable) = decision {
+            self.confirm_mirror_deletions = false;
+            if enable {
+                self.mirror_deletions = true;
+                self.save_config();
+                self.add_status(StatusEvent::Info(
+                    "Mirror mode is on; it applies the next time watching starts".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn show_move_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_move_after_verify {
+            return;
+        }
+
+        let mut decision = None;
+        egui::Window::new(tr("Delete matches from the game folder?"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    tr("The backup becomes the only copy of every match."),
+                );
+                ui.label(tr(
+                    "Matches are deleted from the replay folder once they are backed up and \
+                     every file has been read back and checked against its checksum. The \
+                     match being recorded is never deleted. The game no longer lists deleted \
+                     matches, and backups removed by retention or the quota are gone for good.",
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Turn on")).clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button(tr("Cancel")).clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+
+        if let Some(enable) = decision {
+            self.confirm_move_after_verify = false;
+            if enable {
+                self.move_after_verify = true;
+                self.save_config();
+                self.add_status(StatusEvent::Info(
+                    "Verified matches are deleted from the source from the next time watching \
+                     starts"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+
+    // Asks before restored matches replace folders that are still in the source
+    fn show_restore_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(conflicts) = &self.restore_conflicts else {
+            return;
+        };
+
+        let mut decision = None;
+        egui::Window::new(tr("Overwrite matches?"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr(
+                    "These matches are still in the source folder and will be overwritten:",
+                ));
+                for name in conflicts {
+                    ui.label(format!("• {}", name));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Overwrite")).clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button(tr("Cancel")).clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+
+        if let Some(overwrite) = decision {
+            self.restore_conflicts = None;
+            if overwrite {
+                self.restore_selected(true);
+            }
+        }
+    }
+}
+
+impl eframe::App for SiegeSaverApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.config_changed.swap(false, Ordering::Relaxed) {
+            self.reload_config();
+        }
+        self.theme.apply(ctx);
+
+        if self.instance.take_show_request() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+
+        // Handle tray icon click events
+        let tray_channel = TrayIconEvent::receiver();
+        if let Ok(TrayIconEvent::Click { button, .. }) = tray_channel.try_recv() {
+            if button == MouseButton::Left {
+                // Show and focus window on left click
+                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            } else if button == MouseButton::Right {
+                // Close the application on right click
+                self.should_exit = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+
+        // Handle tray menu events
+        let menu_channel = MenuEvent::receiver();
+        if let Ok(event) = menu_channel.try_recv() {
+            if event.id == self.tray.quit_item_id {
+                // Set should_exit to true and then close
+                self.should_exit = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            } else if event.id == self.tray.watch_item_id {
+                if self.is_watching {
+                    self.stop_watching();
+                } else {
+                    self.start_watching();
+                }
+            } else if event.id == self.tray.pause_item_id {
+                self.pause_watching(PAUSE_DURATION);
+            } else if event.id == self.tray.resume_item_id {
+                self.resume_watching();
+            } else if event.id == self.tray.verify_item_id {
+                self.verify_backups();
+            } else if event.id == self.tray.open_source_item_id {
+                for source in self.source_paths() {
+                    self.open_folder(&source);
+                }
+            } else if event.id == self.tray.open_destination_item_id {
+                self.open_folder(&PathBuf::from(&self.destination_folder));
+            } else if let Some(name) = self.tray.profile_for(&event.id).map(String::from) {
+                self.switch_profile(&name);
+            }
+        }
+
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.remember_window(ctx);
+        }
+        // Intercept close requests - hide window instead of closing unless should_exit is true
+        if ctx.input(|i| i.viewport().close_requested()) && !self.should_exit {
+            // Hide the window instead of closing
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        }
+        // When should_exit is true, the close request will proceed normally and terminate the application
+
+        // Check for status messages from the background thread
+        let mut events = Vec::new();
+        while let Ok(event) = self.status_receiver.try_recv() {
+            events.push(event);
+        }
+        let mut history_changed = false;
+        // Backups change the number of archived matches shown in Discord
+        let mut backed_up = false;
+        for event in events {
+            if let Some(discord) = &self.discord {
+                discord.notify(&event);
+            }
+            if let Some(webhook) = &self.webhook {
+                webhook.notify(&event);
+            }
+            self.desktop_notifications.notify(&event);
+            match event.severity() {
+                Severity::Error => self.tray_error = true,
+                Severity::Success => self.tray_error = false,
+                _ => {}
+            }
+            if let Some(entry) = HistoryEntry::from_event(&event, chrono::Local::now()) {
+                backed_up |= entry.error.is_none();
+                self.history.record(entry);
+                history_changed = true;
+            }
+            match event {
+                StatusEvent::Progress { name, done, total } => {
+                    self.archive_progress = (done < total).then_some((name, done, total));
+                }
+                StatusEvent::CopyProgress {
+                    path,
+                    copied,
+                    total,
+                } => {
+                    if copied >= total {
+                        self.copy_progress.remove(&path);
+                    } else {
+                        let entry =
+                            self.copy_progress
+                                .entry(path)
+                                .or_insert((0, total, Instant::now()));
+                        entry.0 = copied;
+                        entry.1 = total;
+                    }
+                }
+                StatusEvent::RestoreProgress { done, total } => {
+                    self.restore_progress = (done < total).then_some((done, total));
+                }
+                StatusEvent::DestinationOffline { ref destination } => {
+                    self.offline_destinations.insert(destination.clone());
+                    self.add_status(event);
+                }
+                StatusEvent::DestinationOnline { ref destination } => {
+                    self.offline_destinations.remove(destination);
+                    self.add_status(event);
+                }
+                StatusEvent::DestinationMoved { ref from, ref to } => {
+                    self.offline_destinations.remove(from);
+                    self.follow_moved_destination(from, to);
+                    self.add_status(event);
+                }
+                StatusEvent::Verified(report) => {
+                    if !report.problems.is_empty() {
+                        self.damaged_backups.push(report.clone());
+                    }
+                    self.add_status(StatusEvent::Verified(report));
+                }
+                event => {
+                    if matches!(
+                        event,
+                        StatusEvent::FolderArchived { .. } | StatusEvent::CopyError { .. }
+                    ) {
+                        self.archive_progress = None;
+                    }
+                    // A copy that failed midway never reports its end
+                    if matches!(event, StatusEvent::CopyError { .. } | StatusEvent::Stopped) {
+                        self.copy_progress.clear();
+                    }
+                    self.add_status(event);
+                }
+            }
+        }
+
+        if backed_up
+            || self
+                .destination_summary
+                .as_ref()
+                .is_none_or(|(folder, _, _)| *folder != self.destination_folder)
+        {
+            self.refresh_destination_summary();
+        }
+
+        if let Some(presence) = &self.presence {
+            if backed_up || self.presence_watching != Some(self.is_watching) {
+                presence.update(self.is_watching, Path::new(&self.destination_folder));
+                self.presence_watching = Some(self.is_watching);
+            }
+        }
+
+        let connected = self
+            .drive_token
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some((index, refresh_token)) = connected {
+            if let Some(RemoteConfig::GoogleDrive(config)) = self.remote_destinations.get_mut(index)
+            {
+                config.refresh_token = refresh_token;
+                self.save_config();
+            }
+        }
+
+        if history_changed {
+            let path = self.config_path.with_file_name(HISTORY_FILE_NAME);
+            if let Err(e) = self.history.save(&path) {
+                self.add_status(StatusEvent::Error(format!(
+                    "Failed to save backup history: {}",
+                    e
+                )));
+            }
+        }
+
+        self.update_pause(ctx);
+        self.update_tray_icon(ctx);
+        self.check_backup_staleness();
+        self.write_metrics(ctx);
+        self.update_api();
+        self.run_scheduled_sync(ctx);
+        self.update_quiet_hours(ctx);
+        self.check_service(ctx);
+        self.receive_dropped_files(ctx);
+        if self.is_watching && self.stale_warning_enabled {
+            ctx.request_repaint_after(STALE_CHECK_INTERVAL);
+        }
+
+        egui::TopBottomPanel::bottom("footer").show(ctx, |ui| {
+            ui.small(build_info());
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("SiegeSaver - Replay File Backup Utility");
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tab, Tab::Backup, tr("Backup"));
+                if ui
+                    .selectable_value(&mut self.tab, Tab::Restore, tr("Restore"))
+                    .clicked()
+                {
+                    self.refresh_restore_list();
+                }
+                if ui
+                    .selectable_value(&mut self.tab, Tab::Library, tr("Library"))
+                    .clicked()
+                {
+                    self.refresh_library();
+                }
+                ui.selectable_value(&mut self.tab, Tab::History, tr("History"));
+                if ui
+                    .selectable_value(&mut self.tab, Tab::Stats, tr("Stats"))
+                    .clicked()
+                {
+                    self.refresh_stats();
+                }
+                if ui
+                    .selectable_value(&mut self.tab, Tab::Analytics, tr("Analytics"))
+                    .clicked()
+                {
+                    self.refresh_library();
+                }
+            });
+            ui.separator();
+            match self.tab {
+                Tab::Backup => self.show_backup_tab(ui),
+                Tab::Restore => self.show_restore_tab(ui),
+                Tab::Library => self.show_library_tab(ui),
+                Tab::History => self.show_history_tab(ui),
+                Tab::Stats => self.show_stats_tab(ui),
+                Tab::Analytics => self.show_analytics_tab(ui),
+            }
+
+            ui.add_space(20.0);
+
+            for (path, (copied, total, started)) in &self.copy_progress {
+                let mut text = format!(
+                    "{} {}/{}",
+                    path.display(),
+                    format_bytes(*copied),
+                    format_bytes(*total)
+                );
+                if let Some(remaining) = remaining_time(*copied, *total, started.elapsed()) {
+                    text.push_str(&tr_fmt(", {} left", &[&format_countdown(remaining)]));
+                }
+                ui.add(egui::ProgressBar::new(*copied as f32 / *total as f32).text(text));
+            }
+
+            ui.separator();
+            self.show_log(ui);
+        });
+
+        self.show_event_detail(ctx);
+        self.show_restore_confirmation(ctx);
+        self.show_mirror_confirmation(ctx);
+        self.show_move_confirmation(ctx);
+    }
+}
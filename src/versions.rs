@@ -0,0 +1,133 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use chrono::NaiveDateTime;
+use std::path::{Path, PathBuf};
+
+// Folder in the destination that earlier versions of changed files are moved into, at the
+// same place relative to it as the file, e.g. `.versions/Match-A/round1.rec.2025-11-24T14-32-10`
+pub const VERSIONS_FOLDER_NAME: &str = ".versions";
+// Suffix of a kept version: when it was replaced. Versions of a file sort the same way.
+const VERSION_SUFFIX_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+
+// Where a backup keeps earlier versions of the files it replaces, and how many of each
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeptVersions {
+    pub folder: PathBuf,
+    pub keep: usize,
+}
+
+impl KeptVersions {
+    // Moves `stored`, a file or archive about to be replaced, into the versions folder and
+    // deletes its oldest versions beyond the number kept
+    pub fn keep_version(
+        &self,
+        fs: &dyn FileSystem,
+        stored: &Path,
+        now: NaiveDateTime,
+    ) -> std::io::Result<()> {
+        // The versions folder sits next to the backups
+        let Some(relative) = self
+            .folder
+            .parent()
+            .and_then(|destination| stored.strip_prefix(destination).ok())
+        else {
+            return Ok(());
+        };
+        let Some(name) = relative.file_name() else {
+            return Ok(());
+        };
+        let folder = match relative.parent() {
+            Some(parent) => self.folder.join(parent),
+            None => self.folder.clone(),
+        };
+        fs.create_dir_all(&folder)?;
+        let version = folder.join(format!(
+            "{}.{}",
+            name.to_string_lossy(),
+            now.format(VERSION_SUFFIX_FORMAT)
+        ));
+        // A version from earlier in the same second is replaced
+        if fs.metadata(&version).is_ok() {
+            fs.remove(&version)?;
+        }
+        fs.rename(stored, &version)?;
+
+        let mut versions = versions_of(fs, &folder, &name.to_string_lossy())?;
+        let excess = versions.len().saturating_sub(self.keep);
+        for old in versions.drain(..excess) {
+            fs.remove(&old)?;
+        }
+        Ok(())
+    }
+}
+
+// The kept versions of the file `name` in `folder`, oldest first
+pub fn versions_of(
+    fs: &dyn FileSystem,
+    folder: &Path,
+    name: &str,
+) -> std::io::Result<Vec<PathBuf>> {
+    let prefix = format!("{}.", name);
+    let mut versions: Vec<PathBuf> = fs
+        .read_dir(folder)?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|file_name| file_name.to_str()?.strip_prefix(&prefix).map(str::to_owned))
+                .is_some_and(|suffix| {
+                    NaiveDateTime::parse_from_str(&suffix, VERSION_SUFFIX_FORMAT).is_ok()
+                })
+        })
+        .collect();
+    versions.sort();
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::RealFs;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_keep_version() {
+        let destination = std::env::temp_dir().join("siegesaver_versions_test");
+        let _ = std::fs::remove_dir_all(&destination);
+        std::fs::create_dir_all(destination.join("Match-A")).unwrap();
+        let fs = RealFs;
+        let kept = KeptVersions {
+            folder: destination.join(VERSIONS_FOLDER_NAME),
+            keep: 2,
+        };
+        let stored = destination.join("Match-A").join("round1.rec");
+        let time = |minute| {
+            NaiveDate::from_ymd_opt(2025, 11, 24)
+                .unwrap()
+                .and_hms_opt(14, minute, 10)
+                .unwrap()
+        };
+        for minute in 0..3 {
+            std::fs::write(&stored, format!("version {}", minute)).unwrap();
+            kept.keep_version(&fs, &stored, time(minute)).unwrap();
+            assert!(!stored.exists());
+        }
+
+        // Only the two newest versions are left
+        let folder = kept.folder.join("Match-A");
+        let versions = versions_of(&fs, &folder, "round1.rec").unwrap();
+        assert_eq!(
+            versions,
+            [
+                folder.join("round1.rec.2025-11-24T14-01-10"),
+                folder.join("round1.rec.2025-11-24T14-02-10"),
+            ]
+        );
+        assert_eq!(std::fs::read_to_string(&versions[1]).unwrap(), "version 2");
+        // Other files whose name starts with the file's are no versions of it
+        std::fs::write(folder.join("round1.rec.bak"), "other").unwrap();
+        assert_eq!(versions_of(&fs, &folder, "round1.rec").unwrap().len(), 2);
+
+        let _ = std::fs::remove_dir_all(&destination);
+    }
+}
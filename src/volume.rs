@@ -0,0 +1,157 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::FileSystem;
+use crate::manifest::sha256_hex;
+use std::path::{Path, PathBuf};
+
+// A file in the destination folder holding a random ID. External drives and network shares
+// can come back under another drive letter or mount point, and an empty mount point can look
+// like the destination; the ID tells which folder really is it.
+pub const VOLUME_ID_FILE_NAME: &str = "siegesaver-volume-id";
+
+// Where removable drives are mounted, either directly or in a folder per user
+#[cfg(not(windows))]
+const MOUNT_ROOTS: [&str; 4] = ["/media", "/run/media", "/mnt", "/Volumes"];
+
+// The ID written into `folder`, if it has one
+pub fn read_volume_id(fs: &dyn FileSystem, folder: &Path) -> Option<String> {
+    let id = fs.read(&folder.join(VOLUME_ID_FILE_NAME)).ok()?;
+    let id = String::from_utf8(id).ok()?.trim().to_string();
+    (!id.is_empty()).then_some(id)
+}
+
+// The ID of `folder`, written into it first if it has none yet
+pub fn ensure_volume_id(fs: &dyn FileSystem, folder: &Path) -> std::io::Result<String> {
+    if let Some(id) = read_volume_id(fs, folder) {
+        return Ok(id);
+    }
+    let seed = format!(
+        "{:?} {} {}",
+        std::time::SystemTime::now(),
+        std::process::id(),
+        folder.display()
+    );
+    let id = sha256_hex(seed.as_bytes())[..32].to_string();
+    fs.write(&folder.join(VOLUME_ID_FILE_NAME), id.as_bytes())?;
+    Ok(id)
+}
+
+// Looks for the folder with the ID `id` where the drive of `original` may be mounted now,
+// e.g. the same path on another drive letter
+pub fn find_volume(fs: &dyn FileSystem, id: &str, original: &Path) -> Option<PathBuf> {
+    volume_candidates(fs, original)
+        .into_iter()
+        .find(|candidate| {
+            candidate != original && read_volume_id(fs, candidate).as_deref() == Some(id)
+        })
+}
+
+#[cfg(windows)]
+fn volume_candidates(_fs: &dyn FileSystem, original: &Path) -> Vec<PathBuf> {
+    let original = original.to_string_lossy();
+    if original.as_bytes().get(1) != Some(&b':') {
+        return Vec::new();
+    }
+    ('A'..='Z')
+        .map(|letter| PathBuf::from(format!("{}{}", letter, &original[1..])))
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn volume_candidates(fs: &dyn FileSystem, original: &Path) -> Vec<PathBuf> {
+    let roots: Vec<&Path> = MOUNT_ROOTS.iter().map(Path::new).collect();
+    sibling_candidates(fs, original, &roots)
+}
+
+// The same path below every other drive mounted next to the one `original` is on
+#[cfg(any(not(windows), test))]
+fn sibling_candidates(fs: &dyn FileSystem, original: &Path, roots: &[&Path]) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    for mount in original.ancestors() {
+        let Some(parent) = mount.parent() else {
+            continue;
+        };
+        if !roots
+            .iter()
+            .any(|root| parent == *root || parent.parent() == Some(root))
+        {
+            continue;
+        }
+        let Ok(relative) = original.strip_prefix(mount) else {
+            continue;
+        };
+        for sibling in fs.read_dir(parent).unwrap_or_default() {
+            candidates.push(sibling.join(relative));
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{BackupEngine, RealFs};
+    use crate::status::StatusEvent;
+    use std::fs;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_destination_offline_and_moved() {
+        let test_dir = std::env::temp_dir().join("siegesaver_volume_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let source = test_dir.join("replays");
+        let media = test_dir.join("media");
+        let destination = media.join("USB-A").join("backups");
+        fs::create_dir_all(source.join("Match-A")).unwrap();
+        fs::write(source.join("Match-A").join("round1.rec"), "round 1").unwrap();
+        fs::create_dir_all(&destination).unwrap();
+
+        let engine = BackupEngine::new(source.clone(), destination.clone(), Default::default())
+            .with_volume_tracking();
+        let id = read_volume_id(&RealFs, &destination).unwrap();
+        assert_eq!(ensure_volume_id(&RealFs, &destination).unwrap(), id);
+        let (status_tx, status_rx) = channel();
+        assert!(engine.check_destination(&status_tx));
+
+        // Unplugged: the failed backup is held rather than counted as an error
+        fs::rename(media.join("USB-A"), media.join("USB-B")).unwrap();
+        let backup = engine.backup(&source.join("Match-A"), &status_tx).unwrap();
+        assert!(backup.totals.is_none());
+        assert_eq!(engine.stats().lock().unwrap().errors, 0);
+        assert!(!engine.check_destination(&status_tx));
+        assert!(engine.is_offline());
+        // An empty folder in its place is not the destination
+        fs::create_dir_all(&destination).unwrap();
+        assert!(!engine.check_destination(&status_tx));
+        let offline: Vec<_> = status_rx
+            .try_iter()
+            .filter(|event| matches!(event, StatusEvent::DestinationOffline { .. }))
+            .collect();
+        assert_eq!(offline.len(), 1);
+
+        // The drive is found again under its new mount point
+        let moved = media.join("USB-B").join("backups");
+        let candidates = sibling_candidates(&RealFs, &destination, &[&media]);
+        assert!(candidates.contains(&moved));
+        let found = candidates
+            .into_iter()
+            .find(|candidate| read_volume_id(&RealFs, candidate).as_deref() == Some(&id));
+        assert_eq!(found, Some(moved));
+
+        // Plugged back in where it was
+        fs::remove_dir_all(media.join("USB-A")).unwrap();
+        fs::rename(media.join("USB-B"), media.join("USB-A")).unwrap();
+        assert!(engine.check_destination(&status_tx));
+        assert!(!engine.is_offline());
+        assert!(status_rx
+            .try_iter()
+            .any(|event| matches!(event, StatusEvent::DestinationOnline { .. })));
+        assert!(engine
+            .backup(&source.join("Match-A"), &status_tx)
+            .unwrap()
+            .totals
+            .is_some());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
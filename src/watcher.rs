@@ -0,0 +1,912 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::BackupEngine;
+use crate::longpath::{extended_path, regular_path};
+use crate::queue::{BackupJob, BackupQueue};
+use crate::status::StatusEvent;
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta};
+use notify::{Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use notify_debouncer_full::{
+    new_debouncer, new_debouncer_opt, DebounceEventResult, Debouncer, FileIdMap,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+// How long "Pause" suspends backups before watching resumes on its own
+pub const PAUSE_DURATION: Duration = Duration::from_secs(60 * 60);
+
+// Tracks a temporary pause. Callers pass in the current time so the timing logic can be tested.
+#[derive(Default)]
+pub struct PauseSchedule {
+    resume_at: Option<Instant>,
+}
+
+impl PauseSchedule {
+    pub fn pause_for(&mut self, now: Instant, duration: Duration) {
+        self.resume_at = Some(now + duration);
+    }
+
+    pub fn resume(&mut self) {
+        self.resume_at = None;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.resume_at.is_some()
+    }
+
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.resume_at
+            .map(|resume_at| resume_at.saturating_duration_since(now))
+    }
+
+    // Returns true exactly once, when the pause has run out
+    pub fn poll_expired(&mut self, now: Instant) -> bool {
+        if self.resume_at.is_some_and(|resume_at| now >= resume_at) {
+            self.resume_at = None;
+            return true;
+        }
+        false
+    }
+}
+
+pub fn format_countdown(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// How often the staleness watchdog rescans the source folder
+pub const STALE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// Returns the newest modification time among the source folder and its direct children.
+// Match folders get a new mtime whenever a file is added to them, so a single level is enough
+// to notice new replays without walking the whole tree.
+pub fn newest_source_change(source: &Path) -> Option<SystemTime> {
+    let source = extended_path(source);
+    let mut newest = fs::metadata(&source).and_then(|m| m.modified()).ok();
+    if let Ok(entries) = fs::read_dir(&source) {
+        for entry in entries.flatten() {
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                newest = newest.max(Some(modified));
+            }
+        }
+    }
+    newest
+}
+
+// A backup is considered stale when nothing has been backed up for `stale_after`
+// even though the source folder changed since the last backup (or since watching started).
+pub fn is_backup_stale(
+    last_backup: Option<SystemTime>,
+    watching_since: SystemTime,
+    newest_change: Option<SystemTime>,
+    now: SystemTime,
+    stale_after: Duration,
+) -> bool {
+    let reference = last_backup.unwrap_or(watching_since);
+    let elapsed = now.duration_since(reference).unwrap_or_default();
+    let source_changed = newest_change.is_some_and(|changed| changed > reference);
+    elapsed >= stale_after && source_changed
+}
+
+// A running recursive watch on a source folder; dropping it stops the watch
+pub enum FolderWatcher {
+    // Notified by the operating system
+    Native(Debouncer<RecommendedWatcher, FileIdMap>),
+    // Rescans the folder on an interval, for drives the native watch misses events on
+    Polling(Debouncer<PollWatcher, FileIdMap>),
+}
+
+// How a source folder is watched
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchOptions {
+    // How long to wait for a burst of changes to settle before forwarding them
+    pub debounce: Duration,
+    // Rescan the folder instead of relying on the operating system, e.g. on SMB shares and
+    // exFAT drives
+    pub polling: bool,
+    pub poll_interval: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(500),
+            polling: false,
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+fn start_watch<T: Watcher>(
+    source: &Path,
+    debounce: Duration,
+    config: notify::Config,
+    events: Sender<Event>,
+    status_tx: Sender<StatusEvent>,
+) -> Result<Debouncer<T, FileIdMap>, String> {
+    let forward = move |result: DebounceEventResult| match result {
+        Ok(debounced) => {
+            for mut event in debounced {
+                // Reported below the extended path the folder is watched under on Windows
+                for path in &mut event.event.paths {
+                    *path = regular_path(path).into_owned();
+                }
+                if let Err(e) = events.send(event.event) {
+                    let _ = status_tx.send(StatusEvent::WatcherError(format!(
+                        "could not forward event: {}",
+                        e
+                    )));
+                }
+            }
+        }
+        Err(errors) => {
+            for error in errors {
+                let _ = status_tx.send(StatusEvent::WatcherError(format!("{:?}", error)));
+            }
+        }
+    };
+    let mut debouncer =
+        new_debouncer_opt::<_, T, _>(debounce, None, forward, FileIdMap::new(), config)
+            .map_err(|e| format!("Error creating file watcher: {}", e))?;
+
+    debouncer
+        .watcher()
+        .watch(&extended_path(source), RecursiveMode::Recursive)
+        .map_err(|e| format!("Error watching folder: {}", e))?;
+
+    Ok(debouncer)
+}
+
+// Starts watching `source` recursively. Events are forwarded to `events` once no new ones
+// arrived for the debounce time, watcher errors are reported on `status_tx`. When the native
+// watch cannot be set up the folder is polled instead, with a warning.
+pub fn watch_folder(
+    source: &Path,
+    options: &WatchOptions,
+    events: Sender<Event>,
+    status_tx: Sender<StatusEvent>,
+) -> Result<FolderWatcher, String> {
+    if !options.polling {
+        match start_watch(
+            source,
+            options.debounce,
+            notify::Config::default(),
+            events.clone(),
+            status_tx.clone(),
+        ) {
+            Ok(debouncer) => return Ok(FolderWatcher::Native(debouncer)),
+            Err(e) => {
+                let _ = status_tx.send(StatusEvent::Warning(format!(
+                    "{} ({}); checking it every {} seconds instead",
+                    e,
+                    source.display(),
+                    options.poll_interval.as_secs()
+                )));
+            }
+        }
+    }
+    let config = notify::Config::default().with_poll_interval(options.poll_interval);
+    start_watch(source, options.debounce, config, events, status_tx).map(FolderWatcher::Polling)
+}
+
+// A running watch on the config file; dropping it stops the watch
+pub type ConfigWatcher = Debouncer<RecommendedWatcher, FileIdMap>;
+
+// Watches the config file for edits made outside the app, by hand or by a sync tool, and
+// calls `changed` after each. Its folder is watched, as editors often replace the file
+// instead of writing to it.
+pub fn watch_config_file(
+    config_path: &Path,
+    changed: impl Fn() + Send + 'static,
+) -> Result<ConfigWatcher, String> {
+    let (Some(folder), Some(file_name)) = (config_path.parent(), config_path.file_name()) else {
+        return Err(format!(
+            "Cannot watch config file {}",
+            config_path.display()
+        ));
+    };
+    let file_name = file_name.to_os_string();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(500),
+        None,
+        move |result: DebounceEventResult| {
+            let touched = result.is_ok_and(|debounced| {
+                debounced.iter().any(|event| {
+                    event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == Some(file_name.as_os_str()))
+                })
+            });
+            if touched {
+                changed();
+            }
+        },
+    )
+    .map_err(|e| format!("Error creating config file watcher: {}", e))?;
+
+    debouncer
+        .watcher()
+        .watch(folder, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Error watching config file: {}", e))?;
+
+    Ok(debouncer)
+}
+
+// How long a destination waits before a path it failed to back up is tried again. The wait
+// doubles after every failed retry, up to MAX_RETRY_INTERVAL, so a file the game holds
+// locked for a whole round is still picked up without trying it every few seconds.
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+pub const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Retries per path and destination before giving up
+pub const MAX_RETRIES: u32 = 8;
+
+pub fn retry_interval(retries: u32) -> Duration {
+    RETRY_INTERVAL
+        .saturating_mul(2u32.saturating_pow(retries))
+        .min(MAX_RETRY_INTERVAL)
+}
+
+// Paths one destination failed to back up, with how often they were retried and when they
+// are due next. Callers pass in the current time so the timing logic can be tested.
+#[derive(Default)]
+pub struct RetryQueue {
+    pending: BTreeMap<PathBuf, (u32, Instant)>,
+}
+
+impl RetryQueue {
+    // Records the outcome of backing up `path`. Returns false if it failed once too often and
+    // was dropped from the queue.
+    pub fn record(&mut self, path: &Path, succeeded: bool, now: Instant) -> bool {
+        if succeeded {
+            self.pending.remove(path);
+            return true;
+        }
+        let retries = self.pending.get(path).map(|(retries, _)| retries + 1);
+        match retries {
+            Some(retries) if retries > MAX_RETRIES => {
+                self.pending.remove(path);
+                false
+            }
+            retries => {
+                let retries = retries.unwrap_or(0);
+                self.pending
+                    .insert(path.to_path_buf(), (retries, now + retry_interval(retries)));
+                true
+            }
+        }
+    }
+
+    // When `path` is tried next, if it is queued
+    pub fn next_retry(&self, path: &Path) -> Option<Instant> {
+        self.pending.get(path).map(|(_, due)| *due)
+    }
+
+    pub fn due(&self, now: Instant) -> Vec<PathBuf> {
+        self.pending
+            .iter()
+            .filter(|(_, (_, due))| *due <= now)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+// Checks the destination of `engine` and points the queued jobs to where its drive is now,
+// if it moved. Returns whether backups into it can run.
+fn check_destination(
+    engine: &BackupEngine,
+    queue: &BackupQueue,
+    status_tx: &Sender<StatusEvent>,
+) -> bool {
+    let from = engine.destination_root();
+    let available = engine.check_destination(status_tx);
+    let to = engine.destination_root();
+    if to != from {
+        queue.relocate(&from, &to);
+    }
+    available
+}
+
+fn back_up(
+    engine: &BackupEngine,
+    job: &BackupJob,
+    retries: &mut RetryQueue,
+    queue: &BackupQueue,
+    status_tx: &Sender<StatusEvent>,
+) {
+    let path = job.path.as_path();
+    // A path still being written is retried like a failed copy
+    let backup = if engine.wait_until_stable(path) {
+        let Some(backup) = engine.backup_cancellable(path, status_tx, Some(&job.cancel)) else {
+            tracing::debug!("Nothing to back up in {}", path.display());
+            return;
+        };
+        Some(backup)
+    } else {
+        tracing::debug!("{} is still being written", path.display());
+        None
+    };
+    // Waits in the queue until the drive has room again
+    if let Some(backup) = backup.as_ref().filter(|backup| backup.low_space) {
+        let _ = status_tx.send(backup.message.clone());
+        queue.push(engine.source_folder(), &engine.destination_root(), path);
+        return;
+    }
+    // Cancelled jobs are not retried
+    let succeeded = backup
+        .as_ref()
+        .is_some_and(|backup| backup.totals.is_some() || backup.cancelled);
+    // The drive is gone; the job waits in the queue until it is back instead of using up
+    // its retries
+    if !succeeded && !check_destination(engine, queue, status_tx) {
+        queue.push(engine.source_folder(), &engine.destination_root(), path);
+        return;
+    }
+    let now = Instant::now();
+    let retrying = retries.record(path, succeeded, now);
+    if let Some(next_retry) = retries.next_retry(path) {
+        tracing::debug!(
+            "Retrying {} in {} s",
+            path.display(),
+            next_retry.saturating_duration_since(now).as_secs()
+        );
+    }
+    match (backup, retries.next_retry(path)) {
+        // Expected while the game records; only an error once it is given up on
+        (Some(backup), Some(next_retry)) if backup.locked && retrying => {
+            let _ = status_tx.send(StatusEvent::Warning(format!(
+                "{} is in use by another program; trying again in {} s",
+                path.display(),
+                next_retry.saturating_duration_since(now).as_secs()
+            )));
+        }
+        (Some(backup), _) => {
+            let _ = status_tx.send(backup.message);
+        }
+        (None, _) => {}
+    }
+    if !retrying {
+        let _ = status_tx.send(StatusEvent::Warning(format!(
+            "Giving up on {} for {} after {} retries",
+            path.display(),
+            engine.destination_folder().display(),
+            MAX_RETRIES
+        )));
+    }
+}
+
+// How often the event handler wakes up without events, to retry and rescan
+const HANDLER_TICK: Duration = Duration::from_secs(1);
+
+// When the event handlers compare the source folders with the manifests, to back up whatever
+// the watcher missed
+#[derive(Clone, Default)]
+pub struct RescanSchedule {
+    // None to only rescan when asked to
+    pub interval: Option<Duration>,
+    // Bumped to have every handler rescan right away
+    pub requests: Arc<AtomicUsize>,
+}
+
+impl RescanSchedule {
+    pub fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn rescan(
+    engine: &BackupEngine,
+    since: SystemTime,
+    queue: &BackupQueue,
+    status_tx: &Sender<StatusEvent>,
+) {
+    let items = match engine.unsynced_items(since) {
+        Ok(items) => items,
+        Err(e) => {
+            let _ = status_tx.send(StatusEvent::Error(format!(
+                "Error rescanning {}: {}",
+                engine.source_folder().display(),
+                e
+            )));
+            return;
+        }
+    };
+    if !items.is_empty() {
+        let _ = status_tx.send(StatusEvent::Warning(format!(
+            "Rescan found {} changed items in {} the watcher missed",
+            items.len(),
+            engine.source_folder().display()
+        )));
+    }
+    for path in items {
+        queue.push(engine.source_folder(), &engine.destination_root(), &path);
+    }
+}
+
+// Hands the paths deleted by `events` to every engine, for mirror mode, and deletes the
+// backups whose grace period is over
+fn mirror_deletions(events: &[Event], engines: &[BackupEngine], status_tx: &Sender<StatusEvent>) {
+    let removed: Vec<&PathBuf> = events
+        .iter()
+        .filter(|event| matches!(event.kind, EventKind::Remove(_)))
+        .flat_map(|event| &event.paths)
+        .collect();
+    for engine in engines {
+        for path in &removed {
+            engine.source_removed(path, status_tx);
+        }
+        engine.run_due_deletions(status_tx);
+    }
+}
+
+// Queues the paths created or modified by `events` for every engine, with one job per match
+// folder where possible
+fn queue_changes(events: Vec<Event>, engines: &[BackupEngine], queue: &BackupQueue) {
+    // Ignore all other events including deletions, which only mirror mode looks at
+    let paths: Vec<PathBuf> = events
+        .into_iter()
+        .inspect(|event| tracing::trace!(kind = ?event.kind, paths = ?event.paths, "File event"))
+        .filter(|event| matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)))
+        .flat_map(|event| event.paths)
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+    for engine in engines {
+        for path in engine.coalesce_paths(paths.iter().cloned()) {
+            tracing::debug!("Queued {}", path.display());
+            queue.push(engine.source_folder(), &engine.destination_root(), &path);
+        }
+    }
+}
+
+// Backs up every created or modified path until the watcher is dropped
+pub fn handle_file_events(
+    rx: Receiver<Event>,
+    engine: &BackupEngine,
+    status_tx: &Sender<StatusEvent>,
+    paused: &AtomicBool,
+) {
+    handle_mirrored_file_events(
+        rx,
+        std::slice::from_ref(engine),
+        status_tx,
+        paused,
+        &RescanSchedule::default(),
+        &BackupQueue::default(),
+    );
+}
+
+// Backs up every created or modified path into each engine's destination until the watcher
+// is dropped. Changes are queued on `queue` as one job per path and destination, with one job
+// per match folder where possible, and run in queue order. Destinations succeed or fail
+// independently: a path that failed for one is retried for that destination only. Everything
+// changed since the handler started is also rescanned on `schedule`, except for changes made
+// while paused.
+pub fn handle_mirrored_file_events(
+    rx: Receiver<Event>,
+    engines: &[BackupEngine],
+    status_tx: &Sender<StatusEvent>,
+    paused: &AtomicBool,
+    schedule: &RescanSchedule,
+    queue: &BackupQueue,
+) {
+    let Some(source_folder) = engines
+        .first()
+        .map(|engine| engine.source_folder().to_path_buf())
+    else {
+        return;
+    };
+    let _span = tracing::info_span!("watch", source = %source_folder.display()).entered();
+    let mut retries: Vec<RetryQueue> = engines.iter().map(|_| RetryQueue::default()).collect();
+    let mut since = SystemTime::now();
+    let mut last_rescan = Instant::now();
+    let mut handled_requests = schedule.requests.load(Ordering::Relaxed);
+    loop {
+        let events = match rx.recv_timeout(HANDLER_TICK) {
+            // The debouncer sends a whole burst at once
+            Ok(event) => std::iter::once(event).chain(rx.try_iter()).collect(),
+            Err(RecvTimeoutError::Timeout) => Vec::new(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        // Changes made while paused are intentionally not backed up
+        if paused.load(Ordering::Relaxed) {
+            since = SystemTime::now();
+            continue;
+        }
+
+        mirror_deletions(&events, engines, status_tx);
+        queue_changes(events, engines, queue);
+
+        let now = Instant::now();
+        for (engine, retries) in engines.iter().zip(&retries) {
+            for path in retries.due(now) {
+                queue.push(engine.source_folder(), &engine.destination_root(), &path);
+            }
+        }
+        // Resumes the held jobs once an unplugged destination is back or has room again
+        for engine in engines {
+            if engine.is_offline() {
+                check_destination(engine, queue, status_tx);
+            }
+            engine.check_free_space(status_tx);
+        }
+
+        let requests = schedule.requests.load(Ordering::Relaxed);
+        let due = schedule
+            .interval
+            .is_some_and(|interval| now >= last_rescan + interval);
+        if due || requests != handled_requests {
+            tracing::debug!("Rescanning for changes the watcher missed");
+            handled_requests = requests;
+            last_rescan = now;
+            for engine in engines.iter().filter(|engine| !engine.is_offline()) {
+                rescan(engine, since, queue, status_tx);
+            }
+        }
+
+        // New events are queued between jobs, so they can be reordered while others run
+        while !paused.load(Ordering::Relaxed) {
+            let held: Vec<PathBuf> = engines
+                .iter()
+                .filter(|engine| engine.is_offline() || engine.is_low_on_space())
+                .map(BackupEngine::destination_root)
+                .collect();
+            let Some(job) = queue.start_next(&source_folder, &held) else {
+                break;
+            };
+            let target = engines
+                .iter()
+                .zip(&mut retries)
+                .find(|(engine, _)| engine.destination_root() == job.destination_folder);
+            if let Some((engine, retries)) = target {
+                back_up(engine, &job, retries, queue, status_tx);
+            }
+            queue.finish(job.id);
+            let events: Vec<Event> = rx.try_iter().collect();
+            mirror_deletions(&events, engines, status_tx);
+            queue_changes(events, engines, queue);
+        }
+    }
+    queue.clear(&source_folder);
+}
+
+// Parses a time of day written as HH:MM, e.g. the time of the scheduled full sync
+pub fn parse_time_of_day(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").ok()
+}
+
+// The last time a run at `time` of day was due, at or before `now`
+fn latest_daily_run(time: NaiveTime, now: NaiveDateTime) -> NaiveDateTime {
+    let today = now.date().and_time(time);
+    if today <= now {
+        today
+    } else {
+        today - TimeDelta::days(1)
+    }
+}
+
+// Whether the run at `time` of day is due at `now`. It is due once a day; a run missed while
+// SiegeSaver was not running is made up for once. Before the first run, only times after
+// `since` count, so turning the schedule on does not start a run right away.
+pub fn daily_run_due(
+    time: NaiveTime,
+    now: NaiveDateTime,
+    last_run: Option<NaiveDateTime>,
+    since: NaiveDateTime,
+) -> bool {
+    latest_daily_run(time, now) > last_run.unwrap_or(since)
+}
+
+// How long until the next run at `time` of day
+pub fn until_daily_run(time: NaiveTime, now: NaiveDateTime) -> Duration {
+    let next = latest_daily_run(time, now) + TimeDelta::days(1);
+    (next - now).to_std().unwrap_or_default()
+}
+
+// Whether `now` falls into the quiet hours from `start` up to `end`, which span midnight if
+// `end` is earlier than `start`. A window that starts and ends at the same time is empty.
+pub fn in_quiet_hours(start: NaiveTime, end: NaiveTime, now: NaiveTime) -> bool {
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{FileMetadata, FileSystem, RealFs};
+    use crate::mirror::{trashed_backups, DeletionQueue};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_backup_staleness() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        let stale_after = minutes(30);
+
+        // Last backup 45 minutes ago and the source changed after it: stale
+        assert!(is_backup_stale(
+            Some(start),
+            start,
+            Some(start + minutes(10)),
+            start + minutes(45),
+            stale_after,
+        ));
+
+        // Source unchanged since the last backup: not stale, the user simply isn't playing
+        assert!(!is_backup_stale(
+            Some(start + minutes(5)),
+            start,
+            Some(start),
+            start + minutes(45),
+            stale_after,
+        ));
+
+        // Threshold not reached yet
+        assert!(!is_backup_stale(
+            Some(start),
+            start,
+            Some(start + minutes(10)),
+            start + minutes(20),
+            stale_after,
+        ));
+
+        // No backup yet: measured from when watching started
+        assert!(is_backup_stale(
+            None,
+            start,
+            Some(start + minutes(1)),
+            start + minutes(31),
+            stale_after,
+        ));
+    }
+
+    #[test]
+    fn test_pause_schedule_auto_resume() {
+        let start = Instant::now();
+        let mut pause = PauseSchedule::default();
+        assert!(!pause.is_paused());
+        assert!(!pause.poll_expired(start));
+
+        pause.pause_for(start, Duration::from_secs(3600));
+        assert!(pause.is_paused());
+        assert_eq!(
+            pause.remaining(start + Duration::from_secs(600)),
+            Some(Duration::from_secs(3000))
+        );
+        assert!(!pause.poll_expired(start + Duration::from_secs(3599)));
+        assert!(pause.is_paused());
+
+        // Expires once, then stays resumed
+        assert!(pause.poll_expired(start + Duration::from_secs(3600)));
+        assert!(!pause.is_paused());
+        assert!(!pause.poll_expired(start + Duration::from_secs(3700)));
+
+        // Resuming early cancels the countdown
+        pause.pause_for(start, Duration::from_secs(60));
+        pause.resume();
+        assert_eq!(pause.remaining(start), None);
+        assert!(!pause.poll_expired(start + Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_retry_queue() {
+        let start = Instant::now();
+        let path = Path::new("/source/Match-A");
+        let mut queue = RetryQueue::default();
+        assert!(queue.record(path, true, start));
+        assert!(queue.is_empty());
+
+        assert!(queue.record(path, false, start));
+        assert!(queue.due(start).is_empty());
+        assert_eq!(queue.due(start + RETRY_INTERVAL), [path.to_path_buf()]);
+
+        // Succeeding on a retry clears it
+        assert!(queue.record(path, true, start + RETRY_INTERVAL));
+        assert!(queue.is_empty());
+        assert_eq!(queue.next_retry(path), None);
+
+        // Failing again and again backs off further each time, and eventually gives up
+        let mut now = start;
+        assert!(queue.record(path, false, now));
+        for retries in 1..=MAX_RETRIES {
+            now = queue.next_retry(path).unwrap();
+            assert!(queue.record(path, false, now));
+            assert_eq!(queue.next_retry(path), Some(now + retry_interval(retries)));
+        }
+        assert_eq!(queue.len(), 1);
+        assert!(!queue.record(path, false, now + MAX_RETRY_INTERVAL));
+        assert!(queue.is_empty());
+
+        assert_eq!(retry_interval(0), RETRY_INTERVAL);
+        assert_eq!(retry_interval(2), RETRY_INTERVAL * 4);
+        assert_eq!(retry_interval(MAX_RETRIES), MAX_RETRY_INTERVAL);
+        assert_eq!(retry_interval(u32::MAX), MAX_RETRY_INTERVAL);
+    }
+
+    #[test]
+    fn test_daily_run_schedule() {
+        let time = parse_time_of_day(" 03:00 ").unwrap();
+        assert_eq!(parse_time_of_day("3 am"), None);
+        let at = |day: u32, hour: u32, minute: u32| {
+            chrono::NaiveDate::from_ymd_opt(2025, 11, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+        let started = at(22, 20, 0);
+
+        // Not right after starting, but at the next 03:00
+        assert!(!daily_run_due(time, at(22, 23, 0), None, started));
+        assert!(!daily_run_due(time, at(23, 2, 59), None, started));
+        assert!(daily_run_due(time, at(23, 3, 0), None, started));
+        // Once a day
+        let ran = at(23, 3, 0);
+        assert!(!daily_run_due(time, at(23, 12, 0), Some(ran), started));
+        assert!(daily_run_due(time, at(24, 3, 1), Some(ran), started));
+        // A run missed while closed is made up for after starting again
+        assert!(daily_run_due(time, at(26, 9, 0), Some(ran), at(26, 8, 0)));
+
+        assert_eq!(
+            until_daily_run(time, at(23, 2, 0)),
+            Duration::from_secs(60 * 60)
+        );
+        assert_eq!(
+            until_daily_run(time, at(23, 3, 0)),
+            Duration::from_secs(24 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let time = |text| parse_time_of_day(text).unwrap();
+        // During the day
+        let (start, end) = (time("18:00"), time("23:30"));
+        assert!(!in_quiet_hours(start, end, time("17:59")));
+        assert!(in_quiet_hours(start, end, time("18:00")));
+        assert!(in_quiet_hours(start, end, time("23:29")));
+        assert!(!in_quiet_hours(start, end, time("23:30")));
+        // Over midnight
+        let (start, end) = (time("22:00"), time("02:00"));
+        assert!(in_quiet_hours(start, end, time("23:00")));
+        assert!(in_quiet_hours(start, end, time("01:59")));
+        assert!(!in_quiet_hours(start, end, time("02:00")));
+        assert!(!in_quiet_hours(start, end, time("12:00")));
+        // Empty
+        assert!(!in_quiet_hours(start, start, start));
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        assert_eq!(format_countdown(Duration::from_secs(59)), "00:59");
+        assert_eq!(format_countdown(Duration::from_secs(42 * 60 + 10)), "42:10");
+        assert_eq!(format_countdown(Duration::from_secs(3600)), "1:00:00");
+    }
+
+    // The real disk, where copying `trigger` deletes `deleted` from the source and reports it
+    // on the watcher's channel, as if the user deleted it while a backup ran
+    struct DeleteWhileCopying {
+        trigger: PathBuf,
+        deleted: PathBuf,
+        events: Mutex<Option<std::sync::mpsc::Sender<Event>>>,
+    }
+
+    impl FileSystem for DeleteWhileCopying {
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+            RealFs.read_dir(path)
+        }
+        fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+            RealFs.metadata(path)
+        }
+        fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            RealFs.create_dir_all(path)
+        }
+        fn copy(&self, from: &Path, to: &Path) -> std::io::Result<u64> {
+            if from == self.trigger {
+                if let Some(events) = self.events.lock().unwrap().take() {
+                    fs::remove_dir_all(&self.deleted)?;
+                    let removed = Event::new(EventKind::Remove(notify::event::RemoveKind::Folder))
+                        .add_path(self.deleted.clone());
+                    events.send(removed).unwrap();
+                }
+            }
+            RealFs.copy(from, to)
+        }
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            RealFs.read(path)
+        }
+        fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+            RealFs.write(path, contents)
+        }
+        fn sync(&self, path: &Path) -> std::io::Result<()> {
+            RealFs.sync(path)
+        }
+        fn remove(&self, path: &Path) -> std::io::Result<()> {
+            RealFs.remove(path)
+        }
+        fn open_for_read(&self, path: &Path) -> std::io::Result<()> {
+            RealFs.open_for_read(path)
+        }
+        fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+            RealFs.rename(from, to)
+        }
+        fn copy_times(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+            RealFs.copy_times(from, to)
+        }
+    }
+
+    #[test]
+    fn test_deletions_during_a_backup_are_mirrored() {
+        let folder = std::env::temp_dir().join("siegesaver_watcher_mirror_test");
+        let _ = fs::remove_dir_all(&folder);
+        let source = folder.join("source");
+        let destination = folder.join("destination");
+        for name in ["Match-A", "Match-B", "Match-C"] {
+            fs::create_dir_all(source.join(name)).unwrap();
+            fs::write(source.join(name).join("round1.rec"), name).unwrap();
+        }
+        let (events_tx, events_rx) = std::sync::mpsc::channel();
+        let filesystem = Arc::new(DeleteWhileCopying {
+            trigger: source.join("Match-C").join("round1.rec"),
+            deleted: source.join("Match-B"),
+            events: Mutex::new(Some(events_tx.clone())),
+        });
+        let deletions = Arc::new(DeletionQueue::default());
+        let engine = BackupEngine::new(source.clone(), destination.clone(), Default::default())
+            .with_fs(filesystem)
+            .with_mirror_deletions(Arc::clone(&deletions), Duration::ZERO);
+        let (status_tx, _status_rx) = std::sync::mpsc::channel();
+        for name in ["Match-A", "Match-B"] {
+            assert!(engine.backup(&source.join(name), &status_tx).is_some());
+        }
+
+        // Match-B is deleted while Match-C is being backed up. The handler returns once both
+        // senders are gone and it has handled everything.
+        let created = Event::new(EventKind::Create(notify::event::CreateKind::Folder))
+            .add_path(source.join("Match-C"));
+        events_tx.send(created).unwrap();
+        drop(events_tx);
+        handle_mirrored_file_events(
+            events_rx,
+            std::slice::from_ref(&engine),
+            &status_tx,
+            &AtomicBool::new(false),
+            &RescanSchedule::default(),
+            &BackupQueue::default(),
+        );
+
+        assert!(destination.join("Match-C").join("round1.rec").exists());
+        assert!(!destination.join("Match-B").exists());
+        let trashed = trashed_backups(&RealFs, &destination).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0]
+            .folder
+            .join("Match-B")
+            .join("round1.rec")
+            .exists());
+        assert!(destination.join("Match-A").exists());
+
+        let _ = fs::remove_dir_all(&folder);
+    }
+}
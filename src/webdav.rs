@@ -0,0 +1,311 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::remote::{uri_encode, RemoteStore};
+use base64::Engine;
+use digest_auth::{AuthContext, HttpMethod, WwwAuthenticateHeader};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Files larger than this are sent in chunks of this size when the server is a Nextcloud,
+// which otherwise rejects uploads above its PHP upload limit
+pub const CHUNK_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebDavAuth {
+    #[default]
+    Basic,
+    Digest,
+}
+
+// A folder on a WebDAV server, e.g. a Nextcloud or ownCloud share
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebDavConfig {
+    // The folder backups are uploaded into, e.g.
+    // https://cloud.example.com/remote.php/dav/files/alice/Replays
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub auth: WebDavAuth,
+}
+
+impl WebDavConfig {
+    pub fn label(&self) -> String {
+        self.url.trim().trim_end_matches('/').to_string()
+    }
+}
+
+type Response = Result<ureq::Response, ureq::Error>;
+
+// Uploads files below a WebDAV folder, creating the folders in between
+pub struct WebDavStore {
+    agent: ureq::Agent,
+    // The configured folder, without a trailing slash
+    url: String,
+    // Scheme and host of `url`; digest authentication signs the path after it
+    origin: String,
+    username: String,
+    password: String,
+    auth: WebDavAuth,
+    // Nextcloud's folder for chunked uploads of this user, if `url` points into a Nextcloud
+    uploads_url: Option<String>,
+    // Last digest challenge, answered again with an increasing nonce count
+    challenge: Mutex<Option<WwwAuthenticateHeader>>,
+    // Folders known to exist, so they are not created again for every file
+    folders: Mutex<BTreeSet<String>>,
+}
+
+impl WebDavStore {
+    pub fn new(config: &WebDavConfig) -> Result<Self, String> {
+        let url = config.label();
+        let invalid = || {
+            format!(
+                "Invalid WebDAV URL '{}', expected e.g. https://cloud.example.com/remote.php/dav/files/alice/Replays",
+                config.url
+            )
+        };
+        let scheme_length = if url.starts_with("https://") {
+            "https://".len()
+        } else if url.starts_with("http://") {
+            "http://".len()
+        } else {
+            return Err(invalid());
+        };
+        let origin = match url[scheme_length..].find('/') {
+            Some(index) => &url[..scheme_length + index],
+            None => &url,
+        };
+        if origin.len() == scheme_length {
+            return Err(invalid());
+        }
+        if config.username.is_empty() {
+            return Err(format!("No username set for {}", url));
+        }
+        Ok(Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(30))
+                .timeout_read(Duration::from_secs(120))
+                .build(),
+            origin: origin.to_string(),
+            uploads_url: nextcloud_uploads_url(&url),
+            url,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            auth: config.auth,
+            challenge: Mutex::new(None),
+            folders: Mutex::new(BTreeSet::new()),
+        })
+    }
+
+    // The Authorization header for a request, if one can be sent yet. Digest authentication
+    // needs the server's challenge first, which the first request is answered with.
+    fn authorization(&self, method: &str, url: &str) -> std::io::Result<Option<String>> {
+        match self.auth {
+            WebDavAuth::Basic => {
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", self.username, self.password));
+                Ok(Some(format!("Basic {}", credentials)))
+            }
+            WebDavAuth::Digest => {
+                let mut challenge = self.challenge.lock().unwrap_or_else(|e| e.into_inner());
+                let Some(challenge) = challenge.as_mut() else {
+                    return Ok(None);
+                };
+                let path = url.strip_prefix(&self.origin).unwrap_or(url);
+                let context = AuthContext::new_with_method(
+                    self.username.as_str(),
+                    self.password.as_str(),
+                    path,
+                    Option::<&[u8]>::None,
+                    HttpMethod::from(method),
+                );
+                challenge
+                    .respond(&context)
+                    .map(|header| Some(header.to_header_string()))
+                    .map_err(|e| std::io::Error::other(e.to_string()))
+            }
+        }
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> std::io::Result<Response> {
+        let mut challenged = false;
+        loop {
+            let mut request = self.agent.request(method, url);
+            for (name, value) in headers {
+                request = request.set(name, value);
+            }
+            if let Some(authorization) = self.authorization(method, url)? {
+                request = request.set("Authorization", &authorization);
+            }
+            match request.send_bytes(body) {
+                // A new or expired nonce; answer the challenge once
+                Err(ureq::Error::Status(401, response))
+                    if self.auth == WebDavAuth::Digest && !challenged =>
+                {
+                    let header = response.header("WWW-Authenticate").unwrap_or_default();
+                    let challenge = digest_auth::parse(header)
+                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                    *self.challenge.lock().unwrap_or_else(|e| e.into_inner()) = Some(challenge);
+                    challenged = true;
+                }
+                result => return Ok(result),
+            }
+        }
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> std::io::Result<ureq::Response> {
+        match self.send(method, url, headers, body)? {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(code, _)) => Err(std::io::Error::other(format!(
+                "{} {} returned HTTP {}",
+                method, url, code
+            ))),
+            Err(e) => Err(std::io::Error::other(e.to_string())),
+        }
+    }
+
+    // Creates a folder unless it is known to exist; servers answer 405 if it already does
+    fn create_folder(&self, url: &str, headers: &[(&str, &str)]) -> std::io::Result<()> {
+        match self.send("MKCOL", url, headers, &[])? {
+            Ok(_) | Err(ureq::Error::Status(405, _)) => Ok(()),
+            Err(ureq::Error::Status(code, _)) => Err(std::io::Error::other(format!(
+                "Creating {} returned HTTP {}",
+                url, code
+            ))),
+            Err(e) => Err(std::io::Error::other(e.to_string())),
+        }
+    }
+
+    // Creates the folders above `key` below the configured folder
+    fn create_parents(&self, key: &str) -> std::io::Result<()> {
+        let mut url = self.url.clone();
+        let Some((parents, _)) = key.rsplit_once('/') else {
+            return Ok(());
+        };
+        for folder in parents.split('/') {
+            url.push('/');
+            url.push_str(&uri_encode(folder, false));
+            if self
+                .folders
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .contains(&url)
+            {
+                continue;
+            }
+            self.create_folder(&url, &[])?;
+            self.folders
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(url.clone());
+        }
+        Ok(())
+    }
+
+    // Nextcloud's chunked upload: the chunks go into a temporary upload folder, which is then
+    // assembled into the target file in one MOVE
+    fn put_chunked(&self, uploads_url: &str, target: &str, data: &[u8]) -> std::io::Result<()> {
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let upload = format!("{}/siegesaver-{}", uploads_url, id);
+        let total = data.len().to_string();
+        let headers = [("Destination", target), ("OC-Total-Length", total.as_str())];
+        self.create_folder(&upload, &headers[..1])?;
+        let result = data
+            .chunks(CHUNK_SIZE)
+            .enumerate()
+            .try_for_each(|(index, chunk)| {
+                let url = format!("{}/{:05}", upload, index + 1);
+                self.request("PUT", &url, &headers, chunk).map(|_| ())
+            })
+            .and_then(|()| {
+                self.request("MOVE", &format!("{}/.file", upload), &headers, &[])
+                    .map(|_| ())
+            });
+        if result.is_err() {
+            let _ = self.request("DELETE", &upload, &[], &[]);
+        }
+        result
+    }
+}
+
+// Where a Nextcloud keeps chunked uploads for the user whose files `url` points into
+fn nextcloud_uploads_url(url: &str) -> Option<String> {
+    let (base, files) = url.split_once("/remote.php/dav/files/")?;
+    let user = files.split('/').next().filter(|user| !user.is_empty())?;
+    Some(format!("{}/remote.php/dav/uploads/{}", base, user))
+}
+
+impl RemoteStore for WebDavStore {
+    fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()> {
+        self.create_parents(key)?;
+        let target = format!("{}/{}", self.url, uri_encode(key, true));
+        match &self.uploads_url {
+            Some(uploads_url) if data.len() > CHUNK_SIZE => {
+                self.put_chunked(uploads_url, &target, data)
+            }
+            _ => self.request("PUT", &target, &[], data).map(|_| ()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webdav_settings() {
+        let store = WebDavStore::new(&WebDavConfig {
+            url: "https://cloud.example.com/remote.php/dav/files/alice/Replays/".to_string(),
+            username: "alice".to_string(),
+            password: "secret".to_string(),
+            auth: WebDavAuth::Basic,
+        })
+        .unwrap();
+        assert_eq!(store.origin, "https://cloud.example.com");
+        assert_eq!(
+            store.uploads_url.as_deref(),
+            Some("https://cloud.example.com/remote.php/dav/uploads/alice")
+        );
+        assert_eq!(
+            store.authorization("PUT", &store.url).unwrap().as_deref(),
+            Some("Basic YWxpY2U6c2VjcmV0")
+        );
+
+        // Plain WebDAV servers get single uploads, and digest needs a challenge first
+        let store = WebDavStore::new(&WebDavConfig {
+            url: "http://nas:8080".to_string(),
+            username: "alice".to_string(),
+            auth: WebDavAuth::Digest,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(store.origin, "http://nas:8080");
+        assert_eq!(store.uploads_url, None);
+        assert_eq!(store.authorization("PUT", &store.url).unwrap(), None);
+
+        assert!(WebDavStore::new(&WebDavConfig {
+            url: "cloud.example.com/remote.php".to_string(),
+            username: "alice".to_string(),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
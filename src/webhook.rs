@@ -0,0 +1,180 @@
+// Copyright (C) 2025 DatUub | Licensed under GPL-3.0 (see LICENSE file)
+
+use crate::backup::{FileSystem, RealFs};
+use crate::history::HistoryEntry;
+use crate::manifest::Manifest;
+use crate::status::StatusEvent;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+// Checksums of what was written, from the manifest of the destination it was written to
+fn checksums(fs: &dyn FileSystem, destination: &Path) -> Map<String, Value> {
+    let Some(folder) = destination
+        .ancestors()
+        .skip(1)
+        .find(|folder| Manifest::exists(fs, folder))
+    else {
+        return Map::new();
+    };
+    let Some(name) = destination
+        .strip_prefix(folder)
+        .ok()
+        .and_then(|relative| relative.components().next())
+        .map(|first| first.as_os_str().to_string_lossy().into_owned())
+    else {
+        return Map::new();
+    };
+    Manifest::load(fs, folder)
+        .ok()
+        .and_then(|mut manifest| manifest.entries.remove(&name))
+        .map(|entry| {
+            entry
+                .files
+                .into_iter()
+                .map(|(path, file)| (path, Value::String(file.sha256)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The JSON posted for a finished or failed backup; other events are not posted
+pub fn payload(
+    fs: &dyn FileSystem,
+    event: &StatusEvent,
+    time: chrono::DateTime<chrono::Local>,
+) -> Option<Value> {
+    let entry = HistoryEntry::from_event(event, time)?;
+    let detail = event.detail();
+    let path = |path: Option<&Path>| path.map(|path| path.display().to_string());
+    Some(json!({
+        "event": if entry.error.is_some() { "backup_failed" } else { "backup_completed" },
+        "name": entry.name,
+        "time": time.to_rfc3339(),
+        "message": event.to_string(),
+        "source": path(detail.map(|detail| detail.source.as_path())),
+        "destination": path(detail.map(|detail| detail.destination.as_path())),
+        "files": entry.files,
+        "bytes": entry.bytes,
+        "duration_ms": entry.duration_ms,
+        "error": entry.error,
+        "checksums": match detail {
+            Some(detail) if entry.error.is_none() => checksums(fs, &detail.destination),
+            _ => Map::new(),
+        },
+    }))
+}
+
+// POSTs every finished or failed backup as JSON to a URL from a background thread, e.g. for
+// home automation or the user's own scripts
+pub struct WebhookNotifier {
+    tx: Sender<(StatusEvent, chrono::DateTime<chrono::Local>)>,
+}
+
+impl WebhookNotifier {
+    // Failed posts are reported on `status_tx` as warnings
+    pub fn spawn(url: String, status_tx: Sender<StatusEvent>) -> Self {
+        let (tx, rx) = channel();
+        std::thread::spawn(move || post_events(&url, &rx, &status_tx));
+        Self { tx }
+    }
+
+    pub fn notify(&self, event: &StatusEvent) {
+        if HistoryEntry::from_event(event, chrono::Local::now()).is_some() {
+            let _ = self.tx.send((event.clone(), chrono::Local::now()));
+        }
+    }
+}
+
+// Runs until the notifier is dropped
+fn post_events(
+    url: &str,
+    rx: &Receiver<(StatusEvent, chrono::DateTime<chrono::Local>)>,
+    status_tx: &Sender<StatusEvent>,
+) {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build();
+    for (event, time) in rx {
+        let Some(payload) = payload(&RealFs, &event, time) else {
+            continue;
+        };
+        if let Err(e) = agent.post(url).send_json(&payload) {
+            let _ = status_tx.send(StatusEvent::Warning(format!(
+                "Webhook {} failed: {}",
+                url, e
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::sha256_hex;
+    use crate::status::EventDetail;
+    use std::fs;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_webhook_payload() {
+        let test_dir = std::env::temp_dir().join("siegesaver_webhook_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        let destination = test_dir.join("backup");
+        fs::create_dir_all(destination.join("Match-A")).unwrap();
+        fs::write(destination.join("Match-A").join("round1.rec"), b"round one").unwrap();
+        let mut manifest = Manifest::default();
+        manifest
+            .record_backup(
+                &RealFs,
+                &destination,
+                "Match-A",
+                &test_dir.join("replays").join("Match-A"),
+                SystemTime::now(),
+            )
+            .unwrap();
+        manifest.save(&RealFs, &destination).unwrap();
+
+        let time = chrono::Local::now();
+        let event = StatusEvent::FolderBackedUp {
+            name: "Match-A".to_string(),
+            detail: EventDetail {
+                source: test_dir.join("replays").join("Match-A"),
+                destination: destination.join("Match-A"),
+                files: Some(1),
+                bytes: Some(9),
+                duration: Some(Duration::from_millis(20)),
+                error: None,
+            },
+        };
+        let payload = payload(&RealFs, &event, time).unwrap();
+        assert_eq!(payload["event"], "backup_completed");
+        assert_eq!(payload["name"], "Match-A");
+        assert_eq!(payload["bytes"], 9);
+        assert_eq!(payload["duration_ms"], 20);
+        assert_eq!(payload["time"], time.to_rfc3339());
+        assert_eq!(
+            payload["checksums"]["Match-A/round1.rec"],
+            sha256_hex(b"round one")
+        );
+
+        let failed = super::payload(
+            &RealFs,
+            &StatusEvent::CopyError {
+                path: "Match-B/round1.rec".into(),
+                source: "Access denied".to_string(),
+                detail: None,
+            },
+            time,
+        )
+        .unwrap();
+        assert_eq!(failed["event"], "backup_failed");
+        assert_eq!(failed["name"], "Match-B");
+        assert_eq!(failed["error"], "Access denied");
+        assert_eq!(failed["destination"], Value::Null);
+        assert!(super::payload(&RealFs, &StatusEvent::Stopped, time).is_none());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
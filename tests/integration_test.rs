@@ -1,9 +1,39 @@
-use notify::{EventKind, RecursiveMode, Watcher};
-use notify_debouncer_full::{new_debouncer, DebounceEventResult};
+use siegesaver::backup::BackupOptions;
+use siegesaver::queue::BackupQueue;
+use siegesaver::status::StatusEvent;
+use siegesaver::watcher::{
+    handle_file_events, handle_mirrored_file_events, watch_config_file, watch_folder,
+    FolderWatcher, RescanSchedule, WatchOptions,
+};
+use siegesaver::BackupEngine;
 use std::fs;
-use std::sync::mpsc::channel;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::time::Duration;
 
+// Watches `source_dir` with the real watcher and backs up into `dest_dir` on a background
+// thread, exactly like the GUI does. Returns the watcher (dropping it ends the handler thread),
+// the handler thread and the status messages it reports.
+fn start_backup(
+    source_dir: &Path,
+    dest_dir: &Path,
+    options: BackupOptions,
+    watch: &WatchOptions,
+) -> (FolderWatcher, JoinHandle<()>, Receiver<StatusEvent>) {
+    let (event_tx, event_rx) = channel();
+    let (status_tx, status_rx) = channel();
+    let watcher = watch_folder(source_dir, watch, event_tx, status_tx.clone())
+        .expect("Failed to watch source");
+    let engine = BackupEngine::new(source_dir.to_path_buf(), dest_dir.to_path_buf(), options);
+    let handler = std::thread::spawn(move || {
+        handle_file_events(event_rx, &engine, &status_tx, &AtomicBool::new(false));
+    });
+    (watcher, handler, status_rx)
+}
+
 #[test]
 fn test_file_watcher_detects_directories() {
     // Create temporary test directories
@@ -17,84 +47,12 @@ fn test_file_watcher_detects_directories() {
     fs::create_dir_all(&source_dir).expect("Failed to create source directory");
     fs::create_dir_all(&dest_dir).expect("Failed to create dest directory");
 
-    let (tx, rx) = channel();
-    let dest_clone = dest_dir.clone();
-
-    // Create debouncer with shorter timeout for testing
-    let mut debouncer = new_debouncer(
-        Duration::from_millis(100),
-        None,
-        move |result: DebounceEventResult| {
-            if let Ok(events) = result {
-                for event in events {
-                    let _ = tx.send(event.event);
-                }
-            }
-        },
-    )
-    .expect("Failed to create debouncer");
-
-    debouncer
-        .watcher()
-        .watch(&source_dir, RecursiveMode::Recursive)
-        .expect("Failed to watch source directory");
-
-    // Helper function to recursively copy a directory (mirroring main.rs implementation)
-    fn copy_directory_recursive(
-        source: &std::path::PathBuf,
-        destination: &std::path::PathBuf,
-    ) -> std::io::Result<()> {
-        fs::create_dir_all(destination)?;
-        for entry in fs::read_dir(source)? {
-            let entry = entry?;
-            let path = entry.path();
-            let file_name = entry.file_name();
-            let dest_path = destination.join(&file_name);
-            if path.is_dir() {
-                copy_directory_recursive(&path, &dest_path)?;
-            } else {
-                fs::copy(&path, &dest_path)?;
-            }
-        }
-        Ok(())
-    }
-
-    // Spawn a thread to handle directory events
-    let handler = std::thread::spawn(move || {
-        let mut copied_folders = Vec::new();
-        let start = std::time::Instant::now();
-
-        // Listen for events for up to 2 seconds
-        while start.elapsed() < Duration::from_secs(2) {
-            if let Ok(event) = rx.recv_timeout(Duration::from_millis(100)) {
-                match event.kind {
-                    EventKind::Create(_) => {
-                        for path in event.paths {
-                            // Check if the path is a directory
-                            if path.is_dir() {
-                                if let Some(folder_name) = path.file_name() {
-                                    let dest_path = dest_clone.join(folder_name);
-
-                                    // Skip if destination already exists
-                                    if dest_path.exists() {
-                                        continue;
-                                    }
-
-                                    // Copy the entire directory recursively
-                                    if copy_directory_recursive(&path, &dest_path).is_ok() {
-                                        copied_folders
-                                            .push(folder_name.to_string_lossy().to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-        copied_folders
-    });
+    let (watcher, handler, status_rx) = start_backup(
+        &source_dir,
+        &dest_dir,
+        BackupOptions::default(),
+        &WatchOptions::default(),
+    );
 
     // Give the watcher a moment to start
     std::thread::sleep(Duration::from_millis(200));
@@ -114,17 +72,22 @@ fn test_file_watcher_detects_directories() {
     fs::write(match_folder2.join("replay3.rec"), "replay content 3")
         .expect("Failed to write replay file 3");
 
-    // Wait for the handler to finish
-    let copied_folders = handler.join().expect("Handler thread panicked");
+    // Let the debounced events arrive, then stop watching and wait for the handler to finish
+    std::thread::sleep(Duration::from_secs(2));
+    drop(watcher);
+    handler.join().expect("Handler thread panicked");
+    let messages: Vec<String> = status_rx.try_iter().map(|m| m.to_string()).collect();
 
-    // Verify that match folders were copied
+    // Verify that both match folders were reported as backed up
     assert!(
-        copied_folders.contains(&"Match-2025-11-23-001".to_string()),
-        "Match-2025-11-23-001 should have been copied"
+        messages.iter().any(|m| m.contains("Match-2025-11-23-001")),
+        "Match-2025-11-23-001 should have been copied. Status: {:?}",
+        messages
     );
     assert!(
-        copied_folders.contains(&"Match-2025-11-23-002".to_string()),
-        "Match-2025-11-23-002 should have been copied"
+        messages.iter().any(|m| m.contains("Match-2025-11-23-002")),
+        "Match-2025-11-23-002 should have been copied. Status: {:?}",
+        messages
     );
 
     // Verify folders exist in destination with their contents
@@ -188,72 +151,12 @@ fn test_incremental_file_backup() {
     fs::create_dir_all(&source_dir).expect("Failed to create source directory");
     fs::create_dir_all(&dest_dir).expect("Failed to create dest directory");
 
-    let (tx, rx) = channel();
-    let dest_clone = dest_dir.clone();
-    let source_clone = source_dir.clone();
-
-    // Create debouncer with shorter timeout for testing
-    let mut debouncer = new_debouncer(
-        Duration::from_millis(100),
-        None,
-        move |result: DebounceEventResult| {
-            if let Ok(events) = result {
-                for event in events {
-                    let _ = tx.send(event.event);
-                }
-            }
-        },
-    )
-    .expect("Failed to create debouncer");
-
-    debouncer
-        .watcher()
-        .watch(&source_dir, RecursiveMode::Recursive)
-        .expect("Failed to watch source directory");
-
-    // Spawn a thread to handle file events (simulating the new behavior)
-    let handler = std::thread::spawn(move || {
-        let mut backed_up_files = Vec::new();
-        let start = std::time::Instant::now();
-
-        // Listen for events for up to 3 seconds
-        while start.elapsed() < Duration::from_secs(3) {
-            if let Ok(event) = rx.recv_timeout(Duration::from_millis(100)) {
-                match event.kind {
-                    notify::EventKind::Create(_) | notify::EventKind::Modify(_) => {
-                        for path in event.paths {
-                            // Check if the path is a file
-                            if path.is_file() {
-                                // Check if the file has a .rec extension
-                                if let Some(extension) = path.extension() {
-                                    if extension == "rec" {
-                                        // Calculate relative path
-                                        if let Ok(relative_path) = path.strip_prefix(&source_clone)
-                                        {
-                                            let dest_path = dest_clone.join(relative_path);
-
-                                            // Ensure parent directory exists
-                                            if let Some(parent) = dest_path.parent() {
-                                                let _ = fs::create_dir_all(parent);
-                                            }
-
-                                            // Copy the file (overwrite if it exists)
-                                            if fs::copy(&path, &dest_path).is_ok() {
-                                                backed_up_files
-                                                    .push(relative_path.display().to_string());
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-        backed_up_files
-    });
+    let (watcher, handler, status_rx) = start_backup(
+        &source_dir,
+        &dest_dir,
+        BackupOptions::default(),
+        &WatchOptions::default(),
+    );
 
     // Give the watcher a moment to start
     std::thread::sleep(Duration::from_millis(200));
@@ -263,13 +166,13 @@ fn test_incremental_file_backup() {
     fs::create_dir_all(&match_folder).expect("Failed to create match folder");
 
     // Wait for folder creation to be detected
-    std::thread::sleep(Duration::from_millis(300));
+    std::thread::sleep(Duration::from_millis(700));
 
     // Helper function to write a file and wait for detection
     let write_and_wait = |filename: &str, content: &str| {
         fs::write(match_folder.join(filename), content)
             .unwrap_or_else(|_| panic!("Failed to write {}", filename));
-        std::thread::sleep(Duration::from_millis(300));
+        std::thread::sleep(Duration::from_millis(700));
     };
 
     // Now add .rec files one by one (simulating game behavior)
@@ -278,16 +181,19 @@ fn test_incremental_file_backup() {
     write_and_wait("round3.rec", "round 3 data");
 
     // Wait for the handler to finish
-    let backed_up_files = handler.join().expect("Handler thread panicked");
+    std::thread::sleep(Duration::from_millis(500));
+    drop(watcher);
+    handler.join().expect("Handler thread panicked");
+    let backed_up: Vec<String> = status_rx.try_iter().map(|m| m.to_string()).collect();
 
-    // Verify that individual files were backed up
+    // Verify that each round was backed up as it appeared
     let expected_files = ["round1.rec", "round2.rec", "round3.rec"];
     for file in &expected_files {
         assert!(
-            backed_up_files.iter().any(|f| f.contains(file)),
+            backed_up.iter().any(|f| f.contains(file)),
             "{} should have been backed up. Backed up files: {:?}",
             file,
-            backed_up_files
+            backed_up
         );
     }
 
@@ -314,3 +220,137 @@ fn test_incremental_file_backup() {
     // Clean up
     fs::remove_dir_all(&test_dir).expect("Failed to clean up test directory");
 }
+
+#[test]
+fn test_polling_watcher_backs_up_new_matches() {
+    let test_dir = std::env::temp_dir().join("siegesaver_polling_test");
+    let source_dir = test_dir.join("source");
+    let dest_dir = test_dir.join("dest");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&source_dir).expect("Failed to create source directory");
+    fs::create_dir_all(&dest_dir).expect("Failed to create dest directory");
+
+    let watch = WatchOptions {
+        polling: true,
+        poll_interval: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let (watcher, handler, status_rx) =
+        start_backup(&source_dir, &dest_dir, BackupOptions::default(), &watch);
+    assert!(matches!(watcher, FolderWatcher::Polling(_)));
+    std::thread::sleep(Duration::from_millis(500));
+
+    let match_folder = source_dir.join("Match-2025-11-23-001");
+    fs::create_dir_all(&match_folder).expect("Failed to create match folder");
+    fs::write(match_folder.join("replay1.rec"), "replay content 1")
+        .expect("Failed to write replay file");
+
+    std::thread::sleep(Duration::from_secs(3));
+    drop(watcher);
+    handler.join().expect("Handler thread panicked");
+    let messages: Vec<String> = status_rx.try_iter().map(|m| m.to_string()).collect();
+    assert!(
+        dest_dir.join("Match-2025-11-23-001/replay1.rec").exists(),
+        "replay1.rec should have been copied. Status: {:?}",
+        messages
+    );
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn test_backups_are_mirrored_to_every_destination() {
+    let test_dir = std::env::temp_dir().join("siegesaver_mirror_test");
+    let source_dir = test_dir.join("source");
+    let dest_dirs = [test_dir.join("dest"), test_dir.join("mirror")];
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&source_dir).expect("Failed to create source directory");
+    for dest_dir in &dest_dirs {
+        fs::create_dir_all(dest_dir).expect("Failed to create dest directory");
+    }
+
+    let (event_tx, event_rx) = channel();
+    let (status_tx, status_rx) = channel();
+    let watcher = watch_folder(
+        &source_dir,
+        &WatchOptions::default(),
+        event_tx,
+        status_tx.clone(),
+    )
+    .expect("Failed to watch source");
+    let engines: Vec<BackupEngine> = dest_dirs
+        .iter()
+        .map(|dest_dir| {
+            BackupEngine::new(
+                source_dir.clone(),
+                dest_dir.clone(),
+                BackupOptions::default(),
+            )
+        })
+        .collect();
+    let handler = std::thread::spawn(move || {
+        handle_mirrored_file_events(
+            event_rx,
+            &engines,
+            &status_tx,
+            &AtomicBool::new(false),
+            &RescanSchedule::default(),
+            &BackupQueue::default(),
+        );
+    });
+    std::thread::sleep(Duration::from_millis(200));
+
+    let match_folder = source_dir.join("Match-2025-11-25-001");
+    fs::create_dir_all(&match_folder).expect("Failed to create match folder");
+    fs::write(match_folder.join("round1.rec"), "round 1 data").expect("Failed to write replay");
+
+    std::thread::sleep(Duration::from_secs(2));
+    drop(watcher);
+    handler.join().expect("Handler thread panicked");
+    let messages: Vec<String> = status_rx.try_iter().map(|m| m.to_string()).collect();
+
+    for dest_dir in &dest_dirs {
+        assert_eq!(
+            fs::read_to_string(dest_dir.join("Match-2025-11-25-001").join("round1.rec")).ok(),
+            Some("round 1 data".to_string()),
+            "round1.rec should be in {}. Status: {:?}",
+            dest_dir.display(),
+            messages
+        );
+    }
+
+    fs::remove_dir_all(&test_dir).expect("Failed to clean up test directory");
+}
+
+#[test]
+fn test_config_file_edits_are_reported() {
+    let test_dir = std::env::temp_dir().join("siegesaver_config_watch_test");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).expect("Failed to create config directory");
+    let config_path = test_dir.join("config.json");
+    fs::write(&config_path, "{}").expect("Failed to write config");
+
+    let changes = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&changes);
+    let watcher = watch_config_file(&config_path, move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+    })
+    .expect("Failed to watch config file");
+    std::thread::sleep(Duration::from_millis(200));
+
+    // Other files in the folder do not count
+    fs::write(test_dir.join("history.json"), "[]").expect("Failed to write history");
+    std::thread::sleep(Duration::from_secs(2));
+    assert_eq!(changes.load(Ordering::Relaxed), 0);
+
+    // Editors often write a new file and move it over the old one
+    let replacement = test_dir.join("config.json.tmp");
+    fs::write(&replacement, r#"{"destination_folder": "/backup"}"#)
+        .expect("Failed to write new config");
+    fs::rename(&replacement, &config_path).expect("Failed to replace config");
+    std::thread::sleep(Duration::from_secs(2));
+    assert!(changes.load(Ordering::Relaxed) > 0);
+
+    drop(watcher);
+    let _ = fs::remove_dir_all(&test_dir);
+}